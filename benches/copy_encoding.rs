@@ -0,0 +1,87 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use otr_processor::{
+    database::{copy::BinaryCopyEncoder, db_structs::PlayerRating},
+    model::structures::ruleset::Ruleset,
+    utils::test_utils::generate_player_rating
+};
+use postgres_types::{ToSql, Type};
+
+/// Columns of the rating_adjustments COPY
+const TYPES: [Type; 15] = [
+    Type::INT4,
+    Type::INT4,
+    Type::INT4,
+    Type::INT4,
+    Type::FLOAT8,
+    Type::FLOAT8,
+    Type::FLOAT8,
+    Type::FLOAT8,
+    Type::TIMESTAMPTZ,
+    Type::INT4,
+    Type::FLOAT8,
+    Type::BOOL,
+    Type::FLOAT8,
+    Type::INT4,
+    Type::INT4
+];
+
+/// Run which produced the adjustments
+const RUN_ID: i32 = 1;
+
+/// Encodes every adjustment of `ratings`, returning the number of bytes sent
+fn encode(ratings: &[PlayerRating], batch_size: usize) -> usize {
+    let mut encoder = BinaryCopyEncoder::new(&TYPES, batch_size);
+    let mut sent = 0;
+
+    for (parent_id, rating) in ratings.iter().enumerate() {
+        let parent_id = parent_id as i32;
+        for adjustment in &rating.adjustments {
+            let ruleset = adjustment.ruleset as i32;
+            let adjustment_type = adjustment.adjustment_type as i32;
+            let values: [&(dyn ToSql + Sync); 15] = [
+                &adjustment.player_id,
+                &ruleset,
+                &parent_id,
+                &adjustment.match_id,
+                &adjustment.rating_before,
+                &adjustment.rating_after,
+                &adjustment.volatility_before,
+                &adjustment.volatility_after,
+                &adjustment.timestamp,
+                &adjustment_type,
+                &adjustment.average_opponent_rating,
+                &adjustment.provisional,
+                &adjustment.percentile,
+                &adjustment.clamps.0,
+                &RUN_ID
+            ];
+            if let Some(batch) = encoder.encode(&values).unwrap() {
+                sent += batch.len();
+            }
+        }
+    }
+
+    sent + encoder.finish().len()
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let ratings = (1..=10_000)
+        .map(|id| generate_player_rating(id, Ruleset::Osu, 1000.0, 200.0, 50, None, None))
+        .collect::<Vec<_>>();
+    let adjustments = ratings.iter().map(|r| r.adjustments.len() as u64).sum();
+
+    let mut group = c.benchmark_group("copy_rating_adjustments");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(adjustments));
+
+    for kb in [4, 64, 1024] {
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}KB", kb)), &kb, |b, &kb| {
+            b.iter(|| encode(&ratings, kb * 1024))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode);
+criterion_main!(benches);
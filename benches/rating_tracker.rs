@@ -0,0 +1,64 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use otr_processor::{
+    database::db_structs::PlayerRating,
+    model::{rating_tracker::RatingTracker, structures::ruleset::Ruleset},
+    utils::test_utils::generate_player_rating
+};
+use std::collections::HashMap;
+
+const COUNTRIES: [&str; 8] = ["US", "DE", "JP", "KR", "BR", "PL", "NZ", ""];
+
+/// Number of ratings inserted by `bench_insert`
+const INSERT_SIZE: i32 = 500_000;
+
+/// `size` osu! ratings
+fn ratings(size: i32) -> Vec<PlayerRating> {
+    (1..=size)
+        .map(|id| generate_player_rating(id, Ruleset::Osu, 500.0 + (id % 2000) as f64, 100.0, 1, None, None))
+        .collect()
+}
+
+/// A tracker holding `size` osu! ratings spread across a handful of countries
+fn tracker(size: i32) -> RatingTracker {
+    let country_mapping = (1..=size)
+        .map(|id| (id, COUNTRIES[id as usize % COUNTRIES.len()].to_string()))
+        .collect::<HashMap<_, _>>();
+
+    let mut tracker = RatingTracker::new();
+    tracker.set_country_mapping(country_mapping);
+    tracker.insert_or_update(&ratings(size));
+    tracker
+}
+
+/// Inserts the ratings into an empty tracker, with and without preallocating its capacity
+fn bench_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rating_tracker_insert");
+    group.sample_size(10);
+    let ratings = ratings(INSERT_SIZE);
+
+    group.bench_function("growing", |b| {
+        b.iter(|| RatingTracker::new().insert_or_update(&ratings))
+    });
+    group.bench_function("preallocated", |b| {
+        b.iter(|| RatingTracker::with_capacity(ratings.len()).insert_or_update(&ratings))
+    });
+
+    group.finish();
+}
+
+fn bench_sort(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rating_tracker_sort");
+    group.sample_size(10);
+
+    for size in [10_000, 100_000, INSERT_SIZE, 1_000_000] {
+        let mut tracker = tracker(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| tracker.sort())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_insert, bench_sort);
+criterion_main!(benches);
@@ -0,0 +1,11 @@
+-- Every processor run, with the hash of its inputs to skip runs whose inputs are unchanged
+CREATE TABLE IF NOT EXISTS processor_runs (
+    id integer GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    input_hash text NOT NULL,
+    version text NOT NULL,
+    config text NOT NULL,
+    started_at timestamp with time zone NOT NULL,
+    completed_at timestamp with time zone
+);
+
+CREATE INDEX IF NOT EXISTS ix_processor_runs_completed_at ON processor_runs (completed_at);
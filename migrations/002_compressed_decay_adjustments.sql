@@ -0,0 +1,4 @@
+-- Coalesced decay adjustments, written with --compress-decay-adjustments
+ALTER TABLE rating_adjustments
+    ADD COLUMN IF NOT EXISTS decay_count integer,
+    ADD COLUMN IF NOT EXISTS decay_start_timestamp timestamp with time zone;
@@ -0,0 +1,6 @@
+-- The matches, games and manual adjustments skipped by the last run
+CREATE TABLE IF NOT EXISTS processor_skipped_entities (
+    entity_type text NOT NULL,
+    entity_id integer NOT NULL,
+    reason text NOT NULL
+);
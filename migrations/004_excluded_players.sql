@@ -0,0 +1,4 @@
+-- Banned and bot players, whose scores are left out of rating
+CREATE TABLE IF NOT EXISTS excluded_players (
+    player_id integer PRIMARY KEY
+);
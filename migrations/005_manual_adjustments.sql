@@ -0,0 +1,11 @@
+-- Rating corrections inserted by moderation. kind is a ManualAdjustmentKind: 0 overrides the rating, 1 offsets it.
+CREATE TABLE IF NOT EXISTS manual_adjustments (
+    id integer GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    player_id integer NOT NULL,
+    ruleset integer NOT NULL,
+    timestamp timestamp with time zone NOT NULL,
+    kind integer NOT NULL,
+    value double precision NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS ix_manual_adjustments_timestamp ON manual_adjustments (timestamp);
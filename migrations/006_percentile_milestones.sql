@@ -0,0 +1,15 @@
+-- The best percentile of every player, and when they first entered the top 50%, 10% and 1%
+ALTER TABLE player_highest_ranks
+    ADD COLUMN IF NOT EXISTS percentile double precision NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS percentile_date timestamp with time zone;
+
+UPDATE player_highest_ranks SET percentile_date = global_rank_date WHERE percentile_date IS NULL;
+ALTER TABLE player_highest_ranks ALTER COLUMN percentile_date SET NOT NULL;
+
+CREATE TABLE IF NOT EXISTS player_percentile_milestones (
+    player_id integer NOT NULL,
+    ruleset integer NOT NULL,
+    top_percent double precision NOT NULL,
+    reached_at timestamp with time zone NOT NULL,
+    UNIQUE (player_id, ruleset, top_percent)
+);
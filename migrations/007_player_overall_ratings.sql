@@ -0,0 +1,8 @@
+-- Ratings blended across rulesets, written with --overall-ratings
+CREATE TABLE IF NOT EXISTS player_overall_ratings (
+    player_id integer PRIMARY KEY,
+    rating double precision NOT NULL,
+    global_rank integer NOT NULL,
+    percentile double precision NOT NULL,
+    matches_played integer NOT NULL
+);
@@ -0,0 +1,5 @@
+-- The detected mod pool of every classified game. category is a ModCategory.
+CREATE TABLE IF NOT EXISTS game_mod_categories (
+    game_id integer PRIMARY KEY,
+    category integer NOT NULL
+);
@@ -0,0 +1,16 @@
+-- The match adjustments replaced by tournament settlements, written with --settle-tournaments
+CREATE TABLE IF NOT EXISTS tournament_settlement_matches (
+    id integer GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    player_id integer NOT NULL,
+    ruleset integer NOT NULL,
+    tournament_id integer NOT NULL,
+    settled_at timestamp with time zone NOT NULL,
+    match_id integer,
+    rating_before double precision NOT NULL,
+    rating_after double precision NOT NULL,
+    volatility_before double precision NOT NULL,
+    volatility_after double precision NOT NULL,
+    timestamp timestamp with time zone NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS ix_tournament_settlement_matches_timestamp ON tournament_settlement_matches (timestamp);
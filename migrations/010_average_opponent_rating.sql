@@ -0,0 +1,2 @@
+-- The average rating of the opponents faced in a match adjustment
+ALTER TABLE rating_adjustments ADD COLUMN IF NOT EXISTS average_opponent_rating double precision;
@@ -0,0 +1,9 @@
+-- The matches skipped by a run, until they are processed again. reason is a QuarantineReason.
+CREATE TABLE IF NOT EXISTS processing_quarantine (
+    match_id integer NOT NULL,
+    reason integer NOT NULL,
+    details text NOT NULL,
+    quarantined_at timestamp with time zone NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS ix_processing_quarantine_match_id ON processing_quarantine (match_id);
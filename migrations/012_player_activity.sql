@@ -0,0 +1,7 @@
+-- The activity of every rated player at the end of the last run. activity is an Activity.
+CREATE TABLE IF NOT EXISTS player_activity (
+    player_id integer NOT NULL,
+    ruleset integer NOT NULL,
+    activity integer NOT NULL,
+    PRIMARY KEY (player_id, ruleset)
+);
@@ -0,0 +1,2 @@
+-- Whether an adjustment was made during the player's provisional period
+ALTER TABLE rating_adjustments ADD COLUMN IF NOT EXISTS provisional boolean NOT NULL DEFAULT false;
@@ -0,0 +1,2 @@
+-- The rating scaled for display, written with --display-scale
+ALTER TABLE player_ratings ADD COLUMN IF NOT EXISTS display_rating double precision;
@@ -0,0 +1,7 @@
+-- Periods during which players are restricted from tournaments, pausing their decay.
+-- ends_at is NULL while the restriction is ongoing.
+CREATE TABLE IF NOT EXISTS player_restrictions (
+    player_id integer NOT NULL,
+    starts_at timestamp with time zone NOT NULL,
+    ends_at timestamp with time zone
+);
@@ -0,0 +1,9 @@
+-- Players whose global rank crossed a notable rank during the last run, for notifications
+CREATE TABLE IF NOT EXISTS player_rank_changes (
+    player_id integer NOT NULL,
+    ruleset integer NOT NULL,
+    old_rank integer,
+    new_rank integer NOT NULL,
+    delta integer,
+    threshold integer NOT NULL
+);
@@ -0,0 +1,7 @@
+-- How far the placements of every processed match departed from the expected ones
+CREATE TABLE IF NOT EXISTS match_upsets (
+    match_id integer PRIMARY KEY,
+    upset_factor double precision NOT NULL,
+    inverted_pairs integer NOT NULL,
+    compared_pairs integer NOT NULL
+);
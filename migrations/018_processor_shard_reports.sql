@@ -0,0 +1,9 @@
+-- The run report of each ruleset shard, until the coordinator merges them
+CREATE TABLE IF NOT EXISTS processor_shard_reports (
+    ruleset integer PRIMARY KEY,
+    report jsonb NOT NULL,
+    completed_at timestamp with time zone NOT NULL
+);
+
+-- The ruleset processed by a run if it was a shard, so each shard skips only its own unchanged runs
+ALTER TABLE processor_runs ADD COLUMN IF NOT EXISTS shard integer;
@@ -0,0 +1,2 @@
+-- The percentile of the player right after an adjustment
+ALTER TABLE rating_adjustments ADD COLUMN IF NOT EXISTS percentile double precision;
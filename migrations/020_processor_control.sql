@@ -0,0 +1,5 @@
+-- Lets admins halt runs before they save anything, by setting halt on a row
+CREATE TABLE IF NOT EXISTS processor_control (
+    halt boolean NOT NULL DEFAULT false,
+    reason text
+);
@@ -0,0 +1,3 @@
+-- The processor run which produced each rating and adjustment
+ALTER TABLE player_ratings ADD COLUMN IF NOT EXISTS processing_run_id integer;
+ALTER TABLE rating_adjustments ADD COLUMN IF NOT EXISTS processing_run_id integer;
@@ -0,0 +1,2 @@
+-- The rating and volatility bounds applied by an adjustment, see RatingClamps
+ALTER TABLE rating_adjustments ADD COLUMN IF NOT EXISTS clamps integer NOT NULL DEFAULT 0;
@@ -0,0 +1,9 @@
+-- The average lobby and team size of every rated player
+CREATE TABLE IF NOT EXISTS player_lobby_stats (
+    player_id integer NOT NULL,
+    ruleset integer NOT NULL,
+    games integer NOT NULL,
+    average_lobby_size double precision NOT NULL,
+    average_team_size double precision NOT NULL,
+    PRIMARY KEY (player_id, ruleset)
+);
@@ -0,0 +1,668 @@
+use super::effective_config::ResultParameters;
+use crate::{
+    database::copy::DEFAULT_COPY_BATCH_SIZE_KB,
+    model::{
+        constants::{DECAY_DAYS, DECAY_RATE, DECAY_VOLATILITY_INTERVAL_DAYS, DEFAULT_VOLATILITY, MIN_VOLATILITY},
+        decay_sweep::DEFAULT_SWEEP_SAMPLE,
+        model_config::ModelConfig,
+        score_integrity::{IntegrityPolicy, DEFAULT_MAX_SCORE},
+        start_times::MissingStartTimePolicy,
+        structures::{
+            date_range::DateRange, decay_override::DecayOverride, display_scale::DisplayScale, dnf_policy::DnfPolicy,
+            fallback_strategy::FallbackStrategy, game_weights::GameWeights, gamma_strategy::GammaStrategy,
+            integrity_action::IntegrityAction, lobby_beta::LobbyBeta, provisional_period::ProvisionalPeriod,
+            returning_boost::ReturningBoost, ruleset::Ruleset, ruleset_filter::RulesetFilter,
+            short_match_policy::ShortMatchPolicy, weight_strategy::WeightStrategy
+        }
+    },
+    post_process::post_processor::{PostProcessor, PostProcessorKind},
+    report::{
+        memory::{MemoryMonitor, DEFAULT_MEMORY_SAMPLE_INTERVAL_MS},
+        rank_changes::DEFAULT_NOTABLE_RANKS,
+        rating_shift::{ShiftGuard, DEFAULT_MAX_SHIFT_FRACTION, DEFAULT_SHIFT_THRESHOLD},
+        run_report::DEFAULT_MIN_COUNTRY_SIZE,
+        slow_log::{SlowLog, DEFAULT_SLOW_THRESHOLD_MS},
+        updated_players::{UpdateThresholds, DEFAULT_RANK_THRESHOLD, DEFAULT_RATING_THRESHOLD},
+        warnings::{WarningKind, WarningSink}
+    }
+};
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use clap::{builder::TypedValueParser, Parser};
+use std::{path::PathBuf, time::Duration};
+
+/// Command line arguments for the o!TR processor
+#[derive(Parser, Debug, Clone)]
+#[command(version, about = "Processes verified match data into o!TR ratings")]
+pub struct Args {
+    /// Only process matches starting on or after this date (YYYY-MM-DD or RFC 3339).
+    /// Ratings are seeded from the adjustments stored before this date.
+    #[arg(long, value_parser = parse_date)]
+    pub from_date: Option<DateTime<FixedOffset>>,
+
+    /// Only process matches starting on or before this date (YYYY-MM-DD or RFC 3339).
+    /// Ratings only decay up to this date.
+    #[arg(long, value_parser = parse_date)]
+    pub to_date: Option<DateTime<FixedOffset>>,
+
+    /// Compute the ratings as they were at this date (YYYY-MM-DD or RFC 3339) without saving
+    /// them. Later matches are ignored and ratings only decay up to the date. Use the export
+    /// post processor to write the ratings out.
+    #[arg(long, value_parser = parse_date)]
+    pub as_of: Option<DateTime<FixedOffset>>,
+
+    /// Only fetch, process and save the given comma separated rulesets (e.g. osu,taiko).
+    /// The stored data of all other rulesets is left untouched.
+    #[arg(long, value_delimiter = ',')]
+    pub rulesets: Vec<Ruleset>,
+
+    /// Process only this ruleset as one shard of a run split across processes. Shards of
+    /// different rulesets run concurrently and store their run report for --coordinate-shards.
+    #[arg(long, value_name = "RULESET", conflicts_with = "rulesets")]
+    pub shard: Option<Ruleset>,
+
+    /// Wait until the shards of the given comma separated rulesets finished, then merge their
+    /// stored run reports into a single report. Fails if a shard did not store its report.
+    #[arg(long, value_name = "RULESETS", value_delimiter = ',', conflicts_with = "shard")]
+    pub coordinate_shards: Option<Vec<Ruleset>>,
+
+    /// Wait for another processor to finish instead of exiting when it holds the processor lock
+    #[arg(long)]
+    pub wait_for_lock: bool,
+
+    /// Process even if the input data is unchanged since the last successful run
+    #[arg(long)]
+    pub force: bool,
+
+    /// Minimum volatility a player can have after a match, at most the default volatility
+    #[arg(long, default_value_t = MIN_VOLATILITY, value_parser = parse_min_volatility)]
+    pub min_volatility: f64,
+
+    /// Gamma function controlling how quickly volatility converges: inverse-team-count,
+    /// openskill, sigma-proportional, or a number for a constant gamma
+    #[arg(long, default_value_t = GammaStrategy::default())]
+    pub gamma: GammaStrategy,
+
+    /// Rate games of n players with the beta scaled by (n / 2) ^ <exponent>, so that placements
+    /// in large lobbies move ratings less than 1v1s (e.g. 0.5). The beta is constant by default.
+    #[arg(long, value_name = "EXPONENT")]
+    pub lobby_beta: Option<LobbyBeta>,
+
+    /// How Method A and Method B ratings are blended: constant weights, or weights scaled by
+    /// the fraction of games each player participated in
+    #[arg(long, default_value_t = WeightStrategy::default())]
+    pub weights: WeightStrategy,
+
+    /// Weights of warmups (the first game of a match) and tiebreakers (a last game played with
+    /// the wins tied) when the ratings of a match's games are aggregated, as
+    /// warmup=<weight>,tiebreaker=<weight> (e.g. warmup=0,tiebreaker=1.5). All games count
+    /// equally by default.
+    #[arg(long, default_value_t = GameWeights::default())]
+    pub game_weights: GameWeights,
+
+    /// Decay parameters replacing the defaults for a ruleset, as
+    /// <ruleset>:<inactivity days>:<rate>:<minimum> (e.g. catch:240:1.8:900). Can be repeated.
+    #[arg(long = "decay")]
+    pub decay_overrides: Vec<DecayOverride>,
+
+    /// Number of days between volatility growth cycles of inactive players, independent of
+    /// the weekly rating decay. The weekly growth is spread over the cycles.
+    #[arg(long, default_value_t = DECAY_VOLATILITY_INTERVAL_DAYS, value_parser = clap::value_parser!(u64).range(1..))]
+    pub decay_volatility_interval_days: u64,
+
+    /// Rate players as if their volatility were at least <volatility> for their first
+    /// <matches> matches after their rating decayed, as <matches>:<volatility> (e.g. 3:250)
+    #[arg(long)]
+    pub returning_boost: Option<ReturningBoost>,
+
+    /// Rate new players as if their volatility were at least <volatility> in their first
+    /// match, raising it less with every match until <matches> matches were played, as
+    /// <matches>:<volatility> (e.g. 5:300). Their adjustments are flagged as provisional.
+    #[arg(long)]
+    pub provisional_period: Option<ProvisionalPeriod>,
+
+    /// Initial rating of players without rank data: the constant fallback rating, or the
+    /// median of the known ratings in the player's ruleset
+    #[arg(long, default_value_t = FallbackStrategy::default())]
+    pub fallback_rating: FallbackStrategy,
+
+    /// How to handle matches without a start time: skip them, or impute the start time
+    /// from their games
+    #[arg(long, default_value_t = MissingStartTimePolicy::default())]
+    pub missing_start_time: MissingStartTimePolicy,
+
+    /// Use the game score placements stored in the database instead of calculating them from scores
+    #[arg(long)]
+    pub placements_in_db: bool,
+
+    /// How scores of 0 (disconnects) are placed: rank (by score like any other score),
+    /// tied-last (tied below every other score) or exclude (removed from the game)
+    #[arg(long, default_value_t = DnfPolicy::default())]
+    pub dnf_policy: DnfPolicy,
+
+    /// How matches with a single game are rated: rate (like any other match), flag (rated,
+    /// raising a short-match warning), dampen:<factor> (rating and volatility changes scaled
+    /// by a factor in (0, 1]) or min-games:<games> (matches with fewer games are skipped)
+    #[arg(long, default_value_t = ShortMatchPolicy::default())]
+    pub short_match_policy: ShortMatchPolicy,
+
+    /// Largest score considered possible, higher scores fail the integrity checks
+    #[arg(long, default_value_t = DEFAULT_MAX_SCORE)]
+    pub max_score: i32,
+
+    /// What to do with negative scores and scores above --max-score: drop-score, drop-game or fail
+    #[arg(long, default_value_t = IntegrityPolicy::default().invalid_score)]
+    pub invalid_score_policy: IntegrityAction,
+
+    /// What to do with further scores of a player already entered in the same game:
+    /// drop-score, drop-game or fail
+    #[arg(long, default_value_t = IntegrityPolicy::default().duplicate_player)]
+    pub duplicate_player_policy: IntegrityAction,
+
+    /// What to do with games left with a single score: drop-score, drop-game or fail
+    #[arg(long, default_value_t = IntegrityPolicy::default().single_participant)]
+    pub single_participant_policy: IntegrityAction,
+
+    /// Store each run of consecutive decay adjustments as a single summary row
+    #[arg(long)]
+    pub compress_decay_adjustments: bool,
+
+    /// Record the percentile of every player within their ruleset after each of their match
+    /// adjustments, for percentile history graphs
+    #[arg(long)]
+    pub adjustment_percentiles: bool,
+
+    /// Store the match adjustments of each tournament as a single settlement at the end of
+    /// the tournament, keeping the per-match adjustments in the tournament_settlement_matches table
+    #[arg(long)]
+    pub settle_tournaments: bool,
+
+    /// Write player ratings and rating adjustments to staging tables which replace the live
+    /// tables in a short transaction, instead of truncating and refilling them. Only applies
+    /// to runs over all rulesets and dates.
+    #[arg(long)]
+    pub staged_save: bool,
+
+    /// Save the ratings and adjustments of each ruleset as soon as it is processed, while the
+    /// next ruleset is processed. Every ruleset passes the leaderboard checks and the rating
+    /// shift guard before it is saved, and the saves share a single transaction, so a run
+    /// aborted by a later ruleset saves nothing. Conflicts with the options which need all
+    /// results before anything is saved.
+    #[arg(long, conflicts_with_all = ["staged_save", "as_of", "verify_continuity", "fail_on_warning"])]
+    pub progressive_save: bool,
+
+    /// Compute the results without saving them, writing them to the given file instead. The
+    /// processed matches keep awaiting processing until the file is saved with --input-state,
+    /// e.g. after reviewing the run report.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["as_of", "progressive_save"])]
+    pub output_state: Option<PathBuf>,
+
+    /// Store and read back results in this SQLite database instead of Postgres, creating it if
+    /// needed. Matches and players are still read from Postgres, where the processed matches
+    /// stay awaiting processing as their results are not saved there. Meant for local development.
+    #[arg(long)]
+    pub sqlite_store: Option<PathBuf>,
+
+    /// Fail the run instead of rating around gaps in the input data (missing players,
+    /// missing rank data, untracked players or empty games)
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Comma separated kinds of warnings which abort the run before anything is saved:
+    /// fallback-rating-used, empty-game, missing-country, invalid-country, ruleset-mismatch,
+    /// short-match
+    #[arg(long, value_delimiter = ',')]
+    pub fail_on_warning: Vec<WarningKind>,
+
+    /// Instead of processing, move stored ManiaOther ratings into Mania4k or Mania7k based on
+    /// the key count each player played most, reporting the mapping
+    #[arg(long)]
+    pub migrate_mania_other: bool,
+
+    /// Instead of processing, recompute the country ranks of the given country from the stored
+    /// ratings and the current player countries, e.g. after a country correction. Other ranks
+    /// are left untouched, so also recompute the previous country of moved players.
+    #[arg(long, value_name = "COUNTRY")]
+    pub country_ranks: Option<String>,
+
+    /// Instead of processing, count the rating adjustments without a rating, the ratings of
+    /// players who no longer exist and the highest ranks of players who no longer exist.
+    /// Nothing is deleted unless --yes is given.
+    #[arg(long)]
+    pub prune: bool,
+
+    /// Delete the rows counted by --prune
+    #[arg(long, requires = "prune")]
+    pub yes: bool,
+
+    /// Instead of processing, check the environment (connection string, database schema and
+    /// privileges, export path) and print a pass/fail table. Exits with 1 if any check fails.
+    #[arg(long)]
+    pub doctor: bool,
+
+    /// Instead of processing, project the rating changes of the lineup in the given JSON file
+    /// (a ruleset and the placements of each game) using the stored ratings
+    #[arg(long)]
+    pub simulate: Option<PathBuf>,
+
+    /// Instead of processing, replay the decay of a sample of stored players under every
+    /// combination of --sweep-rates and --sweep-inactivity-days, writing their rating
+    /// trajectories as CSV to the given file. Matches are not processed again.
+    #[arg(long)]
+    pub decay_sweep: Option<PathBuf>,
+
+    /// Instead of processing, save the results computed by a run with --output-state and mark
+    /// their matches as processed. Only ratings, adjustments and highest ranks are saved, the
+    /// other website data is left as it is until the next run.
+    #[arg(long, value_name = "FILE", conflicts_with = "output_state")]
+    pub input_state: Option<PathBuf>,
+
+    /// Number of players whose decay is replayed by --decay-sweep
+    #[arg(long, default_value_t = DEFAULT_SWEEP_SAMPLE)]
+    pub sweep_sample: usize,
+
+    /// Comma separated rating lost per weekly decay cycle, swept by --decay-sweep
+    #[arg(long, value_delimiter = ',', default_values_t = [DECAY_RATE])]
+    pub sweep_rates: Vec<f64>,
+
+    /// Comma separated days of inactivity before decay begins, swept by --decay-sweep
+    #[arg(long, value_delimiter = ',', default_values_t = [DECAY_DAYS])]
+    pub sweep_inactivity_days: Vec<u64>,
+
+    /// Save the results even if more than --max-shift-fraction of the stored ratings would
+    /// change by at least --shift-threshold
+    #[arg(long)]
+    pub allow_large_shift: bool,
+
+    /// Minimum rating change for a stored rating to count as shifted by the run
+    #[arg(long, default_value_t = DEFAULT_SHIFT_THRESHOLD)]
+    pub shift_threshold: f64,
+
+    /// Largest fraction (0 to 1) of stored ratings which may be shifted before the run is
+    /// aborted without saving
+    #[arg(long, default_value_t = DEFAULT_MAX_SHIFT_FRACTION)]
+    pub max_shift_fraction: f64,
+
+    /// Compare the recomputed match adjustments of this many players against their stored
+    /// ones, up to the last match both share, and abort without saving if any stored
+    /// adjustment is no longer reproduced
+    #[arg(long)]
+    pub verify_continuity: Option<usize>,
+
+    /// Comma separated steps to run after processing and before saving: validate, stats, export
+    #[arg(long, value_delimiter = ',', default_value = "validate,stats")]
+    pub post_processors: Vec<PostProcessorKind>,
+
+    /// Destination of the ratings written by the export post processor
+    #[arg(long, default_value = "ratings.json")]
+    pub export_path: PathBuf,
+
+    /// Also store a rating blended across all rulesets for every player, weighting each
+    /// ruleset by the number of matches played in it
+    #[arg(long)]
+    pub overall_ratings: bool,
+
+    /// Also store a display rating for every player, mapping ratings onto a display scale
+    /// through comma separated <percentile>:<display> anchors placed on each ruleset's
+    /// leaderboard, e.g. 0:100,50:1000,99:2500,100:3500
+    #[arg(long)]
+    pub display_scale: Option<DisplayScale>,
+
+    /// Also classify every processed game by the mod pool it was played from (NM, HD, HR, DT
+    /// or FM) and store it in the game_mod_categories table
+    #[arg(long)]
+    pub classify_mods: bool,
+
+    /// Also store how surprising the outcome of every processed match was given its players'
+    /// ratings in the match_upsets table
+    #[arg(long)]
+    pub save_upsets: bool,
+
+    /// Also store the matches and games skipped by the run in the processor_skipped_entities table
+    #[arg(long)]
+    pub save_skipped: bool,
+
+    /// Also store the rank changes of players crossing one of the --notable-ranks in the
+    /// player_rank_changes table, replacing those of the previous run
+    #[arg(long)]
+    pub save_rank_changes: bool,
+
+    /// Comma separated global ranks whose crossing is listed as a rank change in the run report
+    #[arg(long, value_delimiter = ',', default_values_t = DEFAULT_NOTABLE_RANKS)]
+    pub notable_ranks: Vec<i32>,
+
+    /// Write the run report as JSON to this path instead of printing it
+    #[arg(long)]
+    pub report_path: Option<PathBuf>,
+
+    /// Minimum rating change for a player to be listed as updated in the run report
+    #[arg(long, default_value_t = DEFAULT_RATING_THRESHOLD)]
+    pub updated_rating_threshold: f64,
+
+    /// Minimum global rank change for a player to be listed as updated in the run report
+    #[arg(long, default_value_t = DEFAULT_RANK_THRESHOLD)]
+    pub updated_rank_threshold: i32,
+
+    /// Countries with fewer rated players than this in a ruleset are flagged in the run
+    /// report. Their country ranks are still computed.
+    #[arg(long, default_value_t = DEFAULT_MIN_COUNTRY_SIZE)]
+    pub min_country_size: usize,
+
+    /// Database queries and pipeline stages taking at least this many milliseconds are
+    /// logged and listed in the run report. 0 disables the slow log.
+    #[arg(long, default_value_t = DEFAULT_SLOW_THRESHOLD_MS)]
+    pub slow_threshold_ms: u64,
+
+    /// Abort the run before anything is saved once the resident memory of the processor
+    /// reaches this many MB, instead of risking the OOM killer. Only enforced on Linux.
+    #[arg(long)]
+    pub memory_ceiling_mb: Option<u64>,
+
+    /// Milliseconds between two background samples of the resident memory, which are
+    /// checked against `--memory-ceiling-mb`
+    #[arg(long, default_value_t = DEFAULT_MEMORY_SAMPLE_INTERVAL_MS, value_parser = clap::value_parser!(u64).range(1..))]
+    pub memory_sample_interval_ms: u64,
+
+    /// Size in KB of the batches in which rating adjustments are copied to the database
+    #[arg(long, default_value_t = DEFAULT_COPY_BATCH_SIZE_KB, value_parser = clap::value_parser!(u64).range(1..).map(|kb| kb as usize))]
+    pub copy_batch_size_kb: usize
+}
+
+impl Args {
+    /// The window of match start times selected by `--from-date`, `--to-date` and `--as-of`
+    pub fn date_range(&self) -> DateRange {
+        let to = match (self.to_date, self.as_of) {
+            (Some(to_date), Some(as_of)) => Some(to_date.min(as_of)),
+            (to_date, as_of) => to_date.or(as_of)
+        };
+
+        DateRange::new(self.from_date, to)
+    }
+
+    /// The rulesets selected by `--rulesets`, or the ruleset of the shard
+    pub fn ruleset_filter(&self) -> RulesetFilter {
+        match self.shard {
+            Some(ruleset) => RulesetFilter::new(&[ruleset]),
+            None => RulesetFilter::new(&self.rulesets)
+        }
+    }
+
+    /// The model configuration selected by the arguments
+    pub fn model_config(&self) -> ModelConfig {
+        ModelConfig {
+            min_volatility: self.min_volatility,
+            gamma: self.gamma,
+            lobby_beta: self.lobby_beta,
+            weights: self.weights,
+            game_weights: self.game_weights,
+            decay: self.decay_overrides.iter().map(|o| (o.ruleset, o.parameters)).collect(),
+            decay_volatility_interval_days: self.decay_volatility_interval_days,
+            returning_boost: self.returning_boost,
+            provisional_period: self.provisional_period,
+            decay_until: self.date_range().to,
+            dnf_policy: self.dnf_policy,
+            short_match_policy: self.short_match_policy
+        }
+    }
+
+    /// The handling of score integrity issues selected by the arguments
+    pub fn integrity_policy(&self) -> IntegrityPolicy {
+        IntegrityPolicy {
+            max_score: self.max_score,
+            invalid_score: self.invalid_score_policy,
+            duplicate_player: self.duplicate_player_policy,
+            single_participant: self.single_participant_policy
+        }
+    }
+
+    /// Collects the warnings of the run, enforcing `--fail-on-warning`
+    pub fn warning_sink(&self) -> WarningSink {
+        WarningSink::new(&self.fail_on_warning)
+    }
+
+    /// The post processors selected by `--post-processors`, in order
+    pub fn post_processors(&self) -> Vec<Box<dyn PostProcessor>> {
+        self.post_processors
+            .iter()
+            .map(|kind| kind.build(&self.export_path))
+            .collect()
+    }
+
+    /// The limit on how much a run may change the stored ratings
+    pub fn shift_guard(&self) -> ShiftGuard {
+        ShiftGuard {
+            threshold: self.shift_threshold,
+            max_fraction: self.max_shift_fraction
+        }
+    }
+
+    /// The thresholds used to decide which players are reported as updated
+    pub fn update_thresholds(&self) -> UpdateThresholds {
+        UpdateThresholds {
+            rating: self.updated_rating_threshold,
+            global_rank: self.updated_rank_threshold
+        }
+    }
+
+    /// The slow log selected by `--slow-threshold-ms`
+    pub fn slow_log(&self) -> SlowLog {
+        match self.slow_threshold_ms {
+            0 => SlowLog::default(),
+            ms => SlowLog::new(Duration::from_millis(ms))
+        }
+    }
+
+    /// The memory monitor selected by `--memory-ceiling-mb`
+    pub fn memory_monitor(&self) -> MemoryMonitor {
+        MemoryMonitor::new(self.memory_ceiling_mb)
+    }
+
+    /// A description of every argument which affects processing results, used as part of
+    /// the input hash, see `ResultParameters`
+    pub fn config_fingerprint(&self) -> String {
+        serde_json::to_string(&ResultParameters::from(self)).expect("Result parameters should be serializable")
+    }
+}
+
+/// Parses either a full RFC 3339 timestamp or a plain date, which is interpreted as midnight UTC
+pub fn parse_date(value: &str) -> Result<DateTime<FixedOffset>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp);
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc().fixed_offset())
+        .map_err(|_| format!("'{}' is not a valid date (expected YYYY-MM-DD or RFC 3339)", value))
+}
+
+/// Parses a minimum volatility, which volatilities are clamped to along with the default
+/// volatility as their maximum
+pub fn parse_min_volatility(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(volatility) if (0.0..=DEFAULT_VOLATILITY).contains(&volatility) => Ok(volatility),
+        _ => Err(format!(
+            "'{}' is not a minimum volatility (expected a number from 0 to {})",
+            value, DEFAULT_VOLATILITY
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_date, parse_min_volatility, Args};
+    use crate::model::{
+        constants::{DECAY_DAYS, DECAY_RATE, DEFAULT_VOLATILITY},
+        decay::DecayParameters,
+        structures::ruleset::Ruleset
+    };
+    use chrono::{TimeZone, Utc};
+    use clap::Parser;
+
+    #[test]
+    fn test_parse_plain_date() {
+        let expected = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap().fixed_offset();
+        assert_eq!(parse_date("2024-03-01"), Ok(expected));
+    }
+
+    #[test]
+    fn test_parse_rfc3339() {
+        let expected = Utc.with_ymd_and_hms(2024, 3, 1, 12, 30, 0).unwrap().fixed_offset();
+        assert_eq!(parse_date("2024-03-01T12:30:00Z"), Ok(expected));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse_date("March 1st").is_err());
+    }
+
+    #[test]
+    fn test_parse_min_volatility() {
+        assert_eq!(parse_min_volatility("100"), Ok(100.0));
+        assert_eq!(parse_min_volatility("0"), Ok(0.0));
+        assert_eq!(
+            parse_min_volatility(&DEFAULT_VOLATILITY.to_string()),
+            Ok(DEFAULT_VOLATILITY)
+        );
+
+        assert!(parse_min_volatility("400").is_err());
+        assert!(parse_min_volatility("-1").is_err());
+        assert!(parse_min_volatility("NaN").is_err());
+        assert!(Args::try_parse_from(["otr-processor-cli", "--min-volatility", "400"]).is_err());
+    }
+
+    #[test]
+    fn test_date_range() {
+        let args = Args::parse_from(["otr-processor-cli", "--from-date", "2024-01-01"]);
+        let range = args.date_range();
+
+        assert_eq!(
+            range.from,
+            Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset())
+        );
+        assert_eq!(range.to, None);
+    }
+
+    #[test]
+    fn test_shard_selects_its_ruleset() {
+        let args = Args::parse_from(["otr-processor-cli", "--shard", "taiko"]);
+        let rulesets = args.ruleset_filter();
+
+        assert!(rulesets.contains(Ruleset::Taiko));
+        assert!(!rulesets.contains(Ruleset::Osu));
+        assert!(Args::try_parse_from(["otr-processor-cli", "--shard", "taiko", "--rulesets", "osu"]).is_err());
+    }
+
+    #[test]
+    fn test_decay_sweep_grid() {
+        let args = Args::parse_from(["otr-processor-cli", "--decay-sweep", "sweep.csv"]);
+        assert_eq!(args.sweep_rates, vec![DECAY_RATE]);
+        assert_eq!(args.sweep_inactivity_days, vec![DECAY_DAYS]);
+
+        let args = Args::parse_from([
+            "otr-processor-cli",
+            "--decay-sweep",
+            "sweep.csv",
+            "--sweep-rates",
+            "3,6",
+            "--sweep-inactivity-days",
+            "90,180"
+        ]);
+        assert_eq!(args.sweep_rates, vec![3.0, 6.0]);
+        assert_eq!(args.sweep_inactivity_days, vec![90, 180]);
+    }
+
+    #[test]
+    fn test_prune_requires_confirmation() {
+        let args = Args::parse_from(["otr-processor-cli", "--prune"]);
+        assert!(args.prune && !args.yes);
+        assert!(Args::parse_from(["otr-processor-cli", "--prune", "--yes"]).yes);
+        assert!(Args::try_parse_from(["otr-processor-cli", "--yes"]).is_err());
+    }
+
+    #[test]
+    fn test_as_of_bounds_date_range() {
+        let args = Args::parse_from(["otr-processor-cli", "--to-date", "2024-03-01", "--as-of", "2024-02-01"]);
+        let as_of = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap().fixed_offset();
+
+        assert_eq!(args.date_range().to, Some(as_of));
+        assert_eq!(args.model_config().decay_until, Some(as_of));
+
+        let args = Args::parse_from(["otr-processor-cli", "--as-of", "2024-02-01"]);
+        assert_eq!(args.date_range().to, Some(as_of));
+    }
+
+    #[test]
+    fn test_to_date_bounds_decay() {
+        let args = Args::parse_from(["otr-processor-cli", "--to-date", "2024-02-01"]);
+        let to_date = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap().fixed_offset();
+        assert_eq!(args.model_config().decay_until, Some(to_date));
+
+        let args = Args::parse_from(["otr-processor-cli", "--from-date", "2024-02-01"]);
+        assert_eq!(args.model_config().decay_until, None);
+    }
+
+    #[test]
+    fn test_config_fingerprint_ignores_force() {
+        let args = Args::parse_from(["otr-processor-cli", "--from-date", "2024-01-01"]);
+        let forced = Args::parse_from([
+            "otr-processor-cli",
+            "--from-date",
+            "2024-01-01",
+            "--force",
+            "--report-path",
+            "report.json"
+        ]);
+        let other = Args::parse_from(["otr-processor-cli", "--from-date", "2024-02-01"]);
+        let settled = Args::parse_from(["otr-processor-cli", "--from-date", "2024-01-01", "--settle-tournaments"]);
+        let skipped = Args::parse_from(["otr-processor-cli", "--from-date", "2024-01-01", "--save-skipped"]);
+        let notable = Args::parse_from([
+            "otr-processor-cli",
+            "--from-date",
+            "2024-01-01",
+            "--save-rank-changes",
+            "--notable-ranks",
+            "10,100"
+        ]);
+        // The persisted results of a computing run must match the input hash of a regular run
+        let computed = Args::parse_from([
+            "otr-processor-cli",
+            "--from-date",
+            "2024-01-01",
+            "--output-state",
+            "state.json"
+        ]);
+
+        assert_eq!(args.config_fingerprint(), forced.config_fingerprint());
+        assert_eq!(args.config_fingerprint(), computed.config_fingerprint());
+        assert_ne!(args.config_fingerprint(), other.config_fingerprint());
+        assert_ne!(args.config_fingerprint(), settled.config_fingerprint());
+        assert_ne!(args.config_fingerprint(), skipped.config_fingerprint());
+        assert_ne!(args.config_fingerprint(), notable.config_fingerprint());
+    }
+
+    #[test]
+    fn test_post_processors() {
+        let default = Args::parse_from(["otr-processor-cli"]);
+        let names = default.post_processors().iter().map(|p| p.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["validate", "stats"]);
+
+        let custom = Args::parse_from(["otr-processor-cli", "--post-processors", "export,validate"]);
+        let names = custom.post_processors().iter().map(|p| p.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["export", "validate"]);
+    }
+
+    #[test]
+    fn test_decay_overrides() {
+        let args = Args::parse_from([
+            "otr-processor-cli",
+            "--decay",
+            "catch:240:1.8:900",
+            "--decay",
+            "mania4k:180:1.8:900"
+        ]);
+        let config = args.model_config();
+
+        assert_eq!(config.decay_parameters(Ruleset::Catch).inactivity_days, 240);
+        assert_eq!(config.decay_parameters(Ruleset::Mania4k).inactivity_days, 180);
+        assert_eq!(config.decay_parameters(Ruleset::Osu), DecayParameters::default());
+    }
+}
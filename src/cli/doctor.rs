@@ -0,0 +1,450 @@
+use super::{args::Args, effective_config::CONNECTION_STRING_ENV};
+use crate::database::db::DbClient;
+use std::{
+    collections::{HashMap, HashSet},
+    env, fmt, fs,
+    path::Path
+};
+
+/// Outcome of a single environment check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// The check does not apply to this environment or could not run
+    Skip
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckStatus::Pass => write!(f, "PASS"),
+            CheckStatus::Fail => write!(f, "FAIL"),
+            CheckStatus::Skip => write!(f, "SKIP")
+        }
+    }
+}
+
+/// A single environment check run by `--doctor`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String
+}
+
+impl Check {
+    pub fn pass(name: &str, detail: impl Into<String>) -> Check {
+        Check::new(name, CheckStatus::Pass, detail)
+    }
+
+    pub fn fail(name: &str, detail: impl Into<String>) -> Check {
+        Check::new(name, CheckStatus::Fail, detail)
+    }
+
+    pub fn skip(name: &str, detail: impl Into<String>) -> Check {
+        Check::new(name, CheckStatus::Skip, detail)
+    }
+
+    fn new(name: &str, status: CheckStatus, detail: impl Into<String>) -> Check {
+        Check {
+            name: name.to_string(),
+            status,
+            detail: detail.into()
+        }
+    }
+}
+
+/// A table used by a processing run with the default arguments, along with the columns
+/// and privileges the run needs
+pub struct TableRequirement {
+    pub name: &'static str,
+    pub columns: &'static [&'static str],
+    pub privileges: &'static [&'static str]
+}
+
+pub const REQUIRED_TABLES: &[TableRequirement] = &[
+    TableRequirement {
+        name: "tournaments",
+        columns: &["id", "name", "ruleset", "processing_status"],
+        privileges: &["SELECT", "UPDATE"]
+    },
+    TableRequirement {
+        name: "matches",
+        columns: &[
+            "id",
+            "name",
+            "start_time",
+            "end_time",
+            "tournament_id",
+            "rating_exempt",
+            "processing_status"
+        ],
+        privileges: &["SELECT", "UPDATE"]
+    },
+    TableRequirement {
+        name: "games",
+        columns: &[
+            "id",
+            "ruleset",
+            "start_time",
+            "end_time",
+            "match_id",
+            "verification_status"
+        ],
+        privileges: &["SELECT"]
+    },
+    TableRequirement {
+        name: "game_scores",
+        columns: &[
+            "id",
+            "player_id",
+            "game_id",
+            "score",
+            "placement",
+            "team",
+            "verification_status"
+        ],
+        privileges: &["SELECT"]
+    },
+    TableRequirement {
+        name: "players",
+        columns: &["id", "username", "country"],
+        privileges: &["SELECT"]
+    },
+    TableRequirement {
+        name: "player_osu_ruleset_data",
+        columns: &["player_id", "ruleset", "global_rank", "earliest_global_rank"],
+        privileges: &["SELECT"]
+    },
+    TableRequirement {
+        name: "excluded_players",
+        columns: &["player_id"],
+        privileges: &["SELECT"]
+    },
+    TableRequirement {
+        name: "player_restrictions",
+        columns: &["player_id", "starts_at", "ends_at"],
+        privileges: &["SELECT"]
+    },
+    TableRequirement {
+        name: "manual_adjustments",
+        columns: &["id", "player_id", "ruleset", "timestamp", "kind", "value"],
+        privileges: &["SELECT"]
+    },
+    TableRequirement {
+        name: "player_ratings",
+        columns: &[
+            "id",
+            "player_id",
+            "ruleset",
+            "rating",
+            "volatility",
+            "percentile",
+            "global_rank",
+            "country_rank",
+            "display_rating",
+            "processing_run_id"
+        ],
+        privileges: &["SELECT", "INSERT", "UPDATE", "DELETE", "TRUNCATE"]
+    },
+    TableRequirement {
+        name: "rating_adjustments",
+        columns: &[
+            "player_id",
+            "ruleset",
+            "player_rating_id",
+            "match_id",
+            "rating_before",
+            "rating_after",
+            "volatility_before",
+            "volatility_after",
+            "timestamp",
+            "adjustment_type",
+            "average_opponent_rating",
+            "provisional",
+            "percentile",
+            "clamps",
+            "processing_run_id"
+        ],
+        privileges: &["SELECT", "INSERT", "DELETE", "TRUNCATE"]
+    },
+    TableRequirement {
+        name: "player_highest_ranks",
+        columns: &[
+            "player_id",
+            "ruleset",
+            "global_rank",
+            "global_rank_date",
+            "country_rank",
+            "country_rank_date",
+            "percentile",
+            "percentile_date"
+        ],
+        privileges: &["SELECT", "INSERT", "UPDATE", "DELETE"]
+    },
+    TableRequirement {
+        name: "player_tournament_stats",
+        columns: &["tournament_id"],
+        privileges: &["DELETE", "TRUNCATE"]
+    },
+    TableRequirement {
+        name: "player_activity",
+        columns: &["player_id", "ruleset", "activity"],
+        privileges: &["INSERT", "DELETE", "TRUNCATE"]
+    },
+    TableRequirement {
+        name: "player_lobby_stats",
+        columns: &[
+            "player_id",
+            "ruleset",
+            "games",
+            "average_lobby_size",
+            "average_team_size"
+        ],
+        privileges: &["INSERT", "DELETE", "TRUNCATE"]
+    },
+    TableRequirement {
+        name: "player_percentile_milestones",
+        columns: &["player_id", "ruleset", "top_percent", "reached_at"],
+        privileges: &["INSERT", "UPDATE", "DELETE", "TRUNCATE"]
+    },
+    TableRequirement {
+        name: "processing_quarantine",
+        columns: &["match_id", "reason", "details", "quarantined_at"],
+        privileges: &["INSERT", "DELETE"]
+    },
+    TableRequirement {
+        name: "processor_runs",
+        columns: &[
+            "id",
+            "input_hash",
+            "version",
+            "config",
+            "shard",
+            "started_at",
+            "completed_at"
+        ],
+        privileges: &["SELECT", "INSERT", "UPDATE"]
+    }
+];
+
+/// Checks that every required table and column exists
+///
+/// # Arguments
+/// * `columns` - The columns of each existing table
+pub fn check_schema(columns: &HashMap<String, HashSet<String>>) -> Check {
+    let missing = REQUIRED_TABLES
+        .iter()
+        .flat_map(|table| match columns.get(table.name) {
+            None => vec![format!("table {}", table.name)],
+            Some(existing) => table
+                .columns
+                .iter()
+                .filter(|column| !existing.contains(**column))
+                .map(|column| format!("{}.{}", table.name, column))
+                .collect()
+        })
+        .collect::<Vec<_>>();
+
+    if missing.is_empty() {
+        Check::pass("schema", format!("{} tables", REQUIRED_TABLES.len()))
+    } else {
+        Check::fail("schema", format!("missing {}", missing.join(", ")))
+    }
+}
+
+/// Checks that the connected role holds every required privilege
+///
+/// # Arguments
+/// * `privileges` - The privileges held on each existing table
+pub fn check_privileges(privileges: &HashMap<String, HashSet<String>>) -> Check {
+    let missing = REQUIRED_TABLES
+        .iter()
+        .flat_map(|table| {
+            table
+                .privileges
+                .iter()
+                .filter(|privilege| {
+                    privileges
+                        .get(table.name)
+                        .is_none_or(|held| !held.contains(**privilege))
+                })
+                .map(|privilege| format!("{} on {}", privilege, table.name))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    if missing.is_empty() {
+        Check::pass("privileges", "all granted")
+    } else {
+        Check::fail("privileges", format!("missing {}", missing.join(", ")))
+    }
+}
+
+/// Checks that files can be written next to `path`, by writing and removing a probe file
+pub fn check_export_path(path: &Path) -> Check {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new(".")
+    };
+    let probe = directory.join(".otr-processor-doctor");
+
+    match fs::write(&probe, b"").and_then(|_| fs::remove_file(&probe)) {
+        Ok(()) => Check::pass("export path", format!("{} is writable", directory.display())),
+        Err(e) => Check::fail("export path", format!("{}: {}", directory.display(), e))
+    }
+}
+
+/// Formats the checks as an aligned pass/fail table
+pub fn format_table(checks: &[Check]) -> String {
+    let width = checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+
+    checks
+        .iter()
+        .map(|c| format!("{:<4}  {:<width$}  {}", c.status, c.name, c.detail, width = width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Whether none of the checks failed
+pub fn all_passed(checks: &[Check]) -> bool {
+    checks.iter().all(|c| c.status != CheckStatus::Fail)
+}
+
+/// Checks the environment of the processor and prints a pass/fail table
+///
+/// # Returns
+/// Whether every check passed
+pub async fn run(args: &Args) -> bool {
+    // The variables may also be set without a .env file
+    dotenv::dotenv().ok();
+
+    let mut checks = Vec::new();
+    let client = match env::var(CONNECTION_STRING_ENV) {
+        Err(_) => {
+            checks.push(Check::fail("connection string", "CONNECTION_STRING is not set"));
+            None
+        }
+        Ok(connection_string) => {
+            checks.push(Check::pass("connection string", "set"));
+            match DbClient::connect(&connection_string).await {
+                Ok(client) => {
+                    checks.push(Check::pass("connection", "connected"));
+                    Some(client)
+                }
+                Err(e) => {
+                    checks.push(Check::fail("connection", e.to_string()));
+                    None
+                }
+            }
+        }
+    };
+
+    match client {
+        Some(client) => {
+            let tables = REQUIRED_TABLES.iter().map(|t| t.name).collect::<Vec<_>>();
+            checks.push(match client.get_table_columns(&tables).await {
+                Ok(columns) => check_schema(&columns),
+                Err(e) => Check::fail("schema", e.to_string())
+            });
+            checks.push(match client.get_table_privileges(&tables).await {
+                Ok(privileges) => check_privileges(&privileges),
+                Err(e) => Check::fail("privileges", e.to_string())
+            });
+        }
+        None => {
+            checks.push(Check::skip("schema", "not connected"));
+            checks.push(Check::skip("privileges", "not connected"));
+        }
+    }
+
+    checks.push(Check::skip(
+        "message broker",
+        "the processor does not use a message broker"
+    ));
+    checks.push(check_export_path(&args.export_path));
+    if let Some(path) = &args.report_path {
+        checks.push(Check {
+            name: "report path".to_string(),
+            ..check_export_path(path)
+        });
+    }
+
+    println!("{}", format_table(&checks));
+    all_passed(&checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        all_passed, check_export_path, check_privileges, check_schema, format_table, Check, CheckStatus,
+        REQUIRED_TABLES
+    };
+    use std::{
+        collections::{HashMap, HashSet},
+        env
+    };
+
+    fn complete<F>(values: F) -> HashMap<String, HashSet<String>>
+    where
+        F: Fn(&super::TableRequirement) -> &'static [&'static str]
+    {
+        REQUIRED_TABLES
+            .iter()
+            .map(|table| {
+                (
+                    table.name.to_string(),
+                    values(table).iter().map(|v| v.to_string()).collect()
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_schema() {
+        let mut columns = complete(|table| table.columns);
+        assert!(all_passed(&[check_schema(&columns)]));
+
+        columns.remove("processor_runs");
+        columns.get_mut("matches").unwrap().remove("rating_exempt");
+        let check = check_schema(&columns);
+
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert_eq!(check.detail, "missing matches.rating_exempt, table processor_runs");
+    }
+
+    #[test]
+    fn test_privileges() {
+        let mut privileges = complete(|table| table.privileges);
+        assert!(all_passed(&[check_privileges(&privileges)]));
+
+        privileges.get_mut("player_ratings").unwrap().remove("TRUNCATE");
+        let check = check_privileges(&privileges);
+
+        assert_eq!(check.detail, "missing TRUNCATE on player_ratings");
+    }
+
+    #[test]
+    fn test_export_path() {
+        let writable = env::temp_dir().join("ratings.json");
+        assert!(all_passed(&[check_export_path(&writable)]));
+
+        let missing = env::temp_dir().join("otr-processor-missing-directory/ratings.json");
+        assert!(!all_passed(&[check_export_path(&missing)]));
+    }
+
+    #[test]
+    fn test_format_table() {
+        let checks = vec![
+            Check::pass("connection", "connected"),
+            Check::skip("message broker", "not used"),
+        ];
+
+        assert_eq!(
+            format_table(&checks),
+            "PASS  connection      connected\nSKIP  message broker  not used"
+        );
+        assert!(all_passed(&checks));
+    }
+}
@@ -0,0 +1,102 @@
+use super::{args::Args, run::write_error};
+use crate::{
+    database::{
+        db::{DbClient, MatchSelection},
+        result_store::ResultStore
+    },
+    error::{Cause, ProcessorError, Stage, StageContext},
+    model::{
+        bootstrap::create_country_mapping,
+        countries::{normalize_country, NormalizedCountry},
+        mania_migration::plan_mania_migration,
+        rating_tracker::RatingTracker,
+        structures::{date_range::DateRange, ruleset_filter::RulesetFilter}
+    }
+};
+use std::fs;
+
+/// Moves legacy ManiaOther ratings into Mania4k or Mania7k and outputs the mapping in place
+/// of the run report
+///
+/// The key counts are taken from every verified match, as most matches were processed before.
+pub(crate) async fn migrate_mania_other(client: &DbClient, args: &Args) -> Result<(), ProcessorError> {
+    let (matches, _) = client
+        .get_matches(
+            &DateRange::default(),
+            &RulesetFilter::default(),
+            MatchSelection::All,
+            args.missing_start_time
+        )
+        .await
+        .stage(Stage::Fetch)?;
+    let ratings = client.get_player_ratings().await.stage(Stage::Fetch)?;
+
+    let mut migration = plan_mania_migration(&ratings, &matches);
+    client.migrate_mania_other(&mut migration).await.stage(Stage::Save)?;
+
+    println!(
+        "ManiaOther migration: {} assigned, {} conflicting, {} unresolved",
+        migration.assignments.len(),
+        migration.conflicts.len(),
+        migration.unresolved.len()
+    );
+
+    let json = serde_json::to_string_pretty(&migration).expect("Migration should be serializable");
+    match &args.report_path {
+        Some(path) => fs::write(path, json).map_err(|source| write_error(path, source))?,
+        None => println!("{}", json)
+    }
+
+    Ok(())
+}
+
+/// Recomputes and saves the country ranks of `country` from the stored ratings
+pub(crate) async fn recompute_country_ranks(client: &DbClient, country: &str) -> Result<(), ProcessorError> {
+    let country = match normalize_country(Some(country)) {
+        NormalizedCountry::Valid(country) => country,
+        NormalizedCountry::Unknown | NormalizedCountry::Invalid => {
+            return Err(ProcessorError::new(
+                Stage::Fetch,
+                Cause::InvalidCountry(country.to_string())
+            ));
+        }
+    };
+
+    let players = client.get_players().await.stage(Stage::Fetch)?;
+    let ratings = client.get_player_ratings().await.stage(Stage::Fetch)?;
+
+    let mut tracker = RatingTracker::new();
+    tracker.set_country_mapping(create_country_mapping(&players));
+    tracker.insert_or_update(&ratings);
+    let updated = tracker.update_country_ranks(&country);
+    client.save_country_ranks(&updated).await.stage(Stage::Save)?;
+
+    println!(
+        "Recomputed the country ranks of {} ratings in {}",
+        updated.len(),
+        country
+    );
+
+    Ok(())
+}
+
+/// Counts the orphaned rows of every table, deleting them with --yes
+pub(crate) async fn prune(client: &DbClient, args: &Args) -> Result<(), ProcessorError> {
+    let before = client.count_orphans().await.stage(Stage::Fetch)?;
+    for (table, count) in &before {
+        println!("{}: {} orphaned rows", table, count);
+    }
+
+    if !args.yes {
+        println!("Nothing was deleted, run again with --yes to delete the orphaned rows");
+        return Ok(());
+    }
+
+    client.prune_orphans().await.stage(Stage::Save)?;
+    let after = client.count_orphans().await.stage(Stage::Fetch)?;
+    for ((table, before), (_, after)) in before.iter().zip(&after) {
+        println!("{}: {} orphaned rows before, {} after", table, before, after);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,10 @@
+pub mod args;
+pub mod doctor;
+pub mod effective_config;
+pub mod maintenance;
+pub mod progressive;
+pub mod run;
+pub mod shards;
+pub mod simulation;
+pub mod state;
+pub mod stop;
@@ -0,0 +1,202 @@
+use super::{args::Args, stop::Stop};
+use crate::{
+    database::{
+        db::DbClient,
+        db_structs::{Match, PlayerRating},
+        result_store::ResultStore,
+        sqlite::SqliteStore
+    },
+    error::{Cause, ProcessorError, Stage, StageContext},
+    model::{
+        leaderboard_checks::check_leaderboards,
+        observer::ProcessingObserver,
+        otr_model::OtrModel,
+        processing_result::ProcessingResult,
+        rank_history::{highest_ranks, record_adjustment_percentiles},
+        structures::{date_range::DateRange, ruleset::Ruleset, ruleset_filter::RulesetFilter}
+    },
+    utils::tournament_settlement::TournamentSettlements
+};
+use std::{collections::HashMap, thread};
+use strum::IntoEnumIterator;
+use tokio::{runtime::Handle, task};
+
+/// Where `process_progressively` saves the results
+pub(crate) enum ProgressiveStore<'a> {
+    /// A connection of its own, whose transaction holds the saves
+    Postgres(DbClient),
+    Sqlite(&'a SqliteStore)
+}
+
+/// Everything needed to check and save the results of a single ruleset, see
+/// `process_progressively`
+pub(crate) struct ProgressiveSave<'a> {
+    pub(crate) args: &'a Args,
+    pub(crate) date_range: &'a DateRange,
+    pub(crate) country_mapping: &'a HashMap<i32, String>,
+    pub(crate) previous_ratings: &'a [PlayerRating],
+    pub(crate) settlements: Option<&'a TournamentSettlements>,
+    pub(crate) run_id: i32
+}
+
+impl ProgressiveSave<'_> {
+    /// Checks the ratings of `ruleset` like all results are checked before a regular save,
+    /// stopping the run if the rating shift guard refuses them
+    fn check(&self, ruleset: Ruleset, ratings: &mut [PlayerRating]) -> Result<(), ProcessorError> {
+        check_leaderboards(ratings, self.country_mapping).stage(Stage::Model)?;
+        if self.args.adjustment_percentiles {
+            record_adjustment_percentiles(ratings);
+        }
+
+        let shift = self.args.shift_guard().measure(self.previous_ratings, ratings);
+        if shift.exceeds_limit && !self.args.allow_large_shift {
+            let refusal = format!(
+                "{} of {} stored {:?} ratings ({:.1}%) would change by at least {}, exceeding the limit of \
+                {:.1}%. Nothing was saved (use --allow-large-shift to override)",
+                shift.ratings_shifted,
+                shift.ratings_compared,
+                ruleset,
+                shift.fraction_shifted * 100.0,
+                self.args.shift_threshold,
+                self.args.max_shift_fraction * 100.0
+            );
+            return Err(ProcessorError::new(Stage::Model, Stop::Refused(refusal)));
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the stored results of `ruleset` in `store` with `ratings`
+    async fn save(
+        &self,
+        store: &dyn ResultStore,
+        ruleset: Ruleset,
+        ratings: &[PlayerRating]
+    ) -> Result<(), ProcessorError> {
+        let rulesets = RulesetFilter::new(&[ruleset]);
+        let highest_ranks = highest_ranks(ratings, self.country_mapping);
+        let compress_decay = self.args.compress_decay_adjustments;
+
+        if self.date_range.is_unbounded() {
+            store
+                .save_results(
+                    ratings,
+                    &highest_ranks,
+                    &rulesets,
+                    compress_decay,
+                    self.settlements,
+                    self.run_id
+                )
+                .await
+        } else {
+            store
+                .save_results_in_range(
+                    ratings,
+                    &highest_ranks,
+                    self.date_range,
+                    &rulesets,
+                    compress_decay,
+                    self.settlements,
+                    self.run_id
+                )
+                .await
+        }
+        .stage(Stage::Save)?;
+
+        println!("Saved the results of {:?}", ruleset);
+        Ok(())
+    }
+}
+
+/// Processes the matches ruleset by ruleset, saving the results of each ruleset while the next
+/// one is processed
+///
+/// The saves share a single transaction, committed once every ruleset is saved. If a ruleset
+/// fails its checks or an error occurs, the rulesets saved before it are discarded.
+///
+/// # Returns
+/// The ratings of every ruleset along with everything skipped, like a regular run
+pub(crate) async fn process_progressively(
+    model: &mut OtrModel,
+    matches: &[Match],
+    rulesets: &RulesetFilter,
+    observer: &mut (impl ProcessingObserver + Send),
+    target: ProgressiveStore<'_>,
+    save: &ProgressiveSave<'_>
+) -> Result<ProcessingResult, ProcessorError> {
+    match target {
+        ProgressiveStore::Postgres(mut connection) => {
+            let transaction = connection.transaction().await.stage(Stage::Save)?;
+            let result = process_and_save(model, matches, rulesets, observer, &transaction.client(), save).await;
+            // Dropping the transaction instead rolls it back
+            if result.is_ok() {
+                transaction.commit().await.stage(Stage::Save)?;
+            }
+            result
+        }
+        ProgressiveStore::Sqlite(store) => {
+            store.begin_saves().stage(Stage::Save)?;
+            let result = process_and_save(model, matches, rulesets, observer, store, save).await;
+            store.end_saves(result.is_ok()).stage(Stage::Save)?;
+            result
+        }
+    }
+}
+
+/// Processes and saves the rulesets for `process_progressively`
+///
+/// # Returns
+/// The ratings of every ruleset along with everything skipped
+async fn process_and_save(
+    model: &mut OtrModel,
+    matches: &[Match],
+    rulesets: &RulesetFilter,
+    observer: &mut (impl ProcessingObserver + Send),
+    store: &dyn ResultStore,
+    save: &ProgressiveSave<'_>
+) -> Result<ProcessingResult, ProcessorError> {
+    let mut result = ProcessingResult::default();
+    let mut pending: Option<(Ruleset, Vec<PlayerRating>)> = None;
+
+    // Every selected ruleset is saved, even without ratings, replacing its stored results like
+    // a regular save does. The last iteration only saves the last ruleset.
+    for ruleset in Ruleset::iter()
+        .filter(|r| rulesets.contains(*r))
+        .map(Some)
+        .chain([None])
+    {
+        let (model, observer) = (&mut *model, &mut *observer);
+
+        // The model runs on its own thread while this one waits for the previous ruleset to save
+        let (processed, saved) = task::block_in_place(|| {
+            thread::scope(|scope| {
+                let processing =
+                    ruleset.map(|ruleset| scope.spawn(move || model.process_ruleset(ruleset, matches, observer)));
+                let saved = match &pending {
+                    Some((ruleset, ratings)) => Handle::current().block_on(save.save(store, *ruleset, ratings)),
+                    None => Ok(())
+                };
+
+                let processed = processing.map(|p| {
+                    p.join()
+                        .map_err(|panic| ProcessorError::new(Stage::Model, Cause::panic(panic)))
+                });
+                (processed, saved)
+            })
+        });
+        saved?;
+        let processed = processed.transpose()?.transpose().stage(Stage::Model)?;
+        if let Some((_, ratings)) = pending.take() {
+            result.ratings.extend(ratings);
+        }
+
+        if let (Some(ruleset), Some(mut processed)) = (ruleset, processed) {
+            save.check(ruleset, &mut processed.ratings)?;
+            result.matches_processed += processed.matches_processed;
+            result.skipped.merge(processed.skipped);
+            pending = Some((ruleset, processed.ratings));
+        }
+    }
+
+    Ok(result)
+}
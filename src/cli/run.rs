@@ -0,0 +1,805 @@
+use super::{
+    args::Args,
+    effective_config::{EffectiveConfig, CONNECTION_STRING_ENV},
+    maintenance::{migrate_mania_other, prune, recompute_country_ranks},
+    progressive::{process_progressively, ProgressiveSave, ProgressiveStore},
+    shards::coordinate_shards,
+    simulation::{decay_sweep, simulate},
+    state::persist_state,
+    stop::Stop
+};
+use crate::{
+    database::{
+        db::{shard_lock_key, DbClient, MatchSelection, PROCESSOR_LOCK_KEY},
+        result_store::{ResultStore, RunRecord},
+        sqlite::SqliteStore
+    },
+    dto::RatingHistoryDto,
+    error::{Cause, ProcessorError, Stage, StageContext},
+    model::{
+        activity::classify_activity,
+        bootstrap::bootstrap,
+        display_ratings::display_ratings,
+        exclusions::exclude_players,
+        leaderboard_checks::check_leaderboards,
+        mod_detection::classify_games,
+        otr_model::OtrModel,
+        overall_ratings::overall_ratings,
+        placements::{apply_dnf_policy, calculate_placements},
+        processing_result::ProcessingResult,
+        rank_history::{highest_ranks, percentile_milestones, record_adjustment_percentiles},
+        restrictions::Restrictions,
+        score_integrity::check_score_integrity,
+        stats_accumulator::StatsAccumulator,
+        upsets::UpsetTracker
+    },
+    post_process::post_processor::{run_post_processors, PostProcessContext},
+    report::{
+        continuity::{check_continuity, sample_players},
+        memory::{MemoryCeilingExceeded, MemoryMonitor},
+        rank_changes::rank_changes,
+        run_report::{ActivityCounts, ClampCounts, CountrySize, DecaySummary, RunReport},
+        slow_log::SlowLog,
+        updated_players::find_updated_players
+    },
+    utils::{
+        input_hash::compute_input_hash, processor_state::ProcessorState, tournament_settlement::TournamentSettlements
+    }
+};
+use clap::ArgMatches;
+use std::{env, io, path::Path, process, sync::Arc, time::Duration};
+
+/// Runs the mode selected by `args` while holding the processor lock, or the lock of its
+/// shard. Runs stopped on purpose (e.g. by a guard or a held lock) return a `Stop`.
+pub async fn run(arg_matches: &ArgMatches, args: &Args) -> Result<(), ProcessorError> {
+    let date_range = args.date_range();
+    let rulesets = args.ruleset_filter();
+    let slow_log = Arc::new(args.slow_log());
+
+    // The variables may also be set without a .env file
+    dotenv::dotenv().ok();
+    let config = EffectiveConfig::new(arg_matches, args, |name| env::var(name).ok());
+    println!("Effective configuration: {}", config.to_json());
+
+    let client: DbClient = client()
+        .await?
+        .with_slow_log(slow_log.clone())
+        .with_copy_batch_size(args.copy_batch_size_kb);
+    let sqlite_store = args
+        .sqlite_store
+        .as_deref()
+        .map(SqliteStore::open)
+        .transpose()
+        .stage(Stage::Fetch)?;
+    let store: &dyn ResultStore = match &sqlite_store {
+        Some(sqlite_store) => sqlite_store,
+        None => &client
+    };
+
+    if let Some(shards) = &args.coordinate_shards {
+        return coordinate_shards(&client, args, shards).await;
+    }
+
+    // Simulations and decay sweeps only read stored ratings, everything else must not run
+    // concurrently except for the shards of different rulesets
+    if let Some(ruleset) = args.shard {
+        if args.wait_for_lock {
+            println!("Waiting for the {:?} shard lock...", ruleset);
+        }
+        if !client
+            .acquire_shard_lock(ruleset, args.wait_for_lock)
+            .await
+            .stage(Stage::Fetch)?
+        {
+            let lock = format!(
+                "processor lock or the {:?} shard lock (advisory lock {})",
+                ruleset,
+                shard_lock_key(ruleset)
+            );
+            return Err(ProcessorError::new(Stage::Fetch, Stop::Locked(lock)));
+        }
+    } else if args.simulate.is_none() && args.decay_sweep.is_none() {
+        if args.wait_for_lock {
+            println!("Waiting for the processor lock...");
+        }
+        if !client
+            .acquire_processor_lock(args.wait_for_lock)
+            .await
+            .stage(Stage::Fetch)?
+        {
+            let lock = format!("processor lock (advisory lock {})", PROCESSOR_LOCK_KEY);
+            return Err(ProcessorError::new(Stage::Fetch, Stop::Locked(lock)));
+        }
+    }
+
+    let result = if args.migrate_mania_other {
+        migrate_mania_other(&client, args).await
+    } else if let Some(country) = &args.country_ranks {
+        recompute_country_ranks(&client, country).await
+    } else if args.prune {
+        prune(&client, args).await
+    } else if let Some(path) = &args.input_state {
+        persist_state(store, &client, args, path).await
+    } else if let Some(path) = &args.simulate {
+        simulate(store, args, path).await
+    } else if let Some(path) = &args.decay_sweep {
+        decay_sweep(store, args, path).await
+    } else {
+        process(args, config, &client, sqlite_store.as_ref(), &slow_log).await
+    };
+
+    // Closing the connection releases the locks too, but the process may outlive the run
+    let released = client.release_locks().await.stage(Stage::Save);
+    result.and(released)
+}
+
+/// Processes the matches selected by `args` and saves the results to the SQLite store if
+/// given, or to Postgres otherwise
+async fn process(
+    args: &Args,
+    config: EffectiveConfig,
+    client: &DbClient,
+    sqlite_store: Option<&SqliteStore>,
+    slow_log: &Arc<SlowLog>
+) -> Result<(), ProcessorError> {
+    let date_range = args.date_range();
+    let rulesets = args.ruleset_filter();
+    let store: &dyn ResultStore = match sqlite_store {
+        Some(sqlite_store) => sqlite_store,
+        None => client
+    };
+
+    let memory = Arc::new(args.memory_monitor());
+    memory.spawn_sampler(
+        Duration::from_millis(args.memory_sample_interval_ms),
+        exit_memory_ceiling
+    );
+
+    // 1. Fetch matches, players and moderation inputs for processing. Processed matches are
+    //    processed again, so they are fetched along with the matches awaiting processing.
+    //    The queries are independent, so they are sent together and pipelined on the
+    //    connection instead of waiting on each other.
+    let (fetched_matches, players, excluded_players, manual_adjustments, restrictions) = tokio::join!(
+        client.get_matches(
+            &date_range,
+            &rulesets,
+            MatchSelection::AwaitingOrProcessed,
+            args.missing_start_time
+        ),
+        client.get_players(),
+        client.get_excluded_players(),
+        client.get_manual_adjustments(&date_range),
+        client.get_player_restrictions()
+    );
+    let (mut matches, start_times) = fetched_matches.stage(Stage::Fetch)?;
+    let mut players = players.stage(Stage::Fetch)?;
+    let excluded_players = excluded_players.stage(Stage::Fetch)?;
+    let mut manual_adjustments = manual_adjustments.stage(Stage::Fetch)?;
+    manual_adjustments.retain(|manual| rulesets.contains(manual.ruleset));
+    let restrictions = restrictions.stage(Stage::Fetch)?;
+    if !start_times.skipped.is_empty() || !start_times.imputed.is_empty() {
+        println!(
+            "Matches without a start time: {} skipped {:?}, {} imputed from games {:?}",
+            start_times.skipped.len(),
+            start_times.skipped,
+            start_times.imputed.len(),
+            start_times.imputed
+        );
+    }
+
+    // Without matches there is nothing to rate, so nothing is truncated or saved
+    if matches.is_empty() {
+        let report = RunReport {
+            config: Some(config),
+            skipped_without_start_time: start_times.skipped,
+            slow_operations: slow_log.operations(),
+            ..Default::default()
+        };
+        output_report(args, &report)?;
+        save_shard_report(client, args, &report).await?;
+        return Err(ProcessorError::new(Stage::Fetch, Stop::NothingToProcess));
+    }
+
+    // Scores which cannot be right would skew the placements of their whole game
+    let integrity_issues = check_score_integrity(&mut matches, &args.integrity_policy()).stage(Stage::Model)?;
+    if !integrity_issues.is_empty() {
+        println!(
+            "Handled {} scores failing integrity checks as set by the integrity policies",
+            integrity_issues.len()
+        );
+    }
+
+    if !args.placements_in_db {
+        let timer = slow_log.stage("calculate_placements");
+        calculate_placements(&mut matches);
+        timer.finish(matches.len());
+        check_memory(&memory, "calculate_placements")?;
+    }
+    let dnf_scores = apply_dnf_policy(&mut matches, args.dnf_policy);
+    if dnf_scores > 0 {
+        println!(
+            "Placed {} scores of 0 with the {} DNF policy",
+            dnf_scores, args.dnf_policy
+        );
+    }
+
+    // Excluded players are dropped from every game and never rated
+    let excluded_scores = exclude_players(&mut matches, &excluded_players);
+    if excluded_scores > 0 {
+        println!(
+            "Removed {} scores of {} excluded players",
+            excluded_scores,
+            excluded_players.len()
+        );
+    }
+
+    players.retain(|player| !excluded_players.contains(&player.id));
+
+    // Skip processing entirely if nothing changed since the last successful run, leaving the
+    // processing statuses as they are. Note that the final decay pass is time-dependent, so a
+    // skipped run also defers any decay which would have occurred since the last run.
+    let input_hash = compute_input_hash(
+        &matches,
+        &players,
+        &manual_adjustments,
+        &restrictions,
+        &args.config_fingerprint()
+    );
+    let last_input_hash = store.get_last_input_hash(args.shard).await.stage(Stage::Fetch)?;
+    if !args.force && last_input_hash.as_deref() == Some(input_hash.as_str()) {
+        println!("Input data unchanged since the last successful run, skipping processing (use --force to override)");
+
+        let report = RunReport {
+            config: Some(config),
+            ..Default::default()
+        };
+        return save_shard_report(client, args, &report).await;
+    }
+
+    // 2. Rollback processing statuses of the matches & tournaments about to be processed
+    client
+        .rollback_processing_statuses(&date_range, &rulesets)
+        .await
+        .stage(Stage::Fetch)?;
+
+    // 3. Generate initial ratings and country mapping, seeding from stored history when processing from a date
+    let mut seeded_ratings = match date_range.from {
+        Some(from_date) => store.get_ratings_as_of(from_date).await.stage(Stage::Fetch)?,
+        None => Vec::new()
+    };
+    seeded_ratings.retain(|rating| !excluded_players.contains(&rating.player_id) && rulesets.contains(rating.ruleset));
+
+    let timer = slow_log.stage("bootstrap");
+    let bootstrap =
+        bootstrap(&players, &matches, seeded_ratings, args.fallback_rating, args.strict).stage(Stage::Model)?;
+    timer.finish(bootstrap.initial_ratings.len());
+    check_memory(&memory, "bootstrap")?;
+
+    if !bootstrap.issues.is_empty() {
+        println!(
+            "Input data is incomplete and will be rated around (use --strict to fail instead): {}",
+            bootstrap.issues
+        );
+    }
+
+    let mut warnings = args.warning_sink();
+    let mut upsets = UpsetTracker::default();
+    warnings.warn_bootstrap(&bootstrap);
+
+    // 4. Create the model
+    let mut model = OtrModel::with_config(
+        &bootstrap.initial_ratings,
+        &bootstrap.country_mapping,
+        args.model_config()
+    );
+    model.stats = StatsAccumulator::new(bootstrap.fallback_ratings());
+    model.manual_adjustments = manual_adjustments;
+    model.restrictions = Restrictions::new(&restrictions);
+
+    // Compare against the stored ratings before they are overwritten
+    let previous_ratings = store.get_player_ratings().await.stage(Stage::Fetch)?;
+    let settlements = args
+        .settle_tournaments
+        .then(|| TournamentSettlements::from_matches(&matches));
+
+    // 5. Process matches. Progressive saves start saving during processing, so the run is
+    //    checked for a halt and started beforehand.
+    let mut run_id = None;
+    let timer = slow_log.stage("process");
+    let ProcessingResult {
+        ratings: mut results,
+        matches_processed,
+        skipped
+    } = if args.progressive_save {
+        if let Some(reason) = client.get_halt().await.stage(Stage::Fetch)? {
+            return Err(ProcessorError::new(Stage::Fetch, Stop::Halted(reason)));
+        }
+        memory.stop_enforcing();
+        let id = store
+            .start_run(&RunRecord {
+                input_hash: input_hash.clone(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                config: config.to_json(),
+                shard: args.shard
+            })
+            .await
+            .stage(Stage::Save)?;
+        run_id = Some(id);
+
+        // Saves run on their own connection, which holds nothing but their transaction
+        let target = match sqlite_store {
+            Some(sqlite_store) => ProgressiveStore::Sqlite(sqlite_store),
+            None => ProgressiveStore::Postgres(
+                self::client()
+                    .await?
+                    .with_slow_log(slow_log.clone())
+                    .with_copy_batch_size(args.copy_batch_size_kb)
+            )
+        };
+        let save = ProgressiveSave {
+            args,
+            date_range: &date_range,
+            country_mapping: &bootstrap.country_mapping,
+            previous_ratings: &previous_ratings,
+            settlements: settlements.as_ref(),
+            run_id: id
+        };
+        process_progressively(
+            &mut model,
+            &matches,
+            &rulesets,
+            &mut (&mut warnings, &mut upsets),
+            target,
+            &save
+        )
+        .await?
+    } else {
+        model
+            .process_with_observer(&matches, &mut (&mut warnings, &mut upsets))
+            .stage(Stage::Model)?
+    };
+    warnings.warn_unknown_countries(&model.rating_tracker);
+    timer.finish(matches.len());
+    check_memory(&memory, "process")?;
+    if !skipped.is_empty() {
+        println!(
+            "Skipped {} matches without games {:?}, {} matches with invalid placements {:?}, {} matches with too \
+            few games {:?}, {} games without scores {:?} and {} manual adjustments without a rating {:?}",
+            skipped.matches_without_games.len(),
+            skipped.matches_without_games,
+            skipped.matches_with_invalid_placements.len(),
+            skipped.matches_with_invalid_placements,
+            skipped.matches_with_too_few_games.len(),
+            skipped.matches_with_too_few_games,
+            skipped.games_without_scores.len(),
+            skipped.games_without_scores,
+            skipped.manual_adjustments_without_rating.len(),
+            skipped.manual_adjustments_without_rating
+        );
+    }
+
+    // Inconsistent ranks are a processing bug, never save them
+    check_leaderboards(&results, &bootstrap.country_mapping).stage(Stage::Model)?;
+
+    // Progressive saves recorded them ruleset by ruleset
+    if args.adjustment_percentiles && !args.progressive_save {
+        let timer = slow_log.stage("adjustment_percentiles");
+        record_adjustment_percentiles(&mut results);
+        timer.finish(results.len());
+    }
+
+    let updated_players = find_updated_players(&previous_ratings, &results, &args.update_thresholds());
+    let rating_shift = args.shift_guard().measure(&previous_ratings, &results);
+    let rank_changes = rank_changes(&previous_ratings, &results, &args.notable_ranks);
+
+    // Settled tournaments are not stored as match adjustments, so their history cannot be compared
+    let continuity = match args.verify_continuity {
+        Some(_) if args.settle_tournaments => {
+            println!("Continuity cannot be verified when tournaments are settled, skipping the check");
+            None
+        }
+        Some(size) => {
+            let sample = sample_players(&results, size);
+            let stored = store.get_rating_adjustments(&sample).await.stage(Stage::Fetch)?;
+            Some(check_continuity(&stored, &results))
+        }
+        None => None
+    };
+
+    let activity = classify_activity(&results, &model.config, &model.restrictions);
+    let mut report = RunReport {
+        config: Some(config),
+        warnings: warnings.counts(),
+        activity: ActivityCounts::from_activities(&activity),
+        decay: DecaySummary::from_ratings(&results, &model.config, &date_range),
+        clamps: ClampCounts::from_ratings(&results, &date_range),
+        matches_processed,
+        rating_exempt_matches: matches.iter().filter(|m| m.rating_exempt).count(),
+        skipped_without_start_time: start_times.skipped,
+        imputed_start_time: start_times.imputed,
+        integrity_issues,
+        excluded_scores,
+        missing_players: bootstrap.issues.missing_players.clone(),
+        unknown_country_players: model.rating_tracker.unknown_country_players().len(),
+        invalid_countries: bootstrap.issues.invalid_countries.clone(),
+        countries: CountrySize::from_tracker(&model.rating_tracker, args.min_country_size),
+        updated_players,
+        rank_changes,
+        rating_shift: Some(rating_shift),
+        continuity,
+        skipped,
+        ..Default::default()
+    };
+
+    for decay in &report.decay {
+        println!(
+            "Decay in {:?}: {} players lost {:.1} rating, {} reached their decay floor and {} the volatility cap",
+            decay.ruleset, decay.players_decayed, decay.rating_lost, decay.reached_floor, decay.volatility_capped
+        );
+    }
+
+    let violations = warnings.violations();
+    if !violations.is_empty() {
+        let refusal = format!(
+            "Raised warnings selected by --fail-on-warning, nothing was saved: {}",
+            violations
+                .iter()
+                .map(|v| format!("{} {}", v.count, v.kind))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        report.slow_operations = slow_log.operations();
+        output_report(args, &report)?;
+        return Err(ProcessorError::new(Stage::Model, Stop::Refused(refusal)));
+    }
+
+    // Already rated matches must keep their adjustments, otherwise their inputs were edited
+    if let Some(check) = report.continuity.as_ref().filter(|c| !c.is_continuous()) {
+        let refusal = format!(
+            "The stored history of {} of {} verified ratings is no longer reproduced, nothing was saved. \
+            First divergences (player, ruleset, match): {}",
+            check.divergences.len(),
+            check.chains_compared,
+            check
+                .divergences
+                .iter()
+                .take(10)
+                .map(|d| format!("({}, {:?}, {})", d.player_id, d.ruleset, d.match_id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        report.slow_operations = slow_log.operations();
+        output_report(args, &report)?;
+        return Err(ProcessorError::new(Stage::Model, Stop::Refused(refusal)));
+    }
+
+    // 6. Run post processors, aborting before anything is saved if one fails
+    let mut context = PostProcessContext {
+        ratings: &results,
+        model: &model,
+        report: &mut report
+    };
+    let timer = slow_log.stage("post_process");
+    run_post_processors(&args.post_processors(), &mut context).stage(Stage::Publish)?;
+    timer.finish(results.len());
+    check_memory(&memory, "post_process")?;
+
+    // Historical ratings must never replace the stored ones, they are only exported
+    if let Some(as_of) = args.as_of {
+        println!(
+            "Computed ratings as of {}, nothing was saved (use the export post processor to write them out)",
+            as_of
+        );
+
+        // Restore the processing statuses reverted by the rollback
+        client
+            .roll_forward_processing_statuses(&matches)
+            .await
+            .stage(Stage::Save)?;
+
+        report.slow_operations = slow_log.operations();
+        report.memory = Some(memory.usage()).filter(|usage| !usage.stages.is_empty());
+        return output_report(args, &report);
+    }
+
+    // Computed results are saved by a later run with --input-state, which then marks the
+    // matches as processed. Until then they stay awaiting processing.
+    if let Some(path) = &args.output_state {
+        let state = ProcessorState {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            input_hash,
+            config: report.config.as_ref().map(EffectiveConfig::to_json).unwrap_or_default(),
+            date_range,
+            rulesets,
+            country_mapping: bootstrap.country_mapping,
+            settlements,
+            match_ids: matches.iter().map(|m| m.id).collect(),
+            ratings: results.iter().map(RatingHistoryDto::from).collect()
+        };
+        state.write(path).map_err(|source| write_error(path, source))?;
+        println!(
+            "Wrote the results of {} matches to {}, nothing was saved (use --input-state to save them)",
+            matches.len(),
+            path.display()
+        );
+
+        report.slow_operations = slow_log.operations();
+        report.memory = Some(memory.usage()).filter(|usage| !usage.stages.is_empty());
+        return output_report(args, &report);
+    }
+
+    // Refuse to save results which would move a large part of the stored ratings, as this
+    // usually indicates a mistuned model rather than new data. Progressive saves checked
+    // every ruleset before saving it.
+    if let Some(shift) = report
+        .rating_shift
+        .as_ref()
+        .filter(|s| s.exceeds_limit && !args.progressive_save)
+    {
+        if args.allow_large_shift {
+            println!(
+                "{:.1}% of stored ratings shifted, saving anyway (--allow-large-shift)",
+                shift.fraction_shifted * 100.0
+            );
+        } else {
+            let refusal = format!(
+                "{} of {} stored ratings ({:.1}%) would change by at least {}, exceeding the limit of {:.1}%. \
+                Nothing was saved (use --allow-large-shift to override)",
+                shift.ratings_shifted,
+                shift.ratings_compared,
+                shift.fraction_shifted * 100.0,
+                args.shift_threshold,
+                args.max_shift_fraction * 100.0
+            );
+            report.slow_operations = slow_log.operations();
+            output_report(args, &report)?;
+            return Err(ProcessorError::new(Stage::Model, Stop::Refused(refusal)));
+        }
+    }
+
+    // Last chance for admins to stop a run in flight, e.g. after discovering bad upstream data.
+    // Nothing was written yet and the matches are still awaiting processing, unless the
+    // ratings were saved progressively after checking for a halt.
+    if !args.progressive_save {
+        if let Some(reason) = client.get_halt().await.stage(Stage::Fetch)? {
+            report.slow_operations = slow_log.operations();
+            output_report(args, &report)?;
+            return Err(ProcessorError::new(Stage::Fetch, Stop::Halted(reason)));
+        }
+    }
+
+    // 7. Save results in database. Aborting partway through would leave partial results.
+    // Every saved row references the run, which is only marked completed once all is saved.
+    memory.stop_enforcing();
+    let run_id = match run_id {
+        Some(run_id) => run_id,
+        None => store
+            .start_run(&RunRecord {
+                input_hash,
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                config: report.config.as_ref().map(EffectiveConfig::to_json).unwrap_or_default(),
+                shard: args.shard
+            })
+            .await
+            .stage(Stage::Save)?
+    };
+    let timer = slow_log.stage("save_results");
+    let highest_ranks = highest_ranks(&results, &bootstrap.country_mapping);
+    let mut staged = args.staged_save;
+    if staged && !(date_range.is_unbounded() && rulesets.is_unrestricted()) {
+        println!("Staged saves only apply to runs over all rulesets and dates, saving in place");
+        staged = false;
+    }
+    if staged && sqlite_store.is_some() {
+        println!("Staged saves only apply to Postgres, saving in place");
+        staged = false;
+    }
+    if staged {
+        let blockers = client.staged_save_blockers().await.stage(Stage::Save)?;
+        if !blockers.is_empty() {
+            println!(
+                "Saving in place, as the staged tables are referenced by {}",
+                blockers.join(", ")
+            );
+            staged = false;
+        }
+    }
+    if args.progressive_save {
+        println!("Ratings and adjustments were saved ruleset by ruleset during processing");
+    } else if staged {
+        client
+            .save_results_staged(
+                &results,
+                &highest_ranks,
+                args.compress_decay_adjustments,
+                settlements.as_ref(),
+                run_id
+            )
+            .await
+            .stage(Stage::Save)?;
+    } else if date_range.is_unbounded() {
+        store
+            .save_results(
+                &results,
+                &highest_ranks,
+                &rulesets,
+                args.compress_decay_adjustments,
+                settlements.as_ref(),
+                run_id
+            )
+            .await
+            .stage(Stage::Save)?;
+    } else {
+        store
+            .save_results_in_range(
+                &results,
+                &highest_ranks,
+                &date_range,
+                &rulesets,
+                args.compress_decay_adjustments,
+                settlements.as_ref(),
+                run_id
+            )
+            .await
+            .stage(Stage::Save)?;
+    }
+
+    // The data only consumed by the website is not part of the result store
+    if sqlite_store.is_none() {
+        client
+            .save_quarantined_matches(&matches, &report.skipped.quarantined_matches)
+            .await
+            .stage(Stage::Save)?;
+        client
+            .save_percentile_milestones(&percentile_milestones(&results))
+            .await
+            .stage(Stage::Save)?;
+        client
+            .save_player_activity(&activity, &rulesets)
+            .await
+            .stage(Stage::Save)?;
+        if date_range.is_unbounded() {
+            client
+                .save_player_lobby_stats(&model.stats.lobby_stats(), &rulesets)
+                .await
+                .stage(Stage::Save)?;
+        } else {
+            println!("Lobby statistics are not updated by a run restricted to dates, as they only cover its matches");
+        }
+        if args.overall_ratings {
+            if rulesets.is_unrestricted() {
+                client
+                    .save_overall_ratings(&overall_ratings(&results))
+                    .await
+                    .stage(Stage::Save)?;
+            } else {
+                println!("Overall ratings blend all rulesets and are not updated by a run restricted to some rulesets");
+            }
+        }
+        if let Some(scale) = &args.display_scale {
+            client
+                .save_display_ratings(&display_ratings(&results, scale))
+                .await
+                .stage(Stage::Save)?;
+        }
+        if args.save_upsets {
+            client.save_match_upsets(&upsets.upsets).await.stage(Stage::Save)?;
+        }
+        if args.classify_mods {
+            let played_mods = client.get_played_mods(&matches).await.stage(Stage::Fetch)?;
+            client
+                .save_game_mod_categories(&classify_games(&played_mods))
+                .await
+                .stage(Stage::Save)?;
+        }
+    } else {
+        println!(
+            "Quarantined matches, percentile milestones, player activity and the other website data \
+        are only saved to Postgres"
+        );
+    }
+    timer.finish(results.len());
+    check_memory(&memory, "save_results")?;
+
+    if args.save_skipped && sqlite_store.is_none() {
+        client.save_skipped_entities(&report.skipped).await.stage(Stage::Save)?;
+    }
+    if args.save_rank_changes && sqlite_store.is_none() {
+        client
+            .save_rank_changes(&report.rank_changes, &rulesets)
+            .await
+            .stage(Stage::Save)?;
+    }
+
+    // 8. Update all match processing statuses. Postgres holds no results of a run saved to
+    //    SQLite, so its matches stay awaiting processing there.
+    if sqlite_store.is_none() {
+        client
+            .roll_forward_processing_statuses(&matches)
+            .await
+            .stage(Stage::Save)?;
+    } else {
+        println!("Results were saved to SQLite, the matches stay awaiting processing in Postgres");
+    }
+
+    // 9. Complete the run so identical inputs can be skipped next time
+    store.complete_run(run_id).await.stage(Stage::Save)?;
+
+    // 10. Output the run report
+    report.slow_operations = slow_log.operations();
+    report.memory = Some(memory.usage()).filter(|usage| !usage.stages.is_empty());
+    output_report(args, &report)?;
+    save_shard_report(client, args, &report).await?;
+
+    println!("Processing complete");
+    Ok(())
+}
+
+/// Stores the report of a shard which finished, for `--coordinate-shards` to merge
+async fn save_shard_report(client: &DbClient, args: &Args, report: &RunReport) -> Result<(), ProcessorError> {
+    if let Some(ruleset) = args.shard {
+        client
+            .save_shard_report(ruleset, &report.to_json())
+            .await
+            .stage(Stage::Publish)?;
+    }
+
+    Ok(())
+}
+
+/// Samples the resident memory after `stage`, stopping the run if it reached the ceiling.
+/// Nothing has been saved at this point and the processing statuses stay rolled back, so the
+/// next run processes the same matches.
+fn check_memory(memory: &MemoryMonitor, stage: &str) -> Result<(), ProcessorError> {
+    memory
+        .sample_stage(stage)
+        .map_err(|e| ProcessorError::new(Stage::Model, Stop::MemoryCeiling(e)))
+}
+
+/// Aborts a run whose memory reached the ceiling between two stages, see `check_memory`. The
+/// sampler runs on its own thread, which cannot stop the run otherwise.
+fn exit_memory_ceiling(e: MemoryCeilingExceeded) {
+    let stop = Stop::MemoryCeiling(e);
+    eprintln!("{}", stop);
+    process::exit(stop.exit_code());
+}
+
+/// Writes the run report to `--report-path`, or prints it if no path was given
+fn output_report(args: &Args, report: &RunReport) -> Result<(), ProcessorError> {
+    match &args.report_path {
+        Some(path) => report.write(path).map_err(|source| write_error(path, source)),
+        None => {
+            println!("{}", report.to_json());
+            Ok(())
+        }
+    }
+}
+
+/// A failure to read one of the inputs of a run from `path`
+pub(crate) fn read_error(path: &Path, source: io::Error) -> ProcessorError {
+    ProcessorError::new(
+        Stage::Fetch,
+        Cause::Read {
+            path: path.to_path_buf(),
+            source
+        }
+    )
+}
+
+/// A failure to write one of the outputs of a run to `path`
+pub(crate) fn write_error(path: &Path, source: io::Error) -> ProcessorError {
+    ProcessorError::new(
+        Stage::Publish,
+        Cause::Io {
+            path: path.to_path_buf(),
+            source
+        }
+    )
+}
+
+async fn client() -> Result<DbClient, ProcessorError> {
+    let connection_string = env::var(CONNECTION_STRING_ENV)
+        .map_err(|_| ProcessorError::new(Stage::Fetch, Cause::MissingVariable(CONNECTION_STRING_ENV)))?;
+
+    DbClient::connect(connection_string.as_str()).await.stage(Stage::Fetch)
+}
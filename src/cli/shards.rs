@@ -0,0 +1,35 @@
+use super::{args::Args, run::write_error};
+use crate::{
+    database::db::DbClient,
+    error::{Cause, ProcessorError, Stage, StageContext},
+    model::structures::ruleset::Ruleset,
+    report::shards::{missing_shards, ShardedRunReport}
+};
+
+/// Waits until no shard holds the processor lock, then merges and outputs the stored reports
+/// of the given shards. Nothing is merged unless every shard stored its report.
+pub(crate) async fn coordinate_shards(
+    client: &DbClient,
+    args: &Args,
+    shards: &[Ruleset]
+) -> Result<(), ProcessorError> {
+    println!("Waiting for the shards to finish...");
+    client.acquire_processor_lock(true).await.stage(Stage::Fetch)?;
+
+    let reports = client.get_shard_reports(shards).await.stage(Stage::Fetch)?;
+    let missing = missing_shards(shards, &reports);
+    if !missing.is_empty() {
+        return Err(ProcessorError::new(Stage::Publish, Cause::MissingShardReports(missing)));
+    }
+
+    let report = ShardedRunReport::merge(reports);
+    match &args.report_path {
+        Some(path) => report.write(path).map_err(|source| write_error(path, source))?,
+        None => println!("{}", report.to_json())
+    }
+
+    // Consumed reports are not merged again by the next coordinator
+    client.delete_shard_reports(shards).await.stage(Stage::Publish)?;
+    println!("Merged the run reports of {} shards", shards.len());
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use super::{
+    args::Args,
+    run::{read_error, write_error}
+};
+use crate::{
+    database::result_store::{ratings_from_adjustments, ResultStore},
+    error::{ProcessorError, Stage, StageContext},
+    model::{
+        decay_sweep::{sweep_decay, to_csv},
+        otr_model::OtrModel,
+        simulation::Lineup
+    },
+    report::continuity::sample_players
+};
+use std::{collections::HashMap, fs, path::Path};
+
+/// Projects the rating changes of the lineup at `path` from the stored ratings and outputs
+/// them in place of the run report
+pub(crate) async fn simulate(store: &dyn ResultStore, args: &Args, path: &Path) -> Result<(), ProcessorError> {
+    let lineup = Lineup::read(path).map_err(|source| read_error(path, source))?;
+
+    let ratings = store.get_player_ratings().await.stage(Stage::Fetch)?;
+    let model = OtrModel::with_config(&ratings, &HashMap::new(), args.model_config());
+    let projected = model.simulate_match(&lineup).stage(Stage::Model)?;
+
+    let json = serde_json::to_string_pretty(&projected).expect("Projected changes should be serializable");
+    match &args.report_path {
+        Some(path) => fs::write(path, json).map_err(|source| write_error(path, source))?,
+        None => println!("{}", json)
+    }
+
+    Ok(())
+}
+
+/// Replays the decay of a sample of stored players under every swept combination of decay
+/// parameters, writing their rating trajectories to `path` as CSV
+pub(crate) async fn decay_sweep(store: &dyn ResultStore, args: &Args, path: &Path) -> Result<(), ProcessorError> {
+    let stored = store.get_player_ratings().await.stage(Stage::Fetch)?;
+    let sample = sample_players(&stored, args.sweep_sample);
+    let adjustments = store.get_rating_adjustments(&sample).await.stage(Stage::Fetch)?;
+    let ratings = ratings_from_adjustments(adjustments);
+
+    let config = args.model_config();
+    let points = sweep_decay(
+        &ratings,
+        &config,
+        &args.sweep_rates,
+        &args.sweep_inactivity_days,
+        config.decay_time()
+    );
+    fs::write(path, to_csv(&points)).map_err(|source| write_error(path, source))?;
+
+    println!(
+        "Wrote {} trajectory points of {} ratings to {}",
+        points.len(),
+        ratings.len(),
+        path.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use super::{args::Args, run::read_error, stop::Stop};
+use crate::{
+    database::{
+        db::DbClient,
+        result_store::{ResultStore, RunRecord}
+    },
+    error::{ProcessorError, Stage, StageContext},
+    model::rank_history::highest_ranks,
+    utils::processor_state::ProcessorState
+};
+use std::{env, path::Path};
+
+/// Saves the results written by a run with --output-state as that run would have saved them,
+/// then marks their matches as processed
+pub(crate) async fn persist_state(
+    store: &dyn ResultStore,
+    client: &DbClient,
+    args: &Args,
+    path: &Path
+) -> Result<(), ProcessorError> {
+    let state = ProcessorState::read(path).map_err(|source| read_error(path, source))?;
+    if state.version != env!("CARGO_PKG_VERSION") {
+        let refusal = format!(
+            "The processor state {} was computed by version {}, nothing was saved (save it with the same version)",
+            path.display(),
+            state.version
+        );
+        return Err(ProcessorError::new(Stage::Fetch, Stop::Refused(refusal)));
+    }
+    if let Some(reason) = client.get_halt().await.stage(Stage::Fetch)? {
+        return Err(ProcessorError::new(Stage::Fetch, Stop::Halted(reason)));
+    }
+
+    let results = state.player_ratings();
+    let run_id = store
+        .start_run(&RunRecord {
+            input_hash: state.input_hash.clone(),
+            version: state.version.clone(),
+            config: state.config.clone(),
+            shard: args.shard
+        })
+        .await
+        .stage(Stage::Save)?;
+    let highest_ranks = highest_ranks(&results, &state.country_mapping);
+    if state.date_range.is_unbounded() {
+        store
+            .save_results(
+                &results,
+                &highest_ranks,
+                &state.rulesets,
+                args.compress_decay_adjustments,
+                state.settlements.as_ref(),
+                run_id
+            )
+            .await
+            .stage(Stage::Save)?;
+    } else {
+        store
+            .save_results_in_range(
+                &results,
+                &highest_ranks,
+                &state.date_range,
+                &state.rulesets,
+                args.compress_decay_adjustments,
+                state.settlements.as_ref(),
+                run_id
+            )
+            .await
+            .stage(Stage::Save)?;
+    }
+
+    // Postgres holds no results saved to SQLite, so their matches stay awaiting processing there
+    if args.sqlite_store.is_none() {
+        client
+            .roll_forward_match_ids(&state.match_ids)
+            .await
+            .stage(Stage::Save)?;
+    }
+    store.complete_run(run_id).await.stage(Stage::Save)?;
+
+    println!(
+        "Saved {} ratings of {} matches from {}",
+        results.len(),
+        state.match_ids.len(),
+        path.display()
+    );
+    Ok(())
+}
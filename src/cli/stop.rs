@@ -0,0 +1,49 @@
+use crate::report::memory::MemoryCeilingExceeded;
+use thiserror::Error;
+
+/// Exit code of a run which found no matches awaiting processing. Nothing is saved and the
+/// run report is still output, so an orchestrator can tell the processor ran.
+pub const EXIT_NOTHING_TO_PROCESS: i32 = 3;
+
+/// Exit code of a run which did not start because another processor holds the processor lock
+pub const EXIT_LOCKED: i32 = 4;
+
+/// Exit code of a run aborted before saving because its resident memory reached
+/// `--memory-ceiling-mb`
+pub const EXIT_MEMORY_CEILING: i32 = 5;
+
+/// Exit code of a run aborted before saving because admins set the halt flag of the
+/// processor_control table
+pub const EXIT_HALTED: i32 = 6;
+
+/// A run stopped on purpose rather than failed, e.g. by a guard or a held lock
+///
+/// Stops are returned like errors, so that the run cleans up before the processor exits with
+/// the exit code of the stop.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum Stop {
+    #[error("No matches awaiting processing, nothing was saved")]
+    NothingToProcess,
+    /// Another processor holds the given lock
+    #[error("Another processor holds the {0}, exiting without changes (use --wait-for-lock to wait for it instead)")]
+    Locked(String),
+    #[error("Aborting, {0}. Nothing was saved (raise --memory-ceiling-mb to allow more)")]
+    MemoryCeiling(MemoryCeilingExceeded),
+    #[error("Processing was halted through processor_control, nothing was saved: {0}")]
+    Halted(String),
+    /// A guard refused the results, e.g. the rating shift guard
+    #[error("{0}")]
+    Refused(String)
+}
+
+impl Stop {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Stop::NothingToProcess => EXIT_NOTHING_TO_PROCESS,
+            Stop::Locked(_) => EXIT_LOCKED,
+            Stop::MemoryCeiling(_) => EXIT_MEMORY_CEILING,
+            Stop::Halted(_) => EXIT_HALTED,
+            Stop::Refused(_) => 1
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use futures_util::SinkExt;
+use postgres_types::{IsNull, ToSql, Type};
+use std::pin::Pin;
+use thiserror::Error;
+use tokio_postgres::CopyInSink;
+
+/// Size in KB of the batches sent to the database during a COPY, unless configured
+pub const DEFAULT_COPY_BATCH_SIZE_KB: usize = 64;
+
+/// Signature which starts every binary COPY, followed by the flags and header extension length
+const SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+
+/// Errors which can occur while writing rows of a COPY
+#[derive(Error, Debug)]
+pub enum CopyError {
+    #[error(transparent)]
+    Postgres(#[from] tokio_postgres::Error),
+    #[error("Failed to encode column {column}: {source}")]
+    Encode {
+        column: usize,
+        source: Box<dyn std::error::Error + Sync + Send>
+    }
+}
+
+/// Encodes rows into the binary COPY format, in batches of at least `batch_size` bytes
+///
+/// Batching is independent of the database connection, so that the encoding throughput
+/// can be measured on its own.
+pub struct BinaryCopyEncoder {
+    types: Vec<Type>,
+    buf: BytesMut,
+    batch_size: usize
+}
+
+impl BinaryCopyEncoder {
+    pub fn new(types: &[Type], batch_size: usize) -> Self {
+        let mut buf = BytesMut::with_capacity(batch_size + SIGNATURE.len() + 8);
+        buf.put_slice(SIGNATURE);
+        buf.put_i32(0);
+        buf.put_i32(0);
+
+        BinaryCopyEncoder {
+            types: types.to_vec(),
+            buf,
+            batch_size
+        }
+    }
+
+    /// Encodes a single row
+    ///
+    /// # Returns
+    /// The encoded rows once they reach the batch size, which are then no longer buffered
+    ///
+    /// # Panics
+    /// Panics if the number of values does not match the number of types
+    pub fn encode(&mut self, values: &[&(dyn ToSql + Sync)]) -> Result<Option<Bytes>, CopyError> {
+        assert_eq!(values.len(), self.types.len(), "row does not match the COPY columns");
+
+        self.buf.put_i16(self.types.len() as i16);
+        for (column, (value, ty)) in values.iter().zip(&self.types).enumerate() {
+            let start = self.buf.len();
+            self.buf.put_i32(0);
+            let len = match value
+                .to_sql_checked(ty, &mut self.buf)
+                .map_err(|source| CopyError::Encode { column, source })?
+            {
+                IsNull::Yes => -1,
+                IsNull::No => (self.buf.len() - start - 4) as i32
+            };
+            self.buf[start..start + 4].copy_from_slice(&len.to_be_bytes());
+        }
+
+        if self.buf.len() < self.batch_size {
+            return Ok(None);
+        }
+
+        let batch = self.buf.split().freeze();
+        self.buf.reserve(self.batch_size);
+        Ok(Some(batch))
+    }
+
+    /// Ends the COPY, returning the rows which are still buffered along with the trailer
+    pub fn finish(&mut self) -> Bytes {
+        self.buf.put_i16(-1);
+        self.buf.split().freeze()
+    }
+}
+
+/// Writes rows of a binary COPY to the database, sending them in batches
+///
+/// Unlike `BinaryCopyInWriter`, whose batches are fixed at 4 KB, the batch size is
+/// configurable. The COPY is aborted unless `finish` is called.
+pub struct BatchedCopyWriter {
+    sink: Pin<Box<CopyInSink<Bytes>>>,
+    encoder: BinaryCopyEncoder
+}
+
+impl BatchedCopyWriter {
+    pub fn new(sink: CopyInSink<Bytes>, types: &[Type], batch_size: usize) -> Self {
+        BatchedCopyWriter {
+            sink: Box::pin(sink),
+            encoder: BinaryCopyEncoder::new(types, batch_size)
+        }
+    }
+
+    /// Writes a single row, sending the batch to the database once it is full
+    pub async fn write(&mut self, values: &[&(dyn ToSql + Sync)]) -> Result<(), CopyError> {
+        if let Some(batch) = self.encoder.encode(values)? {
+            self.sink.send(batch).await?;
+        }
+        Ok(())
+    }
+
+    /// Sends the remaining rows and completes the COPY, returning the number of rows written
+    pub async fn finish(mut self) -> Result<u64, CopyError> {
+        let rest = self.encoder.finish();
+        self.sink.send(rest).await?;
+        Ok(self.sink.as_mut().finish().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BinaryCopyEncoder, CopyError, SIGNATURE};
+    use postgres_types::{ToSql, Type};
+
+    const TYPES: [Type; 2] = [Type::INT4, Type::FLOAT8];
+
+    #[test]
+    fn test_encode_row() {
+        let mut encoder = BinaryCopyEncoder::new(&TYPES, 1024);
+        assert!(encoder.encode(&[&7i32, &None::<f64>]).unwrap().is_none());
+
+        let encoded = encoder.finish();
+        let mut expected = SIGNATURE.to_vec();
+        expected.extend([0, 0, 0, 0, 0, 0, 0, 0]);
+        expected.extend(2i16.to_be_bytes());
+        expected.extend(4i32.to_be_bytes());
+        expected.extend(7i32.to_be_bytes());
+        expected.extend((-1i32).to_be_bytes());
+        expected.extend((-1i16).to_be_bytes());
+        assert_eq!(encoded.as_ref(), expected.as_slice());
+    }
+
+    #[test]
+    fn test_batches_match_unbatched_encoding() {
+        let encode = |batch_size| {
+            let mut encoder = BinaryCopyEncoder::new(&TYPES, batch_size);
+            let mut batches = Vec::new();
+            for i in 0..100 {
+                let values: [&(dyn ToSql + Sync); 2] = [&i, &(i as f64)];
+                batches.extend(encoder.encode(&values).unwrap());
+            }
+            batches.push(encoder.finish());
+            batches
+        };
+
+        let batched = encode(64);
+        let unbatched = encode(1 << 20);
+        assert!(batched.len() > 1);
+        assert!(batched[..batched.len() - 1].iter().all(|b| b.len() >= 64));
+        assert_eq!(unbatched.len(), 1);
+        assert_eq!(batched.concat(), unbatched[0].to_vec());
+    }
+
+    #[test]
+    fn test_encode_names_the_column() {
+        let mut encoder = BinaryCopyEncoder::new(&TYPES, 1024);
+        let error = encoder.encode(&[&7i32, &"not a float"]).unwrap_err();
+        assert!(matches!(error, CopyError::Encode { column: 1, .. }));
+    }
+}
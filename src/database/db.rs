@@ -1,38 +1,456 @@
-use super::db_structs::{
-    Game, GameScore, Match, Player, PlayerHighestRank, PlayerRating, RatingAdjustment, RulesetData
+use super::{
+    copy::{BatchedCopyWriter, DEFAULT_COPY_BATCH_SIZE_KB},
+    db_structs::{
+        Beatmap, DisplayRating, Game, GameModCategory, GameScore, ManualAdjustment, Match, MatchUpset, OverallRating,
+        PercentileMilestone, PlayedMods, Player, PlayerActivity, PlayerHighestRank, PlayerLobbyStats, PlayerRating,
+        PlayerRestriction, RankHistoryPoint, RatingAdjustment, RulesetData
+    },
+    error::{parse_stored, DatabaseError, QueryContext},
+    result_store::{ratings_from_adjustments, ResultStore, RunRecord},
+    tls::{client_config, parse_ssl_mode, SslMode, TlsError}
 };
 use crate::{
-    model::structures::ruleset::Ruleset,
-    utils::progress_utils::{progress_bar, progress_bar_spinner}
+    error::Entity,
+    model::{
+        beatmaps::{GameBeatmap, Mods},
+        mania_migration::ManiaMigration,
+        processing_result::{QuarantinedMatch, SkippedEntities},
+        rank_history::merge_highest_ranks,
+        start_times::{resolve_missing_start_times, MissingStartTimePolicy, StartTimeResolution},
+        structures::{
+            date_range::DateRange, rating_clamps::RatingClamps, ruleset::Ruleset, ruleset_filter::RulesetFilter,
+            verification_status::VerificationStatus
+        }
+    },
+    report::{rank_changes::RankChange, shards::ShardReport, slow_log::SlowLog},
+    utils::{
+        adjustment_rows::adjustment_rows,
+        progress_utils::{progress_bar, progress_bar_spinner},
+        tournament_settlement::TournamentSettlements
+    }
 };
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
 use itertools::Itertools;
 use postgres_types::ToSql;
-use std::{collections::HashMap, sync::Arc};
-use tokio_postgres::{Client, Error, NoTls, Row};
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    ops::Deref,
+    pin::pin,
+    sync::{Arc, Mutex}
+};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_postgres::{
+    binary_copy::BinaryCopyInWriter, types::Type, Client, Connection, Error, NoTls, Row, Transaction
+};
+use tokio_postgres_rustls::MakeRustlsConnect;
+
+/// Key of the advisory lock held by a processor while it modifies stored data
+pub const PROCESSOR_LOCK_KEY: i64 = 0x6f7472;
+
+/// Key of the advisory lock held by the shard processing `ruleset`, see `acquire_shard_lock`
+pub fn shard_lock_key(ruleset: Ruleset) -> i64 {
+    (PROCESSOR_LOCK_KEY << 8) | ruleset as i64
+}
+
+/// Tables written to a staging table and swapped in by `save_results_staged`, in the order
+/// they are swapped
+pub const STAGED_TABLES: [&str; 2] = ["player_ratings", "rating_adjustments"];
+
+/// Tables pruned by `prune_orphans` along with the condition selecting their orphaned rows, in
+/// the order they are pruned. Adjustments of ratings which are about to be pruned are orphans
+/// as well, so that no adjustment is left behind by pruning its rating.
+pub const ORPHANS: [(&str, &str); 3] = [
+    (
+        "rating_adjustments",
+        "NOT EXISTS (SELECT 1 FROM player_ratings pr JOIN players p ON p.id = pr.player_id \
+        WHERE pr.id = rating_adjustments.player_rating_id)"
+    ),
+    (
+        "player_ratings",
+        "NOT EXISTS (SELECT 1 FROM players p WHERE p.id = player_ratings.player_id)"
+    ),
+    (
+        "player_highest_ranks",
+        "NOT EXISTS (SELECT 1 FROM players p WHERE p.id = player_highest_ranks.player_id)"
+    )
+];
+
+/// Which matches `get_matches` fetches by processing status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchSelection {
+    /// Matches awaiting processor data, which a run processes
+    AwaitingProcessing,
+    /// Matches awaiting processor data or already processed, which are the matches awaiting
+    /// processor data once `rollback_processing_statuses` rolled back the processed ones
+    AwaitingOrProcessed,
+    /// Every match regardless of its processing status, e.g. to inspect the matches played so far
+    All
+}
+
+impl MatchSelection {
+    /// The processing statuses of the selected matches, None if any status is selected
+    fn processing_statuses(self) -> Option<Vec<i32>> {
+        match self {
+            MatchSelection::AwaitingProcessing => Some(vec![4]),
+            MatchSelection::AwaitingOrProcessed => Some(vec![4, 5]),
+            MatchSelection::All => None
+        }
+    }
+}
+
+/// Name of the table a staged save writes `table` to
+pub fn staging_table(table: &str) -> String {
+    format!("{}_new", table)
+}
+
+/// Errors which can occur while connecting to the database
+#[derive(Error, Debug)]
+pub enum ConnectError {
+    #[error(transparent)]
+    Postgres(#[from] Error),
+    #[error(transparent)]
+    Tls(#[from] TlsError)
+}
 
+/// Client of the o!TR database
+///
+/// Queries run on `C`, which is the shared connection of the client unless the client was
+/// taken from a `DbTransaction`.
 #[derive(Clone)]
-pub struct DbClient {
-    client: Arc<Client>
+pub struct DbClient<C = Arc<Client>> {
+    client: C,
+    slow_log: Arc<SlowLog>,
+    /// Size in bytes of the batches sent while copying rating adjustments
+    copy_batch_size: usize,
+    /// Beatmaps fetched by `get_beatmaps_for_games`, by beatmap id
+    beatmap_cache: Arc<Mutex<HashMap<i32, Beatmap>>>
 }
 
 impl DbClient {
-    // Connect to the database and return a DbClient instance
-    pub async fn connect(connection_str: &str) -> Result<Self, Error> {
-        let (client, connection) = tokio_postgres::connect(connection_str, NoTls).await?;
+    /// Connect to the database and return a DbClient instance
+    ///
+    /// TLS is used according to the `sslmode` of the connection string, see `database::tls`
+    pub async fn connect(connection_str: &str) -> Result<Self, ConnectError> {
+        let (ssl_mode, connection_str) = parse_ssl_mode(connection_str)?;
+
+        let client = match ssl_mode {
+            SslMode::Disable => {
+                let (client, connection) = tokio_postgres::connect(&connection_str, NoTls).await?;
+                Self::spawn_connection(connection);
+                client
+            }
+            _ => {
+                let tls = MakeRustlsConnect::new(client_config(ssl_mode)?);
+                let (client, connection) = tokio_postgres::connect(&connection_str, tls).await?;
+                Self::spawn_connection(connection);
+                client
+            }
+        };
+
+        Ok(DbClient {
+            client: Arc::new(client),
+            slow_log: Arc::new(SlowLog::default()),
+            copy_batch_size: DEFAULT_COPY_BATCH_SIZE_KB * 1024,
+            beatmap_cache: Arc::new(Mutex::new(HashMap::new()))
+        })
+    }
 
-        // Spawn the connection object to run in the background
+    /// Spawn the connection object to run in the background
+    fn spawn_connection<S, T>(connection: Connection<S, T>)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+        T: AsyncRead + AsyncWrite + Unpin + Send + 'static
+    {
         tokio::spawn(async move {
             if let Err(e) = connection.await {
                 eprintln!("connection error: {}", e);
             }
         });
+    }
 
-        Ok(DbClient {
-            client: Arc::new(client)
+    /// Starts a transaction on the connection of the client, which is committed by
+    /// `DbTransaction::commit` and rolled back if it is dropped instead
+    ///
+    /// The connection must not be shared with clones of the client, whose queries would
+    /// otherwise run within the transaction.
+    pub async fn transaction(&mut self) -> Result<DbTransaction<'_>, DatabaseError> {
+        let client = Arc::get_mut(&mut self.client).ok_or(DatabaseError::SharedConnection)?;
+        Ok(DbTransaction {
+            transaction: client.transaction().await.query("begin_transaction")?,
+            slow_log: self.slow_log.clone(),
+            copy_batch_size: self.copy_batch_size,
+            beatmap_cache: self.beatmap_cache.clone()
         })
     }
 
-    pub async fn get_matches(&self) -> Vec<Match> {
+    // Access the underlying Client
+    pub fn client(&self) -> Arc<Client> {
+        Arc::clone(&self.client)
+    }
+}
+
+/// A transaction started by `DbClient::transaction`
+pub struct DbTransaction<'a> {
+    transaction: Transaction<'a>,
+    slow_log: Arc<SlowLog>,
+    copy_batch_size: usize,
+    beatmap_cache: Arc<Mutex<HashMap<i32, Beatmap>>>
+}
+
+impl DbTransaction<'_> {
+    /// A client whose queries run within the transaction
+    pub fn client(&self) -> DbClient<&Client> {
+        DbClient {
+            client: self.transaction.client(),
+            slow_log: self.slow_log.clone(),
+            copy_batch_size: self.copy_batch_size,
+            beatmap_cache: self.beatmap_cache.clone()
+        }
+    }
+
+    pub async fn commit(self) -> Result<(), DatabaseError> {
+        self.transaction.commit().await.query("commit_transaction")
+    }
+}
+
+impl<C: Deref<Target = Client> + Send + Sync> DbClient<C> {
+    /// Records queries exceeding the threshold of `slow_log`. Slow queries are not recorded
+    /// unless a slow log is set.
+    pub fn with_slow_log(self, slow_log: Arc<SlowLog>) -> Self {
+        DbClient { slow_log, ..self }
+    }
+
+    /// Sends rating adjustments to the database in batches of `kb` KB. Larger batches make
+    /// fewer round trips at the cost of memory.
+    pub fn with_copy_batch_size(self, kb: usize) -> Self {
+        DbClient {
+            copy_batch_size: kb.max(1) * 1024,
+            ..self
+        }
+    }
+
+    /// Fetches the columns of the given tables which are visible to the connected role
+    ///
+    /// # Returns
+    /// The columns of every existing table, by table name
+    pub async fn get_table_columns(&self, tables: &[&str]) -> Result<HashMap<String, HashSet<String>>, Error> {
+        let rows = self
+            .client
+            .query(
+                "SELECT table_name::text, column_name::text FROM information_schema.columns \
+        WHERE table_schema = current_schema() AND table_name = ANY($1)",
+                &[&tables]
+            )
+            .await?;
+
+        let mut columns: HashMap<String, HashSet<String>> = HashMap::new();
+        for row in rows {
+            columns.entry(row.get(0)).or_default().insert(row.get(1));
+        }
+
+        Ok(columns)
+    }
+
+    /// Fetches the privileges the connected role holds on the given tables
+    ///
+    /// # Returns
+    /// The held privileges (e.g. `SELECT`) of every existing table, by table name
+    pub async fn get_table_privileges(&self, tables: &[&str]) -> Result<HashMap<String, HashSet<String>>, Error> {
+        let rows = self
+            .client
+            .query(
+                "SELECT c.relname::text, p.privilege FROM pg_class c \
+        CROSS JOIN unnest(ARRAY['SELECT', 'INSERT', 'UPDATE', 'DELETE', 'TRUNCATE']) AS p(privilege) \
+        WHERE c.relname = ANY($1) AND c.relkind IN ('r', 'p') AND pg_table_is_visible(c.oid) \
+        AND has_table_privilege(c.oid, p.privilege)",
+                &[&tables]
+            )
+            .await?;
+
+        let mut privileges: HashMap<String, HashSet<String>> = HashMap::new();
+        for row in rows {
+            privileges.entry(row.get(0)).or_default().insert(row.get(1));
+        }
+
+        Ok(privileges)
+    }
+
+    /// Acquires the session-level advisory lock held by a processor while it modifies stored
+    /// data, so that concurrent processors don't truncate and save over each other
+    ///
+    /// The lock is held until `release_locks` or until the connection closes. If `wait` is
+    /// set, this blocks until the processor holding the lock releases it.
+    ///
+    /// # Returns
+    /// Whether the lock was acquired
+    pub async fn acquire_processor_lock(&self, wait: bool) -> Result<bool, DatabaseError> {
+        let timer = self.slow_log.query("acquire_processor_lock");
+        let acquired = if wait {
+            self.client
+                .execute("SELECT pg_advisory_lock($1)", &[&PROCESSOR_LOCK_KEY])
+                .await
+                .query("acquire_processor_lock")?;
+            true
+        } else {
+            self.client
+                .query_one("SELECT pg_try_advisory_lock($1)", &[&PROCESSOR_LOCK_KEY])
+                .await
+                .query("acquire_processor_lock")?
+                .get(0)
+        };
+        timer.finish(0);
+
+        Ok(acquired)
+    }
+
+    /// Acquires the locks held by the shard processing `ruleset`: the processor lock, shared
+    /// with the shards of other rulesets, and the lock of the ruleset itself
+    ///
+    /// Shards of different rulesets run concurrently, while shards of the same ruleset and
+    /// processors holding the processor lock exclusively exclude each other. Like the
+    /// processor lock, both locks are held until `release_locks` or until the connection closes.
+    ///
+    /// # Returns
+    /// Whether both locks were acquired
+    pub async fn acquire_shard_lock(&self, ruleset: Ruleset, wait: bool) -> Result<bool, DatabaseError> {
+        let timer = self.slow_log.query("acquire_shard_lock");
+        let mut acquired = true;
+        for (function, key) in [
+            ("advisory_lock_shared", PROCESSOR_LOCK_KEY),
+            ("advisory_lock", shard_lock_key(ruleset))
+        ] {
+            if wait {
+                self.client
+                    .execute(format!("SELECT pg_{}($1)", function).as_str(), &[&key])
+                    .await
+                    .query("acquire_shard_lock")?;
+            } else {
+                acquired = self
+                    .client
+                    .query_one(format!("SELECT pg_try_{}($1)", function).as_str(), &[&key])
+                    .await
+                    .query("acquire_shard_lock")?
+                    .get(0);
+                if !acquired {
+                    break;
+                }
+            }
+        }
+        timer.finish(0);
+
+        Ok(acquired)
+    }
+
+    /// Releases the processor lock and shard locks held by this connection
+    pub async fn release_locks(&self) -> Result<(), DatabaseError> {
+        self.client
+            .execute("SELECT pg_advisory_unlock_all()", &[])
+            .await
+            .query("release_locks")?;
+
+        Ok(())
+    }
+
+    /// Fetches the halt set by admins in the processor_control table, if any, so that runs
+    /// already in flight stop before saving anything. Without a processor_control table, runs
+    /// are never halted.
+    ///
+    /// # Returns
+    /// The reason given for the halt (empty if none), or None if no halt is set
+    pub async fn get_halt(&self) -> Result<Option<String>, DatabaseError> {
+        let timer = self.slow_log.query("get_halt");
+        let exists: bool = self
+            .client
+            .query_one("SELECT to_regclass('processor_control') IS NOT NULL", &[])
+            .await
+            .query("get_halt")?
+            .get(0);
+        let halt = match exists {
+            true => self
+                .client
+                .query_opt(
+                    "SELECT COALESCE(reason, '') FROM processor_control WHERE halt LIMIT 1",
+                    &[]
+                )
+                .await
+                .query("get_halt")?
+                .map(|row| row.get(0)),
+            false => None
+        };
+        timer.finish(halt.iter().count());
+
+        Ok(halt)
+    }
+
+    /// Stores the run report of the shard processing `ruleset`, replacing its previous one
+    pub async fn save_shard_report(&self, ruleset: Ruleset, report: &str) -> Result<(), DatabaseError> {
+        self.client
+            .execute(
+                "INSERT INTO processor_shard_reports (ruleset, report, completed_at) \
+        VALUES ($1, $2::text::jsonb, NOW()) ON CONFLICT (ruleset) \
+        DO UPDATE SET report = EXCLUDED.report, completed_at = EXCLUDED.completed_at",
+                &[&(ruleset as i32), &report]
+            )
+            .await
+            .query("save_shard_report")?;
+
+        Ok(())
+    }
+
+    /// Fetches the stored run reports of the shards of the given rulesets
+    pub async fn get_shard_reports(&self, rulesets: &[Ruleset]) -> Result<Vec<ShardReport>, DatabaseError> {
+        let ids = rulesets.iter().map(|&r| r as i32).collect_vec();
+        let rows = self
+            .client
+            .query(
+                "SELECT ruleset, report::text AS report FROM processor_shard_reports WHERE ruleset = ANY($1)",
+                &[&ids]
+            )
+            .await
+            .query("get_shard_reports")?;
+
+        // Only reports of valid rulesets are selected
+        rows.iter()
+            .filter_map(|row| Some((Ruleset::try_from(row.get::<_, i32>("ruleset")).ok()?, row)))
+            .map(|(ruleset, row)| {
+                Ok(ShardReport {
+                    ruleset,
+                    report: serde_json::from_str(row.get("report")).map_err(|source| {
+                        DatabaseError::InvalidShardReport {
+                            ruleset: ruleset as i32,
+                            source
+                        }
+                    })?
+                })
+            })
+            .collect()
+    }
+
+    /// Deletes the stored run reports of the shards of the given rulesets once they are merged
+    pub async fn delete_shard_reports(&self, rulesets: &[Ruleset]) -> Result<(), DatabaseError> {
+        let ids = rulesets.iter().map(|&r| r as i32).collect_vec();
+        self.client
+            .execute("DELETE FROM processor_shard_reports WHERE ruleset = ANY($1)", &[&ids])
+            .await
+            .query("delete_shard_reports")?;
+
+        Ok(())
+    }
+
+    /// Fetches all matches awaiting processor data whose start time falls within `range`
+    /// and whose tournament is of a ruleset selected by `rulesets`
+    ///
+    /// Matches without a start time (which are only fetched if `range` is unbounded) are
+    /// skipped or have their start time imputed according to `policy`
+    pub async fn get_matches(
+        &self,
+        range: &DateRange,
+        rulesets: &RulesetFilter,
+        selection: MatchSelection,
+        policy: MissingStartTimePolicy
+    ) -> Result<(Vec<Match>, StartTimeResolution), DatabaseError> {
         let mut matches_map: HashMap<i32, Match> = HashMap::new();
         let mut games_map: HashMap<i32, Game> = HashMap::new();
         let mut scores_map: HashMap<i32, GameScore> = HashMap::new();
@@ -43,29 +461,45 @@ impl DbClient {
         // Link game ids and score ids
         let mut game_scores_link_map: HashMap<i32, Vec<i32>> = HashMap::new();
 
+        // Ids of matches without a start time
+        let mut missing_start_times: HashSet<i32> = HashSet::new();
+
         // The WHERE query here does the following:
         //
-        // 1. Only consider matches with a processing_status of 'NeedsProcessorData'.
+        // 1. Only consider matches with a processing_status of 'NeedsProcessorData' (and
+        //     'Done' if processed matches are selected too), unless every match is selected.
         //     This is fine because tournaments which are rejected have matches with a
         //     processing_status of 'Done'.
         // 2. From these matches, we only want the games and scores which are verified.
         //
         //  We can safely assume that for all matches awaiting processor data every
         //     game and game score is completely done with processing
+        // 3. Matches are optionally restricted to those starting within the given date range.
         println!("Fetching matches...");
+        let timer = self.slow_log.query("get_matches");
         let rows = self.client.query("
             SELECT
                 t.id AS tournament_id, t.name AS tournament_name, t.ruleset AS tournament_ruleset,
-                m.id AS match_id, m.name AS match_name, m.start_time AS match_start_time, m.end_time AS match_end_time, m.tournament_id AS match_tournament_id,
+                m.id AS match_id, m.name AS match_name, m.start_time AS match_start_time, m.end_time AS match_end_time, m.tournament_id AS match_tournament_id, m.rating_exempt AS match_rating_exempt,
                 g.id AS game_id, g.ruleset AS game_ruleset, g.start_time AS game_start_time, g.end_time AS game_end_time, g.match_id AS game_match_id,
-                gs.id AS game_score_id, gs.player_id AS game_score_player_id, gs.game_id AS game_score_game_id, gs.score AS game_score_score, gs.placement AS game_score_placement
+                gs.id AS game_score_id, gs.player_id AS game_score_player_id, gs.game_id AS game_score_game_id, gs.score AS game_score_score, gs.placement AS game_score_placement, gs.team AS game_score_team
             FROM tournaments t
             JOIN matches m ON t.id = m.tournament_id
             JOIN games g ON m.id = g.match_id
             JOIN game_scores gs ON g.id = gs.game_id
-            WHERE m.processing_status = 4 AND g.verification_status = 4
-                AND gs.verification_status = 4
-            ORDER BY gs.id", &[]).await.unwrap();
+            WHERE ($5::int[] IS NULL OR m.processing_status = ANY($5)) AND g.verification_status = $4
+                AND gs.verification_status = $4
+                AND ($1::timestamptz IS NULL OR m.start_time >= $1)
+                AND ($2::timestamptz IS NULL OR m.start_time <= $2)
+                AND ($3::int[] IS NULL OR t.ruleset = ANY($3))
+            ORDER BY gs.id", &[
+                &range.from,
+                &range.to,
+                &rulesets.ids(),
+                &(VerificationStatus::Verified as i32),
+                &selection.processing_statuses()
+            ]).await.query("get_matches")?;
+        timer.finish(rows.len());
 
         println!("Matches fetched, iterating...");
 
@@ -74,11 +508,18 @@ impl DbClient {
             let game_id = row.get::<_, i32>("game_id");
             let score_id = row.get::<_, i32>("game_score_id"); // Ensuring the score has the correct game_id
 
-            matches_map
-                .entry(match_id)
-                .or_insert_with(|| Self::match_from_row(&row));
+            if let Entry::Vacant(entry) = matches_map.entry(match_id) {
+                let (match_, has_start_time) = Self::match_from_row(&row)?;
+                if !has_start_time {
+                    missing_start_times.insert(match_id);
+                }
+
+                entry.insert(match_);
+            }
 
-            games_map.entry(game_id).or_insert_with(|| Self::game_from_row(&row));
+            if let Entry::Vacant(entry) = games_map.entry(game_id) {
+                entry.insert(Self::game_from_row(&row)?);
+            }
             scores_map.entry(score_id).or_insert_with(|| Self::score_from_row(&row));
 
             // Link ids back to parents
@@ -112,72 +553,245 @@ impl DbClient {
         }
 
         let mut matches = matches_map.values().cloned().collect_vec();
-        matches.sort_by(|a, b| a.start_time.cmp(&b.start_time));
+        let resolution = resolve_missing_start_times(&mut matches, &missing_start_times, policy);
+
+        // Ties are broken by id so that the processing order is deterministic
+        matches.sort_by_key(|m| (m.start_time, m.id));
 
         println!("Match fetching complete");
-        matches
+        Ok((matches, resolution))
+    }
+
+    /// Fetches the beatmap of every game in `matches` along with the mods it was played with
+    ///
+    /// Beatmaps are cached in memory by id and shared between clones of the client, so
+    /// each beatmap is only fetched once per run.
+    ///
+    /// # Returns
+    /// The beatmap of every game which has one, by game id
+    pub async fn get_beatmaps_for_games(&self, matches: &[Match]) -> Result<HashMap<i32, GameBeatmap>, DatabaseError> {
+        let games = matches
+            .iter()
+            .flat_map(|m| m.games.iter().map(|g| (g.id, g.ruleset)))
+            .collect::<HashMap<_, _>>();
+        let game_ids = games.keys().copied().collect_vec();
+
+        let timer = self.slow_log.query("get_game_beatmaps");
+        let rows = self
+            .client
+            .query(
+                "SELECT id, beatmap_id, mods FROM games WHERE id = ANY($1) AND beatmap_id IS NOT NULL",
+                &[&game_ids]
+            )
+            .await
+            .query("get_game_beatmaps")?;
+        timer.finish(rows.len());
+
+        let played = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.get::<_, i32>("id"),
+                    row.get::<_, i32>("beatmap_id"),
+                    Mods(row.get("mods"))
+                )
+            })
+            .collect_vec();
+
+        let missing = {
+            let cache = self.beatmap_cache.lock().unwrap();
+            played
+                .iter()
+                .map(|(_, beatmap_id, _)| *beatmap_id)
+                .filter(|id| !cache.contains_key(id))
+                .unique()
+                .collect_vec()
+        };
+
+        if !missing.is_empty() {
+            let timer = self.slow_log.query("get_beatmaps");
+            let rows = self
+                .client
+                .query(
+                    "SELECT id, sr, cs, ar, od, hp, bpm, length FROM beatmaps WHERE id = ANY($1)",
+                    &[&missing]
+                )
+                .await
+                .query("get_beatmaps")?;
+            timer.finish(rows.len());
+
+            let mut cache = self.beatmap_cache.lock().unwrap();
+            for row in rows {
+                let beatmap = Beatmap {
+                    id: row.get("id"),
+                    star_rating: row.get("sr"),
+                    circle_size: row.get("cs"),
+                    approach_rate: row.get("ar"),
+                    overall_difficulty: row.get("od"),
+                    health_drain: row.get("hp"),
+                    bpm: row.get("bpm"),
+                    length: row.get("length")
+                };
+                cache.insert(beatmap.id, beatmap);
+            }
+        }
+
+        let cache = self.beatmap_cache.lock().unwrap();
+        Ok(played
+            .into_iter()
+            .filter_map(|(game_id, beatmap_id, mods)| {
+                let beatmap = cache.get(&beatmap_id)?.clone();
+                Some((
+                    game_id,
+                    GameBeatmap {
+                        mods,
+                        adjusted: beatmap.with_mods(mods, games[&game_id]),
+                        beatmap
+                    }
+                ))
+            })
+            .collect())
+    }
+
+    /// Fetches the mods played in every game of `matches`, see `classify_games`
+    ///
+    /// Only the mods of scores present in `matches` are included, so scores which were
+    /// not verified or belong to excluded players don't affect the classification.
+    pub async fn get_played_mods(&self, matches: &[Match]) -> Result<Vec<PlayedMods>, DatabaseError> {
+        let tournaments = matches
+            .iter()
+            .flat_map(|m| m.games.iter().map(move |g| (g.id, (m.tournament_id, m.ruleset))))
+            .collect::<HashMap<_, _>>();
+        let score_ids = matches
+            .iter()
+            .flat_map(|m| m.games.iter().flat_map(|g| g.scores.iter().map(|s| s.id)))
+            .collect_vec();
+
+        let timer = self.slow_log.query("get_played_mods");
+        let rows = self
+            .client
+            .query(
+                "SELECT g.id, g.beatmap_id, g.mods, array_agg(gs.mods) AS score_mods \
+                FROM games g JOIN game_scores gs ON g.id = gs.game_id \
+                WHERE gs.id = ANY($1) AND g.beatmap_id IS NOT NULL \
+                GROUP BY g.id",
+                &[&score_ids]
+            )
+            .await
+            .query("get_played_mods")?;
+        timer.finish(rows.len());
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let game_id = row.get::<_, i32>("id");
+                let (tournament_id, ruleset) = tournaments[&game_id];
+                PlayedMods {
+                    game_id,
+                    tournament_id,
+                    ruleset,
+                    beatmap_id: row.get("beatmap_id"),
+                    game_mods: Mods(row.get("mods")),
+                    score_mods: row.get::<_, Vec<i32>>("score_mods").into_iter().map(Mods).collect()
+                }
+            })
+            .collect())
     }
 
-    pub async fn rollback_processing_statuses(&self) {
-        let tournament_id_sql = "SELECT tournament_id FROM matches WHERE processing_status = 5;";
+    /// Marks processed matches (and their tournaments) as awaiting processor data again.
+    ///
+    /// Only matches starting within `range` of tournaments selected by `rulesets` are rolled
+    /// back so that a restricted run does not leave matches outside of its scope stuck
+    /// awaiting processor data.
+    pub async fn rollback_processing_statuses(
+        &self,
+        range: &DateRange,
+        rulesets: &RulesetFilter
+    ) -> Result<(), DatabaseError> {
+        let tournament_id_sql = "SELECT tournament_id FROM matches WHERE processing_status = 5 \
+        AND ($1::timestamptz IS NULL OR start_time >= $1) AND ($2::timestamptz IS NULL OR start_time <= $2) \
+        AND ($3::int[] IS NULL OR tournament_id IN (SELECT id FROM tournaments WHERE ruleset = ANY($3)));";
         let match_update_sql = "UPDATE matches SET processing_status = 4 \
-        WHERE processing_status = 5;";
+        WHERE processing_status = 5 \
+        AND ($1::timestamptz IS NULL OR start_time >= $1) AND ($2::timestamptz IS NULL OR start_time <= $2) \
+        AND ($3::int[] IS NULL OR tournament_id IN (SELECT id FROM tournaments WHERE ruleset = ANY($3)));";
 
+        let timer = self.slow_log.query("rollback_processing_statuses");
+        let ruleset_ids = rulesets.ids();
         let mut tournament_update_sql = Vec::new();
-        let id_result = self.client.query(tournament_id_sql, &[]).await;
+        let rows = self
+            .client
+            .query(tournament_id_sql, &[&range.from, &range.to, &ruleset_ids])
+            .await
+            .query("rollback_processing_statuses")?;
 
-        if id_result.is_ok() {
-            for row in id_result.unwrap().iter() {
-                tournament_update_sql.push(format!(
-                    "UPDATE tournaments SET processing_status = 4 \
+        for row in rows.iter() {
+            tournament_update_sql.push(format!(
+                "UPDATE tournaments SET processing_status = 4 \
                 WHERE id = {};\n",
-                    row.get::<_, i32>(0)
-                ));
-            }
-        } else {
-            panic!("Failed to fetch tournament ids");
+                row.get::<_, i32>(0)
+            ));
         }
 
-        let p_bar = progress_bar_spinner(2, "Rolling back tournament processing statuses".to_string()).unwrap();
+        let p_bar = progress_bar_spinner(2, "Rolling back tournament processing statuses".to_string());
 
         // Update tournaments
         self.client
             .batch_execute(tournament_update_sql.join("\n").as_str())
             .await
-            .expect("Failed to batch execute tournament processing status rollback");
+            .query("rollback_processing_statuses")?;
 
         p_bar.inc(1);
         p_bar.set_message("Rolling back match processing statuses");
 
         // Update matches
-        self.client
-            .execute(match_update_sql, &[])
+        let rolled_back = self
+            .client
+            .execute(match_update_sql, &[&range.from, &range.to, &ruleset_ids])
             .await
-            .expect("Failed to execute match processing status rollback");
+            .query("rollback_processing_statuses")?;
 
         p_bar.inc(1);
-        p_bar.finish_with_message("Completed processing status rollback for tournaments and matches")
+        p_bar.finish_with_message("Completed processing status rollback for tournaments and matches");
+        timer.finish(rolled_back as usize);
+
+        Ok(())
     }
 
-    fn match_from_row(row: &Row) -> Match {
-        Match {
+    /// Creates a match from a row, also returning whether the row had a start time.
+    /// Missing start and end times are left at their defaults to be resolved later.
+    fn match_from_row(row: &Row) -> Result<(Match, bool), DatabaseError> {
+        let start_time: Option<DateTime<FixedOffset>> = row.get("match_start_time");
+        let end_time: Option<DateTime<FixedOffset>> = row.get("match_end_time");
+
+        let tournament_id = row.get("match_tournament_id");
+        let match_ = Match {
             id: row.get("match_id"),
+            tournament_id,
             name: row.get("match_name"),
-            start_time: row.get("match_start_time"),
-            end_time: row.get("match_end_time"),
-            ruleset: Ruleset::try_from(row.get::<_, i32>("tournament_ruleset")).unwrap(),
+            start_time: start_time.unwrap_or_default(),
+            end_time: end_time.unwrap_or_default(),
+            ruleset: parse_stored(
+                row.get("tournament_ruleset"),
+                "ruleset",
+                Entity::Tournament(tournament_id)
+            )?,
+            rating_exempt: row.get("match_rating_exempt"),
             games: Vec::new()
-        }
+        };
+
+        Ok((match_, start_time.is_some()))
     }
 
-    fn game_from_row(row: &Row) -> Game {
-        Game {
-            id: row.get("game_id"),
-            ruleset: Ruleset::try_from(row.get::<_, i32>("game_ruleset")).unwrap(),
+    fn game_from_row(row: &Row) -> Result<Game, DatabaseError> {
+        let id = row.get("game_id");
+        Ok(Game {
+            id,
+            ruleset: parse_stored(row.get("game_ruleset"), "ruleset", Entity::Game(id))?,
             start_time: row.get("game_start_time"),
             end_time: row.get("game_end_time"),
             scores: Vec::new()
-        }
+        })
     }
 
     fn score_from_row(row: &Row) -> GameScore {
@@ -186,54 +800,98 @@ impl DbClient {
             player_id: row.get("game_score_player_id"),
             game_id: row.get("game_score_game_id"),
             score: row.get("game_score_score"),
-            placement: row.get("game_score_placement")
+            placement: row.get("game_score_placement"),
+            team: row.get("game_score_team")
         }
     }
 
-    pub async fn get_players(&self) -> Vec<Player> {
+    pub async fn get_players(&self) -> Result<Vec<Player>, DatabaseError> {
         println!("Fetching players...");
-        let mut players: Vec<Player> = Vec::new();
-        let rows = self
-            .client
-            .query(
-                "SELECT p.id AS player_id, p.username AS username, \
+        let players_query = async {
+            let timer = self.slow_log.query("get_players");
+            let rows = self
+                .client
+                .query(
+                    "SELECT p.id AS player_id, p.username AS username, \
         p.country AS country, prd.ruleset AS ruleset, prd.earliest_global_rank AS earliest_global_rank,\
           prd.global_rank AS global_rank FROM players p \
-        LEFT JOIN player_osu_ruleset_data prd ON prd.player_id = p.id",
-                &[]
-            )
-            .await
-            .unwrap();
+        LEFT JOIN player_osu_ruleset_data prd ON prd.player_id = p.id \
+        ORDER BY p.id",
+                    &[]
+                )
+                .await
+                .query("get_players")?;
+            timer.finish(rows.len());
+            Ok(rows)
+        };
+        let (rank_history, rows) = tokio::join!(self.get_rank_history(), players_query);
+        let (mut rank_history, rows) = (rank_history?, rows?);
+
+        let mut players: Vec<Player> = Vec::new();
 
         let mut current_player_id = -1;
         for row in rows {
-            if row.get::<_, i32>("player_id") != current_player_id {
+            let player_id = row.get::<_, i32>("player_id");
+            let data = self.ruleset_data_from_row(&row).map(|mut data| {
+                data.rank_history = rank_history.remove(&(player_id, data.ruleset)).unwrap_or_default();
+                data
+            });
+
+            if player_id != current_player_id {
                 let player = Player {
-                    id: row.get("player_id"),
+                    id: player_id,
                     username: row.get("username"),
                     country: row.get("country"),
-                    ruleset_data: self.ruleset_data_from_row(&row).map(|data| vec![data])
+                    ruleset_data: data.map(|data| vec![data])
                 };
                 players.push(player);
-                current_player_id = row.get("player_id");
-            } else {
+                current_player_id = player_id;
+            } else if let Some(ruleset_data) = data {
                 // Same player, new ruleset data
-
-                let data = self.ruleset_data_from_row(&row);
-                if let Some(ruleset_data) = data {
-                    players
-                        .last_mut()
-                        .unwrap()
-                        .ruleset_data
-                        .clone()
-                        .unwrap_or_default()
-                        .push(ruleset_data);
-                }
+                players
+                    .last_mut()
+                    .unwrap()
+                    .ruleset_data
+                    .get_or_insert_with(Vec::new)
+                    .push(ruleset_data);
             }
         }
 
         println!("Players fetched");
-        players
+        Ok(players)
+    }
+
+    /// Fetches the recorded global rank history of every player, keyed by (player, ruleset)
+    /// and ordered by timestamp
+    async fn get_rank_history(&self) -> Result<HashMap<(i32, Ruleset), Vec<RankHistoryPoint>>, DatabaseError> {
+        let timer = self.slow_log.query("get_rank_history");
+        let rows = self
+            .client
+            .query(
+                "SELECT player_id, ruleset, global_rank, timestamp FROM player_rank_history \
+        ORDER BY player_id, ruleset, timestamp",
+                &[]
+            )
+            .await
+            .query("get_rank_history")?;
+        timer.finish(rows.len());
+
+        let mut history: HashMap<(i32, Ruleset), Vec<RankHistoryPoint>> = HashMap::new();
+        for row in rows {
+            let Ok(ruleset) = Ruleset::try_from(row.get::<_, i32>("ruleset")) else {
+                continue;
+            };
+
+            history
+                .entry((row.get("player_id"), ruleset))
+                .or_default()
+                .push(RankHistoryPoint {
+                    timestamp: row.get("timestamp"),
+                    global_rank: row.get("global_rank")
+                });
+        }
+
+        Ok(history)
     }
 
     fn ruleset_data_from_row(&self, row: &Row) -> Option<RulesetData> {
@@ -241,229 +899,1210 @@ impl DbClient {
         let global_rank = row.try_get::<_, i32>("global_rank");
         let earliest_global_rank = row.try_get::<_, Option<i32>>("earliest_global_rank");
 
-        if ruleset.is_ok() && global_rank.is_ok() && earliest_global_rank.is_ok() {
-            let parsed_ruleset = Ruleset::try_from(ruleset.unwrap());
-            if parsed_ruleset.is_err() {
-                // Return nothing
-                return None;
-            }
+        if let (Ok(ruleset), Ok(global_rank), Ok(earliest_global_rank)) = (ruleset, global_rank, earliest_global_rank) {
+            // Return nothing if the ruleset cannot be parsed
+            let parsed_ruleset = Ruleset::try_from(ruleset).ok()?;
 
             return Some(RulesetData {
-                ruleset: parsed_ruleset.unwrap(),
-                global_rank: global_rank.unwrap(),
-                earliest_global_rank: earliest_global_rank.unwrap()
+                ruleset: parsed_ruleset,
+                global_rank,
+                earliest_global_rank,
+                rank_history: Vec::new()
             });
         }
 
         None
     }
 
-    pub async fn save_results(&self, player_ratings: &[PlayerRating]) {
-        self.truncate_table("rating_adjustments").await;
-        self.truncate_table("player_ratings").await;
-        self.truncate_table("player_tournament_stats").await;
-
-        self.save_ratings_and_adjustments_with_mapping(&player_ratings).await;
-
-        self.insert_or_update_highest_ranks(player_ratings).await;
-    }
-
-    async fn save_ratings_and_adjustments_with_mapping(&self, player_ratings: &&[PlayerRating]) {
-        let p_bar = progress_bar(player_ratings.len() as u64, "Saving player ratings to db".to_string()).unwrap();
+    /// Moves legacy ManiaOther ratings, their adjustments and highest ranks into the rulesets
+    /// assigned by `migration`.
+    ///
+    /// Assignments of players who already have a rating in the target ruleset are skipped and
+    /// recorded in `migration.conflicts`. Ranks and percentiles of moved ratings are stale
+    /// until the next run.
+    pub async fn migrate_mania_other(&self, migration: &mut ManiaMigration) -> Result<(), DatabaseError> {
+        let player_ids = migration.assignments.iter().map(|a| a.player_id).collect_vec();
+        let existing: HashSet<(i32, i32)> = self
+            .client
+            .query(
+                "SELECT player_id, ruleset FROM player_ratings WHERE player_id = ANY($1) AND ruleset IN ($2, $3)",
+                &[&player_ids, &(Ruleset::Mania4k as i32), &(Ruleset::Mania7k as i32)]
+            )
+            .await
+            .query("migrate_mania_other")?
+            .iter()
+            .map(|row| (row.get("player_id"), row.get("ruleset")))
+            .collect();
 
-        let mut mapping: HashMap<i32, Vec<RatingAdjustment>> = HashMap::new();
-        let parent_ids = self.save_player_ratings(player_ratings).await;
+        let (conflicting, movable): (Vec<_>, Vec<_>) = migration
+            .assignments
+            .iter()
+            .partition(|a| existing.contains(&(a.player_id, a.target as i32)));
+        migration.conflicts = conflicting.iter().map(|a| a.player_id).collect();
+
+        // Sent as a single query string, which Postgres runs in a single transaction, so that
+        // a failure leaves every table unchanged
+        let mut updates = Vec::new();
+        let mut moved = Vec::new();
+        for target in [Ruleset::Mania4k, Ruleset::Mania7k] {
+            let ids = movable
+                .iter()
+                .filter(|a| a.target == target)
+                .map(|a| a.player_id)
+                .collect_vec();
+            if ids.is_empty() {
+                continue;
+            }
 
-        p_bar.inc(1);
-        p_bar.finish();
+            for table in [
+                "player_ratings",
+                "rating_adjustments",
+                "player_highest_ranks",
+                "player_percentile_milestones"
+            ] {
+                updates.push(format!(
+                    "UPDATE {} SET ruleset = {} WHERE ruleset = {} AND player_id = ANY(ARRAY[{}]::int[]);",
+                    table,
+                    target as i32,
+                    Ruleset::ManiaOther as i32,
+                    ids.iter().join(",")
+                ));
+            }
+            moved.push((target, ids.len()));
+        }
 
-        for (i, rating) in player_ratings.iter().enumerate() {
-            let parent_id = parent_ids.get(i).unwrap();
-            mapping.insert(*parent_id, rating.adjustments.clone());
+        if !updates.is_empty() {
+            self.client
+                .batch_execute(&updates.join(" "))
+                .await
+                .query("migrate_mania_other")?;
+        }
+        for (target, count) in moved {
+            println!("Moved {} ManiaOther ratings to {:?}", count, target);
         }
 
-        println!("Adjustment parent_id mapping created");
+        Ok(())
+    }
 
-        self.save_rating_adjustments(&mapping).await;
+    /// Counts the orphaned rows of every table which `prune_orphans` deletes from, see `ORPHANS`
+    pub async fn count_orphans(&self) -> Result<Vec<(&'static str, i64)>, DatabaseError> {
+        let mut counts = Vec::with_capacity(ORPHANS.len());
+        for (table, condition) in ORPHANS {
+            let row = self
+                .client
+                .query_one(&format!("SELECT COUNT(*) FROM {} WHERE {}", table, condition), &[])
+                .await
+                .query("count_orphans")?;
+            counts.push((table, row.get(0)));
+        }
 
-        println!("Rating adjustments saved");
+        Ok(counts)
     }
 
-    /// Save all rating adjustments in a single batch query
-    async fn save_rating_adjustments(&self, adjustment_mapping: &HashMap<i32, Vec<RatingAdjustment>>) {
-        // Prepare the base query
-        let base_query = "INSERT INTO rating_adjustments (player_id, ruleset, player_rating_id, match_id, \
-        rating_before, rating_after, volatility_before, volatility_after, timestamp, adjustment_type) \
-        VALUES ";
+    /// Deletes the orphaned rows of every table in a single transaction, see `ORPHANS`
+    pub async fn prune_orphans(&self) -> Result<(), DatabaseError> {
+        let deletes = ORPHANS
+            .iter()
+            .map(|(table, condition)| format!("DELETE FROM {} WHERE {};", table, condition))
+            .join(" ");
 
-        // Collect parameters for batch insertion
-        let mut values: Vec<String> = Vec::new();
+        let timer = self.slow_log.query("prune_orphans");
+        self.client
+            .batch_execute(&format!("BEGIN; {} COMMIT;", deletes))
+            .await
+            .query("prune_orphans")?;
+        timer.finish(ORPHANS.len());
 
-        let p_bar = progress_bar(
-            adjustment_mapping.len() as u64,
-            "Creating rating adjustment queries".to_string()
-        )
-        .unwrap();
-        for (player_rating_id, adjustments) in adjustment_mapping.iter() {
-            for adjustment in adjustments {
-                // Create a tuple for each adjustment
-                let match_id = adjustment.match_id.map_or("NULL".to_string(), |id| id.to_string());
-
-                let value_tuple = format!(
-                    "({}, {}, {}, {}, {}, {}, {}, {}, '{}', {})",
-                    adjustment.player_id,
-                    adjustment.ruleset as i32,
-                    player_rating_id,
-                    match_id,
-                    adjustment.rating_before,
-                    adjustment.rating_after,
-                    adjustment.volatility_before,
-                    adjustment.volatility_after,
-                    adjustment.timestamp.format("%Y-%m-%d %H:%M:%S"), // Assuming timestamp is NaiveDateTime
-                    adjustment.adjustment_type as i32
-                );
-                values.push(value_tuple);
-            }
+        Ok(())
+    }
 
-            p_bar.inc(1);
-        }
+    /// Fetches the ids of players who must never be rated (e.g. bots or staff accounts)
+    pub async fn get_excluded_players(&self) -> Result<HashSet<i32>, DatabaseError> {
+        let rows = self
+            .client
+            .query("SELECT player_id FROM excluded_players", &[])
+            .await
+            .query("get_excluded_players")?;
 
-        p_bar.finish();
+        Ok(rows.iter().map(|row| row.get("player_id")).collect())
+    }
+
+    /// Fetches the periods during which players were restricted from playing
+    pub async fn get_player_restrictions(&self) -> Result<Vec<PlayerRestriction>, DatabaseError> {
+        let timer = self.slow_log.query("get_player_restrictions");
+        let rows = self
+            .client
+            .query("SELECT player_id, starts_at, ends_at FROM player_restrictions", &[])
+            .await
+            .query("get_player_restrictions")?;
+        timer.finish(rows.len());
 
-        // Combine the query with all the values
-        let full_query = format!("{}{}", base_query, values.join(", "));
-        let empty: Vec<String> = Vec::new();
+        Ok(rows
+            .iter()
+            .map(|row| PlayerRestriction {
+                player_id: row.get("player_id"),
+                starts_at: row.get("starts_at"),
+                ends_at: row.get("ends_at")
+            })
+            .collect())
+    }
 
-        // Execute the batch query
-        self.client
-            .execute_raw(&full_query, &empty)
+    /// Fetches the manual adjustments taking effect within `range`, in chronological order
+    pub async fn get_manual_adjustments(&self, range: &DateRange) -> Result<Vec<ManualAdjustment>, DatabaseError> {
+        let timer = self.slow_log.query("get_manual_adjustments");
+        let rows = self
+            .client
+            .query(
+                "SELECT id, player_id, ruleset, timestamp, kind, value FROM manual_adjustments \
+        WHERE ($1::timestamptz IS NULL OR timestamp >= $1) AND ($2::timestamptz IS NULL OR timestamp <= $2) \
+        ORDER BY timestamp, id",
+                &[&range.from, &range.to]
+            )
             .await
-            .expect("Failed to execute bulk insert");
+            .query("get_manual_adjustments")?;
+        timer.finish(rows.len());
+
+        rows.iter()
+            .map(|row| {
+                let id = row.get("id");
+                Ok(ManualAdjustment {
+                    id,
+                    player_id: row.get("player_id"),
+                    ruleset: parse_stored(row.get("ruleset"), "ruleset", Entity::ManualAdjustment(id))?,
+                    timestamp: row.get("timestamp"),
+                    kind: parse_stored(row.get("kind"), "kind", Entity::ManualAdjustment(id))?,
+                    value: row.get("value")
+                })
+            })
+            .collect()
     }
 
-    /// Saves multiple PlayerRatings, returning a vector of primary keys
-    async fn save_player_ratings(&self, player_ratings: &[PlayerRating]) -> Vec<i32> {
-        // Create a list of value placeholders
-        let mut query = "INSERT INTO player_ratings (player_id, ruleset, rating, volatility, \
-                     percentile, global_rank, country_rank) VALUES"
-            .to_string();
-        let mut value_placeholders: Vec<String> = Vec::new();
+    fn rating_adjustment_from_row(row: &Row) -> Result<RatingAdjustment, DatabaseError> {
+        let player_id = row.get("player_id");
+        Ok(RatingAdjustment {
+            player_id,
+            ruleset: parse_stored(row.get("ruleset"), "ruleset", Entity::Player(player_id))?,
+            match_id: row.get("match_id"),
+            rating_before: row.get("rating_before"),
+            rating_after: row.get("rating_after"),
+            volatility_before: row.get("volatility_before"),
+            volatility_after: row.get("volatility_after"),
+            timestamp: row.get("timestamp"),
+            adjustment_type: parse_stored(row.get("adjustment_type"), "adjustment_type", Entity::Player(player_id))?,
+            average_opponent_rating: row.get("average_opponent_rating"),
+            provisional: row.get("provisional"),
+            percentile: row.get("percentile"),
+            clamps: RatingClamps(row.get("clamps"))
+        })
+    }
 
-        for rating in player_ratings.iter() {
-            // Directly embed the values into the query string
-            value_placeholders.push(format!(
-                "({}, {}, {}, {}, {}, {}, {})",
-                rating.player_id,
-                rating.ruleset as i32,
-                rating.rating,
-                rating.volatility,
-                rating.percentile,
-                rating.global_rank,
-                rating.country_rank
-            ));
+    /// Saves the results like `save_results`, but writes the player ratings and rating
+    /// adjustments to staging tables which replace the live tables in a short transaction
+    ///
+    /// Readers keep seeing the previous results until the swap, and no transaction is held
+    /// open while millions of rows are copied. The staging tables are created like the live
+    /// ones, including defaults, constraints and indexes. Foreign keys between staged tables
+    /// are recreated once they are filled. Check `staged_save_blockers` first, as foreign
+    /// keys referencing a staged table from elsewhere cannot be carried over.
+    pub async fn save_results_staged(
+        &self,
+        player_ratings: &[PlayerRating],
+        highest_ranks: &HashMap<(i32, Ruleset), PlayerHighestRank>,
+        compress_decay: bool,
+        settlements: Option<&TournamentSettlements>,
+        run_id: i32
+    ) -> Result<(), DatabaseError> {
+        self.truncate_table("player_tournament_stats").await?;
+        self.truncate_table("player_percentile_milestones").await?;
+        if settlements.is_some() {
+            self.truncate_table("tournament_settlement_matches").await?;
         }
 
-        query += &value_placeholders.join(", ");
-        query += " RETURNING id";
+        let foreign_keys = self.staged_foreign_keys().await?;
+        for table in STAGED_TABLES {
+            self.create_staging_table(table).await?;
+        }
 
-        // Execute the batch insert
-        let rows = self.client.query(query.as_str(), &[]).await.unwrap();
+        let parent_ids = self
+            .save_player_ratings(&staging_table("player_ratings"), player_ratings, run_id)
+            .await?;
 
-        // Collect and return the IDs
-        rows.iter().map(|row| row.get("id")).collect()
-    }
+        println!("Player ratings staged");
 
-    async fn insert_or_update_highest_ranks(&self, player_ratings: &[PlayerRating]) {
-        println!("Fetching all highest ranks");
-        let current_highest_ranks = self.get_highest_ranks().await;
+        self.save_rating_adjustments(
+            &staging_table("rating_adjustments"),
+            player_ratings,
+            &parent_ids,
+            &DateRange::default(),
+            compress_decay,
+            settlements,
+            run_id
+        )
+        .await?;
+
+        println!("Rating adjustments staged");
+
+        let timer = self.slow_log.query("add staged foreign keys");
+        for (table, definition) in &foreign_keys {
+            self.client
+                .execute(
+                    format!("ALTER TABLE {} ADD {}", staging_table(table), definition).as_str(),
+                    &[]
+                )
+                .await
+                .query("add staged foreign keys")?;
+        }
+        timer.finish(foreign_keys.len());
 
-        println!("Found {} highest ranks", current_highest_ranks.len());
-        // If the current rank is None, create it. If the current rank is Some and
-        // either the PlayerRating's global rank or country rank is higher than the current highest
-        // rank, update it.
-        //
-        // Only update values which are higher than the current highest rank
+        self.swap_staging_tables().await?;
 
-        let pbar = progress_bar(player_ratings.len() as u64, "Updating highest ranks".to_string()).unwrap();
+        self.insert_or_update_highest_ranks(highest_ranks).await?;
 
-        for rating in player_ratings {
-            if let Some(Some(current_rank)) = current_highest_ranks.get(&(rating.player_id, rating.ruleset)) {
-                if rating.global_rank < current_rank.global_rank {
-                    self.update_highest_rank(rating.player_id, rating).await;
-                }
-            } else {
-                self.insert_highest_rank(rating.player_id, rating).await;
-            }
+        Ok(())
+    }
 
-            pbar.inc(1);
-        }
+    /// Foreign keys and views referencing a staged table from outside the staged tables,
+    /// which would keep referencing the replaced table and prevent `save_results_staged`
+    /// from dropping it
+    pub async fn staged_save_blockers(&self) -> Result<Vec<String>, DatabaseError> {
+        let rows = self
+            .client
+            .query(
+                "SELECT conname AS name, conrelid::regclass::text AS source, confrelid::regclass::text AS target \
+        FROM pg_constraint WHERE contype = 'f' AND confrelid::regclass::text = ANY($1) \
+        AND NOT conrelid::regclass::text = ANY($1) \
+        UNION ALL SELECT 'view', view_name::text, table_name::text FROM information_schema.view_table_usage \
+        WHERE table_schema = current_schema() AND table_name = ANY($1)",
+                &[&STAGED_TABLES.to_vec()]
+            )
+            .await
+            .query("staged_save_blockers")?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                format!(
+                    "{} ({} -> {})",
+                    row.get::<_, String>("name"),
+                    row.get::<_, String>("source"),
+                    row.get::<_, String>("target")
+                )
+            })
+            .collect())
     }
 
-    async fn get_highest_ranks(&self) -> HashMap<(i32, Ruleset), Option<PlayerHighestRank>> {
-        let query = "SELECT * FROM player_highest_ranks";
-        let row = self.client.query(query, &[]).await.ok();
-
-        match row {
-            Some(rows) => {
-                let mut map: HashMap<(i32, Ruleset), Option<PlayerHighestRank>> = HashMap::new();
-                for row in rows {
-                    let player_id = row.get::<_, i32>("player_id");
-                    let ruleset = Ruleset::try_from(row.get::<_, i32>("ruleset")).unwrap();
-                    map.insert(
-                        (player_id, ruleset),
-                        Some(PlayerHighestRank {
-                            id: row.get("id"),
-                            player_id,
-                            global_rank: row.get("global_rank"),
-                            global_rank_date: row.get("global_rank_date"),
-                            country_rank: row.get("country_rank"),
-                            country_rank_date: row.get("country_rank_date"),
-                            ruleset
-                        })
+    /// The sequences owned by a column of a staged table (i.e. serial columns), as
+    /// `(sequence, table, column)`. The staging tables share them through their defaults, so
+    /// ownership must move to the staging table before the replaced table is dropped.
+    async fn staged_owned_sequences(&self) -> Result<Vec<(String, String, String)>, DatabaseError> {
+        let rows = self
+            .client
+            .query(
+                "SELECT s.oid::regclass::text AS sequence, d.refobjid::regclass::text AS source, a.attname::text AS column \
+        FROM pg_depend d JOIN pg_class s ON s.oid = d.objid AND s.relkind = 'S' \
+        JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid \
+        WHERE d.deptype = 'a' AND d.refobjid::regclass::text = ANY($1)",
+                &[&STAGED_TABLES.to_vec()]
+            )
+            .await
+            .query("staged_owned_sequences")?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("sequence"), row.get("source"), row.get("column")))
+            .collect())
+    }
+
+    /// The foreign keys of the staged tables, as `(table, definition)`, with references to
+    /// other staged tables pointing at their staging table instead
+    async fn staged_foreign_keys(&self) -> Result<Vec<(String, String)>, DatabaseError> {
+        let rows = self
+            .client
+            .query(
+                "SELECT conrelid::regclass::text AS source, pg_get_constraintdef(oid) AS definition \
+        FROM pg_constraint WHERE contype = 'f' AND conrelid::regclass::text = ANY($1) ORDER BY conname",
+                &[&STAGED_TABLES.to_vec()]
+            )
+            .await
+            .query("staged_foreign_keys")?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let mut definition: String = row.get("definition");
+                for table in STAGED_TABLES {
+                    definition = definition.replace(
+                        &format!("REFERENCES {}(", table),
+                        &format!("REFERENCES {}(", staging_table(table))
                     );
                 }
 
-                map
+                (row.get("source"), definition)
+            })
+            .collect())
+    }
+
+    /// Creates an empty staging table shaped like `table`, dropping any left behind by an
+    /// interrupted run
+    async fn create_staging_table(&self, table: &str) -> Result<(), DatabaseError> {
+        let staging = staging_table(table);
+        self.client
+            .batch_execute(&format!(
+                "DROP TABLE IF EXISTS {staging}; CREATE TABLE {staging} (LIKE {table} INCLUDING ALL);"
+            ))
+            .await
+            .query("create_staging_table")?;
+
+        println!("Created the {} staging table", staging);
+
+        Ok(())
+    }
+
+    /// Replaces the live tables with their staging tables in a single transaction, then drops
+    /// the replaced tables
+    async fn swap_staging_tables(&self) -> Result<(), DatabaseError> {
+        let renames = STAGED_TABLES
+            .iter()
+            .map(|table| {
+                format!(
+                    "ALTER TABLE {table} RENAME TO {table}_old; ALTER TABLE {} RENAME TO {table};",
+                    staging_table(table)
+                )
+            })
+            .chain(
+                self.staged_owned_sequences()
+                    .await?
+                    .into_iter()
+                    .map(|(sequence, table, column)| format!("ALTER SEQUENCE {sequence} OWNED BY {table}.{column};"))
+            )
+            .join(" ");
+        let old_tables = STAGED_TABLES.iter().map(|table| format!("{}_old", table)).join(", ");
+
+        let timer = self.slow_log.query("swap staging tables");
+        self.client
+            .batch_execute(&format!("BEGIN; {} COMMIT;", renames))
+            .await
+            .query("swap staging tables")?;
+        timer.finish(STAGED_TABLES.len());
+
+        self.client
+            .batch_execute(&format!("DROP TABLE {};", old_tables))
+            .await
+            .query("swap staging tables")?;
+
+        println!("Swapped in the staged {} tables", STAGED_TABLES.join(" and "));
+
+        Ok(())
+    }
+
+    async fn delete_rating_adjustments_in_range(
+        &self,
+        range: &DateRange,
+        rulesets: &RulesetFilter
+    ) -> Result<(), DatabaseError> {
+        let timer = self.slow_log.query("delete_rating_adjustments_in_range");
+        let deleted = self
+            .client
+            .execute(
+                "DELETE FROM rating_adjustments \
+        WHERE ($1::timestamptz IS NULL OR timestamp >= $1) AND ($2::timestamptz IS NULL OR timestamp <= $2) \
+        AND ($3::int[] IS NULL OR ruleset = ANY($3))",
+                &[&range.from, &range.to, &rulesets.ids()]
+            )
+            .await
+            .query("delete_rating_adjustments_in_range")?;
+        timer.finish(deleted as usize);
+
+        println!("Deleted {} rating adjustments within the processing window", deleted);
+
+        Ok(())
+    }
+
+    async fn delete_settlement_matches_in_range(
+        &self,
+        range: &DateRange,
+        rulesets: &RulesetFilter
+    ) -> Result<(), DatabaseError> {
+        let timer = self.slow_log.query("delete_settlement_matches_in_range");
+        let deleted = self
+            .client
+            .execute(
+                "DELETE FROM tournament_settlement_matches \
+        WHERE ($1::timestamptz IS NULL OR timestamp >= $1) AND ($2::timestamptz IS NULL OR timestamp <= $2) \
+        AND ($3::int[] IS NULL OR ruleset = ANY($3))",
+                &[&range.from, &range.to, &rulesets.ids()]
+            )
+            .await
+            .query("delete_settlement_matches_in_range")?;
+        timer.finish(deleted as usize);
+
+        println!(
+            "Deleted {} settled match adjustments within the processing window",
+            deleted
+        );
+
+        Ok(())
+    }
+
+    /// Updates existing player ratings in place, inserting those that do not exist yet,
+    /// and returns the primary key of each rating in order
+    ///
+    /// Both the updates and the inserts are sent as a single statement. Without
+    /// `update_existing` the stored ratings are left untouched and only their keys are
+    /// looked up, which keeps the current ratings when a bounded window is saved.
+    async fn upsert_player_ratings(
+        &self,
+        player_ratings: &[PlayerRating],
+        update_existing: bool,
+        run_id: i32
+    ) -> Result<Vec<i32>, DatabaseError> {
+        let timer = self.slow_log.query("upsert_player_ratings");
+        let columns = |ratings: &[&PlayerRating]| {
+            (
+                ratings.iter().map(|r| r.player_id).collect_vec(),
+                ratings.iter().map(|r| r.ruleset as i32).collect_vec(),
+                ratings.iter().map(|r| r.rating).collect_vec(),
+                ratings.iter().map(|r| r.volatility).collect_vec(),
+                ratings.iter().map(|r| r.percentile).collect_vec(),
+                ratings.iter().map(|r| r.global_rank).collect_vec(),
+                ratings.iter().map(|r| r.country_rank).collect_vec()
+            )
+        };
+        let id_of = |row: &Row| {
+            (
+                (row.get::<_, i32>("player_id"), row.get::<_, i32>("ruleset")),
+                row.get::<_, i32>("id")
+            )
+        };
+
+        let (player_ids, rulesets, ratings, volatilities, percentiles, global_ranks, country_ranks) =
+            columns(&player_ratings.iter().collect_vec()[..]);
+        let existing = if update_existing {
+            self.client
+                .query(
+                    "UPDATE player_ratings pr SET rating = u.rating, volatility = u.volatility, \
+            percentile = u.percentile, global_rank = u.global_rank, country_rank = u.country_rank, \
+            processing_run_id = $8 \
+            FROM UNNEST($1::int[], $2::int[], $3::float8[], $4::float8[], $5::float8[], $6::int[], $7::int[]) \
+            AS u(player_id, ruleset, rating, volatility, percentile, global_rank, country_rank) \
+            WHERE pr.player_id = u.player_id AND pr.ruleset = u.ruleset \
+            RETURNING pr.id, pr.player_id, pr.ruleset",
+                    &[
+                        &player_ids,
+                        &rulesets,
+                        &ratings,
+                        &volatilities,
+                        &percentiles,
+                        &global_ranks,
+                        &country_ranks,
+                        &run_id
+                    ]
+                )
+                .await
+        } else {
+            self.client
+                .query(
+                    "SELECT pr.id, pr.player_id, pr.ruleset FROM player_ratings pr \
+            JOIN UNNEST($1::int[], $2::int[]) AS u(player_id, ruleset) \
+            ON pr.player_id = u.player_id AND pr.ruleset = u.ruleset",
+                    &[&player_ids, &rulesets]
+                )
+                .await
+        };
+        let mut ids: HashMap<(i32, i32), i32> = existing.query("upsert_player_ratings")?.iter().map(id_of).collect();
+
+        let new_ratings = player_ratings
+            .iter()
+            .filter(|r| !ids.contains_key(&(r.player_id, r.ruleset as i32)))
+            .collect_vec();
+        if !new_ratings.is_empty() {
+            let (player_ids, rulesets, ratings, volatilities, percentiles, global_ranks, country_ranks) =
+                columns(&new_ratings[..]);
+            let rows = self
+                .client
+                .query(
+                    "INSERT INTO player_ratings (player_id, ruleset, rating, volatility, percentile, global_rank, \
+            country_rank, processing_run_id) \
+            SELECT *, $8::int FROM UNNEST($1::int[], $2::int[], $3::float8[], $4::float8[], $5::float8[], $6::int[], \
+            $7::int[]) RETURNING id, player_id, ruleset",
+                    &[
+                        &player_ids,
+                        &rulesets,
+                        &ratings,
+                        &volatilities,
+                        &percentiles,
+                        &global_ranks,
+                        &country_ranks,
+                        &run_id
+                    ]
+                )
+                .await
+                .query("upsert_player_ratings")?;
+            ids.extend(rows.iter().map(id_of));
+        }
+        timer.finish(player_ratings.len());
+
+        println!(
+            "Upserted {} player ratings, {} of them new",
+            player_ratings.len(),
+            new_ratings.len()
+        );
+
+        player_ratings
+            .iter()
+            .map(|r| {
+                ids.get(&(r.player_id, r.ruleset as i32))
+                    .copied()
+                    .ok_or_else(|| DatabaseError::MissingRow {
+                        query: "upsert_player_ratings".to_string(),
+                        entity: Entity::Player(r.player_id)
+                    })
+            })
+            .collect()
+    }
+
+    async fn save_ratings_and_adjustments(
+        &self,
+        player_ratings: &[PlayerRating],
+        compress_decay: bool,
+        settlements: Option<&TournamentSettlements>,
+        run_id: i32
+    ) -> Result<(), DatabaseError> {
+        let parent_ids = self
+            .save_player_ratings("player_ratings", player_ratings, run_id)
+            .await?;
+
+        println!("Player ratings saved");
+
+        self.save_rating_adjustments(
+            "rating_adjustments",
+            player_ratings,
+            &parent_ids,
+            &DateRange::default(),
+            compress_decay,
+            settlements,
+            run_id
+        )
+        .await?;
+
+        println!("Rating adjustments saved");
+
+        Ok(())
+    }
+
+    /// Streams the adjustments of every rating within `range` into `table` with a binary COPY
+    ///
+    /// `parent_ids` holds the primary key of each rating in order. Adjustments are written
+    /// straight from the ratings, so no copy of the full adjustment history is built in memory.
+    ///
+    /// If `compress_decay` is set, each run of consecutive decay adjustments is stored as a
+    /// single row along with the number of adjustments and the timestamp of the first one
+    ///
+    /// If `settlements` are given, each run of consecutive match adjustments of a tournament
+    /// is stored as a single settlement, and the settled match adjustments are kept in the
+    /// tournament_settlement_matches table instead
+    ///
+    /// Every row is stored along with `run_id`, the run which produced it
+    #[allow(clippy::too_many_arguments)]
+    async fn save_rating_adjustments(
+        &self,
+        table: &str,
+        player_ratings: &[PlayerRating],
+        parent_ids: &[i32],
+        range: &DateRange,
+        compress_decay: bool,
+        settlements: Option<&TournamentSettlements>,
+        run_id: i32
+    ) -> Result<(), DatabaseError> {
+        let mut columns = "player_id, ruleset, player_rating_id, match_id, rating_before, rating_after, \
+        volatility_before, volatility_after, timestamp, adjustment_type, average_opponent_rating, provisional, \
+        percentile, clamps, processing_run_id"
+            .to_string();
+        let mut types = vec![
+            Type::INT4,
+            Type::INT4,
+            Type::INT4,
+            Type::INT4,
+            Type::FLOAT8,
+            Type::FLOAT8,
+            Type::FLOAT8,
+            Type::FLOAT8,
+            Type::TIMESTAMPTZ,
+            Type::INT4,
+            Type::FLOAT8,
+            Type::BOOL,
+            Type::FLOAT8,
+            Type::INT4,
+            Type::INT4,
+        ];
+        if compress_decay {
+            columns += ", decay_count, decay_start_timestamp";
+            types.extend([Type::INT4, Type::TIMESTAMPTZ]);
+        }
+
+        let timer = self.slow_log.query("save_rating_adjustments");
+        let sink = self
+            .client
+            .copy_in(&format!("COPY {} ({}) FROM STDIN BINARY", table, columns))
+            .await
+            .query("save_rating_adjustments")?;
+        let mut writer = BatchedCopyWriter::new(sink, &types, self.copy_batch_size);
+
+        let mut settled_matches = Vec::new();
+        let p_bar = progress_bar(player_ratings.len() as u64, "Saving rating adjustments".to_string());
+        for rating_rows in adjustment_rows(player_ratings, parent_ids, range, compress_decay, settlements) {
+            let parent_id = &rating_rows.player_rating_id;
+            settled_matches.extend(rating_rows.settled_matches);
+
+            for row in &rating_rows.rows {
+                let adjustment = &row.adjustment;
+                let ruleset = adjustment.ruleset as i32;
+                let adjustment_type = adjustment.adjustment_type as i32;
+
+                let mut values: Vec<&(dyn ToSql + Sync)> = vec![
+                    &adjustment.player_id,
+                    &ruleset,
+                    parent_id,
+                    &adjustment.match_id,
+                    &adjustment.rating_before,
+                    &adjustment.rating_after,
+                    &adjustment.volatility_before,
+                    &adjustment.volatility_after,
+                    &adjustment.timestamp,
+                    &adjustment_type,
+                    &adjustment.average_opponent_rating,
+                    &adjustment.provisional,
+                    &adjustment.percentile,
+                    &adjustment.clamps.0,
+                    &run_id,
+                ];
+                // Summary rows additionally record the size and start of the decay run
+                if compress_decay {
+                    values.push(&row.count);
+                    values.push(&row.first_timestamp);
+                }
+
+                writer.write(&values).await.query("save_rating_adjustments")?;
             }
-            None => HashMap::new()
+
+            p_bar.inc(1);
+        }
+
+        let written = writer.finish().await.query("save_rating_adjustments")?;
+        p_bar.finish();
+        timer.finish(written as usize);
+
+        if settlements.is_some() {
+            self.save_settled_matches(&settled_matches).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores the match adjustments replaced by tournament settlements, each along with its
+    /// tournament and the time it was settled at
+    async fn save_settled_matches(
+        &self,
+        settled_matches: &[(i32, DateTime<FixedOffset>, RatingAdjustment)]
+    ) -> Result<(), DatabaseError> {
+        let timer = self.slow_log.query("save_settled_matches");
+        let sink = self
+            .client
+            .copy_in(
+                "COPY tournament_settlement_matches (player_id, ruleset, tournament_id, settled_at, match_id, \
+            rating_before, rating_after, volatility_before, volatility_after, timestamp) FROM STDIN BINARY"
+            )
+            .await
+            .query("save_settled_matches")?;
+        let mut writer = BatchedCopyWriter::new(
+            sink,
+            &[
+                Type::INT4,
+                Type::INT4,
+                Type::INT4,
+                Type::TIMESTAMPTZ,
+                Type::INT4,
+                Type::FLOAT8,
+                Type::FLOAT8,
+                Type::FLOAT8,
+                Type::FLOAT8,
+                Type::TIMESTAMPTZ
+            ],
+            self.copy_batch_size
+        );
+
+        for (tournament_id, settled_at, adjustment) in settled_matches {
+            writer
+                .write(&[
+                    &adjustment.player_id,
+                    &(adjustment.ruleset as i32),
+                    tournament_id,
+                    settled_at,
+                    &adjustment.match_id,
+                    &adjustment.rating_before,
+                    &adjustment.rating_after,
+                    &adjustment.volatility_before,
+                    &adjustment.volatility_after,
+                    &adjustment.timestamp
+                ])
+                .await
+                .query("save_settled_matches")?;
+        }
+
+        let written = writer.finish().await.query("save_settled_matches")?;
+        timer.finish(written as usize);
+
+        println!("Saved {} settled match adjustments", written);
+
+        Ok(())
+    }
+
+    /// Saves multiple PlayerRatings, returning a vector of primary keys
+    async fn save_player_ratings(
+        &self,
+        table: &str,
+        player_ratings: &[PlayerRating],
+        run_id: i32
+    ) -> Result<Vec<i32>, DatabaseError> {
+        // Create a list of value placeholders
+        let mut query = format!(
+            "INSERT INTO {} (player_id, ruleset, rating, volatility, \
+                     percentile, global_rank, country_rank, processing_run_id) VALUES",
+            table
+        );
+        let mut value_placeholders: Vec<String> = Vec::new();
+
+        for rating in player_ratings.iter() {
+            // Directly embed the values into the query string
+            value_placeholders.push(format!(
+                "({}, {}, {}, {}, {}, {}, {}, {})",
+                rating.player_id,
+                rating.ruleset as i32,
+                rating.rating,
+                rating.volatility,
+                rating.percentile,
+                rating.global_rank,
+                rating.country_rank,
+                run_id
+            ));
+        }
+
+        query += &value_placeholders.join(", ");
+        query += " RETURNING id";
+
+        // Execute the batch insert
+        let timer = self.slow_log.query("save_player_ratings");
+        let rows = self
+            .client
+            .query(query.as_str(), &[])
+            .await
+            .query("save_player_ratings")?;
+        timer.finish(rows.len());
+
+        // Collect and return the IDs
+        Ok(rows.iter().map(|row| row.get("id")).collect())
+    }
+
+    /// Stores the highest ranks reached during the run, keeping stored ranks which are better.
+    /// Global and country ranks are compared independently, each keeping the date it was reached.
+    async fn insert_or_update_highest_ranks(
+        &self,
+        highest_ranks: &HashMap<(i32, Ruleset), PlayerHighestRank>
+    ) -> Result<(), DatabaseError> {
+        println!("Fetching all highest ranks");
+        let current_highest_ranks = self.get_highest_ranks().await?;
+
+        println!("Found {} highest ranks", current_highest_ranks.len());
+
+        let pbar = progress_bar(highest_ranks.len() as u64, "Updating highest ranks".to_string());
+        let timer = self.slow_log.query("insert_or_update_highest_ranks");
+
+        for (key, highest) in highest_ranks {
+            if let Some(Some(current)) = current_highest_ranks.get(key) {
+                let merged = merge_highest_ranks(current, highest);
+                if merged != *current {
+                    self.update_highest_rank(&merged).await?;
+                }
+            } else {
+                self.insert_highest_rank(highest).await?;
+            }
+
+            pbar.inc(1);
         }
+
+        timer.finish(highest_ranks.len());
+
+        Ok(())
     }
 
-    async fn insert_highest_rank(&self, player_id: i32, player_rating: &PlayerRating) {
-        let timestamp = player_rating.adjustments.last().unwrap().timestamp;
-        let query = "INSERT INTO player_highest_ranks (player_id, ruleset, global_rank, global_rank_date, country_rank, country_rank_date) VALUES ($1, $2, $3, $4, $5, $6)";
+    async fn get_highest_ranks(&self) -> Result<HashMap<(i32, Ruleset), Option<PlayerHighestRank>>, DatabaseError> {
+        let query = "SELECT * FROM player_highest_ranks";
+        let rows = self.client.query(query, &[]).await.query("get_highest_ranks")?;
+
+        let mut map: HashMap<(i32, Ruleset), Option<PlayerHighestRank>> = HashMap::new();
+        for row in rows {
+            let player_id = row.get::<_, i32>("player_id");
+            let ruleset = parse_stored(row.get("ruleset"), "ruleset", Entity::Player(player_id))?;
+            map.insert(
+                (player_id, ruleset),
+                Some(PlayerHighestRank {
+                    id: row.get("id"),
+                    player_id,
+                    global_rank: row.get("global_rank"),
+                    global_rank_date: row.get("global_rank_date"),
+                    country_rank: row.get("country_rank"),
+                    country_rank_date: row.get("country_rank_date"),
+                    percentile: row.get("percentile"),
+                    percentile_date: row.get("percentile_date"),
+                    ruleset
+                })
+            );
+        }
+
+        Ok(map)
+    }
+
+    async fn insert_highest_rank(&self, highest_rank: &PlayerHighestRank) -> Result<(), DatabaseError> {
+        let query = "INSERT INTO player_highest_ranks (player_id, ruleset, global_rank, global_rank_date, country_rank, country_rank_date, percentile, percentile_date) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)";
         let values: &[&(dyn ToSql + Sync)] = &[
-            &player_id,
-            &(player_rating.ruleset as i32),
-            &player_rating.global_rank,
-            &timestamp,
-            &player_rating.country_rank,
-            &timestamp
+            &highest_rank.player_id,
+            &(highest_rank.ruleset as i32),
+            &highest_rank.global_rank,
+            &highest_rank.global_rank_date,
+            &highest_rank.country_rank,
+            &highest_rank.country_rank_date,
+            &highest_rank.percentile,
+            &highest_rank.percentile_date
         ];
 
-        self.client.execute(query, values).await.unwrap();
+        self.client.execute(query, values).await.query("insert_highest_rank")?;
+
+        Ok(())
     }
 
-    async fn update_highest_rank(&self, player_id: i32, player_rating: &PlayerRating) {
-        let timestamp = player_rating.adjustments.last().unwrap().timestamp;
-        let query = "UPDATE player_highest_ranks SET global_rank = $1, global_rank_date = $2, country_rank = $3, country_rank_date = $4 WHERE player_id = $5 AND ruleset = $6";
+    async fn update_highest_rank(&self, highest_rank: &PlayerHighestRank) -> Result<(), DatabaseError> {
+        let query = "UPDATE player_highest_ranks SET global_rank = $1, global_rank_date = $2, country_rank = $3, country_rank_date = $4, percentile = $5, percentile_date = $6 WHERE player_id = $7 AND ruleset = $8";
         let values: &[&(dyn ToSql + Sync)] = &[
-            &player_rating.global_rank,
-            &timestamp,
-            &player_rating.country_rank,
-            &timestamp,
-            &player_id,
-            &(player_rating.ruleset as i32)
+            &highest_rank.global_rank,
+            &highest_rank.global_rank_date,
+            &highest_rank.country_rank,
+            &highest_rank.country_rank_date,
+            &highest_rank.percentile,
+            &highest_rank.percentile_date,
+            &highest_rank.player_id,
+            &(highest_rank.ruleset as i32)
         ];
 
-        self.client.execute(query, values).await.unwrap();
+        self.client.execute(query, values).await.query("update_highest_rank")?;
+
+        Ok(())
+    }
+
+    /// Stores the percentile milestones reached during the run. A milestone which is already
+    /// stored keeps the earlier of the two dates, as date-restricted runs only see part of
+    /// the history.
+    pub async fn save_percentile_milestones(&self, milestones: &[PercentileMilestone]) -> Result<(), DatabaseError> {
+        let timer = self.slow_log.query("save_percentile_milestones");
+        let p_bar = progress_bar(milestones.len() as u64, "Saving percentile milestones".to_string());
+
+        let query = "INSERT INTO player_percentile_milestones (player_id, ruleset, top_percent, reached_at) \
+        VALUES ($1, $2, $3, $4) ON CONFLICT (player_id, ruleset, top_percent) \
+        DO UPDATE SET reached_at = LEAST(player_percentile_milestones.reached_at, EXCLUDED.reached_at)";
+        for milestone in milestones {
+            self.client
+                .execute(
+                    query,
+                    &[
+                        &milestone.player_id,
+                        &(milestone.ruleset as i32),
+                        &milestone.top_percent,
+                        &milestone.reached_at
+                    ]
+                )
+                .await
+                .query("save_percentile_milestones")?;
+
+            p_bar.inc(1);
+        }
+
+        p_bar.finish();
+        timer.finish(milestones.len());
+
+        Ok(())
+    }
+
+    /// Replaces the stored activity of the processed rulesets
+    pub async fn save_player_activity(
+        &self,
+        activities: &[PlayerActivity],
+        rulesets: &RulesetFilter
+    ) -> Result<(), DatabaseError> {
+        match rulesets.ids() {
+            None => self.truncate_table("player_activity").await?,
+            Some(ids) => self.delete_rulesets("player_activity", &ids).await?
+        }
+
+        let timer = self.slow_log.query("save_player_activity");
+        let sink = self
+            .client
+            .copy_in("COPY player_activity (player_id, ruleset, activity) FROM STDIN BINARY")
+            .await
+            .query("save_player_activity")?;
+        let mut writer = pin!(BinaryCopyInWriter::new(sink, &[Type::INT4, Type::INT4, Type::INT4]));
+
+        for activity in activities {
+            writer
+                .as_mut()
+                .write(&[
+                    &activity.player_id,
+                    &(activity.ruleset as i32),
+                    &(activity.activity as i32)
+                ])
+                .await
+                .query("save_player_activity")?;
+        }
+
+        let written = writer.as_mut().finish().await.query("save_player_activity")?;
+        timer.finish(written as usize);
+
+        println!("Saved the activity of {} player ratings", written);
+
+        Ok(())
     }
 
-    pub async fn roll_forward_processing_statuses(&self, matches: &[Match]) {
+    /// Replaces the stored lobby statistics of the processed rulesets
+    pub async fn save_player_lobby_stats(
+        &self,
+        stats: &[PlayerLobbyStats],
+        rulesets: &RulesetFilter
+    ) -> Result<(), DatabaseError> {
+        match rulesets.ids() {
+            None => self.truncate_table("player_lobby_stats").await?,
+            Some(ids) => self.delete_rulesets("player_lobby_stats", &ids).await?
+        }
+
+        let timer = self.slow_log.query("save_player_lobby_stats");
+        let sink = self
+            .client
+            .copy_in(
+                "COPY player_lobby_stats (player_id, ruleset, games, average_lobby_size, average_team_size) \
+            FROM STDIN BINARY"
+            )
+            .await
+            .query("save_player_lobby_stats")?;
+        let mut writer = pin!(BinaryCopyInWriter::new(
+            sink,
+            &[Type::INT4, Type::INT4, Type::INT4, Type::FLOAT8, Type::FLOAT8]
+        ));
+
+        for stat in stats {
+            writer
+                .as_mut()
+                .write(&[
+                    &stat.player_id,
+                    &(stat.ruleset as i32),
+                    &stat.games,
+                    &stat.average_lobby_size,
+                    &stat.average_team_size
+                ])
+                .await
+                .query("save_player_lobby_stats")?;
+        }
+
+        let written = writer.as_mut().finish().await.query("save_player_lobby_stats")?;
+        timer.finish(written as usize);
+
+        println!("Saved the lobby statistics of {} players", written);
+
+        Ok(())
+    }
+
+    /// Replaces the stored overall ratings with those of the current run
+    pub async fn save_overall_ratings(&self, overall_ratings: &[OverallRating]) -> Result<(), DatabaseError> {
+        self.truncate_table("player_overall_ratings").await?;
+
+        let timer = self.slow_log.query("save_overall_ratings");
+        let sink = self
+            .client
+            .copy_in(
+                "COPY player_overall_ratings (player_id, rating, global_rank, percentile, matches_played) \
+            FROM STDIN BINARY"
+            )
+            .await
+            .query("save_overall_ratings")?;
+        let mut writer = pin!(BinaryCopyInWriter::new(
+            sink,
+            &[Type::INT4, Type::FLOAT8, Type::INT4, Type::FLOAT8, Type::INT4]
+        ));
+
+        for overall in overall_ratings {
+            writer
+                .as_mut()
+                .write(&[
+                    &overall.player_id,
+                    &overall.rating,
+                    &overall.global_rank,
+                    &overall.percentile,
+                    &(overall.matches_played as i32)
+                ])
+                .await
+                .query("save_overall_ratings")?;
+        }
+
+        let written = writer.as_mut().finish().await.query("save_overall_ratings")?;
+        timer.finish(written as usize);
+
+        println!("Saved {} overall ratings", written);
+
+        Ok(())
+    }
+
+    /// Sets the display rating of the stored player ratings, which must already be saved
+    pub async fn save_display_ratings(&self, display_ratings: &[DisplayRating]) -> Result<(), DatabaseError> {
+        let player_ids = display_ratings.iter().map(|d| d.player_id).collect_vec();
+        let rulesets = display_ratings.iter().map(|d| d.ruleset as i32).collect_vec();
+        let values = display_ratings.iter().map(|d| d.display_rating).collect_vec();
+
+        let timer = self.slow_log.query("save_display_ratings");
+        let updated = self
+            .client
+            .execute(
+                "UPDATE player_ratings pr SET display_rating = d.display_rating \
+        FROM UNNEST($1::int[], $2::int[], $3::float8[]) AS d(player_id, ruleset, display_rating) \
+        WHERE pr.player_id = d.player_id AND pr.ruleset = d.ruleset",
+                &[&player_ids, &rulesets, &values]
+            )
+            .await
+            .query("save_display_ratings")?;
+        timer.finish(updated as usize);
+
+        println!("Saved {} display ratings", updated);
+
+        Ok(())
+    }
+
+    /// Sets the country rank of the stored player ratings, leaving every other column untouched
+    pub async fn save_country_ranks(&self, ratings: &[&PlayerRating]) -> Result<(), DatabaseError> {
+        let player_ids = ratings.iter().map(|r| r.player_id).collect_vec();
+        let rulesets = ratings.iter().map(|r| r.ruleset as i32).collect_vec();
+        let country_ranks = ratings.iter().map(|r| r.country_rank).collect_vec();
+
+        let timer = self.slow_log.query("save_country_ranks");
+        let updated = self
+            .client
+            .execute(
+                "UPDATE player_ratings pr SET country_rank = c.country_rank \
+        FROM UNNEST($1::int[], $2::int[], $3::int[]) AS c(player_id, ruleset, country_rank) \
+        WHERE pr.player_id = c.player_id AND pr.ruleset = c.ruleset",
+                &[&player_ids, &rulesets, &country_ranks]
+            )
+            .await
+            .query("save_country_ranks")?;
+        timer.finish(updated as usize);
+
+        println!("Saved {} country ranks", updated);
+
+        Ok(())
+    }
+
+    /// Replaces the rank changes of the processed rulesets with those of the current run, for
+    /// the notification service to pick up
+    pub async fn save_rank_changes(
+        &self,
+        rank_changes: &[RankChange],
+        rulesets: &RulesetFilter
+    ) -> Result<(), DatabaseError> {
+        match rulesets.ids() {
+            None => self.truncate_table("player_rank_changes").await?,
+            Some(ids) => self.delete_rulesets("player_rank_changes", &ids).await?
+        }
+
+        let player_ids = rank_changes.iter().map(|c| c.player_id).collect_vec();
+        let rulesets = rank_changes.iter().map(|c| c.ruleset as i32).collect_vec();
+        let old_ranks = rank_changes.iter().map(|c| c.old_rank).collect_vec();
+        let new_ranks = rank_changes.iter().map(|c| c.new_rank).collect_vec();
+        let deltas = rank_changes.iter().map(|c| c.delta).collect_vec();
+        let thresholds = rank_changes.iter().map(|c| c.threshold).collect_vec();
+
+        let timer = self.slow_log.query("save_rank_changes");
+        let inserted = self
+            .client
+            .execute(
+                "INSERT INTO player_rank_changes (player_id, ruleset, old_rank, new_rank, delta, threshold) \
+        SELECT * FROM UNNEST($1::int[], $2::int[], $3::int[], $4::int[], $5::int[], $6::int[])",
+                &[&player_ids, &rulesets, &old_ranks, &new_ranks, &deltas, &thresholds]
+            )
+            .await
+            .query("save_rank_changes")?;
+        timer.finish(inserted as usize);
+
+        println!("Saved {} rank changes", inserted);
+
+        Ok(())
+    }
+
+    /// Stores the detected mod pool of every classified game, replacing any earlier
+    /// classification of the same game
+    pub async fn save_game_mod_categories(&self, categories: &[GameModCategory]) -> Result<(), DatabaseError> {
+        let timer = self.slow_log.query("save_game_mod_categories");
+        let p_bar = progress_bar(categories.len() as u64, "Saving game mod categories".to_string());
+
+        let query = "INSERT INTO game_mod_categories (game_id, category) VALUES ($1, $2) \
+        ON CONFLICT (game_id) DO UPDATE SET category = EXCLUDED.category";
+        for category in categories {
+            self.client
+                .execute(query, &[&category.game_id, &(category.category as i32)])
+                .await
+                .query("save_game_mod_categories")?;
+
+            p_bar.inc(1);
+        }
+
+        p_bar.finish();
+        timer.finish(categories.len());
+
+        Ok(())
+    }
+
+    /// Stores the upset factor of every processed match, replacing any earlier one of the
+    /// same match
+    pub async fn save_match_upsets(&self, upsets: &[MatchUpset]) -> Result<(), DatabaseError> {
+        let match_ids = upsets.iter().map(|u| u.match_id).collect_vec();
+        let factors = upsets.iter().map(|u| u.upset_factor).collect_vec();
+        let inverted = upsets.iter().map(|u| u.inverted_pairs).collect_vec();
+        let compared = upsets.iter().map(|u| u.compared_pairs).collect_vec();
+
+        let timer = self.slow_log.query("save_match_upsets");
+        let saved = self
+            .client
+            .execute(
+                "INSERT INTO match_upsets (match_id, upset_factor, inverted_pairs, compared_pairs) \
+        SELECT * FROM UNNEST($1::int[], $2::float8[], $3::int[], $4::int[]) \
+        ON CONFLICT (match_id) DO UPDATE SET upset_factor = EXCLUDED.upset_factor, \
+        inverted_pairs = EXCLUDED.inverted_pairs, compared_pairs = EXCLUDED.compared_pairs",
+                &[&match_ids, &factors, &inverted, &compared]
+            )
+            .await
+            .query("save_match_upsets")?;
+        timer.finish(saved as usize);
+
+        println!("Saved {} match upsets", saved);
+
+        Ok(())
+    }
+
+    pub async fn roll_forward_processing_statuses(&self, matches: &[Match]) -> Result<(), DatabaseError> {
+        self.roll_forward_match_ids(&matches.iter().map(|f| f.id).collect_vec())
+            .await
+    }
+
+    /// Marks the given matches and their tournaments as processed, see
+    /// `roll_forward_processing_statuses`
+    pub async fn roll_forward_match_ids(&self, match_ids: &[i32]) -> Result<(), DatabaseError> {
         println!("Updating processing status for all matches");
+        let timer = self.slow_log.query("roll_forward_processing_statuses");
 
-        let data = matches.iter().map(|f| f.id).collect_vec();
-        let match_id_str = data.into_iter().join(",");
+        let match_id_str = match_ids.iter().join(",");
 
         // Fetch the tournament ids
         let tournament_fetch_sql = format!(
@@ -476,7 +2115,7 @@ impl DbClient {
             .client
             .query(tournament_fetch_sql.as_str(), &[])
             .await
-            .unwrap()
+            .query("roll_forward_processing_statuses")?
             .iter()
             .map(|f| f.get::<_, i32>("tournament_id"))
             .collect_vec();
@@ -487,7 +2126,10 @@ impl DbClient {
             match_id_str
         );
 
-        self.client.execute(match_update_sql.as_str(), &[]).await.unwrap();
+        self.client
+            .execute(match_update_sql.as_str(), &[])
+            .await
+            .query("roll_forward_processing_statuses")?;
 
         let tournament_id_str = tournament_ids.into_iter().join(",");
         let tournament_update_sql = format!(
@@ -496,23 +2138,345 @@ impl DbClient {
             tournament_id_str
         );
 
-        self.client.execute(tournament_update_sql.as_str(), &[]).await.unwrap();
+        self.client
+            .execute(tournament_update_sql.as_str(), &[])
+            .await
+            .query("roll_forward_processing_statuses")?;
+        timer.finish(match_ids.len());
+
+        Ok(())
+    }
+
+    /// Replaces the contents of the processor_skipped_entities table with the matches and
+    /// games skipped by the current run, so that they can be reviewed and fixed
+    /// Replaces the quarantine entries of the processed matches with the matches skipped by
+    /// this run, so fixed matches leave the quarantine once they are processed again
+    pub async fn save_quarantined_matches(
+        &self,
+        matches: &[Match],
+        quarantined: &[QuarantinedMatch]
+    ) -> Result<(), DatabaseError> {
+        let timer = self.slow_log.query("save_quarantined_matches");
+        let match_ids = matches.iter().map(|m| m.id).collect::<Vec<_>>();
+        self.client
+            .execute(
+                "DELETE FROM processing_quarantine WHERE match_id = ANY($1)",
+                &[&match_ids]
+            )
+            .await
+            .query("save_quarantined_matches")?;
+
+        let query = "INSERT INTO processing_quarantine (match_id, reason, details, quarantined_at) \
+        VALUES ($1, $2, $3, NOW())";
+        for entry in quarantined {
+            self.client
+                .execute(query, &[&entry.match_id, &(entry.reason as i32), &entry.details])
+                .await
+                .query("save_quarantined_matches")?;
+        }
+        timer.finish(quarantined.len());
+
+        println!("Quarantined {} matches", quarantined.len());
+
+        Ok(())
+    }
+
+    pub async fn save_skipped_entities(&self, skipped: &SkippedEntities) -> Result<(), DatabaseError> {
+        self.truncate_table("processor_skipped_entities").await?;
+
+        if skipped.is_empty() {
+            return Ok(());
+        }
+
+        let values = skipped
+            .matches_without_games
+            .iter()
+            .map(|id| format!("('match', {}, 'no games')", id))
+            .chain(
+                skipped
+                    .matches_with_invalid_placements
+                    .iter()
+                    .map(|id| format!("('match', {}, 'invalid placements')", id))
+            )
+            .chain(
+                skipped
+                    .matches_with_too_few_games
+                    .iter()
+                    .map(|id| format!("('match', {}, 'too few games')", id))
+            )
+            .chain(
+                skipped
+                    .games_without_scores
+                    .iter()
+                    .map(|id| format!("('game', {}, 'no scores')", id))
+            )
+            .chain(
+                skipped
+                    .manual_adjustments_without_rating
+                    .iter()
+                    .map(|id| format!("('manual_adjustment', {}, 'no rating')", id))
+            )
+            .join(", ");
+
+        let query = format!(
+            "INSERT INTO processor_skipped_entities (entity_type, entity_id, reason) VALUES {}",
+            values
+        );
+
+        self.client
+            .execute(query.as_str(), &[])
+            .await
+            .query("save_skipped_entities")?;
+
+        Ok(())
     }
 
-    async fn truncate_table(&self, table: &str) {
+    /// Deletes the rows of the given rulesets from a table with a ruleset column, the
+    /// counterpart of `truncate_table` for runs restricted to some rulesets
+    async fn delete_rulesets(&self, table: &str, ruleset_ids: &[i32]) -> Result<(), DatabaseError> {
+        let timer = self.slow_log.query(&format!("delete rulesets {}", table));
+        let deleted = self
+            .client
+            .execute(
+                format!("DELETE FROM {} WHERE ruleset = ANY($1)", table).as_str(),
+                &[&ruleset_ids]
+            )
+            .await
+            .query("delete_rulesets")?;
+        timer.finish(deleted as usize);
+
+        println!(
+            "Deleted {} rows of rulesets {:?} from the {} table",
+            deleted, ruleset_ids, table
+        );
+
+        Ok(())
+    }
+
+    async fn delete_tournament_stats_of_rulesets(&self, ruleset_ids: &[i32]) -> Result<(), DatabaseError> {
+        let timer = self.slow_log.query("delete rulesets player_tournament_stats");
+        let deleted = self
+            .client
+            .execute(
+                "DELETE FROM player_tournament_stats \
+        WHERE tournament_id IN (SELECT id FROM tournaments WHERE ruleset = ANY($1))",
+                &[&ruleset_ids]
+            )
+            .await
+            .query("delete rulesets player_tournament_stats")?;
+        timer.finish(deleted as usize);
+
+        println!(
+            "Deleted {} rows of rulesets {:?} from the player_tournament_stats table",
+            deleted, ruleset_ids
+        );
+
+        Ok(())
+    }
+
+    async fn truncate_table(&self, table: &str) -> Result<(), DatabaseError> {
+        let timer = self.slow_log.query(&format!("truncate {}", table));
         self.client
             .execute(
                 format!("TRUNCATE TABLE {} RESTART IDENTITY CASCADE", table).as_str(),
                 &[]
             )
             .await
-            .unwrap();
+            .query("truncate_table")?;
+        timer.finish(0);
 
         println!("Truncated the {} table!", table);
+
+        Ok(())
     }
+}
 
-    // Access the underlying Client
-    pub fn client(&self) -> Arc<Client> {
-        Arc::clone(&self.client)
+#[async_trait]
+impl<C: Deref<Target = Client> + Send + Sync> ResultStore for DbClient<C> {
+    async fn get_ratings_as_of(&self, timestamp: DateTime<FixedOffset>) -> Result<Vec<PlayerRating>, DatabaseError> {
+        println!("Fetching rating adjustments before {}...", timestamp);
+        let timer = self.slow_log.query("get_ratings_as_of");
+        let rows = self
+            .client
+            .query(
+                "SELECT player_id, ruleset, match_id, rating_before, rating_after, volatility_before, \
+        volatility_after, timestamp, adjustment_type, average_opponent_rating, provisional, percentile, clamps \
+        FROM rating_adjustments \
+        WHERE timestamp < $1 ORDER BY player_id, ruleset, timestamp",
+                &[&timestamp]
+            )
+            .await
+            .query("get_ratings_as_of")?;
+        timer.finish(rows.len());
+
+        let adjustments = rows
+            .iter()
+            .map(Self::rating_adjustment_from_row)
+            .collect::<Result<Vec<_>, _>>()?;
+        let ratings = ratings_from_adjustments(adjustments);
+        println!("Reconstructed {} ratings", ratings.len());
+        Ok(ratings)
+    }
+
+    async fn get_player_ratings(&self) -> Result<Vec<PlayerRating>, DatabaseError> {
+        println!("Fetching stored player ratings...");
+        let timer = self.slow_log.query("get_player_ratings");
+        let rows = self
+            .client
+            .query(
+                "SELECT id, player_id, ruleset, rating, volatility, percentile, global_rank, country_rank \
+        FROM player_ratings",
+                &[]
+            )
+            .await
+            .query("get_player_ratings")?;
+        timer.finish(rows.len());
+
+        rows.iter()
+            .map(|row| {
+                let player_id = row.get("player_id");
+                Ok(PlayerRating {
+                    id: row.get("id"),
+                    player_id,
+                    ruleset: parse_stored(row.get("ruleset"), "ruleset", Entity::Player(player_id))?,
+                    rating: row.get("rating"),
+                    volatility: row.get("volatility"),
+                    percentile: row.get("percentile"),
+                    global_rank: row.get("global_rank"),
+                    country_rank: row.get("country_rank"),
+                    adjustments: Vec::new()
+                })
+            })
+            .collect()
+    }
+
+    async fn get_rating_adjustments(&self, player_ids: &[i32]) -> Result<Vec<RatingAdjustment>, DatabaseError> {
+        let timer = self.slow_log.query("get_rating_adjustments");
+        let rows = self
+            .client
+            .query(
+                "SELECT player_id, ruleset, match_id, rating_before, rating_after, volatility_before, \
+        volatility_after, timestamp, adjustment_type, average_opponent_rating, provisional, percentile, clamps \
+        FROM rating_adjustments \
+        WHERE player_id = ANY($1) ORDER BY player_id, ruleset, timestamp",
+                &[&player_ids]
+            )
+            .await
+            .query("get_rating_adjustments")?;
+        timer.finish(rows.len());
+
+        rows.iter().map(Self::rating_adjustment_from_row).collect()
+    }
+
+    async fn save_results(
+        &self,
+        player_ratings: &[PlayerRating],
+        highest_ranks: &HashMap<(i32, Ruleset), PlayerHighestRank>,
+        rulesets: &RulesetFilter,
+        compress_decay: bool,
+        settlements: Option<&TournamentSettlements>,
+        run_id: i32
+    ) -> Result<(), DatabaseError> {
+        match rulesets.ids() {
+            None => {
+                self.truncate_table("rating_adjustments").await?;
+                self.truncate_table("player_ratings").await?;
+                self.truncate_table("player_tournament_stats").await?;
+                self.truncate_table("player_percentile_milestones").await?;
+                if settlements.is_some() {
+                    self.truncate_table("tournament_settlement_matches").await?;
+                }
+            }
+            Some(ids) => {
+                self.delete_rulesets("rating_adjustments", &ids).await?;
+                self.delete_rulesets("player_ratings", &ids).await?;
+                self.delete_tournament_stats_of_rulesets(&ids).await?;
+                self.delete_rulesets("player_percentile_milestones", &ids).await?;
+                if settlements.is_some() {
+                    self.delete_rulesets("tournament_settlement_matches", &ids).await?;
+                }
+            }
+        }
+
+        self.save_ratings_and_adjustments(player_ratings, compress_decay, settlements, run_id)
+            .await?;
+
+        self.insert_or_update_highest_ranks(highest_ranks).await?;
+
+        Ok(())
+    }
+
+    async fn save_results_in_range(
+        &self,
+        player_ratings: &[PlayerRating],
+        highest_ranks: &HashMap<(i32, Ruleset), PlayerHighestRank>,
+        range: &DateRange,
+        rulesets: &RulesetFilter,
+        compress_decay: bool,
+        settlements: Option<&TournamentSettlements>,
+        run_id: i32
+    ) -> Result<(), DatabaseError> {
+        self.delete_rating_adjustments_in_range(range, rulesets).await?;
+        if settlements.is_some() {
+            self.delete_settlement_matches_in_range(range, rulesets).await?;
+        }
+
+        let parent_ids = self
+            .upsert_player_ratings(player_ratings, range.to.is_none(), run_id)
+            .await?;
+        self.save_rating_adjustments(
+            "rating_adjustments",
+            player_ratings,
+            &parent_ids,
+            range,
+            compress_decay,
+            settlements,
+            run_id
+        )
+        .await?;
+
+        println!("Rating adjustments saved");
+
+        self.insert_or_update_highest_ranks(highest_ranks).await?;
+
+        Ok(())
+    }
+
+    async fn start_run(&self, run: &RunRecord) -> Result<i32, DatabaseError> {
+        Ok(self
+            .client
+            .query_one(
+                "INSERT INTO processor_runs (input_hash, version, config, shard, started_at) \
+                VALUES ($1, $2, $3, $4, NOW()) RETURNING id",
+                &[&run.input_hash, &run.version, &run.config, &run.shard.map(|r| r as i32)]
+            )
+            .await
+            .query("start_run")?
+            .get("id"))
+    }
+
+    async fn get_last_input_hash(&self, shard: Option<Ruleset>) -> Result<Option<String>, DatabaseError> {
+        Ok(self
+            .client
+            .query_opt(
+                "SELECT input_hash FROM processor_runs WHERE completed_at IS NOT NULL \
+                AND shard IS NOT DISTINCT FROM $1 ORDER BY completed_at DESC LIMIT 1",
+                &[&shard.map(|r| r as i32)]
+            )
+            .await
+            .query("get_last_input_hash")?
+            .map(|row| row.get("input_hash")))
+    }
+
+    async fn complete_run(&self, run_id: i32) -> Result<(), DatabaseError> {
+        self.client
+            .execute(
+                "UPDATE processor_runs SET completed_at = NOW() WHERE id = $1",
+                &[&run_id]
+            )
+            .await
+            .query("complete_run")?;
+
+        Ok(())
     }
 }
@@ -1,4 +1,10 @@
-use crate::model::structures::{rating_adjustment_type::RatingAdjustmentType, ruleset::Ruleset};
+use crate::model::{
+    beatmaps::Mods,
+    structures::{
+        activity::Activity, manual_adjustment_kind::ManualAdjustmentKind, mod_category::ModCategory,
+        rating_adjustment_type::RatingAdjustmentType, rating_clamps::RatingClamps, ruleset::Ruleset
+    }
+};
 use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
 
@@ -21,17 +27,31 @@ pub struct Player {
 pub struct RulesetData {
     pub ruleset: Ruleset,
     pub global_rank: i32,
-    pub earliest_global_rank: Option<i32>
+    pub earliest_global_rank: Option<i32>,
+    /// Recorded global ranks over time, in ascending timestamp order. Empty when no
+    /// history is available.
+    #[serde(default)]
+    pub rank_history: Vec<RankHistoryPoint>
+}
+
+/// A player's global rank in a ruleset at a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RankHistoryPoint {
+    pub timestamp: DateTime<FixedOffset>,
+    pub global_rank: i32
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Match {
     pub id: i32,
+    pub tournament_id: i32,
     pub name: String,
     pub start_time: DateTime<FixedOffset>,
     pub end_time: DateTime<FixedOffset>,
     // Populated in the db query (uses the tournament's ruleset)
     pub ruleset: Ruleset,
+    /// Exhibition matches (e.g. showmatches) are marked as processed but never affect ratings
+    pub rating_exempt: bool,
     pub games: Vec<Game>
 }
 
@@ -44,16 +64,64 @@ pub struct Game {
     pub scores: Vec<GameScore>
 }
 
+/// Metadata of a beatmap, without mods applied
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Beatmap {
+    pub id: i32,
+    pub star_rating: f64,
+    /// Circle size, or the key count of mania beatmaps
+    pub circle_size: f64,
+    pub approach_rate: f64,
+    pub overall_difficulty: f64,
+    pub health_drain: f64,
+    pub bpm: f64,
+    /// Length in seconds
+    pub length: i32
+}
+
+/// The mods a game was played with, both as set for the lobby and as chosen by each player
+#[derive(Debug, Clone)]
+pub struct PlayedMods {
+    pub game_id: i32,
+    pub tournament_id: i32,
+    pub ruleset: Ruleset,
+    pub beatmap_id: i32,
+    pub game_mods: Mods,
+    pub score_mods: Vec<Mods>
+}
+
+/// How surprising the outcome of a match was given its players' ratings, see `match_upset`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatchUpset {
+    pub match_id: i32,
+    /// Rating gap of the inverted pairs per compared pair, 0 if every game ended in rating order
+    pub upset_factor: f64,
+    /// Number of pairs in which the lower rated player placed ahead
+    pub inverted_pairs: i32,
+    /// Number of pairs of rated players placed differently within a game
+    pub compared_pairs: i32
+}
+
+/// The detected mod pool of a game, see `classify_games`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GameModCategory {
+    pub game_id: i32,
+    pub category: ModCategory
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct GameScore {
     pub id: i32,
     pub player_id: i32,
     pub game_id: i32,
     pub score: i32,
-    pub placement: i32
+    pub placement: i32,
+    /// Team the score was set for, 0 if the game was not played in teams
+    pub team: i32
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
 pub struct PlayerRating {
     /// Unknown until insertion
     pub id: i32,
@@ -72,6 +140,7 @@ pub struct PlayerRating {
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
 pub struct RatingAdjustment {
     pub player_id: i32,
     pub ruleset: Ruleset,
@@ -81,10 +150,47 @@ pub struct RatingAdjustment {
     pub volatility_before: f64,
     pub volatility_after: f64,
     pub timestamp: DateTime<FixedOffset>,
-    pub adjustment_type: RatingAdjustmentType
+    pub adjustment_type: RatingAdjustmentType,
+    /// Average rating of the opponents faced in the match before it was rated, None for
+    /// adjustments which are not matches
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub average_opponent_rating: Option<f64>,
+    /// Whether the player was rated with a raised volatility because they were new, see
+    /// `ModelConfig::provisional_period`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub provisional: bool,
+    /// Percentile of the player within their ruleset right after the match, None for
+    /// adjustments which are not matches or when not computed, see `record_adjustment_percentiles`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub percentile: Option<f64>,
+    /// The bounds the rating and volatility after the adjustment were clamped to, if any
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub clamps: RatingClamps
+}
+
+/// A period during which a player was restricted from playing, e.g. by a ban
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PlayerRestriction {
+    pub player_id: i32,
+    pub starts_at: DateTime<FixedOffset>,
+    /// None while the restriction is ongoing
+    pub ends_at: Option<DateTime<FixedOffset>>
+}
+
+/// A rating correction inserted by moderation into the manual_adjustments table
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ManualAdjustment {
+    pub id: i32,
+    pub player_id: i32,
+    pub ruleset: Ruleset,
+    /// When the correction takes effect. It is applied before any match starting at or after it.
+    pub timestamp: DateTime<FixedOffset>,
+    pub kind: ManualAdjustmentKind,
+    /// The new rating, or the amount added to the rating, depending on `kind`
+    pub value: f64
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Clone, Serialize, PartialEq)]
 pub struct PlayerHighestRank {
     pub id: i32,
     pub ruleset: Ruleset,
@@ -92,5 +198,60 @@ pub struct PlayerHighestRank {
     pub global_rank_date: DateTime<FixedOffset>,
     pub country_rank: i32,
     pub country_rank_date: DateTime<FixedOffset>,
+    /// The highest percentile reached in the global leaderboard
+    pub percentile: f64,
+    pub percentile_date: DateTime<FixedOffset>,
     pub player_id: i32
 }
+
+/// A player's rating blended across all rulesets, see `model::overall_ratings`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct OverallRating {
+    pub player_id: i32,
+    pub rating: f64,
+    pub global_rank: i32,
+    pub percentile: f64,
+    /// Number of matches the player was rated in, across all rulesets
+    pub matches_played: usize
+}
+
+/// A rating scaled for display, see `model::display_ratings`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DisplayRating {
+    pub player_id: i32,
+    pub ruleset: Ruleset,
+    pub display_rating: f64
+}
+
+/// The activity of a player in a ruleset at the end of a run
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PlayerActivity {
+    pub player_id: i32,
+    pub ruleset: Ruleset,
+    pub activity: Activity
+}
+
+/// The average size of the lobbies and teams a player played their rated games in, within
+/// a ruleset
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PlayerLobbyStats {
+    pub player_id: i32,
+    pub ruleset: Ruleset,
+    /// Number of rated games played
+    pub games: i32,
+    /// Mean number of scores per game
+    pub average_lobby_size: f64,
+    /// Mean number of scores per game set for the player's team, including their own. Games
+    /// not played in teams count as a team of 1.
+    pub average_team_size: f64
+}
+
+/// The first time a player was within the top `top_percent` percent of a ruleset's
+/// global leaderboard
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PercentileMilestone {
+    pub player_id: i32,
+    pub ruleset: Ruleset,
+    pub top_percent: f64,
+    pub reached_at: DateTime<FixedOffset>
+}
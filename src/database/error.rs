@@ -0,0 +1,118 @@
+use super::copy::CopyError;
+use crate::error::Entity;
+use thiserror::Error;
+
+/// Errors of the Postgres and SQLite result stores
+///
+/// Queries are identified by the name they are recorded under in the slow log, so that a
+/// failure can be matched with the timings of the same run.
+#[derive(Error, Debug)]
+pub enum DatabaseError {
+    #[error("Query {query} failed: {source}")]
+    Postgres {
+        query: String,
+        source: tokio_postgres::Error
+    },
+    #[error("Query {query} failed to encode column {column}: {source}")]
+    Encode {
+        query: String,
+        column: usize,
+        source: Box<dyn std::error::Error + Sync + Send>
+    },
+    #[error("SQLite query {query} failed: {source}")]
+    Sqlite { query: String, source: rusqlite::Error },
+    #[error("Stored {column} {value} of {entity} is not valid")]
+    InvalidValue {
+        entity: Entity,
+        column: &'static str,
+        value: i32
+    },
+    #[error("Stored report of the {ruleset} shard is not valid JSON: {source}")]
+    InvalidShardReport { ruleset: i32, source: serde_json::Error },
+    #[error("Query {query} returned no row for {entity}")]
+    MissingRow { query: String, entity: Entity },
+    #[error("A transaction cannot start on a connection shared with other clients")]
+    SharedConnection
+}
+
+impl DatabaseError {
+    /// The stored entity the error relates to, if any
+    pub fn entity(&self) -> Option<Entity> {
+        match self {
+            DatabaseError::InvalidValue { entity, .. } | DatabaseError::MissingRow { entity, .. } => Some(*entity),
+            // Invalid values found while SQLite maps a row are wrapped into a conversion failure
+            DatabaseError::Sqlite {
+                source: rusqlite::Error::FromSqlConversionFailure(_, _, e),
+                ..
+            } => e.downcast_ref::<DatabaseError>().and_then(DatabaseError::entity),
+            _ => None
+        }
+    }
+}
+
+/// Attaches the name of the failed query to a database error
+pub(crate) trait QueryContext<T> {
+    fn query(self, query: &str) -> Result<T, DatabaseError>;
+}
+
+impl<T> QueryContext<T> for Result<T, tokio_postgres::Error> {
+    fn query(self, query: &str) -> Result<T, DatabaseError> {
+        self.map_err(|source| DatabaseError::Postgres {
+            query: query.to_string(),
+            source
+        })
+    }
+}
+
+impl<T> QueryContext<T> for Result<T, CopyError> {
+    fn query(self, query: &str) -> Result<T, DatabaseError> {
+        self.map_err(|e| match e {
+            CopyError::Postgres(source) => DatabaseError::Postgres {
+                query: query.to_string(),
+                source
+            },
+            CopyError::Encode { column, source } => DatabaseError::Encode {
+                query: query.to_string(),
+                column,
+                source
+            }
+        })
+    }
+}
+
+impl<T> QueryContext<T> for Result<T, rusqlite::Error> {
+    fn query(self, query: &str) -> Result<T, DatabaseError> {
+        self.map_err(|source| DatabaseError::Sqlite {
+            query: query.to_string(),
+            source
+        })
+    }
+}
+
+/// Parses an enum stored as its integer value, e.g. a ruleset
+pub(crate) fn parse_stored<T: TryFrom<i32>>(
+    value: i32,
+    column: &'static str,
+    entity: Entity
+) -> Result<T, DatabaseError> {
+    T::try_from(value).map_err(|_| DatabaseError::InvalidValue { entity, column, value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_stored, DatabaseError};
+    use crate::{error::Entity, model::structures::ruleset::Ruleset};
+
+    #[test]
+    fn test_parse_stored_names_the_entity() {
+        assert_eq!(
+            parse_stored::<Ruleset>(0, "ruleset", Entity::Player(7)).unwrap(),
+            Ruleset::Osu
+        );
+
+        let error = parse_stored::<Ruleset>(42, "ruleset", Entity::Player(7)).unwrap_err();
+        assert!(matches!(error, DatabaseError::InvalidValue { value: 42, .. }));
+        assert_eq!(error.entity(), Some(Entity::Player(7)));
+        assert_eq!(error.to_string(), "Stored ruleset 42 of player 7 is not valid");
+    }
+}
@@ -1,2 +1,7 @@
+pub mod copy;
 pub mod db;
 pub mod db_structs;
+pub mod error;
+pub mod result_store;
+pub mod sqlite;
+pub mod tls;
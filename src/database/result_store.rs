@@ -0,0 +1,128 @@
+use super::{
+    db_structs::{PlayerHighestRank, PlayerRating, RatingAdjustment},
+    error::DatabaseError
+};
+use crate::{
+    model::structures::{date_range::DateRange, ruleset::Ruleset, ruleset_filter::RulesetFilter},
+    utils::tournament_settlement::TournamentSettlements
+};
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
+use std::collections::HashMap;
+
+/// A processor run, recorded before its results are saved so that every saved rating and
+/// adjustment can be traced back to the run which produced it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    /// Hash of the inputs of the run, see `compute_input_hash`
+    pub input_hash: String,
+    /// Version of the processor binary
+    pub version: String,
+    /// The effective configuration of the run as JSON, see `EffectiveConfig`
+    pub config: String,
+    /// The ruleset processed by the run if it was a shard, see `Args::shard`
+    pub shard: Option<Ruleset>
+}
+
+/// Where the results of a run are stored and read back from by later runs
+///
+/// `DbClient` stores results in the o!TR Postgres database, next to the input data.
+/// `SqliteStore` stores them in a local file for development and tests which should not need
+/// a Postgres server. Data only consumed by the website (e.g. percentile milestones or player
+/// activity) is not part of the store and only saved to Postgres.
+#[async_trait]
+pub trait ResultStore: Send + Sync {
+    /// Reconstructs every player's rating as it stood immediately before `timestamp`
+    /// from the stored rating adjustment history.
+    ///
+    /// The rating and volatility of each returned PlayerRating are taken from the last
+    /// adjustment before `timestamp`. Percentiles and ranks are left for the rating tracker.
+    async fn get_ratings_as_of(&self, timestamp: DateTime<FixedOffset>) -> Result<Vec<PlayerRating>, DatabaseError>;
+
+    /// Fetches the currently stored player ratings, without their adjustments
+    async fn get_player_ratings(&self) -> Result<Vec<PlayerRating>, DatabaseError>;
+
+    /// Fetches the stored rating adjustments of the given players, ordered by player, ruleset
+    /// and time
+    async fn get_rating_adjustments(&self, player_ids: &[i32]) -> Result<Vec<RatingAdjustment>, DatabaseError>;
+
+    /// Replaces all stored ratings and adjustments with the results of a full run.
+    ///
+    /// Every rating and adjustment is stored along with `run_id`, see `start_run`.
+    /// If `compress_decay` is set, consecutive decay adjustments are stored as summary rows.
+    /// If `settlements` are given, the match adjustments of each tournament are stored as
+    /// settlements, keeping the settled match adjustments separately.
+    ///
+    /// If the run is restricted to some rulesets, only the stored data of those rulesets is
+    /// replaced and the data of all other rulesets is left untouched.
+    async fn save_results(
+        &self,
+        player_ratings: &[PlayerRating],
+        highest_ranks: &HashMap<(i32, Ruleset), PlayerHighestRank>,
+        rulesets: &RulesetFilter,
+        compress_decay: bool,
+        settlements: Option<&TournamentSettlements>,
+        run_id: i32
+    ) -> Result<(), DatabaseError>;
+
+    /// Saves the results of a date-restricted run.
+    ///
+    /// Unlike `save_results`, nothing is truncated. Stored adjustments within `range` are
+    /// replaced by the newly computed adjustments within `range`. When `range` has no end
+    /// the player ratings are updated in place, otherwise the stored ratings still reflect
+    /// the adjustments after the window and are kept. Players who were not rated before are
+    /// inserted either way.
+    #[allow(clippy::too_many_arguments)]
+    async fn save_results_in_range(
+        &self,
+        player_ratings: &[PlayerRating],
+        highest_ranks: &HashMap<(i32, Ruleset), PlayerHighestRank>,
+        range: &DateRange,
+        rulesets: &RulesetFilter,
+        compress_decay: bool,
+        settlements: Option<&TournamentSettlements>,
+        run_id: i32
+    ) -> Result<(), DatabaseError>;
+
+    /// Records the start of a run before its results are saved
+    ///
+    /// # Returns
+    /// The id of the run, stored with every rating and adjustment it saves
+    async fn start_run(&self, run: &RunRecord) -> Result<i32, DatabaseError>;
+
+    /// Returns the input hash recorded by the most recent completed run of `shard`, or of the
+    /// runs which were not a shard if None. Shards hash different inputs, so each is only
+    /// compared with its own previous run.
+    async fn get_last_input_hash(&self, shard: Option<Ruleset>) -> Result<Option<String>, DatabaseError>;
+
+    /// Marks a run as completed once all of its results are saved
+    async fn complete_run(&self, run_id: i32) -> Result<(), DatabaseError>;
+}
+
+/// Folds stored adjustments, ordered by player, ruleset and time, into the rating each chain
+/// ends with
+pub fn ratings_from_adjustments(adjustments: impl IntoIterator<Item = RatingAdjustment>) -> Vec<PlayerRating> {
+    let mut ratings: Vec<PlayerRating> = Vec::new();
+    for adjustment in adjustments {
+        match ratings.last_mut() {
+            Some(rating) if rating.player_id == adjustment.player_id && rating.ruleset == adjustment.ruleset => {
+                rating.rating = adjustment.rating_after;
+                rating.volatility = adjustment.volatility_after;
+                rating.adjustments.push(adjustment);
+            }
+            _ => ratings.push(PlayerRating {
+                id: 0,
+                player_id: adjustment.player_id,
+                ruleset: adjustment.ruleset,
+                rating: adjustment.rating_after,
+                volatility: adjustment.volatility_after,
+                percentile: 0.0,
+                global_rank: 0,
+                country_rank: 0,
+                adjustments: vec![adjustment]
+            })
+        }
+    }
+
+    ratings
+}
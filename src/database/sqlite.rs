@@ -0,0 +1,848 @@
+use super::{
+    db_structs::{PlayerHighestRank, PlayerRating, RatingAdjustment},
+    error::{parse_stored, DatabaseError, QueryContext},
+    result_store::{ratings_from_adjustments, ResultStore, RunRecord}
+};
+use crate::{
+    error::Entity,
+    model::{
+        rank_history::merge_highest_ranks,
+        structures::{
+            date_range::DateRange, rating_clamps::RatingClamps, ruleset::Ruleset, ruleset_filter::RulesetFilter
+        }
+    },
+    utils::{adjustment_rows::adjustment_rows, tournament_settlement::TournamentSettlements}
+};
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Utc};
+use itertools::Itertools;
+use rusqlite::{params, types::Type, Connection, OptionalExtension, Row};
+use std::{collections::HashMap, path::Path, sync::Mutex};
+
+/// Tables of the results stored by `SqliteStore`, mirroring their Postgres counterparts
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS player_ratings (
+    id INTEGER PRIMARY KEY,
+    player_id INTEGER NOT NULL,
+    ruleset INTEGER NOT NULL,
+    rating REAL NOT NULL,
+    volatility REAL NOT NULL,
+    percentile REAL NOT NULL,
+    global_rank INTEGER NOT NULL,
+    country_rank INTEGER NOT NULL,
+    processing_run_id INTEGER,
+    UNIQUE (player_id, ruleset)
+);
+CREATE TABLE IF NOT EXISTS rating_adjustments (
+    id INTEGER PRIMARY KEY,
+    player_id INTEGER NOT NULL,
+    ruleset INTEGER NOT NULL,
+    player_rating_id INTEGER NOT NULL,
+    match_id INTEGER,
+    rating_before REAL NOT NULL,
+    rating_after REAL NOT NULL,
+    volatility_before REAL NOT NULL,
+    volatility_after REAL NOT NULL,
+    timestamp TEXT NOT NULL,
+    adjustment_type INTEGER NOT NULL,
+    average_opponent_rating REAL,
+    provisional INTEGER NOT NULL,
+    percentile REAL,
+    clamps INTEGER NOT NULL DEFAULT 0,
+    decay_count INTEGER,
+    decay_start_timestamp TEXT,
+    processing_run_id INTEGER
+);
+CREATE INDEX IF NOT EXISTS rating_adjustments_player ON rating_adjustments (player_id, ruleset, timestamp);
+CREATE TABLE IF NOT EXISTS tournament_settlement_matches (
+    id INTEGER PRIMARY KEY,
+    player_id INTEGER NOT NULL,
+    ruleset INTEGER NOT NULL,
+    tournament_id INTEGER NOT NULL,
+    settled_at TEXT NOT NULL,
+    match_id INTEGER,
+    rating_before REAL NOT NULL,
+    rating_after REAL NOT NULL,
+    volatility_before REAL NOT NULL,
+    volatility_after REAL NOT NULL,
+    timestamp TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS player_highest_ranks (
+    id INTEGER PRIMARY KEY,
+    player_id INTEGER NOT NULL,
+    ruleset INTEGER NOT NULL,
+    global_rank INTEGER NOT NULL,
+    global_rank_date TEXT NOT NULL,
+    country_rank INTEGER NOT NULL,
+    country_rank_date TEXT NOT NULL,
+    percentile REAL NOT NULL,
+    percentile_date TEXT NOT NULL,
+    UNIQUE (player_id, ruleset)
+);
+CREATE TABLE IF NOT EXISTS processor_runs (
+    id INTEGER PRIMARY KEY,
+    input_hash TEXT NOT NULL,
+    version TEXT NOT NULL,
+    config TEXT NOT NULL,
+    shard INTEGER,
+    started_at TEXT NOT NULL,
+    completed_at TEXT
+);";
+
+const ADJUSTMENT_COLUMNS: &str = "player_id, ruleset, match_id, rating_before, rating_after, volatility_before, \
+volatility_after, timestamp, adjustment_type, average_opponent_rating, provisional, percentile, clamps";
+
+/// Stores results in a local SQLite database, creating its tables if they do not exist
+///
+/// Meant for local development and tests. Every save runs in a single savepoint, which joins
+/// the transaction started by `begin_saves` if any.
+/// Timestamps are stored as UTC text, which sorts chronologically.
+pub struct SqliteStore {
+    connection: Mutex<Connection>
+}
+
+impl SqliteStore {
+    /// Opens or creates the database file at `path`
+    pub fn open(path: &Path) -> Result<SqliteStore, DatabaseError> {
+        Connection::open(path)
+            .and_then(Self::with_connection)
+            .query("open_sqlite_store")
+    }
+
+    /// Creates a database which only lives as long as the store
+    pub fn in_memory() -> rusqlite::Result<SqliteStore> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> rusqlite::Result<SqliteStore> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(SqliteStore {
+            connection: Mutex::new(connection)
+        })
+    }
+
+    /// Fetches the stored highest ranks by player and ruleset
+    pub fn get_highest_ranks(&self) -> Result<HashMap<(i32, Ruleset), PlayerHighestRank>, DatabaseError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare(
+                "SELECT id, player_id, ruleset, global_rank, global_rank_date, country_rank, country_rank_date, \
+            percentile, percentile_date FROM player_highest_ranks"
+            )
+            .query("get_highest_ranks")?;
+
+        statement
+            .query_map([], |row| {
+                let player_id = row.get("player_id")?;
+                let highest_rank = PlayerHighestRank {
+                    id: row.get("id")?,
+                    player_id,
+                    ruleset: parse_column(row, "ruleset", Entity::Player(player_id))?,
+                    global_rank: row.get("global_rank")?,
+                    global_rank_date: row.get("global_rank_date")?,
+                    country_rank: row.get("country_rank")?,
+                    country_rank_date: row.get("country_rank_date")?,
+                    percentile: row.get("percentile")?,
+                    percentile_date: row.get("percentile_date")?
+                };
+                Ok(((highest_rank.player_id, highest_rank.ruleset), highest_rank))
+            })
+            .and_then(|rows| rows.collect())
+            .query("get_highest_ranks")
+    }
+
+    fn rating_adjustment_from_row(row: &Row) -> rusqlite::Result<RatingAdjustment> {
+        let player_id = row.get("player_id")?;
+        Ok(RatingAdjustment {
+            player_id,
+            ruleset: parse_column(row, "ruleset", Entity::Player(player_id))?,
+            match_id: row.get("match_id")?,
+            rating_before: row.get("rating_before")?,
+            rating_after: row.get("rating_after")?,
+            volatility_before: row.get("volatility_before")?,
+            volatility_after: row.get("volatility_after")?,
+            timestamp: row.get("timestamp")?,
+            adjustment_type: parse_column(row, "adjustment_type", Entity::Player(player_id))?,
+            average_opponent_rating: row.get("average_opponent_rating")?,
+            provisional: row.get("provisional")?,
+            percentile: row.get("percentile")?,
+            clamps: RatingClamps(row.get("clamps")?)
+        })
+    }
+
+    /// Fetches the stored adjustments matching `condition`, ordered by player, ruleset and time
+    fn query_adjustments(
+        &self,
+        query: &str,
+        condition: &str,
+        values: impl rusqlite::Params
+    ) -> Result<Vec<RatingAdjustment>, DatabaseError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare(&format!(
+                "SELECT {} FROM rating_adjustments WHERE {} ORDER BY player_id, ruleset, timestamp",
+                ADJUSTMENT_COLUMNS, condition
+            ))
+            .query(query)?;
+
+        statement
+            .query_map(values, Self::rating_adjustment_from_row)
+            .and_then(|rows| rows.collect())
+            .query(query)
+    }
+
+    /// Starts a transaction which every following save joins until `end_saves`, so that results
+    /// saved ruleset by ruleset are kept or discarded together
+    pub fn begin_saves(&self) -> Result<(), DatabaseError> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute_batch("BEGIN").query("begin_saves")
+    }
+
+    /// Ends the transaction started by `begin_saves`, committing the saves made since if
+    /// `commit` is set and discarding them otherwise
+    pub fn end_saves(&self, commit: bool) -> Result<(), DatabaseError> {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .execute_batch(if commit { "COMMIT" } else { "ROLLBACK" })
+            .query("end_saves")
+    }
+}
+
+#[async_trait]
+impl ResultStore for SqliteStore {
+    async fn get_ratings_as_of(&self, timestamp: DateTime<FixedOffset>) -> Result<Vec<PlayerRating>, DatabaseError> {
+        let adjustments = self.query_adjustments("get_ratings_as_of", "timestamp < ?1", [utc(timestamp)])?;
+        Ok(ratings_from_adjustments(adjustments))
+    }
+
+    async fn get_player_ratings(&self) -> Result<Vec<PlayerRating>, DatabaseError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare(
+                "SELECT id, player_id, ruleset, rating, volatility, percentile, global_rank, country_rank \
+            FROM player_ratings ORDER BY id"
+            )
+            .query("get_player_ratings")?;
+
+        statement
+            .query_map([], |row| {
+                let player_id = row.get("player_id")?;
+                Ok(PlayerRating {
+                    id: row.get("id")?,
+                    player_id,
+                    ruleset: parse_column(row, "ruleset", Entity::Player(player_id))?,
+                    rating: row.get("rating")?,
+                    volatility: row.get("volatility")?,
+                    percentile: row.get("percentile")?,
+                    global_rank: row.get("global_rank")?,
+                    country_rank: row.get("country_rank")?,
+                    adjustments: Vec::new()
+                })
+            })
+            .and_then(|rows| rows.collect())
+            .query("get_player_ratings")
+    }
+
+    async fn get_rating_adjustments(&self, player_ids: &[i32]) -> Result<Vec<RatingAdjustment>, DatabaseError> {
+        self.query_adjustments(
+            "get_rating_adjustments",
+            &format!("player_id IN ({})", player_ids.iter().join(", ")),
+            []
+        )
+    }
+
+    async fn save_results(
+        &self,
+        player_ratings: &[PlayerRating],
+        highest_ranks: &HashMap<(i32, Ruleset), PlayerHighestRank>,
+        rulesets: &RulesetFilter,
+        compress_decay: bool,
+        settlements: Option<&TournamentSettlements>,
+        run_id: i32
+    ) -> Result<(), DatabaseError> {
+        let mut connection = self.connection.lock().unwrap();
+        let tx = connection.savepoint().query("save_results")?;
+
+        let condition = ruleset_condition(rulesets);
+        for table in ["rating_adjustments", "player_ratings", "tournament_settlement_matches"] {
+            if table != "tournament_settlement_matches" || settlements.is_some() {
+                tx.execute(&format!("DELETE FROM {} WHERE {}", table, condition), [])
+                    .query("save_results")?;
+            }
+        }
+
+        let parent_ids = player_ratings
+            .iter()
+            .map(|rating| {
+                tx.query_row(
+                    "INSERT INTO player_ratings (player_id, ruleset, rating, volatility, percentile, global_rank, \
+                country_rank, processing_run_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) RETURNING id",
+                    rating_values(rating, run_id),
+                    |row| row.get(0)
+                )
+            })
+            .collect::<rusqlite::Result<Vec<i32>>>()
+            .query("save_player_ratings")?;
+
+        save_rating_adjustments(
+            &tx,
+            player_ratings,
+            &parent_ids,
+            &DateRange::default(),
+            compress_decay,
+            settlements,
+            run_id
+        )
+        .query("save_rating_adjustments")?;
+        save_highest_ranks(&tx, highest_ranks).query("save_highest_ranks")?;
+
+        tx.commit().query("save_results")
+    }
+
+    async fn save_results_in_range(
+        &self,
+        player_ratings: &[PlayerRating],
+        highest_ranks: &HashMap<(i32, Ruleset), PlayerHighestRank>,
+        range: &DateRange,
+        rulesets: &RulesetFilter,
+        compress_decay: bool,
+        settlements: Option<&TournamentSettlements>,
+        run_id: i32
+    ) -> Result<(), DatabaseError> {
+        let mut connection = self.connection.lock().unwrap();
+        let tx = connection.savepoint().query("save_results_in_range")?;
+
+        let condition = format!(
+            "(?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp <= ?2) AND {}",
+            ruleset_condition(rulesets)
+        );
+        let bounds = [range.from.map(utc), range.to.map(utc)];
+        tx.execute(&format!("DELETE FROM rating_adjustments WHERE {}", condition), bounds)
+            .query("delete_rating_adjustments_in_range")?;
+        if settlements.is_some() {
+            tx.execute(
+                &format!("DELETE FROM tournament_settlement_matches WHERE {}", condition),
+                bounds
+            )
+            .query("delete_settlement_matches_in_range")?;
+        }
+
+        // Stored ratings already include the adjustments after a bounded window
+        let on_conflict = if range.to.is_none() {
+            "DO UPDATE SET rating = excluded.rating, volatility = excluded.volatility, \
+            percentile = excluded.percentile, global_rank = excluded.global_rank, \
+            country_rank = excluded.country_rank, processing_run_id = excluded.processing_run_id"
+        } else {
+            "DO NOTHING"
+        };
+        let parent_ids = player_ratings
+            .iter()
+            .map(|rating| {
+                tx.execute(
+                    &format!(
+                        "INSERT INTO player_ratings (player_id, ruleset, rating, volatility, percentile, \
+                    global_rank, country_rank, processing_run_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) \
+                    ON CONFLICT (player_id, ruleset) {}",
+                        on_conflict
+                    ),
+                    rating_values(rating, run_id)
+                )?;
+                tx.query_row(
+                    "SELECT id FROM player_ratings WHERE player_id = ?1 AND ruleset = ?2",
+                    (rating.player_id, rating.ruleset as i32),
+                    |row| row.get(0)
+                )
+            })
+            .collect::<rusqlite::Result<Vec<i32>>>()
+            .query("upsert_player_ratings")?;
+
+        save_rating_adjustments(
+            &tx,
+            player_ratings,
+            &parent_ids,
+            range,
+            compress_decay,
+            settlements,
+            run_id
+        )
+        .query("save_rating_adjustments")?;
+        save_highest_ranks(&tx, highest_ranks).query("save_highest_ranks")?;
+
+        tx.commit().query("save_results_in_range")
+    }
+
+    async fn start_run(&self, run: &RunRecord) -> Result<i32, DatabaseError> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "INSERT INTO processor_runs (input_hash, version, config, shard, started_at) \
+            VALUES (?1, ?2, ?3, ?4, ?5) RETURNING id",
+                params![
+                    run.input_hash,
+                    run.version,
+                    run.config,
+                    run.shard.map(|r| r as i32),
+                    Utc::now()
+                ],
+                |row| row.get(0)
+            )
+            .query("start_run")
+    }
+
+    async fn get_last_input_hash(&self, shard: Option<Ruleset>) -> Result<Option<String>, DatabaseError> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT input_hash FROM processor_runs WHERE completed_at IS NOT NULL AND shard IS ?1 \
+            ORDER BY completed_at DESC, id DESC LIMIT 1",
+                params![shard.map(|r| r as i32)],
+                |row| row.get(0)
+            )
+            .optional()
+            .query("get_last_input_hash")
+    }
+
+    async fn complete_run(&self, run_id: i32) -> Result<(), DatabaseError> {
+        self.connection
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE processor_runs SET completed_at = ?1 WHERE id = ?2",
+                params![Utc::now(), run_id]
+            )
+            .query("complete_run")?;
+
+        Ok(())
+    }
+}
+
+/// Parses an enum stored as its integer value while mapping a row, see `parse_stored`
+fn parse_column<T: TryFrom<i32>>(row: &Row, column: &'static str, entity: Entity) -> rusqlite::Result<T> {
+    parse_stored(row.get(column)?, column, entity).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(
+            row.as_ref().column_index(column).unwrap_or_default(),
+            Type::Integer,
+            Box::new(e)
+        )
+    })
+}
+
+/// Timestamps are stored in UTC so that their text sorts chronologically
+fn utc(timestamp: DateTime<FixedOffset>) -> DateTime<Utc> {
+    timestamp.with_timezone(&Utc)
+}
+
+/// A condition selecting the rows of the filtered rulesets
+fn ruleset_condition(rulesets: &RulesetFilter) -> String {
+    match rulesets.ids() {
+        None => "1 = 1".to_string(),
+        Some(ids) => format!("ruleset IN ({})", ids.iter().join(", "))
+    }
+}
+
+fn rating_values(rating: &PlayerRating, run_id: i32) -> impl rusqlite::Params {
+    (
+        rating.player_id,
+        rating.ruleset as i32,
+        rating.rating,
+        rating.volatility,
+        rating.percentile,
+        rating.global_rank,
+        rating.country_rank,
+        run_id
+    )
+}
+
+/// Stores the adjustments of every rating within `range` along with `run_id`, see
+/// `adjustment_rows`
+fn save_rating_adjustments(
+    tx: &Connection,
+    player_ratings: &[PlayerRating],
+    parent_ids: &[i32],
+    range: &DateRange,
+    compress_decay: bool,
+    settlements: Option<&TournamentSettlements>,
+    run_id: i32
+) -> rusqlite::Result<()> {
+    let mut insert_adjustment = tx.prepare(&format!(
+        "INSERT INTO rating_adjustments ({}, player_rating_id, decay_count, decay_start_timestamp, \
+        processing_run_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        ADJUSTMENT_COLUMNS
+    ))?;
+    let mut insert_settled_match = tx.prepare(
+        "INSERT INTO tournament_settlement_matches (player_id, ruleset, tournament_id, settled_at, match_id, \
+        rating_before, rating_after, volatility_before, volatility_after, timestamp) \
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"
+    )?;
+
+    for rating_rows in adjustment_rows(player_ratings, parent_ids, range, compress_decay, settlements) {
+        for row in &rating_rows.rows {
+            let adjustment = &row.adjustment;
+            // Summary rows additionally record the size and start of the decay run
+            let (count, first_timestamp) = match compress_decay {
+                true => (Some(row.count), Some(utc(row.first_timestamp))),
+                false => (None, None)
+            };
+
+            insert_adjustment.execute(params![
+                adjustment.player_id,
+                adjustment.ruleset as i32,
+                adjustment.match_id,
+                adjustment.rating_before,
+                adjustment.rating_after,
+                adjustment.volatility_before,
+                adjustment.volatility_after,
+                utc(adjustment.timestamp),
+                adjustment.adjustment_type as i32,
+                adjustment.average_opponent_rating,
+                adjustment.provisional,
+                adjustment.percentile,
+                adjustment.clamps.0,
+                rating_rows.player_rating_id,
+                count,
+                first_timestamp,
+                run_id
+            ])?;
+        }
+
+        for (tournament_id, settled_at, adjustment) in &rating_rows.settled_matches {
+            insert_settled_match.execute(params![
+                adjustment.player_id,
+                adjustment.ruleset as i32,
+                tournament_id,
+                utc(*settled_at),
+                adjustment.match_id,
+                adjustment.rating_before,
+                adjustment.rating_after,
+                adjustment.volatility_before,
+                adjustment.volatility_after,
+                utc(adjustment.timestamp)
+            ])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Stores the highest ranks reached during the run, keeping stored ranks which are better,
+/// see `merge_highest_ranks`
+fn save_highest_ranks(
+    tx: &Connection,
+    highest_ranks: &HashMap<(i32, Ruleset), PlayerHighestRank>
+) -> rusqlite::Result<()> {
+    for highest in highest_ranks.values() {
+        let stored = tx
+            .query_row(
+                "SELECT global_rank, global_rank_date, country_rank, country_rank_date, percentile, percentile_date \
+            FROM player_highest_ranks WHERE player_id = ?1 AND ruleset = ?2",
+                params![highest.player_id, highest.ruleset as i32],
+                |row| {
+                    Ok(PlayerHighestRank {
+                        global_rank: row.get(0)?,
+                        global_rank_date: row.get(1)?,
+                        country_rank: row.get(2)?,
+                        country_rank_date: row.get(3)?,
+                        percentile: row.get(4)?,
+                        percentile_date: row.get(5)?,
+                        ..highest.clone()
+                    })
+                }
+            )
+            .optional()?;
+        let merged = match stored {
+            Some(stored) => merge_highest_ranks(&stored, highest),
+            None => highest.clone()
+        };
+
+        tx.execute(
+            "INSERT INTO player_highest_ranks (player_id, ruleset, global_rank, global_rank_date, country_rank, \
+        country_rank_date, percentile, percentile_date) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) \
+        ON CONFLICT (player_id, ruleset) DO UPDATE SET global_rank = excluded.global_rank, \
+        global_rank_date = excluded.global_rank_date, country_rank = excluded.country_rank, \
+        country_rank_date = excluded.country_rank_date, percentile = excluded.percentile, \
+        percentile_date = excluded.percentile_date",
+            params![
+                merged.player_id,
+                merged.ruleset as i32,
+                merged.global_rank,
+                utc(merged.global_rank_date),
+                merged.country_rank,
+                utc(merged.country_rank_date),
+                merged.percentile,
+                utc(merged.percentile_date)
+            ]
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SqliteStore;
+    use crate::{
+        database::{db_structs::PlayerHighestRank, result_store::{ResultStore, RunRecord}},
+        error::Entity,
+        model::structures::{
+            date_range::DateRange,
+            ruleset::Ruleset::{Osu, Taiko},
+            ruleset_filter::RulesetFilter
+        },
+        utils::test_utils::generate_player_rating
+    };
+    use chrono::{Duration, TimeZone, Utc};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_save_and_read_results() {
+        let store = SqliteStore::in_memory().unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let end = start + Duration::days(10);
+        let ratings = vec![
+            generate_player_rating(1, Osu, 1200.0, 100.0, 3, Some(start), Some(end)),
+            generate_player_rating(2, Taiko, 900.0, 150.0, 2, Some(start), Some(end)),
+        ];
+
+        store
+            .save_results(&ratings, &HashMap::new(), &RulesetFilter::default(), false, None, 1)
+            .await
+            .unwrap();
+
+        let stored = store.get_player_ratings().await.unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!((stored[0].player_id, stored[0].rating), (1, 1200.0));
+
+        let adjustments = store.get_rating_adjustments(&[1]).await.unwrap();
+        assert_eq!(adjustments, ratings[0].adjustments);
+
+        // Only the initial adjustments precede the middle of the history
+        let as_of = store.get_ratings_as_of(start + Duration::days(1)).await.unwrap();
+        assert_eq!(as_of.len(), 2);
+        assert_eq!(as_of[0].adjustments.len(), 1);
+        assert_eq!(as_of[0].rating, ratings[0].adjustments[0].rating_after);
+    }
+
+    #[tokio::test]
+    async fn test_restricted_saves_keep_other_data() {
+        let store = SqliteStore::in_memory().unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let ratings = vec![
+            generate_player_rating(1, Osu, 1200.0, 100.0, 3, Some(start), Some(start + Duration::days(10))),
+            generate_player_rating(1, Taiko, 900.0, 150.0, 2, Some(start), Some(start + Duration::days(10))),
+        ];
+        store
+            .save_results(&ratings, &HashMap::new(), &RulesetFilter::default(), false, None, 1)
+            .await
+            .unwrap();
+
+        // A ruleset-restricted run leaves Taiko untouched
+        let mut osu = ratings[0].clone();
+        osu.rating = 1300.0;
+        store
+            .save_results(&[osu], &HashMap::new(), &RulesetFilter::new(&[Osu]), false, None, 1)
+            .await
+            .unwrap();
+        let stored = store.get_player_ratings().await.unwrap();
+        assert_eq!(stored.len(), 2);
+        assert!(stored.iter().any(|r| r.ruleset == Osu && r.rating == 1300.0));
+        assert!(stored.iter().any(|r| r.ruleset == Taiko && r.rating == 900.0));
+
+        // A date-restricted run replaces the adjustments within its range and updates ratings in place
+        let range = DateRange::new(Some(start + Duration::days(6)), None);
+        let mut taiko = ratings[1].clone();
+        taiko.rating = 950.0;
+        store
+            .save_results_in_range(
+                &[taiko.clone()],
+                &HashMap::new(),
+                &range,
+                &RulesetFilter::new(&[Taiko]),
+                false,
+                None,
+                1
+            )
+            .await
+            .unwrap();
+        assert!(store
+            .get_player_ratings()
+            .await
+            .unwrap()
+            .iter()
+            .any(|r| r.ruleset == Taiko && r.rating == 950.0));
+        let adjustments = store.get_rating_adjustments(&[1]).await.unwrap();
+        assert_eq!(adjustments.iter().filter(|a| a.ruleset == Taiko).count(), 2);
+        assert_eq!(adjustments.iter().filter(|a| a.ruleset == Osu).count(), 3);
+
+        // A window with an end keeps the current ratings, which include the later adjustments,
+        // while players who were not rated before are still inserted
+        let range = DateRange::new(Some(start), Some(start + Duration::days(6)));
+        taiko.rating = 700.0;
+        let newcomer = generate_player_rating(2, Taiko, 800.0, 200.0, 2, Some(start), Some(start + Duration::days(1)));
+        store
+            .save_results_in_range(
+                &[taiko, newcomer],
+                &HashMap::new(),
+                &range,
+                &RulesetFilter::new(&[Taiko]),
+                false,
+                None,
+                2
+            )
+            .await
+            .unwrap();
+        let stored = store.get_player_ratings().await.unwrap();
+        assert!(stored
+            .iter()
+            .any(|r| r.player_id == 1 && r.ruleset == Taiko && r.rating == 950.0));
+        assert!(stored
+            .iter()
+            .any(|r| r.player_id == 2 && r.ruleset == Taiko && r.rating == 800.0));
+    }
+
+    #[tokio::test]
+    async fn test_discarded_saves_keep_previous_results() {
+        let store = SqliteStore::in_memory().unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let ratings = vec![
+            generate_player_rating(1, Osu, 1200.0, 100.0, 3, Some(start), Some(start + Duration::days(10))),
+            generate_player_rating(1, Taiko, 900.0, 150.0, 2, Some(start), Some(start + Duration::days(10))),
+        ];
+        store
+            .save_results(&ratings, &HashMap::new(), &RulesetFilter::default(), false, None, 1)
+            .await
+            .unwrap();
+
+        let mut osu = ratings[0].clone();
+        osu.rating = 1300.0;
+        store.begin_saves().unwrap();
+        store
+            .save_results(&[osu.clone()], &HashMap::new(), &RulesetFilter::new(&[Osu]), false, None, 2)
+            .await
+            .unwrap();
+        store.end_saves(false).unwrap();
+        assert!(store
+            .get_player_ratings()
+            .await
+            .unwrap()
+            .iter()
+            .any(|r| r.ruleset == Osu && r.rating == 1200.0));
+
+        store.begin_saves().unwrap();
+        store
+            .save_results(&[osu], &HashMap::new(), &RulesetFilter::new(&[Osu]), false, None, 2)
+            .await
+            .unwrap();
+        store.end_saves(true).unwrap();
+        assert!(store
+            .get_player_ratings()
+            .await
+            .unwrap()
+            .iter()
+            .any(|r| r.ruleset == Osu && r.rating == 1300.0));
+    }
+
+    #[tokio::test]
+    async fn test_highest_ranks_keep_best() {
+        let store = SqliteStore::in_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let highest = |global_rank, country_rank, date| PlayerHighestRank {
+            id: 0,
+            ruleset: Osu,
+            global_rank,
+            global_rank_date: date,
+            country_rank,
+            country_rank_date: date,
+            percentile: 0.5,
+            percentile_date: date,
+            player_id: 1
+        };
+
+        for reached in [highest(10, 5, date), highest(20, 2, date + Duration::days(1))] {
+            store
+                .save_results(
+                    &[],
+                    &HashMap::from([((1, Osu), reached)]),
+                    &RulesetFilter::default(),
+                    false,
+                    None,
+                    1
+                )
+                .await
+                .unwrap();
+        }
+
+        let stored = &store.get_highest_ranks().unwrap()[&(1, Osu)];
+        assert_eq!((stored.global_rank, stored.global_rank_date), (10, date));
+        assert_eq!(
+            (stored.country_rank, stored.country_rank_date),
+            (2, date + Duration::days(1))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_stored_values_name_the_player() {
+        let store = SqliteStore::in_memory().unwrap();
+        store
+            .connection
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO player_ratings (player_id, ruleset, rating, volatility, percentile, global_rank, \
+            country_rank) VALUES (7, 42, 1000.0, 100.0, 0.5, 1, 1)",
+                []
+            )
+            .unwrap();
+
+        let error = store.get_player_ratings().await.unwrap_err();
+        assert_eq!(error.entity(), Some(Entity::Player(7)));
+    }
+
+    #[tokio::test]
+    async fn test_input_hash() {
+        let store = SqliteStore::in_memory().unwrap();
+        assert_eq!(store.get_last_input_hash(None).await.unwrap(), None);
+
+        let run = |input_hash: &str| RunRecord {
+            input_hash: input_hash.to_string(),
+            version: "1.0.0".to_string(),
+            config: "{}".to_string(),
+            shard: None
+        };
+        let first = store.start_run(&run("first")).await.unwrap();
+        store.complete_run(first).await.unwrap();
+        let second = store.start_run(&run("second")).await.unwrap();
+        store.complete_run(second).await.unwrap();
+        assert_eq!(
+            store.get_last_input_hash(None).await.unwrap().as_deref(),
+            Some("second")
+        );
+
+        // A run which did not complete is not skipped next time
+        store.start_run(&run("third")).await.unwrap();
+        assert_eq!(
+            store.get_last_input_hash(None).await.unwrap().as_deref(),
+            Some("second")
+        );
+
+        // Shards are only compared with their own runs
+        let shard = store
+            .start_run(&RunRecord {
+                shard: Some(Taiko),
+                ..run("taiko")
+            })
+            .await
+            .unwrap();
+        store.complete_run(shard).await.unwrap();
+        assert_eq!(
+            store.get_last_input_hash(None).await.unwrap().as_deref(),
+            Some("second")
+        );
+        assert_eq!(
+            store.get_last_input_hash(Some(Taiko)).await.unwrap().as_deref(),
+            Some("taiko")
+        );
+        assert_eq!(store.get_last_input_hash(Some(Osu)).await.unwrap(), None);
+    }
+}
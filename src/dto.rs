@@ -0,0 +1,202 @@
+//! Serializable views of the processor's results, shared by everything which writes them out
+//!
+//! The database structs serialize with their Rust field names. Every JSON leaving the
+//! processor uses the camelCase types of this module instead, so all consumers see the same
+//! field names no matter which feature produced the JSON.
+
+use crate::{
+    database::db_structs::{PlayerRating, RatingAdjustment},
+    model::structures::{rating_adjustment_type::RatingAdjustmentType, rating_clamps::RatingClamps, ruleset::Ruleset},
+    report::run_report::RunReport
+};
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+/// A final rating, without the adjustments which led to it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerRatingDto {
+    pub player_id: i32,
+    pub ruleset: Ruleset,
+    pub rating: f64,
+    pub volatility: f64,
+    pub percentile: f64,
+    pub global_rank: i32,
+    pub country_rank: i32
+}
+
+impl From<&PlayerRating> for PlayerRatingDto {
+    fn from(rating: &PlayerRating) -> Self {
+        PlayerRatingDto {
+            player_id: rating.player_id,
+            ruleset: rating.ruleset,
+            rating: rating.rating,
+            volatility: rating.volatility,
+            percentile: rating.percentile,
+            global_rank: rating.global_rank,
+            country_rank: rating.country_rank
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RatingAdjustmentDto {
+    pub player_id: i32,
+    pub ruleset: Ruleset,
+    pub match_id: Option<i32>,
+    pub rating_before: f64,
+    pub rating_after: f64,
+    pub volatility_before: f64,
+    pub volatility_after: f64,
+    pub timestamp: DateTime<FixedOffset>,
+    pub adjustment_type: RatingAdjustmentType,
+    pub average_opponent_rating: Option<f64>,
+    pub provisional: bool,
+    pub percentile: Option<f64>,
+    pub clamps: RatingClamps
+}
+
+impl From<&RatingAdjustment> for RatingAdjustmentDto {
+    fn from(adjustment: &RatingAdjustment) -> Self {
+        RatingAdjustmentDto {
+            player_id: adjustment.player_id,
+            ruleset: adjustment.ruleset,
+            match_id: adjustment.match_id,
+            rating_before: adjustment.rating_before,
+            rating_after: adjustment.rating_after,
+            volatility_before: adjustment.volatility_before,
+            volatility_after: adjustment.volatility_after,
+            timestamp: adjustment.timestamp,
+            adjustment_type: adjustment.adjustment_type,
+            average_opponent_rating: adjustment.average_opponent_rating,
+            provisional: adjustment.provisional,
+            percentile: adjustment.percentile,
+            clamps: adjustment.clamps
+        }
+    }
+}
+
+impl From<&RatingAdjustmentDto> for RatingAdjustment {
+    fn from(adjustment: &RatingAdjustmentDto) -> Self {
+        RatingAdjustment {
+            player_id: adjustment.player_id,
+            ruleset: adjustment.ruleset,
+            match_id: adjustment.match_id,
+            rating_before: adjustment.rating_before,
+            rating_after: adjustment.rating_after,
+            volatility_before: adjustment.volatility_before,
+            volatility_after: adjustment.volatility_after,
+            timestamp: adjustment.timestamp,
+            adjustment_type: adjustment.adjustment_type,
+            average_opponent_rating: adjustment.average_opponent_rating,
+            provisional: adjustment.provisional,
+            percentile: adjustment.percentile,
+            clamps: adjustment.clamps
+        }
+    }
+}
+
+/// A final rating along with the adjustments which led to it, e.g. to save it later
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RatingHistoryDto {
+    #[serde(flatten)]
+    pub rating: PlayerRatingDto,
+    pub adjustments: Vec<RatingAdjustmentDto>
+}
+
+impl From<&PlayerRating> for RatingHistoryDto {
+    fn from(rating: &PlayerRating) -> Self {
+        RatingHistoryDto {
+            rating: PlayerRatingDto::from(rating),
+            adjustments: rating.adjustments.iter().map(RatingAdjustmentDto::from).collect()
+        }
+    }
+}
+
+impl From<&RatingHistoryDto> for PlayerRating {
+    fn from(history: &RatingHistoryDto) -> Self {
+        let rating = &history.rating;
+        PlayerRating {
+            id: 0,
+            player_id: rating.player_id,
+            ruleset: rating.ruleset,
+            rating: rating.rating,
+            volatility: rating.volatility,
+            percentile: rating.percentile,
+            global_rank: rating.global_rank,
+            country_rank: rating.country_rank,
+            adjustments: history.adjustments.iter().map(RatingAdjustment::from).collect()
+        }
+    }
+}
+
+/// The headline numbers of a run, for consumers which don't need the full `RunReport`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunSummaryDto {
+    pub matches_processed: usize,
+    /// Number of matches which could not be rated
+    pub matches_skipped: usize,
+    /// Ids of players whose stored rating or rank changed beyond the update thresholds
+    pub updated_players: Vec<i32>,
+    /// Number of warnings raised, of any kind
+    pub warnings: usize
+}
+
+impl From<&RunReport> for RunSummaryDto {
+    fn from(report: &RunReport) -> Self {
+        RunSummaryDto {
+            matches_processed: report.matches_processed,
+            matches_skipped: report.skipped.quarantined_matches.len(),
+            updated_players: report.updated_players.clone(),
+            warnings: report.warnings.iter().map(|w| w.count).sum()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PlayerRatingDto, RatingAdjustmentDto, RatingHistoryDto, RunSummaryDto};
+    use crate::{
+        database::db_structs::PlayerRating, model::structures::ruleset::Ruleset::Osu, report::run_report::RunReport,
+        utils::test_utils::generate_player_rating
+    };
+
+    #[test]
+    fn test_fields_are_camel_case() {
+        let rating = generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None);
+
+        let json = serde_json::to_value(PlayerRatingDto::from(&rating)).unwrap();
+        assert_eq!(json["playerId"], 1);
+        assert_eq!(json["globalRank"], rating.global_rank);
+        assert!(json.get("adjustments").is_none());
+
+        let json = serde_json::to_value(RatingAdjustmentDto::from(&rating.adjustments[0])).unwrap();
+        assert_eq!(json["ratingAfter"], rating.adjustments[0].rating_after);
+        assert_eq!(json["adjustmentType"], 0);
+
+        let report = RunReport {
+            matches_processed: 3,
+            updated_players: vec![1],
+            ..Default::default()
+        };
+        let json = serde_json::to_value(RunSummaryDto::from(&report)).unwrap();
+        assert_eq!(json["matchesProcessed"], 3);
+        assert_eq!(json["updatedPlayers"][0], 1);
+    }
+    #[test]
+    fn test_rating_history_round_trips() {
+        let rating = generate_player_rating(1, Osu, 1000.0, 100.0, 3, None, None);
+
+        let json = serde_json::to_string(&RatingHistoryDto::from(&rating)).unwrap();
+        let history: RatingHistoryDto = serde_json::from_str(&json).unwrap();
+        // Ids are unknown until the rating is saved
+        let restored = PlayerRating {
+            id: rating.id,
+            ..PlayerRating::from(&history)
+        };
+        assert_eq!(restored, rating);
+    }
+}
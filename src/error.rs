@@ -0,0 +1,254 @@
+//! Errors which abort a run, along with the stage of the run and the entity they occurred at.
+//!
+//! Each module keeps its own error type (e.g. `DatabaseError` or `BootstrapError`). The
+//! processor wraps them into a `ProcessorError` once it knows which stage they surfaced in.
+use crate::{
+    cli::stop::Stop,
+    database::{db::ConnectError, error::DatabaseError},
+    model::{
+        bootstrap::BootstrapError, leaderboard_checks::LeaderboardError, otr_model::ModelError,
+        score_integrity::IntegrityError, simulation::SimulationError, structures::ruleset::Ruleset
+    },
+    post_process::post_processor::PostProcessError
+};
+use std::{any::Any, fmt, io, path::PathBuf};
+use thiserror::Error;
+
+/// The stages of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Connecting to the database and fetching the input data and stored results
+    Fetch,
+    /// Checking the input data and computing ratings
+    Model,
+    /// Saving the results and updating processing statuses
+    Save,
+    /// Running post processors and writing reports
+    Publish
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Fetch => write!(f, "fetch"),
+            Stage::Model => write!(f, "model"),
+            Stage::Save => write!(f, "save"),
+            Stage::Publish => write!(f, "publish")
+        }
+    }
+}
+
+/// An entity of the o!TR database an error relates to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entity {
+    Tournament(i32),
+    Match(i32),
+    Game(i32),
+    Player(i32),
+    ManualAdjustment(i32)
+}
+
+impl fmt::Display for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entity::Tournament(id) => write!(f, "tournament {}", id),
+            Entity::Match(id) => write!(f, "match {}", id),
+            Entity::Game(id) => write!(f, "game {}", id),
+            Entity::Player(id) => write!(f, "player {}", id),
+            Entity::ManualAdjustment(id) => write!(f, "manual adjustment {}", id)
+        }
+    }
+}
+
+/// The error of a module which aborted a run
+#[derive(Error, Debug)]
+pub enum Cause {
+    #[error(transparent)]
+    Connect(#[from] ConnectError),
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+    #[error(transparent)]
+    Integrity(#[from] IntegrityError),
+    #[error(transparent)]
+    Bootstrap(#[from] BootstrapError),
+    #[error(transparent)]
+    Leaderboard(#[from] LeaderboardError),
+    #[error(transparent)]
+    Model(#[from] ModelError),
+    #[error(transparent)]
+    Simulation(#[from] SimulationError),
+    #[error(transparent)]
+    PostProcess(#[from] PostProcessError),
+    #[error("The {0} environment variable is not set")]
+    MissingVariable(&'static str),
+    #[error("Processing panicked: {0}")]
+    Panic(String),
+    #[error("No run report was stored by the shards of {0:?}, as they failed or never ran")]
+    MissingShardReports(Vec<Ruleset>),
+    #[error("{0} is not a valid country code")]
+    InvalidCountry(String),
+    #[error("Failed to read {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("Failed to write {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+    #[error(transparent)]
+    Stopped(#[from] Stop)
+}
+
+impl Cause {
+    /// Wraps the payload of a thread which panicked, which is the panic message unless the
+    /// thread panicked with another value
+    pub fn panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => payload
+                .downcast_ref::<&str>()
+                .map_or("unknown panic", |m| m)
+                .to_string()
+        };
+        Cause::Panic(message)
+    }
+
+    /// The entity the error relates to, if it names one. Integrity errors name the match of
+    /// their first failing score, model errors the match which could not be rated.
+    pub fn entity(&self) -> Option<Entity> {
+        match self {
+            Cause::Database(e) => e.entity(),
+            Cause::Integrity(e) => e.issues.first().map(|issue| Entity::Match(issue.match_id)),
+            Cause::Bootstrap(BootstrapError::InvalidInitialRating { player_id, .. }) => {
+                Some(Entity::Player(*player_id))
+            }
+            Cause::Model(ModelError::UnratedPlayer { match_id, .. }) => Some(Entity::Match(*match_id)),
+            Cause::Simulation(SimulationError::UnratedPlayer { player_id, .. }) => Some(Entity::Player(*player_id)),
+            _ => None
+        }
+    }
+}
+
+/// An error which aborted a run, along with the stage it surfaced in
+#[derive(Error, Debug)]
+#[error("The {stage} stage failed: {source}")]
+pub struct ProcessorError {
+    pub stage: Stage,
+    /// The entity the error relates to, if known
+    pub entity: Option<Entity>,
+    pub source: Box<Cause>
+}
+
+impl ProcessorError {
+    pub fn new(stage: Stage, source: impl Into<Cause>) -> Self {
+        let source = source.into();
+        ProcessorError {
+            stage,
+            entity: source.entity(),
+            source: Box::new(source)
+        }
+    }
+
+    /// The stop which ended the run, if it was stopped on purpose rather than failed
+    pub fn stop(&self) -> Option<&Stop> {
+        match self.source.as_ref() {
+            Cause::Stopped(stop) => Some(stop),
+            _ => None
+        }
+    }
+}
+
+/// Attaches the stage of a run to the errors of a module
+pub trait StageContext<T> {
+    fn stage(self, stage: Stage) -> Result<T, ProcessorError>;
+}
+
+impl<T, E: Into<Cause>> StageContext<T> for Result<T, E> {
+    fn stage(self, stage: Stage) -> Result<T, ProcessorError> {
+        self.map_err(|e| ProcessorError::new(stage, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cause, Entity, ProcessorError, Stage, StageContext};
+    use crate::{
+        database::error::DatabaseError,
+        model::{
+            otr_model::ModelError,
+            score_integrity::{IntegrityError, IntegrityIssue, IntegrityIssueKind},
+            simulation::SimulationError,
+            structures::ruleset::Ruleset
+        }
+    };
+
+    #[test]
+    fn test_entity_is_taken_from_the_cause() {
+        let error = ProcessorError::new(
+            Stage::Fetch,
+            DatabaseError::InvalidValue {
+                entity: Entity::Game(3),
+                column: "ruleset",
+                value: 9
+            }
+        );
+        assert_eq!(error.entity, Some(Entity::Game(3)));
+        assert_eq!(
+            error.to_string(),
+            "The fetch stage failed: Stored ruleset 9 of game 3 is not valid"
+        );
+
+        let error = ProcessorError::new(
+            Stage::Model,
+            IntegrityError {
+                issues: vec![IntegrityIssue {
+                    kind: IntegrityIssueKind::InvalidScore,
+                    match_id: 5,
+                    game_id: 6,
+                    score_id: 7,
+                    player_id: 8
+                }]
+            }
+        );
+        assert_eq!(error.entity, Some(Entity::Match(5)));
+
+        let result: Result<(), _> = Err(SimulationError::UnratedPlayer {
+            player_id: 4,
+            ruleset: Ruleset::Osu
+        });
+        let error = result.stage(Stage::Model).unwrap_err();
+        assert_eq!((error.stage, error.entity), (Stage::Model, Some(Entity::Player(4))));
+
+        let error = ProcessorError::new(
+            Stage::Model,
+            ModelError::UnratedPlayer {
+                match_id: 2,
+                player_id: 4,
+                ruleset: Ruleset::Taiko
+            }
+        );
+        assert_eq!(error.entity, Some(Entity::Match(2)));
+        assert_eq!(
+            error.to_string(),
+            "The model stage failed: Player 4 of match 2 has no rating in ruleset Taiko"
+        );
+    }
+
+    #[test]
+    fn test_errors_without_an_entity() {
+        let error = ProcessorError::new(Stage::Publish, SimulationError::EmptyLineup);
+        assert_eq!(error.entity, None);
+        assert_eq!(error.stage.to_string(), "publish");
+
+        let panic = std::thread::spawn(|| panic!("rating {} failed", 3)).join().unwrap_err();
+        let error = ProcessorError::new(Stage::Model, Cause::panic(panic));
+        assert_eq!(error.entity, None);
+        assert_eq!(
+            error.to_string(),
+            "The model stage failed: Processing panicked: rating 3 failed"
+        );
+
+        let error = ProcessorError::new(Stage::Publish, Cause::MissingShardReports(vec![Ruleset::Taiko]));
+        assert_eq!(
+            error.to_string(),
+            "The publish stage failed: No run report was stored by the shards of [Taiko], as they failed or never \
+            ran"
+        );
+    }
+}
@@ -1,6 +1,17 @@
+//! o!TR processor
+//!
+//! Turns verified tournament match data into o!TR ratings. Most consumers only need
+//! the types re-exported by [`prelude`].
+
 extern crate core;
 extern crate lazy_static;
 
+pub mod cli;
 pub mod database;
+pub mod dto;
+pub mod error;
 pub mod model;
+pub mod post_process;
+pub mod prelude;
+pub mod report;
 pub mod utils;
@@ -0,0 +1,67 @@
+use super::{decay::DecaySystem, model_config::ModelConfig, restrictions::Restrictions};
+use crate::database::db_structs::{PlayerActivity, PlayerRating};
+
+/// Classifies the activity of every rating at the time ratings were decayed to, using the
+/// decay parameters of each rating's ruleset. Time players spent restricted does not count
+/// towards their inactivity.
+pub fn classify_activity(
+    ratings: &[PlayerRating],
+    config: &ModelConfig,
+    restrictions: &Restrictions
+) -> Vec<PlayerActivity> {
+    let time = config.decay_time();
+
+    ratings
+        .iter()
+        .map(|rating| PlayerActivity {
+            player_id: rating.player_id,
+            ruleset: rating.ruleset,
+            activity: DecaySystem::with_parameters(time, config.decay_parameters(rating.ruleset))
+                .with_restrictions(restrictions)
+                .activity(rating)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::classify_activity;
+    use crate::{
+        model::{
+            decay::DecayParameters,
+            model_config::ModelConfig,
+            restrictions::Restrictions,
+            structures::{
+                activity::Activity,
+                ruleset::Ruleset::{Osu, Taiko}
+            }
+        },
+        utils::test_utils::generate_player_rating
+    };
+    use chrono::{Duration, TimeZone, Utc};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_ruleset_decay_parameters_apply() {
+        let last_played = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let ratings = [Osu, Taiko]
+            .map(|ruleset| generate_player_rating(1, ruleset, 2000.0, 200.0, 2, Some(last_played), Some(last_played)));
+
+        // Taiko players stay active for longer
+        let config = ModelConfig {
+            decay: HashMap::from([(
+                Taiko,
+                DecayParameters {
+                    inactivity_days: 365,
+                    ..Default::default()
+                }
+            )]),
+            decay_until: Some(last_played + Duration::days(200)),
+            ..Default::default()
+        };
+        let activity = classify_activity(&ratings, &config, &Restrictions::default());
+
+        assert_eq!(activity[0].activity, Activity::Decaying);
+        assert_eq!(activity[1].activity, Activity::Active);
+    }
+}
@@ -0,0 +1,186 @@
+use super::ruleset_policy::ruleset_policy;
+use crate::{database::db_structs::Beatmap, model::structures::ruleset::Ruleset};
+
+/// Mods enabled for a game, as the osu! mod bitflags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Mods(pub i32);
+
+impl Mods {
+    pub const EASY: i32 = 1 << 1;
+    pub const HIDDEN: i32 = 1 << 3;
+    pub const HARD_ROCK: i32 = 1 << 4;
+    pub const DOUBLE_TIME: i32 = 1 << 6;
+    pub const HALF_TIME: i32 = 1 << 8;
+    pub const NIGHTCORE: i32 = 1 << 9;
+    pub const FLASHLIGHT: i32 = 1 << 10;
+    pub const FREE_MOD: i32 = 1 << 21;
+
+    pub fn contains(&self, flag: i32) -> bool {
+        self.0 & flag != 0
+    }
+
+    /// The mods which change how a beatmap plays, with Nightcore folded into Double Time
+    ///
+    /// Mods such as No Fail or Free Mod itself are dropped, as they don't decide which mod
+    /// pool a game belongs to.
+    pub fn gameplay(&self) -> Mods {
+        let mut mods = Mods(
+            self.0
+                & (Self::EASY
+                    | Self::HIDDEN
+                    | Self::HARD_ROCK
+                    | Self::DOUBLE_TIME
+                    | Self::HALF_TIME
+                    | Self::NIGHTCORE
+                    | Self::FLASHLIGHT)
+        );
+        if mods.contains(Self::NIGHTCORE) {
+            mods.0 = (mods.0 & !Self::NIGHTCORE) | Self::DOUBLE_TIME;
+        }
+        mods
+    }
+
+    /// Playback rate of the beatmap's audio
+    pub fn rate(&self) -> f64 {
+        if self.contains(Self::DOUBLE_TIME) || self.contains(Self::NIGHTCORE) {
+            1.5
+        } else if self.contains(Self::HALF_TIME) {
+            0.75
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A game's beatmap along with the mods it was played with
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameBeatmap {
+    pub mods: Mods,
+    /// The beatmap as stored
+    pub beatmap: Beatmap,
+    /// The beatmap's stats as played, see `Beatmap::with_mods`
+    pub adjusted: Beatmap
+}
+
+impl Beatmap {
+    /// The beatmap's stats as they are played with `mods` in `ruleset`
+    ///
+    /// Difficulty settings are scaled by Hard Rock and Easy and capped at 10, with the circle
+    /// size left unchanged in rulesets where it is not scaled (e.g. the key count of mania). Rate changing mods scale the BPM
+    /// and length, and the approach rate and overall difficulty are converted through their
+    /// timing windows. The star rating is not adjusted, as it requires a difficulty
+    /// calculation.
+    pub fn with_mods(&self, mods: Mods, ruleset: Ruleset) -> Beatmap {
+        let mut adjusted = self.clone();
+        let scales_circle_size = ruleset_policy(ruleset).scales_circle_size();
+
+        let multiplier = if mods.contains(Mods::HARD_ROCK) {
+            Some(1.4)
+        } else if mods.contains(Mods::EASY) {
+            Some(0.5)
+        } else {
+            None
+        };
+        if let Some(multiplier) = multiplier {
+            let circle_multiplier = if mods.contains(Mods::HARD_ROCK) { 1.3 } else { 0.5 };
+            if scales_circle_size {
+                adjusted.circle_size = (adjusted.circle_size * circle_multiplier).min(10.0);
+            }
+            adjusted.approach_rate = (adjusted.approach_rate * multiplier).min(10.0);
+            adjusted.overall_difficulty = (adjusted.overall_difficulty * multiplier).min(10.0);
+            adjusted.health_drain = (adjusted.health_drain * multiplier).min(10.0);
+        }
+
+        let rate = mods.rate();
+        if rate != 1.0 {
+            adjusted.bpm *= rate;
+            adjusted.length = (adjusted.length as f64 / rate).round() as i32;
+            adjusted.approach_rate = approach_rate_from_ms(approach_rate_ms(adjusted.approach_rate) / rate);
+            adjusted.overall_difficulty =
+                overall_difficulty_from_ms(overall_difficulty_ms(adjusted.overall_difficulty) / rate);
+        }
+
+        adjusted
+    }
+}
+
+/// Time in milliseconds a hit object is shown before it must be hit
+fn approach_rate_ms(approach_rate: f64) -> f64 {
+    if approach_rate <= 5.0 {
+        1800.0 - 120.0 * approach_rate
+    } else {
+        1200.0 - 150.0 * (approach_rate - 5.0)
+    }
+}
+
+fn approach_rate_from_ms(ms: f64) -> f64 {
+    if ms >= 1200.0 {
+        (1800.0 - ms) / 120.0
+    } else {
+        5.0 + (1200.0 - ms) / 150.0
+    }
+}
+
+/// Hit window in milliseconds of a 300
+fn overall_difficulty_ms(overall_difficulty: f64) -> f64 {
+    80.0 - 6.0 * overall_difficulty
+}
+
+fn overall_difficulty_from_ms(ms: f64) -> f64 {
+    (80.0 - ms) / 6.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mods;
+    use crate::{database::db_structs::Beatmap, model::structures::ruleset::Ruleset};
+    use approx::assert_abs_diff_eq;
+
+    fn beatmap() -> Beatmap {
+        Beatmap {
+            id: 1,
+            star_rating: 5.9,
+            circle_size: 4.0,
+            approach_rate: 9.0,
+            overall_difficulty: 8.0,
+            health_drain: 5.0,
+            bpm: 180.0,
+            length: 240
+        }
+    }
+
+    #[test]
+    fn test_no_mod_is_unchanged() {
+        assert_eq!(beatmap().with_mods(Mods::default(), Ruleset::Osu), beatmap());
+    }
+
+    #[test]
+    fn test_hard_rock() {
+        let adjusted = beatmap().with_mods(Mods(Mods::HARD_ROCK), Ruleset::Osu);
+
+        assert_abs_diff_eq!(adjusted.circle_size, 5.2);
+        assert_abs_diff_eq!(adjusted.approach_rate, 10.0);
+        assert_abs_diff_eq!(adjusted.overall_difficulty, 10.0);
+        assert_abs_diff_eq!(adjusted.health_drain, 7.0);
+    }
+
+    #[test]
+    fn test_double_time() {
+        let adjusted = beatmap().with_mods(Mods(Mods::DOUBLE_TIME), Ruleset::Osu);
+
+        assert_abs_diff_eq!(adjusted.bpm, 270.0);
+        assert_eq!(adjusted.length, 160);
+        // AR9 is 600ms, 400ms at 1.5x
+        assert_abs_diff_eq!(adjusted.approach_rate, 5.0 + 800.0 / 150.0, epsilon = 1e-9);
+        // OD8 is a 32ms window, 21.33ms at 1.5x
+        assert_abs_diff_eq!(adjusted.overall_difficulty, (80.0 - 32.0 / 1.5) / 6.0, epsilon = 1e-9);
+        assert_eq!(adjusted.star_rating, beatmap().star_rating);
+    }
+
+    #[test]
+    fn test_mania_key_count_is_unchanged() {
+        let adjusted = beatmap().with_mods(Mods(Mods::HARD_ROCK | Mods::EASY), Ruleset::Mania4k);
+
+        assert_eq!(adjusted.circle_size, 4.0);
+    }
+}
@@ -0,0 +1,491 @@
+use crate::{
+    database::db_structs::{Match, Player, PlayerRating},
+    model::{
+        countries::{normalize_country, InvalidCountry, NormalizedCountry},
+        rating_utils::{create_initial_ratings, merge_seeded_ratings},
+        structures::{fallback_strategy::FallbackStrategy, ruleset::Ruleset}
+    }
+};
+use itertools::Itertools;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt
+};
+use thiserror::Error;
+
+/// Errors which prevent the model from being bootstrapped
+#[derive(Error, Debug, PartialEq)]
+pub enum BootstrapError {
+    /// An initial rating could not be computed for a player
+    #[error("Initial rating for player {player_id} in ruleset {ruleset:?} is NaN or <= 0.0 ({rating})")]
+    InvalidInitialRating {
+        player_id: i32,
+        ruleset: Ruleset,
+        rating: f64
+    },
+    /// Strict mode is enabled and the input data has gaps which would be rated around
+    #[error("Input data is incomplete: {0}")]
+    IncompleteData(DataIssues)
+}
+
+/// Gaps in the input data which processing normally works around with fallbacks
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DataIssues {
+    /// Ids of players referenced by scores but absent from the players list, in ascending order.
+    /// These players receive the fallback rating and have no country.
+    pub missing_players: Vec<i32>,
+    /// Players without rank data for a ruleset they are newly rated in, who receive the
+    /// fallback rating
+    pub missing_ruleset_data: Vec<(i32, Ruleset)>,
+    /// Participants of a match without a rating in the match's ruleset, who are skipped
+    /// when decay is applied before the match
+    pub untracked_players: Vec<(i32, Ruleset)>,
+    /// Ids of games without any scores, which are skipped
+    pub empty_games: Vec<i32>,
+    /// Stored country codes which are not valid ISO 3166-1 alpha-2 codes, sorted by code.
+    /// Players with these codes are treated as having an unknown country.
+    pub invalid_countries: Vec<InvalidCountry>
+}
+
+impl DataIssues {
+    pub fn is_empty(&self) -> bool {
+        self.missing_players.is_empty()
+            && self.missing_ruleset_data.is_empty()
+            && self.untracked_players.is_empty()
+            && self.empty_games.is_empty()
+            && self.invalid_countries.is_empty()
+    }
+}
+
+impl fmt::Display for DataIssues {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} missing players {:?}, {} players missing ruleset data {:?}, \
+            {} untracked players {:?}, {} empty games {:?}, {} invalid country codes {:?}",
+            self.missing_players.len(),
+            self.missing_players,
+            self.missing_ruleset_data.len(),
+            self.missing_ruleset_data,
+            self.untracked_players.len(),
+            self.untracked_players,
+            self.empty_games.len(),
+            self.empty_games,
+            self.invalid_countries.len(),
+            self.invalid_countries.iter().map(|c| &c.code).collect_vec()
+        )
+    }
+}
+
+/// Everything needed to construct an OtrModel for a processing run
+#[derive(Debug)]
+pub struct Bootstrap {
+    /// Starting ratings for every player participating in the processed matches
+    pub initial_ratings: Vec<PlayerRating>,
+    /// Maps player ids to their country codes
+    pub country_mapping: HashMap<i32, String>,
+    /// Gaps in the input data which were worked around
+    pub issues: DataIssues
+}
+
+impl Bootstrap {
+    /// The (player, ruleset) pairs whose initial rating is the fallback rating
+    pub fn fallback_ratings(&self) -> HashSet<(i32, Ruleset)> {
+        let missing: HashSet<i32> = self.issues.missing_players.iter().copied().collect();
+
+        self.initial_ratings
+            .iter()
+            .map(|r| (r.player_id, r.ruleset))
+            .filter(|(id, _)| missing.contains(id))
+            .chain(self.issues.missing_ruleset_data.iter().copied())
+            .collect()
+    }
+}
+
+/// Prepares the inputs of a processing run
+///
+/// 1. Identifies players referenced by scores who are missing from `players`
+/// 2. Creates initial ratings for all participating players, including missing ones, whose
+///    fallback rating is chosen by `fallback`
+/// 3. Merges in `seeded_ratings`, which take precedence over initial ratings
+/// 4. Validates the initial ratings
+/// 5. Collects any other gaps in the input data, failing if `strict` is set and any were found
+/// 6. Builds the country mapping
+pub fn bootstrap(
+    players: &[Player],
+    matches: &[Match],
+    seeded_ratings: Vec<PlayerRating>,
+    fallback: FallbackStrategy,
+    strict: bool
+) -> Result<Bootstrap, BootstrapError> {
+    let missing_players = find_missing_players(players, matches);
+    let seeded_keys: HashSet<(i32, Ruleset)> = seeded_ratings.iter().map(|r| (r.player_id, r.ruleset)).collect();
+
+    // Missing players have no rank data, so they are given the fallback rating
+    let placeholders = missing_players.iter().map(|&id| Player {
+        id,
+        username: None,
+        country: None,
+        ruleset_data: None
+    });
+    let all_players = players.iter().cloned().chain(placeholders).collect_vec();
+
+    let created = create_initial_ratings(&all_players, matches, &seeded_ratings, fallback);
+    let initial_ratings = merge_seeded_ratings(seeded_ratings, created);
+    validate_initial_ratings(&initial_ratings)?;
+
+    let players_by_id: HashMap<i32, &Player> = players.iter().map(|p| (p.id, p)).collect();
+    let missing_ruleset_data = initial_ratings
+        .iter()
+        .filter(|r| !seeded_keys.contains(&(r.player_id, r.ruleset)))
+        .filter(|r| {
+            players_by_id
+                .get(&r.player_id)
+                .is_some_and(|p| !has_rank_data(p, r.ruleset))
+        })
+        .map(|r| (r.player_id, r.ruleset))
+        .sorted_by_key(|&(id, ruleset)| (id, ruleset as i32))
+        .collect_vec();
+
+    let issues = DataIssues {
+        untracked_players: find_untracked_players(&initial_ratings, matches),
+        empty_games: find_empty_games(matches),
+        invalid_countries: find_invalid_countries(players),
+        missing_players,
+        missing_ruleset_data
+    };
+
+    if strict && !issues.is_empty() {
+        return Err(BootstrapError::IncompleteData(issues));
+    }
+
+    Ok(Bootstrap {
+        initial_ratings,
+        country_mapping: create_country_mapping(players),
+        issues
+    })
+}
+
+/// Maps each player id to its normalized country code, using an empty string for unknown
+/// and invalid countries
+pub fn create_country_mapping(players: &[Player]) -> HashMap<i32, String> {
+    players
+        .iter()
+        .map(|p| {
+            let country = match normalize_country(p.country.as_deref()) {
+                NormalizedCountry::Valid(country) => country,
+                NormalizedCountry::Unknown | NormalizedCountry::Invalid => String::new()
+            };
+
+            (p.id, country)
+        })
+        .collect()
+}
+
+/// Returns the stored country codes which cannot be normalized to an ISO 3166-1 alpha-2 code
+pub fn find_invalid_countries(players: &[Player]) -> Vec<InvalidCountry> {
+    players
+        .iter()
+        .filter_map(|p| p.country.as_deref())
+        .filter(|code| normalize_country(Some(code)) == NormalizedCountry::Invalid)
+        .counts()
+        .into_iter()
+        .map(|(code, players)| InvalidCountry {
+            code: code.to_string(),
+            players
+        })
+        .sorted_by(|a, b| a.code.cmp(&b.code))
+        .collect()
+}
+
+/// Returns the ids of all players referenced by scores who are not present in `players`
+pub fn find_missing_players(players: &[Player], matches: &[Match]) -> Vec<i32> {
+    let known: HashSet<i32> = players.iter().map(|p| p.id).collect();
+
+    matches
+        .iter()
+        .flat_map(|m| m.games.iter())
+        .flat_map(|g| g.scores.iter())
+        .map(|s| s.player_id)
+        .filter(|id| !known.contains(id))
+        .unique()
+        .sorted()
+        .collect()
+}
+
+/// Returns every match participant who has no rating in the ruleset of the match,
+/// which happens when a game is played in a different ruleset than its match
+pub fn find_untracked_players(ratings: &[PlayerRating], matches: &[Match]) -> Vec<(i32, Ruleset)> {
+    let rated: HashSet<(i32, Ruleset)> = ratings.iter().map(|r| (r.player_id, r.ruleset)).collect();
+
+    matches
+        .iter()
+        .flat_map(|m| {
+            m.games
+                .iter()
+                .flat_map(|g| g.scores.iter())
+                .map(move |s| (s.player_id, m.ruleset))
+        })
+        .filter(|key| !rated.contains(key))
+        .unique()
+        .sorted_by_key(|&(id, ruleset)| (id, ruleset as i32))
+        .collect()
+}
+
+/// Returns the ids of all games without any scores
+pub fn find_empty_games(matches: &[Match]) -> Vec<i32> {
+    matches
+        .iter()
+        .flat_map(|m| m.games.iter())
+        .filter(|g| g.scores.is_empty())
+        .map(|g| g.id)
+        .sorted()
+        .collect()
+}
+
+fn has_rank_data(player: &Player, ruleset: Ruleset) -> bool {
+    player.ruleset_data.iter().flatten().any(|data| data.ruleset == ruleset)
+}
+
+fn validate_initial_ratings(ratings: &[PlayerRating]) -> Result<(), BootstrapError> {
+    match ratings.iter().find(|r| r.rating.is_nan() || r.rating <= 0.0) {
+        Some(r) => Err(BootstrapError::InvalidInitialRating {
+            player_id: r.player_id,
+            ruleset: r.ruleset,
+            rating: r.rating
+        }),
+        None => Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bootstrap, create_country_mapping, find_empty_games, find_invalid_countries, find_missing_players,
+        validate_initial_ratings, BootstrapError
+    };
+    use crate::{
+        database::db_structs::Player,
+        model::{
+            constants::FALLBACK_RATING,
+            countries::InvalidCountry,
+            structures::{
+                fallback_strategy::FallbackStrategy,
+                ruleset::Ruleset::{Osu, Taiko}
+            }
+        },
+        utils::test_utils::{
+            generate_game, generate_match, generate_placement, generate_player_rating, generate_ruleset_data
+        }
+    };
+    use chrono::Utc;
+
+    fn player(id: i32, country: Option<&str>) -> Player {
+        Player {
+            id,
+            username: None,
+            country: country.map(|c| c.to_string()),
+            ruleset_data: None
+        }
+    }
+
+    #[test]
+    fn test_country_mapping() {
+        let players = vec![player(1, Some("US")), player(2, None)];
+        let mapping = create_country_mapping(&players);
+
+        assert_eq!(mapping.get(&1), Some(&"US".to_string()));
+        assert_eq!(mapping.get(&2), Some(&String::new()));
+    }
+
+    #[test]
+    fn test_country_mapping_normalizes_codes() {
+        let players = vec![
+            player(1, Some("de")),
+            player(2, Some("YU")),
+            player(3, Some("??")),
+            player(4, Some("??")),
+            player(5, Some("ABC")),
+        ];
+        let mapping = create_country_mapping(&players);
+
+        assert_eq!(mapping[&1], "DE");
+        assert_eq!(mapping[&2], "RS");
+        assert_eq!(mapping[&3], "");
+        assert_eq!(mapping[&5], "");
+
+        let invalid = find_invalid_countries(&players);
+        assert_eq!(
+            invalid,
+            vec![
+                InvalidCountry {
+                    code: "??".to_string(),
+                    players: 2
+                },
+                InvalidCountry {
+                    code: "ABC".to_string(),
+                    players: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_missing_players() {
+        let placements = vec![
+            generate_placement(1, 1),
+            generate_placement(3, 2),
+            generate_placement(2, 3),
+        ];
+        let games = vec![generate_game(1, &placements), generate_game(2, &placements)];
+        let matches = vec![generate_match(1, Osu, &games, Utc::now().fixed_offset())];
+
+        let players = vec![player(1, Some("US"))];
+
+        assert_eq!(find_missing_players(&players, &matches), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_bootstrap_rates_missing_players_with_fallback() {
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 2)];
+        let matches = vec![generate_match(
+            1,
+            Osu,
+            &[generate_game(1, &placements)],
+            Utc::now().fixed_offset()
+        )];
+        let players = vec![player(1, Some("US"))];
+
+        let result = bootstrap(&players, &matches, Vec::new(), FallbackStrategy::Constant, false).unwrap();
+
+        assert_eq!(result.issues.missing_players, vec![2]);
+        assert_eq!(result.initial_ratings.len(), 2);
+
+        let missing = result.initial_ratings.iter().find(|r| r.player_id == 2).unwrap();
+        assert_eq!(missing.rating, FALLBACK_RATING);
+        assert!(!result.country_mapping.contains_key(&2));
+    }
+
+    #[test]
+    fn test_bootstrap_prefers_seeded_ratings() {
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 2)];
+        let matches = vec![generate_match(
+            1,
+            Osu,
+            &[generate_game(1, &placements)],
+            Utc::now().fixed_offset()
+        )];
+        let players = vec![player(1, Some("US")), player(2, Some("US"))];
+        let seeded = vec![generate_player_rating(1, Osu, 1234.0, 100.0, 2, None, None)];
+
+        let result = bootstrap(&players, &matches, seeded, FallbackStrategy::Constant, false).unwrap();
+
+        assert!(result.issues.missing_players.is_empty());
+        let seeded_rating = result.initial_ratings.iter().find(|r| r.player_id == 1).unwrap();
+        assert_eq!(seeded_rating.rating, 1234.0);
+    }
+
+    #[test]
+    fn test_validate_initial_ratings() {
+        let mut rating = generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None);
+        assert_eq!(validate_initial_ratings(std::slice::from_ref(&rating)), Ok(()));
+
+        rating.rating = f64::NAN;
+        assert!(matches!(
+            validate_initial_ratings(&[rating]),
+            Err(BootstrapError::InvalidInitialRating { player_id: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_missing_ruleset_data() {
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 2)];
+        let matches = vec![generate_match(
+            1,
+            Osu,
+            &[generate_game(1, &placements)],
+            Utc::now().fixed_offset()
+        )];
+
+        let mut ranked = player(1, Some("US"));
+        ranked.ruleset_data = Some(vec![generate_ruleset_data(Osu, 1000, None)]);
+        let mut other_ruleset = player(2, Some("US"));
+        other_ruleset.ruleset_data = Some(vec![generate_ruleset_data(Taiko, 1000, None)]);
+
+        let result = bootstrap(
+            &[ranked, other_ruleset],
+            &matches,
+            Vec::new(),
+            FallbackStrategy::Constant,
+            false
+        )
+        .unwrap();
+
+        assert_eq!(result.issues.missing_ruleset_data, vec![(2, Osu)]);
+    }
+
+    #[test]
+    fn test_untracked_players() {
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 2)];
+        let mut game = generate_game(1, &placements);
+        game.ruleset = Taiko;
+
+        // Players are only rated in the ruleset of the game, not of the match
+        let matches = vec![generate_match(1, Osu, &[game], Utc::now().fixed_offset())];
+        let players = vec![player(1, Some("US")), player(2, Some("US"))];
+
+        let result = bootstrap(&players, &matches, Vec::new(), FallbackStrategy::Constant, false).unwrap();
+
+        assert_eq!(result.issues.untracked_players, vec![(1, Osu), (2, Osu)]);
+    }
+
+    #[test]
+    fn test_find_empty_games() {
+        let placements = vec![generate_placement(1, 1)];
+        let games = vec![generate_game(1, &placements), generate_game(2, &[])];
+        let matches = vec![generate_match(1, Osu, &games, Utc::now().fixed_offset())];
+
+        assert_eq!(find_empty_games(&matches), vec![2]);
+    }
+
+    #[test]
+    fn test_strict_fails_on_incomplete_data() {
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 2)];
+        let matches = vec![generate_match(
+            1,
+            Osu,
+            &[generate_game(1, &placements)],
+            Utc::now().fixed_offset()
+        )];
+        let players = vec![player(1, Some("US"))];
+
+        let result = bootstrap(&players, &matches, Vec::new(), FallbackStrategy::Constant, true);
+
+        match result {
+            Err(BootstrapError::IncompleteData(issues)) => {
+                assert_eq!(issues.missing_players, vec![2]);
+                assert_eq!(issues.missing_ruleset_data, vec![(1, Osu)]);
+            }
+            other => panic!("Expected incomplete data error, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_strict_passes_on_complete_data() {
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 2)];
+        let matches = vec![generate_match(
+            1,
+            Osu,
+            &[generate_game(1, &placements)],
+            Utc::now().fixed_offset()
+        )];
+        let players = (1..=2)
+            .map(|id| Player {
+                ruleset_data: Some(vec![generate_ruleset_data(Osu, 1000, None)]),
+                ..player(id, Some("US"))
+            })
+            .collect::<Vec<_>>();
+
+        assert!(bootstrap(&players, &matches, Vec::new(), FallbackStrategy::Constant, true).is_ok());
+    }
+}
@@ -0,0 +1,25 @@
+use chrono::{DateTime, FixedOffset, Utc};
+
+/// Source of the current time, which the final decay pass decays ratings to unless
+/// `ModelConfig::decay_until` is set
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<FixedOffset>;
+}
+
+/// The system time
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<FixedOffset> {
+        Utc::now().fixed_offset()
+    }
+}
+
+/// A clock stopped at a fixed time, so results don't depend on when they are computed
+pub struct FixedClock(pub DateTime<FixedOffset>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<FixedOffset> {
+        self.0
+    }
+}
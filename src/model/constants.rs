@@ -17,6 +17,10 @@ pub const DECAY_RATE: f64 = 0.06 * MULTIPLIER;
 /// Initial volatility, higher values indicate more uncertainty in the rating
 pub const DEFAULT_VOLATILITY: f64 = 5.0 * MULTIPLIER;
 
+/// Default minimum volatility a player can have after a match
+/// Prevents long-active players' ratings from freezing as volatility converges towards zero
+pub const MIN_VOLATILITY: f64 = 1.0 * MULTIPLIER;
+
 /// Fallback default rating used when rating cannot be identified from osu! rank information
 pub const FALLBACK_RATING: f64 = 15.0 * MULTIPLIER;
 
@@ -45,6 +49,10 @@ pub const TAU: f64 = DEFAULT_VOLATILITY / 100.0;
 /// Squared due to working with variance rather than standard deviation
 pub const DECAY_VOLATILITY_GROWTH_RATE: f64 = 0.08 * (MULTIPLIER * MULTIPLIER);
 
+/// Number of days between volatility growth cycles during decay
+/// The growth rate above is per week and scaled to the interval
+pub const DECAY_VOLATILITY_INTERVAL_DAYS: u64 = 7;
+
 /// Weight applied to Method A in the final rating calculation
 /// Method A: Uses current rating for unplayed games
 pub const WEIGHT_A: f64 = 0.9;
@@ -53,3 +61,11 @@ pub const WEIGHT_A: f64 = 0.9;
 /// Method B: Assumes last place for unplayed games
 /// Always equals 1 - WEIGHT_A to ensure weights sum to 1
 pub const WEIGHT_B: f64 = 1.0 - WEIGHT_A;
+
+/// Weight applied to Method A for a player who played none of a match's games
+/// when weights scale with participation
+pub const MIN_PARTICIPATION_WEIGHT_A: f64 = 0.5;
+
+/// Weight applied to Method A for a player who played every game of a match
+/// when weights scale with participation
+pub const MAX_PARTICIPATION_WEIGHT_A: f64 = 0.98;
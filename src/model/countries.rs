@@ -0,0 +1,105 @@
+use serde::Serialize;
+
+/// Every officially assigned ISO 3166-1 alpha-2 code, in alphabetical order
+const ISO_3166_ALPHA2: [&str; 249] = [
+    "AD", "AE", "AF", "AG", "AI", "AL", "AM", "AO", "AQ", "AR", "AS", "AT", "AU", "AW", "AX", "AZ", "BA", "BB", "BD",
+    "BE", "BF", "BG", "BH", "BI", "BJ", "BL", "BM", "BN", "BO", "BQ", "BR", "BS", "BT", "BV", "BW", "BY", "BZ", "CA",
+    "CC", "CD", "CF", "CG", "CH", "CI", "CK", "CL", "CM", "CN", "CO", "CR", "CU", "CV", "CW", "CX", "CY", "CZ", "DE",
+    "DJ", "DK", "DM", "DO", "DZ", "EC", "EE", "EG", "EH", "ER", "ES", "ET", "FI", "FJ", "FK", "FM", "FO", "FR", "GA",
+    "GB", "GD", "GE", "GF", "GG", "GH", "GI", "GL", "GM", "GN", "GP", "GQ", "GR", "GS", "GT", "GU", "GW", "GY", "HK",
+    "HM", "HN", "HR", "HT", "HU", "ID", "IE", "IL", "IM", "IN", "IO", "IQ", "IR", "IS", "IT", "JE", "JM", "JO", "JP",
+    "KE", "KG", "KH", "KI", "KM", "KN", "KP", "KR", "KW", "KY", "KZ", "LA", "LB", "LC", "LI", "LK", "LR", "LS", "LT",
+    "LU", "LV", "LY", "MA", "MC", "MD", "ME", "MF", "MG", "MH", "MK", "ML", "MM", "MN", "MO", "MP", "MQ", "MR", "MS",
+    "MT", "MU", "MV", "MW", "MX", "MY", "MZ", "NA", "NC", "NE", "NF", "NG", "NI", "NL", "NO", "NP", "NR", "NU", "NZ",
+    "OM", "PA", "PE", "PF", "PG", "PH", "PK", "PL", "PM", "PN", "PR", "PS", "PT", "PW", "PY", "QA", "RE", "RO", "RS",
+    "RU", "RW", "SA", "SB", "SC", "SD", "SE", "SG", "SH", "SI", "SJ", "SK", "SL", "SM", "SN", "SO", "SR", "SS", "ST",
+    "SV", "SX", "SY", "SZ", "TC", "TD", "TF", "TG", "TH", "TJ", "TK", "TL", "TM", "TN", "TO", "TR", "TT", "TV", "TW",
+    "TZ", "UA", "UG", "UM", "US", "UY", "UZ", "VA", "VC", "VE", "VG", "VI", "VN", "VU", "WF", "WS", "YE", "YT", "ZA",
+    "ZM", "ZW"
+];
+
+/// Withdrawn or unofficial codes and the code of their successor
+const HISTORICAL_CODES: [(&str, &str); 9] = [
+    ("AN", "CW"), // Netherlands Antilles
+    ("BU", "MM"), // Burma
+    ("CS", "RS"), // Serbia and Montenegro
+    ("DD", "DE"), // East Germany
+    ("SU", "RU"), // Soviet Union
+    ("TP", "TL"), // East Timor
+    ("UK", "GB"), // United Kingdom
+    ("YU", "RS"), // Yugoslavia
+    ("ZR", "CD")  // Zaire
+];
+
+/// Code used by osu! for players without a country
+const UNKNOWN_CODE: &str = "XX";
+
+/// A country code which is not a valid ISO 3166-1 alpha-2 code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvalidCountry {
+    /// The code as stored
+    pub code: String,
+    /// Number of players with the code
+    pub players: usize
+}
+
+/// The result of normalizing a stored country code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NormalizedCountry {
+    /// A current ISO 3166-1 alpha-2 code
+    Valid(String),
+    /// No country is known (no code, an empty code or the osu! unknown code)
+    Unknown,
+    /// The code is neither an ISO 3166-1 alpha-2 code nor a known historical code
+    Invalid
+}
+
+/// Normalizes a country code: surrounding whitespace is removed, letters are uppercased and
+/// historical codes (e.g. YU) are replaced by their current code
+pub fn normalize_country(code: Option<&str>) -> NormalizedCountry {
+    let code = code.unwrap_or_default().trim().to_ascii_uppercase();
+    if code.is_empty() || code == UNKNOWN_CODE {
+        return NormalizedCountry::Unknown;
+    }
+
+    let current = HISTORICAL_CODES
+        .iter()
+        .find(|(historical, _)| *historical == code)
+        .map_or(code.as_str(), |(_, current)| current);
+
+    match ISO_3166_ALPHA2.binary_search(&current) {
+        Ok(_) => NormalizedCountry::Valid(current.to_string()),
+        Err(_) => NormalizedCountry::Invalid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_country, NormalizedCountry, ISO_3166_ALPHA2};
+
+    fn valid(code: &str) -> NormalizedCountry {
+        NormalizedCountry::Valid(code.to_string())
+    }
+
+    #[test]
+    fn test_codes_are_sorted() {
+        assert!(ISO_3166_ALPHA2.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize_country(Some("US")), valid("US"));
+        assert_eq!(normalize_country(Some(" de ")), valid("DE"));
+        assert_eq!(normalize_country(Some("yu")), valid("RS"));
+        assert_eq!(normalize_country(Some("UK")), valid("GB"));
+
+        assert_eq!(normalize_country(None), NormalizedCountry::Unknown);
+        assert_eq!(normalize_country(Some("")), NormalizedCountry::Unknown);
+        assert_eq!(normalize_country(Some("XX")), NormalizedCountry::Unknown);
+
+        assert_eq!(normalize_country(Some("ZZ")), NormalizedCountry::Invalid);
+        assert_eq!(normalize_country(Some("USA")), NormalizedCountry::Invalid);
+        assert_eq!(normalize_country(Some("??")), NormalizedCountry::Invalid);
+    }
+}
@@ -1,22 +1,36 @@
 /// Core decay implementation for the o!TR system.
 ///
 /// The decay system is responsible for gradually reducing player ratings during periods of inactivity.
-/// Players who don't participate in matches for a certain period (defined by `DECAY_DAYS`) will have
+/// Players who don't participate in matches for a certain period (`DECAY_DAYS` by default) will have
 /// their ratings decay at a weekly rate, while their volatility gradually increases.
 ///
 /// # Key Concepts
 /// - Decay Floor: A minimum rating threshold based on a player's peak rating
 /// - Weekly Decay: Rating reductions occur in weekly intervals after the decay period
-/// - Volatility Growth: Player volatility increases with each decay cycle
+/// - Volatility Growth: Player volatility increases with each volatility cycle, weekly by
+///   default. Rating and volatility cycles falling at the same time share one adjustment.
+/// - Decay Parameters: The inactivity period, rate and minimum can differ per ruleset
+/// - Restrictions: Time a player spent restricted from playing (e.g. banned) does not count
+///   towards their inactivity, pausing decay until the restriction ends
 use super::{
-    constants::{DECAY_DAYS, DECAY_MINIMUM, DECAY_RATE, DECAY_VOLATILITY_GROWTH_RATE, DEFAULT_VOLATILITY},
+    constants::{
+        DECAY_DAYS, DECAY_MINIMUM, DECAY_RATE, DECAY_VOLATILITY_GROWTH_RATE, DECAY_VOLATILITY_INTERVAL_DAYS,
+        DEFAULT_VOLATILITY
+    },
+    restrictions::Restrictions,
     structures::rating_adjustment_type::RatingAdjustmentType
 };
 use crate::{
     database::db_structs::{PlayerRating, RatingAdjustment},
-    model::structures::rating_adjustment_type::RatingAdjustmentType::{Decay, Initial}
+    model::structures::{
+        activity::Activity,
+        rating_adjustment_type::RatingAdjustmentType::{Decay, Initial},
+        rating_clamps::RatingClamps
+    }
 };
 use chrono::{DateTime, Duration, FixedOffset};
+use serde::Serialize;
+use std::collections::BTreeMap;
 use thiserror::Error;
 
 /// Possible errors that can occur during the decay process
@@ -39,18 +53,70 @@ pub enum DecayError {
     BelowDecayFloor
 }
 
+/// Tunable parameters of the decay system, see `ModelConfig::decay_parameters`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecayParameters {
+    /// Number of days a player can be inactive before their rating begins to decay
+    pub inactivity_days: u64,
+    /// Amount of rating lost per decay cycle
+    pub rate: f64,
+    /// Minimum rating that any player can decay to
+    pub minimum: f64,
+    /// Number of days between volatility growth cycles, independent of the weekly rating
+    /// decay cycles
+    pub volatility_interval_days: u64
+}
+
+impl Default for DecayParameters {
+    fn default() -> Self {
+        DecayParameters {
+            inactivity_days: DECAY_DAYS,
+            rate: DECAY_RATE,
+            minimum: DECAY_MINIMUM,
+            volatility_interval_days: DECAY_VOLATILITY_INTERVAL_DAYS
+        }
+    }
+}
+
+/// A point in time at which decay applies, see `DecaySystem::calculate_decay_ticks`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct DecayTick {
+    /// Whether the rating decays at this tick
+    rating: bool,
+    /// Whether the volatility grows at this tick
+    volatility: bool
+}
+
 /// Core decay system implementation
 ///
 /// The DecaySystem uses a reference time to determine if and how much decay should be applied
 /// to player ratings. This allows for historical processing as well as current-time updates.
-pub struct DecaySystem {
-    current_time: DateTime<FixedOffset>
+pub struct DecaySystem<'a> {
+    current_time: DateTime<FixedOffset>,
+    parameters: DecayParameters,
+    restrictions: Option<&'a Restrictions>
 }
 
-impl DecaySystem {
-    /// Creates a new DecaySystem with the specified reference time
+impl<'a> DecaySystem<'a> {
+    /// Creates a new DecaySystem with the specified reference time and the default parameters
     pub fn new(current_time: DateTime<FixedOffset>) -> Self {
-        Self { current_time }
+        Self::with_parameters(current_time, DecayParameters::default())
+    }
+
+    /// Creates a new DecaySystem with the specified reference time and parameters
+    pub fn with_parameters(current_time: DateTime<FixedOffset>, parameters: DecayParameters) -> Self {
+        Self {
+            current_time,
+            parameters,
+            restrictions: None
+        }
+    }
+
+    /// Pauses the inactivity of players while they are restricted from playing
+    pub fn with_restrictions(mut self, restrictions: &'a Restrictions) -> Self {
+        self.restrictions = Some(restrictions);
+        self
     }
 
     /// Applies rating decay to a player if necessary
@@ -64,50 +130,73 @@ impl DecaySystem {
     /// - `Ok(Some(rating))` if decay was applied
     /// - `Ok(None)` if no decay was necessary
     /// - `Err(DecayError)` if decay couldn't be applied
-    pub fn decay<'a>(&self, player_rating: &'a mut PlayerRating) -> Result<Option<&'a PlayerRating>, DecayError> {
+    pub fn decay<'r>(&self, player_rating: &'r mut PlayerRating) -> Result<Option<&'r PlayerRating>, DecayError> {
         self.validate_decay(player_rating)?;
 
         let last_play_time = self.get_last_play_time(player_rating)?;
-        let decay_timestamps = self.calculate_decay_timestamps(player_rating, last_play_time);
+        let decay_ticks = self.calculate_decay_ticks(player_rating, last_play_time);
 
-        if decay_timestamps.is_empty() {
+        if decay_ticks.is_empty() {
             return Ok(None);
         }
 
-        self.apply_decay_adjustments(player_rating, decay_timestamps);
+        self.apply_decay_adjustments(player_rating, decay_ticks);
         Ok(Some(player_rating))
     }
 
+    /// Classifies whether a player is active, decaying or dormant at the reference time
+    ///
+    /// Inactive players are decaying until their rating reaches the decay floor, after which
+    /// they are dormant. Players who were never rated in a match are dormant.
+    pub fn activity(&self, player_rating: &PlayerRating) -> Activity {
+        match self.get_last_play_time(player_rating) {
+            Err(_) => Activity::Dormant,
+            Ok(last_play_time) if self.is_active(player_rating, last_play_time) => Activity::Active,
+            Ok(_) if player_rating.rating > self.calculate_decay_floor(player_rating) => Activity::Decaying,
+            Ok(_) => Activity::Dormant
+        }
+    }
+
     /// Calculates the minimum rating (floor) for a player based on their peak rating
     ///
     /// The decay floor is the maximum of:
-    /// - The system-wide minimum (DECAY_MINIMUM)
-    /// - Half of the sum of DECAY_MINIMUM and the player's peak rating
+    /// - The decay minimum (DECAY_MINIMUM by default)
+    /// - Half of the sum of the decay minimum and the player's peak rating
     ///
     /// This ensures that higher-rated players have a higher floor, preventing
     /// complete rating collapse during long periods of inactivity.
-    pub fn calculate_decay_floor(&self, player_rating: &PlayerRating) -> f64 {
+    pub(crate) fn calculate_decay_floor(&self, player_rating: &PlayerRating) -> f64 {
         let peak_rating = player_rating
             .adjustments
             .iter()
             .map(|adj| adj.rating_after)
             .fold(f64::NEG_INFINITY, f64::max);
 
-        DECAY_MINIMUM.max(0.5 * (DECAY_MINIMUM + peak_rating))
+        self.decay_floor_of_peak(peak_rating)
     }
 
-    /// Calculates new volatility after a decay cycle
+    /// Calculates the decay floor of a player whose peak rating is `peak_rating`, see
+    /// `calculate_decay_floor`
+    pub(crate) fn decay_floor_of_peak(&self, peak_rating: f64) -> f64 {
+        let minimum = self.parameters.minimum;
+        minimum.max(0.5 * (minimum + peak_rating))
+    }
+
+    /// Calculates new volatility after a volatility cycle
     ///
-    /// Volatility increases with each decay cycle but is capped at DEFAULT_VOLATILITY.
-    /// The growth follows a square root formula to provide diminishing returns.
-    pub fn calculate_decay_volatility(&self, current_volatility: f64) -> f64 {
-        let new_volatility = (current_volatility.powf(2.0) + DECAY_VOLATILITY_GROWTH_RATE).sqrt();
+    /// Volatility increases with each cycle but is capped at DEFAULT_VOLATILITY.
+    /// The growth follows a square root formula to provide diminishing returns. The weekly
+    /// growth rate is scaled to the volatility interval, so shorter intervals grow the
+    /// volatility in smaller steps.
+    pub(crate) fn calculate_decay_volatility(&self, current_volatility: f64) -> f64 {
+        let growth = DECAY_VOLATILITY_GROWTH_RATE * self.parameters.volatility_interval_days as f64 / 7.0;
+        let new_volatility = (current_volatility.powf(2.0) + growth).sqrt();
         new_volatility.min(DEFAULT_VOLATILITY)
     }
 
     /// Calculates new rating after decay, ensuring it doesn't fall below the decay floor
-    pub fn calculate_decay_rating(&self, current_rating: f64, decay_floor: f64) -> f64 {
-        (current_rating - DECAY_RATE).max(decay_floor)
+    pub(crate) fn calculate_decay_rating(&self, current_rating: f64, decay_floor: f64) -> f64 {
+        (current_rating - self.parameters.rate).max(decay_floor)
     }
 
     /// Validates whether decay can be applied to a player rating
@@ -124,7 +213,7 @@ impl DecaySystem {
 
         let last_play_time = self.get_last_play_time(player_rating)?;
 
-        if self.is_active(last_play_time) {
+        if self.is_active(player_rating, last_play_time) {
             return Err(DecayError::PlayerActive);
         }
 
@@ -156,58 +245,124 @@ impl DecaySystem {
     /// Determines if a player's `last_play_time` is within the active period
     ///
     /// A player is considered active if their last play time was within
-    /// DECAY_DAYS of the current reference time.
-    fn is_active(&self, last_play_time: DateTime<FixedOffset>) -> bool {
-        self.current_time - last_play_time < Duration::days(DECAY_DAYS as i64)
+    /// the inactivity period (DECAY_DAYS by default) of the current reference time, not
+    /// counting time the player was restricted.
+    fn is_active(&self, player_rating: &PlayerRating, last_play_time: DateTime<FixedOffset>) -> bool {
+        let inactive_for = match self.restrictions {
+            Some(restrictions) => {
+                restrictions.unrestricted_time(player_rating.player_id, last_play_time, self.current_time)
+            }
+            None => self.current_time - last_play_time
+        };
+
+        inactive_for < Duration::days(self.parameters.inactivity_days as i64)
+    }
+
+    /// The time at which the player has been unrestricted for `duration` since `from`, None
+    /// if they are restricted indefinitely before that
+    fn after_unrestricted(
+        &self,
+        player_rating: &PlayerRating,
+        from: DateTime<FixedOffset>,
+        duration: Duration
+    ) -> Option<DateTime<FixedOffset>> {
+        match self.restrictions {
+            Some(restrictions) => restrictions.after_unrestricted(player_rating.player_id, from, duration),
+            None => Some(from + duration)
+        }
     }
 
-    /// Calculates timestamps for each decay cycle that should be applied
+    /// Calculates the ticks of the rating and volatility decay cycles that should be applied
+    ///
+    /// Both schedules:
+    /// 1. Start after the inactivity period
+    /// 2. Occur weekly (rating) or every volatility interval (volatility) thereafter
+    /// 3. Stop when the current time is reached
+    ///
+    /// Time the player was restricted does not count towards either schedule, so cycles
+    /// resume where they left off once a restriction ends.
     ///
-    /// Decay cycles:
-    /// 1. Start after DECAY_DAYS of inactivity
-    /// 2. Occur weekly thereafter
-    /// 3. Stop when either:
-    ///    - Current time is reached
-    ///    - Rating hits decay floor
-    fn calculate_decay_timestamps(
+    /// Cycles of both schedules falling at the same time are merged into a single tick.
+    /// Cycles at or before the player's last adjustment (e.g. a manual adjustment made while
+    /// inactive, or a cycle which was already applied) are skipped, keeping the adjustment
+    /// history chronological.
+    fn calculate_decay_ticks(
         &self,
         player_rating: &PlayerRating,
         last_play_time: DateTime<FixedOffset>
-    ) -> Vec<DateTime<FixedOffset>> {
-        let decay_start = last_play_time + Duration::days(DECAY_DAYS as i64);
-        let mut timestamps = Vec::new();
-        let floor = self.calculate_decay_floor(player_rating);
-
-        let mut current_time = decay_start;
-        while current_time <= self.current_time {
-            timestamps.push(current_time);
-            current_time += Duration::weeks(1);
+    ) -> BTreeMap<DateTime<FixedOffset>, DecayTick> {
+        let inactivity = Duration::days(self.parameters.inactivity_days as i64);
+        let last_adjustment_time = player_rating.adjustments.last().map(|a| a.timestamp);
+        let mut ticks: BTreeMap<DateTime<FixedOffset>, DecayTick> = BTreeMap::new();
+
+        let schedules = [
+            (Duration::weeks(1), true),
+            (
+                Duration::days(self.parameters.volatility_interval_days.max(1) as i64),
+                false
+            )
+        ];
+        for (interval, is_rating) in schedules {
+            let mut elapsed = inactivity;
+            while let Some(current_time) = self
+                .after_unrestricted(player_rating, last_play_time, elapsed)
+                .filter(|time| *time <= self.current_time)
+            {
+                if last_adjustment_time.is_none_or(|last| current_time > last) {
+                    let tick = ticks.entry(current_time).or_default();
+                    if is_rating {
+                        tick.rating = true;
+                    } else {
+                        tick.volatility = true;
+                    }
+                }
+                elapsed += interval;
+            }
         }
 
-        timestamps
+        ticks
     }
 
     /// Applies decay adjustments to a player's rating
     ///
-    /// For each decay cycle:
-    /// 1. Calculates new rating and volatility
+    /// For each decay tick:
+    /// 1. Calculates new rating and volatility, for whichever cycles fall on the tick
     /// 2. Creates a decay adjustment record
     /// 3. Updates the player's current rating and volatility
-    fn apply_decay_adjustments(&self, player_rating: &mut PlayerRating, timestamps: Vec<DateTime<FixedOffset>>) {
+    ///
+    /// Once the rating hits the decay floor, neither the rating nor the volatility decays
+    /// any further.
+    fn apply_decay_adjustments(
+        &self,
+        player_rating: &mut PlayerRating,
+        ticks: BTreeMap<DateTime<FixedOffset>, DecayTick>
+    ) {
         let mut current_rating = player_rating.rating;
         let mut current_volatility = player_rating.volatility;
         let floor = self.calculate_decay_floor(player_rating);
 
-        let mut adjustments = Vec::with_capacity(timestamps.len());
+        let mut adjustments = Vec::with_capacity(ticks.len());
 
-        for timestamp in timestamps {
-            let new_rating = self.calculate_decay_rating(current_rating, floor);
-            let new_volatility = self.calculate_decay_volatility(current_volatility);
+        for (timestamp, tick) in ticks {
+            let new_rating = if tick.rating {
+                self.calculate_decay_rating(current_rating, floor)
+            } else {
+                current_rating
+            };
+            let new_volatility = if tick.volatility {
+                self.calculate_decay_volatility(current_volatility)
+            } else {
+                current_volatility
+            };
 
             // Stop if we've hit the floor (no more decay possible)
-            if new_rating == current_rating {
+            if tick.rating && new_rating == current_rating {
                 break;
             }
+            // A volatility cycle with the volatility already at its cap changes nothing
+            if new_rating == current_rating && new_volatility == current_volatility {
+                continue;
+            }
 
             adjustments.push(RatingAdjustment {
                 player_id: player_rating.player_id,
@@ -218,7 +373,11 @@ impl DecaySystem {
                 volatility_before: current_volatility,
                 volatility_after: new_volatility,
                 timestamp,
-                adjustment_type: Decay
+                adjustment_type: Decay,
+                average_opponent_rating: None,
+                provisional: false,
+                percentile: None,
+                clamps: RatingClamps::default()
             });
 
             current_rating = new_rating;
@@ -231,10 +390,55 @@ impl DecaySystem {
     }
 }
 
+/// Decays the ratings of inactive players during processing, see `OtrModelBuilder::decay`
+pub trait RatingDecay: Send + Sync {
+    /// Decays `player_rating` up to `current_time`, see `DecaySystem::decay`
+    fn decay<'r>(
+        &self,
+        player_rating: &'r mut PlayerRating,
+        current_time: DateTime<FixedOffset>,
+        parameters: DecayParameters,
+        restrictions: &Restrictions
+    ) -> Result<Option<&'r PlayerRating>, DecayError>;
+}
+
+/// Decays ratings with the `DecaySystem`
+pub struct StandardDecay;
+
+impl RatingDecay for StandardDecay {
+    fn decay<'r>(
+        &self,
+        player_rating: &'r mut PlayerRating,
+        current_time: DateTime<FixedOffset>,
+        parameters: DecayParameters,
+        restrictions: &Restrictions
+    ) -> Result<Option<&'r PlayerRating>, DecayError> {
+        DecaySystem::with_parameters(current_time, parameters)
+            .with_restrictions(restrictions)
+            .decay(player_rating)
+    }
+}
+
+/// Never decays any rating
+pub struct NoDecay;
+
+impl RatingDecay for NoDecay {
+    fn decay<'r>(
+        &self,
+        _player_rating: &'r mut PlayerRating,
+        _current_time: DateTime<FixedOffset>,
+        _parameters: DecayParameters,
+        _restrictions: &Restrictions
+    ) -> Result<Option<&'r PlayerRating>, DecayError> {
+        Ok(None)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
+        database::db_structs::PlayerRestriction,
         model::structures::{rating_adjustment_type::RatingAdjustmentType, ruleset::Ruleset},
         utils::test_utils::generate_player_rating
     };
@@ -300,6 +504,31 @@ mod tests {
         assert_eq!(system.decay(&mut rating), Err(DecayError::BelowDecayFloor));
     }
 
+    #[test]
+    fn test_activity() {
+        let last_played = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let active = DecaySystem::new(last_played + Duration::days(1));
+        let inactive = DecaySystem::new(last_played + Duration::days(DECAY_DAYS as i64));
+
+        let rating = generate_player_rating(1, Ruleset::Osu, 2000.0, 200.0, 2, Some(last_played), Some(last_played));
+        assert_eq!(active.activity(&rating), Activity::Active);
+        assert_eq!(inactive.activity(&rating), Activity::Decaying);
+
+        let at_floor = generate_player_rating(
+            1,
+            Ruleset::Osu,
+            DECAY_MINIMUM,
+            200.0,
+            2,
+            Some(last_played),
+            Some(last_played)
+        );
+        assert_eq!(inactive.activity(&at_floor), Activity::Dormant);
+
+        let initial = generate_player_rating(1, Ruleset::Osu, 2000.0, 200.0, 1, Some(last_played), Some(last_played));
+        assert_eq!(active.activity(&initial), Activity::Dormant);
+    }
+
     #[test]
     fn test_single_decay_cycle() {
         let last_played = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
@@ -379,7 +608,11 @@ mod tests {
             volatility_before: 200.0,
             volatility_after: 200.0,
             timestamp: Utc::now().fixed_offset(),
-            adjustment_type: RatingAdjustmentType::Match
+            adjustment_type: RatingAdjustmentType::Match,
+            average_opponent_rating: None,
+            provisional: false,
+            percentile: None,
+            clamps: RatingClamps::default()
         });
 
         let floor = system.calculate_decay_floor(&rating);
@@ -388,4 +621,186 @@ mod tests {
         assert_abs_diff_eq!(floor, expected_floor);
         assert!(floor >= DECAY_MINIMUM);
     }
+
+    #[test]
+    fn test_custom_parameters() {
+        let last_played = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let current_time = last_played + Duration::days(DECAY_DAYS as i64);
+        let parameters = DecayParameters {
+            inactivity_days: DECAY_DAYS * 2,
+            rate: DECAY_RATE * 2.0,
+            minimum: 500.0,
+            ..DecayParameters::default()
+        };
+        let rating = generate_player_rating(
+            1,
+            Ruleset::Catch,
+            2000.0,
+            200.0,
+            2,
+            Some(last_played),
+            Some(last_played)
+        );
+
+        // Still within the longer inactivity period
+        let system = DecaySystem::with_parameters(current_time, parameters);
+        assert_eq!(system.decay(&mut rating.clone()), Err(DecayError::PlayerActive));
+
+        let later = current_time + Duration::days(DECAY_DAYS as i64);
+        let system = DecaySystem::with_parameters(later, parameters);
+        let mut decayed = rating.clone();
+        system.decay(&mut decayed).unwrap();
+
+        let decay = decayed.adjustments.last().unwrap();
+        assert_eq!(decay.timestamp, last_played + Duration::days(2 * DECAY_DAYS as i64));
+        assert_abs_diff_eq!(
+            decay.rating_before - decay.rating_after,
+            DECAY_RATE * 2.0,
+            epsilon = 1e-9
+        );
+
+        let peak_rating = rating
+            .adjustments
+            .iter()
+            .map(|adj| adj.rating_after)
+            .fold(f64::NEG_INFINITY, f64::max);
+        assert_abs_diff_eq!(
+            system.calculate_decay_floor(&rating),
+            500.0_f64.max(0.5 * (500.0 + peak_rating))
+        );
+    }
+
+    #[test]
+    fn test_daily_volatility_cycles() {
+        let last_played = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let decay_start = last_played + Duration::days(DECAY_DAYS as i64);
+        let rating = generate_player_rating(1, Ruleset::Osu, 2000.0, 200.0, 2, Some(last_played), Some(last_played));
+        let current_time = decay_start + Duration::weeks(1);
+
+        let weekly = DecaySystem::new(current_time);
+        let mut weekly_decayed = rating.clone();
+        weekly.decay(&mut weekly_decayed).unwrap();
+
+        let daily = DecaySystem::with_parameters(
+            current_time,
+            DecayParameters {
+                volatility_interval_days: 1,
+                ..DecayParameters::default()
+            }
+        );
+        let mut daily_decayed = rating.clone();
+        daily.decay(&mut daily_decayed).unwrap();
+
+        let decays = daily_decayed
+            .adjustments
+            .iter()
+            .filter(|adj| adj.adjustment_type == Decay)
+            .collect::<Vec<_>>();
+
+        // Rating cycles at the start and a week later coincide with volatility cycles
+        assert_eq!(decays.len(), 8);
+        assert_eq!(decays[0].timestamp, decay_start);
+        assert_eq!(decays[7].timestamp, current_time);
+        let rating_cycles = decays
+            .iter()
+            .filter(|adj| adj.rating_after != adj.rating_before)
+            .map(|adj| adj.timestamp)
+            .collect::<Vec<_>>();
+        assert_eq!(rating_cycles, vec![decay_start, current_time]);
+        assert!(decays.iter().all(|adj| adj.volatility_after > adj.volatility_before));
+
+        // The same rating is lost, while the variance grows by a seventh of the weekly growth
+        // at each of the 8 daily cycles instead of the full growth at each of the 2 weekly ones
+        assert_abs_diff_eq!(daily_decayed.rating, weekly_decayed.rating);
+        let weekly_variance = weekly_decayed.volatility.powi(2) - rating.volatility.powi(2);
+        let daily_variance = daily_decayed.volatility.powi(2) - rating.volatility.powi(2);
+        assert_abs_diff_eq!(weekly_variance, 2.0 * DECAY_VOLATILITY_GROWTH_RATE, epsilon = 1e-6);
+        assert_abs_diff_eq!(daily_variance, 8.0 * DECAY_VOLATILITY_GROWTH_RATE / 7.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_decay_resumes_after_last_adjustment() {
+        let last_played = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let decay_start = last_played + Duration::days(DECAY_DAYS as i64);
+        let mut rating =
+            generate_player_rating(1, Ruleset::Osu, 2000.0, 200.0, 2, Some(last_played), Some(last_played));
+
+        // A manual adjustment between the first and second decay cycle
+        let manual_time = decay_start + Duration::days(3);
+        rating.adjustments.push(RatingAdjustment {
+            player_id: 1,
+            ruleset: Ruleset::Osu,
+            match_id: None,
+            rating_before: rating.rating,
+            rating_after: rating.rating,
+            volatility_before: rating.volatility,
+            volatility_after: rating.volatility,
+            timestamp: manual_time,
+            adjustment_type: RatingAdjustmentType::Manual,
+            average_opponent_rating: None,
+            provisional: false,
+            percentile: None,
+            clamps: RatingClamps::default()
+        });
+
+        let system = DecaySystem::new(decay_start + Duration::weeks(2));
+        let result = system.decay(&mut rating).unwrap().unwrap();
+
+        let decay_times = result
+            .adjustments
+            .iter()
+            .filter(|adj| adj.adjustment_type == Decay)
+            .map(|adj| adj.timestamp)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decay_times,
+            vec![decay_start + Duration::weeks(1), decay_start + Duration::weeks(2)]
+        );
+    }
+
+    #[test]
+    fn test_restrictions_pause_decay() {
+        let last_played = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let banned_at = last_played + Duration::days(10);
+        let restrictions = Restrictions::new(&[PlayerRestriction {
+            player_id: 1,
+            starts_at: banned_at,
+            ends_at: Some(banned_at + Duration::days(30))
+        }]);
+
+        // Decay starts 30 days late and continues weekly afterwards
+        let decay_start = last_played + Duration::days(DECAY_DAYS as i64 + 30);
+        let mut rating =
+            generate_player_rating(1, Ruleset::Osu, 2000.0, 200.0, 2, Some(last_played), Some(last_played));
+        let system = DecaySystem::new(decay_start + Duration::weeks(1)).with_restrictions(&restrictions);
+        let result = system.decay(&mut rating).unwrap().unwrap();
+
+        let decay_times = result
+            .adjustments
+            .iter()
+            .filter(|adj| adj.adjustment_type == Decay)
+            .map(|adj| adj.timestamp)
+            .collect::<Vec<_>>();
+        assert_eq!(decay_times, vec![decay_start, decay_start + Duration::weeks(1)]);
+
+        // Other players decay as usual
+        let mut other = generate_player_rating(2, Ruleset::Osu, 2000.0, 200.0, 2, Some(last_played), Some(last_played));
+        assert_eq!(system.decay(&mut other).unwrap().unwrap().adjustments.len(), 2 + 6);
+    }
+
+    #[test]
+    fn test_ongoing_restriction_keeps_player_active() {
+        let last_played = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let restrictions = Restrictions::new(&[PlayerRestriction {
+            player_id: 1,
+            starts_at: last_played + Duration::days(10),
+            ends_at: None
+        }]);
+        let system = DecaySystem::new(last_played + Duration::days(365)).with_restrictions(&restrictions);
+        let mut rating =
+            generate_player_rating(1, Ruleset::Osu, 2000.0, 200.0, 2, Some(last_played), Some(last_played));
+
+        assert_eq!(system.activity(&rating), Activity::Active);
+        assert_eq!(system.decay(&mut rating), Err(DecayError::PlayerActive));
+    }
 }
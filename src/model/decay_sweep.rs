@@ -0,0 +1,179 @@
+use super::{
+    decay::{DecayParameters, DecaySystem},
+    model_config::ModelConfig,
+    structures::rating_adjustment_type::RatingAdjustmentType
+};
+use crate::database::db_structs::{PlayerRating, RatingAdjustment};
+use chrono::{DateTime, FixedOffset};
+use itertools::Itertools;
+use std::fmt::Write;
+
+/// Number of players whose decay is replayed, unless configured
+pub const DEFAULT_SWEEP_SAMPLE: usize = 100;
+
+/// Columns of the CSV written by `to_csv`
+const CSV_HEADER: &str = "inactivity_days,rate,player_id,ruleset,timestamp,adjustment_type,rating,volatility";
+
+/// A point of a player's rating trajectory under one combination of decay parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrajectoryPoint {
+    pub inactivity_days: u64,
+    pub rate: f64,
+    pub player_id: i32,
+    pub adjustment: RatingAdjustment
+}
+
+/// Replays the decay of every rating under each combination of `rates` and `inactivity_days`
+///
+/// Only decay is recomputed, matches are not processed again: the stored decay adjustments
+/// are dropped and every other adjustment keeps its rating change, applied on top of the
+/// rating as decayed under the swept parameters. Ratings then decay up to `until`. All other
+/// decay parameters are taken from `config`.
+///
+/// # Returns
+/// The replayed adjustments of every rating, for each combination in order of the grid
+pub fn sweep_decay(
+    ratings: &[PlayerRating],
+    config: &ModelConfig,
+    rates: &[f64],
+    inactivity_days: &[u64],
+    until: DateTime<FixedOffset>
+) -> Vec<TrajectoryPoint> {
+    inactivity_days
+        .iter()
+        .cartesian_product(rates)
+        .flat_map(|(&days, &rate)| {
+            ratings.iter().flat_map(move |rating| {
+                let parameters = DecayParameters {
+                    inactivity_days: days,
+                    rate,
+                    ..config.decay_parameters(rating.ruleset)
+                };
+                replay_decay(rating, parameters, until)
+                    .adjustments
+                    .into_iter()
+                    .map(move |adjustment| TrajectoryPoint {
+                        inactivity_days: days,
+                        rate,
+                        player_id: rating.player_id,
+                        adjustment
+                    })
+            })
+        })
+        .collect()
+}
+
+/// Replays the adjustments of `rating` with decay recomputed under `parameters`, see
+/// `sweep_decay`
+fn replay_decay(rating: &PlayerRating, parameters: DecayParameters, until: DateTime<FixedOffset>) -> PlayerRating {
+    let mut replayed = PlayerRating {
+        adjustments: Vec::with_capacity(rating.adjustments.len()),
+        ..rating.clone()
+    };
+
+    for adjustment in rating
+        .adjustments
+        .iter()
+        .filter(|a| a.adjustment_type != RatingAdjustmentType::Decay)
+    {
+        // Inactive players decay up to the adjustment, which is not an error to report
+        if !replayed.adjustments.is_empty() {
+            let _ = DecaySystem::with_parameters(adjustment.timestamp, parameters).decay(&mut replayed);
+        }
+
+        let (rating_before, volatility_before) = if replayed.adjustments.is_empty() {
+            (adjustment.rating_before, adjustment.volatility_before)
+        } else {
+            (replayed.rating, replayed.volatility)
+        };
+        let rating_after = rating_before + adjustment.rating_after - adjustment.rating_before;
+        replayed.adjustments.push(RatingAdjustment {
+            rating_before,
+            rating_after,
+            volatility_before,
+            ..adjustment.clone()
+        });
+        replayed.rating = rating_after;
+        replayed.volatility = adjustment.volatility_after;
+    }
+
+    let _ = DecaySystem::with_parameters(until, parameters).decay(&mut replayed);
+    replayed
+}
+
+/// Writes the trajectories as CSV, one row per adjustment along with its decay parameters
+pub fn to_csv(points: &[TrajectoryPoint]) -> String {
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for point in points {
+        let adjustment = &point.adjustment;
+        writeln!(
+            csv,
+            "{},{},{},{:?},{},{:?},{},{}",
+            point.inactivity_days,
+            point.rate,
+            point.player_id,
+            adjustment.ruleset,
+            adjustment.timestamp.to_rfc3339(),
+            adjustment.adjustment_type,
+            adjustment.rating_after,
+            adjustment.volatility_after
+        )
+        .expect("Writing to a String cannot fail");
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sweep_decay, to_csv};
+    use crate::{
+        model::{
+            constants::{DECAY_DAYS, DECAY_RATE},
+            decay::DecaySystem,
+            model_config::ModelConfig,
+            structures::{rating_adjustment_type::RatingAdjustmentType, ruleset::Ruleset::Osu}
+        },
+        utils::test_utils::generate_player_rating
+    };
+    use approx::assert_abs_diff_eq;
+    use chrono::{Duration, TimeZone, Utc};
+    use itertools::Itertools;
+
+    #[test]
+    fn test_sweep_replays_decay() {
+        let start = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let until = start + Duration::days(365);
+        let mut rating = generate_player_rating(1, Osu, 1500.0, 200.0, 3, Some(start), Some(start));
+        DecaySystem::new(until).decay(&mut rating).unwrap();
+        let config = ModelConfig::default();
+
+        let points = sweep_decay(&[rating.clone()], &config, &[3.6, 7.2], &[100, 400], until);
+        let final_rating = |days, rate| {
+            points
+                .iter()
+                .rev()
+                .find(|p| p.inactivity_days == days && p.rate == rate)
+                .unwrap()
+                .adjustment
+                .rating_after
+        };
+
+        // The stored decay is reproduced with the parameters it was computed with
+        let default = sweep_decay(&[rating.clone()], &config, &[DECAY_RATE], &[DECAY_DAYS], until);
+        assert_abs_diff_eq!(default.last().unwrap().adjustment.rating_after, rating.rating, epsilon = 1e-9);
+
+        // Faster and earlier decay leaves a lower rating, no decay within the year leaves it
+        assert!(final_rating(100, 7.2) < final_rating(100, 3.6));
+        assert!(final_rating(100, 3.6) < final_rating(400, 3.6));
+        assert_abs_diff_eq!(final_rating(400, 7.2), 1500.0);
+        assert!(points
+            .iter()
+            .filter(|p| p.inactivity_days == 400)
+            .all(|p| p.adjustment.adjustment_type != RatingAdjustmentType::Decay));
+
+        let csv = to_csv(&points);
+        let lines = csv.lines().collect_vec();
+        assert_eq!(lines.len(), points.len() + 1);
+        assert!(lines[1].starts_with("100,3.6,1,Osu,2020-01-01T00:00:00+00:00,Initial,"));
+    }
+}
@@ -0,0 +1,136 @@
+use super::structures::display_scale::DisplayScale;
+use crate::database::db_structs::{DisplayRating, PlayerRating};
+use itertools::Itertools;
+
+/// Scales the ratings of every ruleset onto `scale`, placing its anchors at the ratings found
+/// at their percentiles of the ruleset's leaderboard
+///
+/// The scaling preserves the order of ratings and, between two anchors, their relative
+/// distances, so the display ratings only differ from the internal ones in how they are
+/// stretched.
+///
+/// # Returns
+/// The display ratings ordered by ruleset and player id
+pub fn display_ratings(ratings: &[PlayerRating], scale: &DisplayScale) -> Vec<DisplayRating> {
+    let mut display_ratings = Vec::with_capacity(ratings.len());
+
+    for (ruleset, ruleset_ratings) in ratings
+        .iter()
+        .into_group_map_by(|r| r.ruleset)
+        .into_iter()
+        .sorted_by_key(|(ruleset, _)| *ruleset as i32)
+    {
+        let sorted = ruleset_ratings
+            .iter()
+            .map(|r| r.rating)
+            .sorted_by(f64::total_cmp)
+            .collect_vec();
+        let anchors = scale
+            .anchors
+            .iter()
+            .map(|a| (quantile(&sorted, a.percentile), a.display))
+            .collect_vec();
+
+        display_ratings.extend(
+            ruleset_ratings
+                .iter()
+                .sorted_by_key(|r| r.player_id)
+                .map(|r| DisplayRating {
+                    player_id: r.player_id,
+                    ruleset,
+                    display_rating: scale_rating(r.rating, &anchors)
+                })
+        );
+    }
+
+    display_ratings
+}
+
+/// The rating at `percentile` of the ascending `sorted` ratings, interpolating between the
+/// two closest ratings
+fn quantile(sorted: &[f64], percentile: f64) -> f64 {
+    let position = percentile / 100.0 * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+/// Interpolates `rating` between the anchors, given as `(rating, display)` in increasing order
+fn scale_rating(rating: f64, anchors: &[(f64, f64)]) -> f64 {
+    let (first_rating, first_display) = anchors[0];
+    if rating < first_rating {
+        return first_display;
+    }
+
+    anchors
+        .iter()
+        .tuple_windows()
+        .find(|(lower, upper)| lower.0 <= rating && rating < upper.0)
+        .map(|((lower_rating, lower_display), (upper_rating, upper_display))| {
+            lower_display + (upper_display - lower_display) * (rating - lower_rating) / (upper_rating - lower_rating)
+        })
+        .unwrap_or(anchors[anchors.len() - 1].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::display_ratings;
+    use crate::{
+        model::structures::{
+            display_scale::DisplayScale,
+            ruleset::Ruleset::{Osu, Taiko}
+        },
+        utils::test_utils::generate_player_rating
+    };
+    use approx::assert_abs_diff_eq;
+    use itertools::Itertools;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_anchors_map_percentiles() {
+        let ratings = (1..=5)
+            .map(|id| generate_player_rating(id, Osu, 500.0 + 100.0 * id as f64, 100.0, 1, None, None))
+            .collect_vec();
+        let scale = DisplayScale::from_str("0:100,50:1000,100:3500").unwrap();
+
+        let displayed = display_ratings(&ratings, &scale)
+            .iter()
+            .map(|d| d.display_rating)
+            .collect_vec();
+
+        // 600 is the lowest rating, 800 the median and 1000 the highest
+        for (actual, expected) in displayed.iter().zip([100.0, 550.0, 1000.0, 2250.0, 3500.0]) {
+            assert_abs_diff_eq!(*actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_rulesets_are_scaled_independently() {
+        let ratings = vec![
+            generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None),
+            generate_player_rating(2, Osu, 2000.0, 100.0, 1, None, None),
+            generate_player_rating(1, Taiko, 500.0, 100.0, 1, None, None),
+            generate_player_rating(2, Taiko, 600.0, 100.0, 1, None, None),
+        ];
+        let scale = DisplayScale::from_str("0:100,100:3500").unwrap();
+
+        let displayed = display_ratings(&ratings, &scale)
+            .iter()
+            .map(|d| (d.player_id, d.ruleset, d.display_rating))
+            .collect_vec();
+
+        assert_eq!(
+            displayed,
+            vec![(1, Osu, 100.0), (2, Osu, 3500.0), (1, Taiko, 100.0), (2, Taiko, 3500.0)]
+        );
+    }
+
+    #[test]
+    fn test_single_rating() {
+        let ratings = vec![generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None)];
+        let scale = DisplayScale::from_str("0:100,100:3500").unwrap();
+
+        assert_eq!(display_ratings(&ratings, &scale)[0].display_rating, 3500.0);
+    }
+}
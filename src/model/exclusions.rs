@@ -0,0 +1,90 @@
+use crate::database::db_structs::{Game, Match};
+use itertools::Itertools;
+use std::collections::HashSet;
+
+/// Removes the scores of excluded players (e.g. bots or staff accounts) from every game.
+///
+/// The placements of the remaining scores in affected games are re-ranked so that they stay
+/// contiguous, keeping their relative order and ties. This works the same whether placements
+/// were calculated from scores or read from the database.
+///
+/// # Returns
+/// The number of removed scores
+pub fn exclude_players(matches: &mut [Match], excluded: &HashSet<i32>) -> usize {
+    if excluded.is_empty() {
+        return 0;
+    }
+
+    let mut removed = 0;
+    for game in matches.iter_mut().flat_map(|m| m.games.iter_mut()) {
+        let before = game.scores.len();
+        game.scores.retain(|s| !excluded.contains(&s.player_id));
+
+        if game.scores.len() != before {
+            removed += before - game.scores.len();
+            rerank_placements(game);
+        }
+    }
+
+    removed
+}
+
+/// Re-ranks placements so each score is placed one after the number of scores placed ahead of it
+pub(crate) fn rerank_placements(game: &mut Game) {
+    let placements = game.scores.iter().map(|s| s.placement).collect_vec();
+
+    for score in &mut game.scores {
+        score.placement = 1 + placements.iter().filter(|&&other| other < score.placement).count() as i32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::exclude_players;
+    use crate::{
+        model::structures::ruleset::Ruleset::Osu,
+        utils::test_utils::{generate_game, generate_match, generate_placement}
+    };
+    use chrono::Utc;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_excluded_scores_removed_and_placements_reranked() {
+        let placements = vec![
+            generate_placement(1, 2),
+            generate_placement(2, 1),
+            generate_placement(3, 3),
+            generate_placement(4, 3),
+        ];
+        let mut matches = vec![generate_match(
+            1,
+            Osu,
+            &[generate_game(1, &placements)],
+            Utc::now().fixed_offset()
+        )];
+
+        let removed = exclude_players(&mut matches, &HashSet::from([2]));
+
+        assert_eq!(removed, 1);
+        let remaining = matches[0].games[0]
+            .scores
+            .iter()
+            .map(|s| (s.player_id, s.placement))
+            .collect::<Vec<_>>();
+        assert_eq!(remaining, vec![(1, 1), (3, 2), (4, 2)]);
+    }
+
+    #[test]
+    fn test_unaffected_games_keep_placements() {
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 3)];
+        let mut matches = vec![generate_match(
+            1,
+            Osu,
+            &[generate_game(1, &placements)],
+            Utc::now().fixed_offset()
+        )];
+
+        assert_eq!(exclude_players(&mut matches, &HashSet::from([5])), 0);
+        assert_eq!(matches[0].games[0].scores[1].placement, 3);
+    }
+}
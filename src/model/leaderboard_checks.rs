@@ -0,0 +1,195 @@
+use crate::{database::db_structs::PlayerRating, model::structures::ruleset::Ruleset};
+use itertools::Itertools;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Inconsistencies between the ranks and percentiles of the final ratings, which indicate
+/// a bug in ranking rather than bad input data
+#[derive(Error, Debug, PartialEq)]
+pub enum LeaderboardError {
+    #[error("Global rank {rank} is held by more than one player in ruleset {ruleset:?}")]
+    DuplicateGlobalRank { ruleset: Ruleset, rank: i32 },
+    #[error("Global ranks in ruleset {ruleset:?} skip from {previous} to {rank}")]
+    NonContiguousGlobalRanks { ruleset: Ruleset, previous: i32, rank: i32 },
+    #[error("Country ranks of {country} in ruleset {ruleset:?} are not contiguous from 1 (found {rank} at position {position})")]
+    NonContiguousCountryRanks {
+        country: String,
+        ruleset: Ruleset,
+        position: i32,
+        rank: i32
+    },
+    #[error("Percentile of global rank {rank} in ruleset {ruleset:?} is higher than that of the rank above")]
+    NonMonotonicPercentile { ruleset: Ruleset, rank: i32 }
+}
+
+/// Verifies the leaderboards formed by `ratings` after `RatingTracker::sort`
+///
+/// - Global ranks of each ruleset are unique and contiguous from 1
+/// - Percentiles do not increase as the global rank increases
+/// - Country ranks of each country and ruleset are contiguous from 1. Players without a
+///   known country in `country_mapping` are not checked.
+///
+/// # Returns
+/// The first violation found
+pub fn check_leaderboards(
+    ratings: &[PlayerRating],
+    country_mapping: &HashMap<i32, String>
+) -> Result<(), LeaderboardError> {
+    let by_ruleset = ratings.iter().into_group_map_by(|r| r.ruleset);
+
+    for (ruleset, mut board) in by_ruleset.into_iter().sorted_by_key(|(ruleset, _)| *ruleset as i32) {
+        board.sort_by_key(|r| r.global_rank);
+
+        let mut previous: Option<&PlayerRating> = None;
+        for rating in board {
+            let previous_rank = previous.map_or(0, |p| p.global_rank);
+            if rating.global_rank == previous_rank {
+                return Err(LeaderboardError::DuplicateGlobalRank {
+                    ruleset,
+                    rank: rating.global_rank
+                });
+            }
+            if rating.global_rank != previous_rank + 1 {
+                return Err(LeaderboardError::NonContiguousGlobalRanks {
+                    ruleset,
+                    previous: previous_rank,
+                    rank: rating.global_rank
+                });
+            }
+            if previous.is_some_and(|p| rating.percentile > p.percentile) {
+                return Err(LeaderboardError::NonMonotonicPercentile {
+                    ruleset,
+                    rank: rating.global_rank
+                });
+            }
+
+            previous = Some(rating);
+        }
+    }
+
+    let by_country = ratings
+        .iter()
+        .filter_map(|r| {
+            country_mapping
+                .get(&r.player_id)
+                .filter(|c| !c.is_empty())
+                .map(|country| ((country.clone(), r.ruleset), r.country_rank))
+        })
+        .into_group_map();
+
+    for ((country, ruleset), ranks) in by_country
+        .into_iter()
+        .sorted_by_key(|((country, ruleset), _)| (country.clone(), *ruleset as i32))
+    {
+        for (position, rank) in (1..).zip(ranks.into_iter().sorted()) {
+            if rank != position {
+                return Err(LeaderboardError::NonContiguousCountryRanks {
+                    country,
+                    ruleset,
+                    position,
+                    rank
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_leaderboards, LeaderboardError};
+    use crate::{
+        database::db_structs::PlayerRating,
+        model::{
+            rating_tracker::RatingTracker,
+            structures::ruleset::Ruleset::{self, Mania7k, Osu}
+        },
+        utils::test_utils::generate_player_rating
+    };
+    use std::collections::HashMap;
+
+    fn ranked(id: i32, ruleset: Ruleset, global_rank: i32, country_rank: i32, percentile: f64) -> PlayerRating {
+        let mut rating = generate_player_rating(id, ruleset, 1000.0, 100.0, 2, None, None);
+        rating.global_rank = global_rank;
+        rating.country_rank = country_rank;
+        rating.percentile = percentile;
+        rating
+    }
+
+    fn countries() -> HashMap<i32, String> {
+        HashMap::from([(1, "US".to_string()), (2, "US".to_string()), (3, "DE".to_string())])
+    }
+
+    /// Ratings ranked by the tracker pass every check, in every ruleset
+    #[test]
+    fn test_sorted_tracker_is_consistent() {
+        let mut tracker = RatingTracker::new();
+        tracker.set_country_mapping(countries());
+        tracker.insert_or_update(&[
+            generate_player_rating(1, Osu, 1000.0, 100.0, 2, None, None),
+            generate_player_rating(2, Osu, 1200.0, 100.0, 2, None, None),
+            generate_player_rating(3, Osu, 900.0, 100.0, 2, None, None),
+            generate_player_rating(4, Osu, 1100.0, 100.0, 2, None, None),
+            generate_player_rating(1, Mania7k, 1000.0, 100.0, 2, None, None)
+        ]);
+        tracker.sort();
+
+        assert_eq!(check_leaderboards(&tracker.get_all_ratings(), &countries()), Ok(()));
+    }
+
+    #[test]
+    fn test_global_rank_violations() {
+        let duplicate = vec![ranked(1, Osu, 1, 1, 50.0), ranked(2, Osu, 1, 2, 50.0)];
+        assert_eq!(
+            check_leaderboards(&duplicate, &countries()),
+            Err(LeaderboardError::DuplicateGlobalRank { ruleset: Osu, rank: 1 })
+        );
+
+        let gap = vec![ranked(1, Osu, 1, 1, 50.0), ranked(2, Osu, 3, 2, 0.0)];
+        assert_eq!(
+            check_leaderboards(&gap, &countries()),
+            Err(LeaderboardError::NonContiguousGlobalRanks {
+                ruleset: Osu,
+                previous: 1,
+                rank: 3
+            })
+        );
+
+        let unranked = vec![ranked(1, Osu, 0, 1, 0.0)];
+        assert!(check_leaderboards(&unranked, &countries()).is_err());
+    }
+
+    #[test]
+    fn test_percentile_violation() {
+        let ratings = vec![ranked(1, Osu, 1, 1, 0.0), ranked(2, Osu, 2, 2, 50.0)];
+
+        assert_eq!(
+            check_leaderboards(&ratings, &countries()),
+            Err(LeaderboardError::NonMonotonicPercentile { ruleset: Osu, rank: 2 })
+        );
+    }
+
+    #[test]
+    fn test_country_rank_violation() {
+        // Player 3 is the only DE player, player 4 has no country and is not checked
+        let valid = vec![
+            ranked(1, Osu, 1, 1, 75.0),
+            ranked(2, Osu, 2, 2, 50.0),
+            ranked(3, Osu, 3, 1, 25.0),
+            ranked(4, Osu, 4, 0, 0.0),
+        ];
+        assert_eq!(check_leaderboards(&valid, &countries()), Ok(()));
+
+        let gap = vec![ranked(1, Osu, 1, 1, 50.0), ranked(2, Osu, 2, 3, 0.0)];
+        assert_eq!(
+            check_leaderboards(&gap, &countries()),
+            Err(LeaderboardError::NonContiguousCountryRanks {
+                country: "US".to_string(),
+                ruleset: Osu,
+                position: 2,
+                rank: 3
+            })
+        );
+    }
+}
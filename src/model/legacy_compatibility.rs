@@ -0,0 +1,298 @@
+//! Compatibility harness comparing the model against outputs of the legacy Python processor
+//!
+//! Fixtures live in `test_data/legacy` and record the rating and volatility of every player
+//! after each match of a sample tournament, as produced by the legacy processor. The
+//! fixture's matches are processed with the model configured to match the legacy constants
+//! and every result must agree within the fixture's tolerance. Intentional divergences are
+//! listed in the fixture together with the reason, and are not compared.
+
+use crate::{
+    database::db_structs::{Match, PlayerPlacement, RatingAdjustment},
+    model::{
+        model_config::ModelConfig,
+        observer::ProcessingObserver,
+        otr_model::OtrModel,
+        structures::{gamma_strategy::GammaStrategy, ruleset::Ruleset}
+    },
+    utils::test_utils::{generate_game, generate_match, generate_player_rating}
+};
+use chrono::{DateTime, FixedOffset};
+use serde::Deserialize;
+use std::{collections::HashMap, fs, path::Path};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyFixture {
+    /// Maximum absolute difference of ratings and volatilities
+    tolerance: f64,
+    /// Gamma strategy the legacy processor is equivalent to, parsed like `--gamma`
+    gamma: Option<String>,
+    min_volatility: Option<f64>,
+    ruleset: Ruleset,
+    initial_ratings: Vec<LegacyRating>,
+    matches: Vec<LegacyMatch>,
+    /// Results after each match, in processing order
+    expected: Vec<LegacyResult>,
+    #[serde(default)]
+    divergences: Vec<Divergence>
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyRating {
+    player_id: i32,
+    rating: f64,
+    volatility: f64
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyMatch {
+    id: i32,
+    start_time: DateTime<FixedOffset>,
+    /// The placements of every game, in the order the games were played
+    games: Vec<Vec<PlayerPlacement>>
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyResult {
+    match_id: i32,
+    ratings: Vec<LegacyRating>
+}
+
+/// A result which intentionally differs from the legacy processor
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Divergence {
+    match_id: i32,
+    /// The diverging player, or every player of the match if absent
+    player_id: Option<i32>,
+    reason: String
+}
+
+impl Divergence {
+    fn covers(&self, match_id: i32, player_id: i32) -> bool {
+        self.match_id == match_id && self.player_id.is_none_or(|id| id == player_id)
+    }
+}
+
+impl LegacyFixture {
+    fn read(path: &Path) -> LegacyFixture {
+        let json = fs::read_to_string(path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+        serde_json::from_str(&json).unwrap_or_else(|e| panic!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    fn config(&self) -> ModelConfig {
+        let mut config = ModelConfig::default();
+        if let Some(gamma) = &self.gamma {
+            config.gamma = gamma.parse::<GammaStrategy>().expect("Fixture gamma should be valid");
+        }
+        if let Some(min_volatility) = self.min_volatility {
+            config.min_volatility = min_volatility;
+        }
+
+        config
+    }
+
+    fn matches(&self) -> Vec<Match> {
+        self.matches
+            .iter()
+            .map(|m| {
+                let games = (1..)
+                    .zip(&m.games)
+                    .map(|(id, placements)| {
+                        let mut game = generate_game(m.id * 1000 + id, placements);
+                        game.ruleset = self.ruleset;
+                        game
+                    })
+                    .collect::<Vec<_>>();
+
+                generate_match(m.id, self.ruleset, &games, m.start_time)
+            })
+            .collect()
+    }
+}
+
+/// Records the rating of every player after each match
+#[derive(Default)]
+struct ResultRecorder {
+    results: Vec<(i32, Vec<RatingAdjustment>)>
+}
+
+impl ProcessingObserver for ResultRecorder {
+    fn on_match_processed(&mut self, match_: &Match, adjustments: &[RatingAdjustment]) {
+        self.results.push((match_.id, adjustments.to_vec()));
+    }
+}
+
+/// Processes the fixture and describes every result which differs from the legacy output
+/// by more than the tolerance and is not a documented divergence
+fn compare(fixture: &LegacyFixture) -> Vec<String> {
+    let first_match = fixture
+        .matches
+        .iter()
+        .map(|m| m.start_time)
+        .min()
+        .expect("Fixture should contain matches");
+    let initial_ratings = fixture
+        .initial_ratings
+        .iter()
+        .map(|r| {
+            generate_player_rating(
+                r.player_id,
+                fixture.ruleset,
+                r.rating,
+                r.volatility,
+                1,
+                Some(first_match),
+                Some(first_match)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut model = OtrModel::with_config(&initial_ratings, &HashMap::new(), fixture.config());
+    let mut recorder = ResultRecorder::default();
+    if let Err(error) = model.process_with_observer(&fixture.matches(), &mut recorder) {
+        return vec![error.to_string()];
+    }
+
+    let mut mismatches = Vec::new();
+    if recorder.results.len() != fixture.expected.len() {
+        mismatches.push(format!(
+            "Processed {} matches, expected results of {}",
+            recorder.results.len(),
+            fixture.expected.len()
+        ));
+    }
+
+    for ((match_id, adjustments), expected) in recorder.results.iter().zip(&fixture.expected) {
+        if *match_id != expected.match_id {
+            mismatches.push(format!(
+                "Processed match {} where {} was expected",
+                match_id, expected.match_id
+            ));
+            continue;
+        }
+
+        for legacy in &expected.ratings {
+            if fixture
+                .divergences
+                .iter()
+                .any(|d| d.covers(*match_id, legacy.player_id))
+            {
+                continue;
+            }
+
+            let Some(actual) = adjustments.iter().find(|a| a.player_id == legacy.player_id) else {
+                mismatches.push(format!("Match {}: player {} was not rated", match_id, legacy.player_id));
+                continue;
+            };
+
+            if (actual.rating_after - legacy.rating).abs() > fixture.tolerance
+                || (actual.volatility_after - legacy.volatility).abs() > fixture.tolerance
+            {
+                mismatches.push(format!(
+                    "Match {}: player {} has rating {:.4} / volatility {:.4}, legacy {:.4} / {:.4}",
+                    match_id,
+                    legacy.player_id,
+                    actual.rating_after,
+                    actual.volatility_after,
+                    legacy.rating,
+                    legacy.volatility
+                ));
+            }
+        }
+    }
+
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, LegacyFixture};
+    use std::{
+        fs,
+        path::{Path, PathBuf}
+    };
+
+    const FIXTURE_DIR: &str = "test_data/legacy";
+
+    fn fixture_paths() -> Vec<PathBuf> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE_DIR);
+        let mut paths = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok().map(|e| e.path()))
+                    .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        paths.sort();
+
+        paths
+    }
+
+    /// Every legacy fixture is reproduced within its tolerance
+    #[test]
+    fn test_legacy_fixtures() {
+        let paths = fixture_paths();
+        assert!(!paths.is_empty(), "No legacy fixtures found in {}", FIXTURE_DIR);
+
+        for path in paths {
+            let fixture = LegacyFixture::read(&path);
+            for divergence in &fixture.divergences {
+                println!(
+                    "{}: match {} diverges: {}",
+                    path.display(),
+                    divergence.match_id,
+                    divergence.reason
+                );
+            }
+
+            let mismatches = compare(&fixture);
+            assert!(
+                mismatches.is_empty(),
+                "{} differs from the legacy processor:\n{}",
+                path.display(),
+                mismatches.join("\n")
+            );
+        }
+    }
+
+    fn fixture(expected_rating: f64) -> LegacyFixture {
+        serde_json::from_value(serde_json::json!({
+            "tolerance": 1e-6,
+            "ruleset": 0,
+            "initialRatings": [
+                { "playerId": 1, "rating": 1000.0, "volatility": 200.0 },
+                { "playerId": 2, "rating": 1000.0, "volatility": 200.0 }
+            ],
+            "matches": [{
+                "id": 1,
+                "startTime": "2023-12-01T21:50:13-05:00",
+                "games": [[{ "playerId": 1, "placement": 1 }, { "playerId": 2, "placement": 2 }]]
+            }],
+            "expected": [{
+                "matchId": 1,
+                "ratings": [{ "playerId": 1, "rating": expected_rating, "volatility": 200.0 }]
+            }]
+        }))
+        .unwrap()
+    }
+
+    /// The harness reports results outside the tolerance and skips documented divergences
+    #[test]
+    fn test_compare_reports_mismatches() {
+        let mut diverging = fixture(1000.0);
+        let mismatches = compare(&diverging);
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].starts_with("Match 1: player 1"));
+
+        diverging.divergences = serde_json::from_value(serde_json::json!([
+            { "matchId": 1, "playerId": 1, "reason": "Winning never lowers a rating" }
+        ]))
+        .unwrap();
+        assert!(compare(&diverging).is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    database::db_structs::{Match, PlayerRating},
+    model::structures::ruleset::Ruleset
+};
+use serde::Serialize;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet}
+};
+
+/// The ruleset a legacy ManiaOther rating is moved to
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManiaAssignment {
+    pub player_id: i32,
+    pub target: Ruleset,
+    /// Number of Mania4k games the player participated in
+    pub games_4k: usize,
+    /// Number of Mania7k games the player participated in
+    pub games_7k: usize
+}
+
+/// Plan for moving legacy ManiaOther ratings into Mania4k or Mania7k
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManiaMigration {
+    /// Players whose ManiaOther rating can be moved, in ascending order of player id
+    pub assignments: Vec<ManiaAssignment>,
+    /// Players with a ManiaOther rating but no dominant key count (no 4k or 7k games, or
+    /// equally many of both), in ascending order
+    pub unresolved: Vec<i32>,
+    /// Players whose assignment was not applied because they already have a rating in the
+    /// target ruleset, in ascending order. Filled in when the migration is applied.
+    pub conflicts: Vec<i32>
+}
+
+/// Assigns every player with a ManiaOther rating to the mania ruleset they played the most
+/// games in. Key counts are taken from the ruleset of each game.
+pub fn plan_mania_migration(ratings: &[PlayerRating], matches: &[Match]) -> ManiaMigration {
+    let legacy_players: HashSet<i32> = ratings
+        .iter()
+        .filter(|r| r.ruleset == Ruleset::ManiaOther)
+        .map(|r| r.player_id)
+        .collect();
+
+    // (4k games, 7k games) per legacy player
+    let mut participation: HashMap<i32, (usize, usize)> = HashMap::new();
+    for game in matches.iter().flat_map(|m| m.games.iter()) {
+        for score in game.scores.iter().filter(|s| legacy_players.contains(&s.player_id)) {
+            let counts = participation.entry(score.player_id).or_default();
+            match game.ruleset {
+                Ruleset::Mania4k => counts.0 += 1,
+                Ruleset::Mania7k => counts.1 += 1,
+                _ => {}
+            }
+        }
+    }
+
+    let mut migration = ManiaMigration::default();
+    let mut players = legacy_players.into_iter().collect::<Vec<_>>();
+    players.sort();
+
+    for player_id in players {
+        let (games_4k, games_7k) = participation.get(&player_id).copied().unwrap_or_default();
+        let target = match games_4k.cmp(&games_7k) {
+            Ordering::Greater => Ruleset::Mania4k,
+            Ordering::Less => Ruleset::Mania7k,
+            Ordering::Equal => {
+                migration.unresolved.push(player_id);
+                continue;
+            }
+        };
+
+        migration.assignments.push(ManiaAssignment {
+            player_id,
+            target,
+            games_4k,
+            games_7k
+        });
+    }
+
+    migration
+}
+
+#[cfg(test)]
+mod tests {
+    use super::plan_mania_migration;
+    use crate::{
+        database::db_structs::Game,
+        model::structures::ruleset::Ruleset::{self, Mania4k, Mania7k, ManiaOther},
+        utils::test_utils::{generate_game, generate_match, generate_placement, generate_player_rating}
+    };
+    use chrono::Utc;
+
+    fn game(id: i32, ruleset: Ruleset, players: &[i32]) -> Game {
+        let placements = players
+            .iter()
+            .enumerate()
+            .map(|(i, &id)| generate_placement(id, i as i32 + 1))
+            .collect::<Vec<_>>();
+        let mut game = generate_game(id, &placements);
+        game.ruleset = ruleset;
+        game
+    }
+
+    #[test]
+    fn test_assigns_dominant_key_count() {
+        let ratings = vec![
+            generate_player_rating(1, ManiaOther, 1000.0, 100.0, 2, None, None),
+            generate_player_rating(2, ManiaOther, 1000.0, 100.0, 2, None, None),
+            generate_player_rating(3, ManiaOther, 1000.0, 100.0, 2, None, None),
+            // Not a legacy rating
+            generate_player_rating(4, Mania4k, 1000.0, 100.0, 2, None, None),
+        ];
+        let games = vec![
+            game(1, Mania4k, &[1, 2, 4]),
+            game(2, Mania4k, &[1, 3]),
+            game(3, Mania7k, &[2, 3]),
+            game(4, Mania7k, &[2]),
+        ];
+        let matches = vec![generate_match(1, Mania4k, &games, Utc::now().fixed_offset())];
+
+        let migration = plan_mania_migration(&ratings, &matches);
+
+        let assigned = migration
+            .assignments
+            .iter()
+            .map(|a| (a.player_id, a.target, a.games_4k, a.games_7k))
+            .collect::<Vec<_>>();
+        assert_eq!(assigned, vec![(1, Mania4k, 2, 0), (2, Mania7k, 1, 2)]);
+
+        // Player 3 played as many 4k as 7k games
+        assert_eq!(migration.unresolved, vec![3]);
+        assert!(migration.conflicts.is_empty());
+    }
+}
@@ -1,6 +1,34 @@
+pub mod activity;
+pub mod beatmaps;
+pub mod bootstrap;
+pub mod clock;
 pub mod constants;
+pub mod countries;
 pub mod decay;
+pub mod decay_sweep;
+pub mod display_ratings;
+pub mod exclusions;
+pub mod leaderboard_checks;
+#[cfg(test)]
+mod legacy_compatibility;
+pub mod mania_migration;
+pub mod mod_detection;
+pub mod model_config;
+pub mod observer;
 pub mod otr_model;
+pub mod otr_model_builder;
+pub mod overall_ratings;
+pub mod placements;
+pub mod processing_result;
+pub mod rank_history;
+pub mod rating_snapshot;
 pub mod rating_tracker;
 pub mod rating_utils;
+pub mod restrictions;
+pub mod ruleset_policy;
+pub mod score_integrity;
+pub mod simulation;
+pub mod start_times;
+pub mod stats_accumulator;
 pub mod structures;
+pub mod upsets;
@@ -0,0 +1,188 @@
+use crate::{
+    database::db_structs::{GameModCategory, PlayedMods},
+    model::{beatmaps::Mods, ruleset_policy::ruleset_policy, structures::mod_category::ModCategory}
+};
+use itertools::Itertools;
+
+/// Classifies every game by the mod pool it was played from
+///
+/// The freemod flag of a lobby is unreliable, as referees often set mods per player instead.
+/// Rather, the mods actually played (the lobby's mods along with each player's) are compared
+/// across every game of a beatmap within a tournament. A beatmap played with a single set of
+/// mods throughout is classified by that set, and a beatmap played with differing mods, or
+/// in a lobby with freemod enabled, is classified as FM. Every game of a beatmap within a
+/// tournament shares its classification.
+///
+/// Uniform mod combinations which are not a mod pool of their own (e.g. HDHR or EZ) are
+/// classified as FM. Mods which are optional in the ruleset (see
+/// `RulesetPolicy::optional_mods`) are only considered when every game was played with them.
+///
+/// # Returns
+/// The classification of every game, in order of game id
+pub fn classify_games(played: &[PlayedMods]) -> Vec<GameModCategory> {
+    played
+        .iter()
+        .into_group_map_by(|p| (p.tournament_id, p.beatmap_id))
+        .into_values()
+        .flat_map(|games| {
+            let category = classify_beatmap(&games);
+            games.into_iter().map(move |p| GameModCategory {
+                game_id: p.game_id,
+                category
+            })
+        })
+        .sorted_by_key(|c| c.game_id)
+        .collect()
+}
+
+fn classify_beatmap(games: &[&PlayedMods]) -> ModCategory {
+    if games.iter().any(|p| p.game_mods.contains(Mods::FREE_MOD)) {
+        return ModCategory::FreeMod;
+    }
+
+    let played = games
+        .iter()
+        .flat_map(|p| {
+            let game_mods = p.game_mods.gameplay();
+            // A game without scores still tells which mods the lobby was set to
+            let score_mods = if p.score_mods.is_empty() {
+                vec![Mods::default()]
+            } else {
+                p.score_mods.clone()
+            };
+            score_mods
+                .into_iter()
+                .map(move |mods| Mods(game_mods.0 | mods.gameplay().0))
+        })
+        .unique()
+        .collect_vec();
+
+    let optional = ruleset_policy(games[0].ruleset).optional_mods();
+    let required = played.iter().map(|mods| Mods(mods.0 & !optional.0)).unique().collect_vec();
+    let pool = match (played.as_slice(), required.as_slice()) {
+        ([mods], _) | (_, [mods]) => *mods,
+        _ => return ModCategory::FreeMod
+    };
+
+    match pool {
+        Mods(0) => ModCategory::NoMod,
+        Mods(Mods::HIDDEN) => ModCategory::Hidden,
+        Mods(Mods::HARD_ROCK) => ModCategory::HardRock,
+        Mods(Mods::DOUBLE_TIME) => ModCategory::DoubleTime,
+        _ => ModCategory::FreeMod
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::classify_games;
+    use crate::{
+        database::db_structs::{GameModCategory, PlayedMods},
+        model::{
+            beatmaps::Mods,
+            structures::{mod_category::ModCategory, ruleset::Ruleset}
+        }
+    };
+
+    fn played(game_id: i32, tournament_id: i32, beatmap_id: i32, game_mods: i32, score_mods: &[i32]) -> PlayedMods {
+        PlayedMods {
+            game_id,
+            tournament_id,
+            ruleset: Ruleset::Osu,
+            beatmap_id,
+            game_mods: Mods(game_mods),
+            score_mods: score_mods.iter().map(|m| Mods(*m)).collect()
+        }
+    }
+
+    fn categories(played: &[PlayedMods]) -> Vec<ModCategory> {
+        classify_games(played).into_iter().map(|c| c.category).collect()
+    }
+
+    #[test]
+    fn test_uniform_mods() {
+        let no_fail = 1;
+        let games = vec![
+            played(1, 1, 10, 0, &[no_fail, 0]),
+            played(2, 1, 11, Mods::HIDDEN, &[0, 0]),
+            played(3, 1, 12, 0, &[Mods::HARD_ROCK, Mods::HARD_ROCK]),
+            played(4, 1, 13, Mods::DOUBLE_TIME, &[]),
+            played(5, 1, 13, 0, &[Mods::NIGHTCORE, Mods::DOUBLE_TIME]),
+        ];
+
+        assert_eq!(
+            categories(&games),
+            vec![
+                ModCategory::NoMod,
+                ModCategory::Hidden,
+                ModCategory::HardRock,
+                ModCategory::DoubleTime,
+                ModCategory::DoubleTime,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mixed_mods_are_free_mod() {
+        let games = vec![
+            // Everyone happened to play HR in one match, but not in the other
+            played(1, 1, 10, 0, &[Mods::HARD_ROCK, Mods::HARD_ROCK]),
+            played(2, 1, 10, 0, &[Mods::HARD_ROCK, Mods::HIDDEN]),
+            // Free mod enabled, even though nobody picked a mod
+            played(3, 1, 11, Mods::FREE_MOD, &[0, 0]),
+            // HDHR is not a mod pool of its own
+            played(4, 1, 12, Mods::HIDDEN | Mods::HARD_ROCK, &[0]),
+        ];
+
+        assert_eq!(categories(&games), vec![ModCategory::FreeMod; 4]);
+    }
+
+    #[test]
+    fn test_beatmaps_classified_per_tournament() {
+        let games = vec![
+            played(1, 1, 10, 0, &[Mods::HIDDEN]),
+            played(2, 2, 10, 0, &[Mods::HIDDEN, 0]),
+        ];
+
+        assert_eq!(
+            classify_games(&games),
+            vec![
+                GameModCategory {
+                    game_id: 1,
+                    category: ModCategory::Hidden
+                },
+                GameModCategory {
+                    game_id: 2,
+                    category: ModCategory::FreeMod
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_optional_mods_of_catch() {
+        let catch = |mut p: PlayedMods| {
+            p.ruleset = Ruleset::Catch;
+            p
+        };
+        let games = vec![
+            // Hidden added by some players keeps the pool of the others
+            catch(played(1, 1, 10, 0, &[Mods::HIDDEN, 0])),
+            catch(played(2, 1, 11, Mods::HARD_ROCK, &[Mods::HIDDEN, 0])),
+            // Hidden played by everyone is still the HD pool
+            catch(played(3, 1, 12, 0, &[Mods::HIDDEN, Mods::HIDDEN])),
+            // Hidden is not optional in osu!
+            played(4, 1, 13, 0, &[Mods::HIDDEN, 0]),
+        ];
+
+        assert_eq!(
+            categories(&games),
+            vec![
+                ModCategory::NoMod,
+                ModCategory::HardRock,
+                ModCategory::Hidden,
+                ModCategory::FreeMod,
+            ]
+        );
+    }
+}
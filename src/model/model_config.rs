@@ -0,0 +1,80 @@
+use super::{
+    constants::{DECAY_VOLATILITY_INTERVAL_DAYS, MIN_VOLATILITY},
+    decay::DecayParameters,
+    structures::{
+        dnf_policy::DnfPolicy, game_weights::GameWeights, gamma_strategy::GammaStrategy, lobby_beta::LobbyBeta,
+        provisional_period::ProvisionalPeriod, returning_boost::ReturningBoost, ruleset::Ruleset,
+        short_match_policy::ShortMatchPolicy, weight_strategy::WeightStrategy
+    }
+};
+use chrono::{DateTime, FixedOffset, Utc};
+use std::collections::HashMap;
+
+/// Tunable parameters of the o!TR model
+///
+/// Defaults mirror the values in `constants`, allowing individual parameters
+/// to be overridden at runtime without a rebuild.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelConfig {
+    /// Minimum volatility a player can have after a match
+    pub min_volatility: f64,
+    /// Gamma function of the PlackettLuce model, controlling how quickly volatility converges
+    pub gamma: GammaStrategy,
+    /// Scaling of the beta passed to the rating model by the number of players in each game, a
+    /// constant beta if None
+    pub lobby_beta: Option<LobbyBeta>,
+    /// How Method A and Method B ratings are blended for each player
+    pub weights: WeightStrategy,
+    /// Weights of warmups and tiebreakers when the ratings of a match's games are aggregated
+    pub game_weights: GameWeights,
+    /// Decay parameters of rulesets which do not use the defaults, e.g. rulesets with fewer
+    /// tournaments whose players have fewer opportunities to stay active
+    pub decay: HashMap<Ruleset, DecayParameters>,
+    /// Number of days between volatility growth cycles during decay, in all rulesets
+    pub decay_volatility_interval_days: u64,
+    /// Volatility boost of players returning from inactivity, if any
+    pub returning_boost: Option<ReturningBoost>,
+    /// Volatility raise of players in their first matches, if any
+    pub provisional_period: Option<ProvisionalPeriod>,
+    /// Time up to which the final decay pass decays ratings, the current time if None
+    pub decay_until: Option<DateTime<FixedOffset>>,
+    /// How scores of 0 are placed. Method B ties players who missed a game with tied-last
+    /// DNFs, see `placements::apply_dnf_policy` for the placements themselves.
+    pub dnf_policy: DnfPolicy,
+    /// How matches with a single game are rated
+    pub short_match_policy: ShortMatchPolicy
+}
+
+impl Default for ModelConfig {
+    fn default() -> Self {
+        ModelConfig {
+            min_volatility: MIN_VOLATILITY,
+            gamma: GammaStrategy::default(),
+            lobby_beta: None,
+            weights: WeightStrategy::default(),
+            game_weights: GameWeights::default(),
+            decay: HashMap::new(),
+            decay_volatility_interval_days: DECAY_VOLATILITY_INTERVAL_DAYS,
+            returning_boost: None,
+            provisional_period: None,
+            decay_until: None,
+            dnf_policy: DnfPolicy::default(),
+            short_match_policy: ShortMatchPolicy::default()
+        }
+    }
+}
+
+impl ModelConfig {
+    /// The decay parameters of `ruleset`
+    pub fn decay_parameters(&self, ruleset: Ruleset) -> DecayParameters {
+        DecayParameters {
+            volatility_interval_days: self.decay_volatility_interval_days,
+            ..self.decay.get(&ruleset).copied().unwrap_or_default()
+        }
+    }
+
+    /// The time the final decay pass decays ratings to, see `decay_until`
+    pub fn decay_time(&self) -> DateTime<FixedOffset> {
+        self.decay_until.unwrap_or_else(|| Utc::now().fixed_offset())
+    }
+}
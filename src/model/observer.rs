@@ -0,0 +1,55 @@
+use crate::{
+    database::db_structs::{Match, RatingAdjustment},
+    report::warnings::Warning
+};
+
+/// Receives callbacks while `OtrModel::process_with_observer` runs, e.g. to inspect the state
+/// of processing in tests or to trace individual players
+///
+/// Every method has an empty default implementation.
+pub trait ProcessingObserver {
+    /// Called after a match has been rated, with the adjustments it produced. Rating exempt
+    /// matches produce no adjustments. Games without scores have been removed from `match_`.
+    fn on_match_processed(&mut self, _match_: &Match, _adjustments: &[RatingAdjustment]) {}
+
+    /// Called after a manual adjustment has been applied
+    fn on_manual_adjustment(&mut self, _adjustment: &RatingAdjustment) {}
+
+    /// Called whenever processing works around a data quality issue
+    fn on_warning(&mut self, _warning: &Warning) {}
+}
+
+/// Observes nothing
+impl ProcessingObserver for () {}
+
+impl<T: ProcessingObserver + ?Sized> ProcessingObserver for &mut T {
+    fn on_match_processed(&mut self, match_: &Match, adjustments: &[RatingAdjustment]) {
+        (**self).on_match_processed(match_, adjustments);
+    }
+
+    fn on_manual_adjustment(&mut self, adjustment: &RatingAdjustment) {
+        (**self).on_manual_adjustment(adjustment);
+    }
+
+    fn on_warning(&mut self, warning: &Warning) {
+        (**self).on_warning(warning);
+    }
+}
+
+/// Notifies both observers, the first one first
+impl<A: ProcessingObserver, B: ProcessingObserver> ProcessingObserver for (A, B) {
+    fn on_match_processed(&mut self, match_: &Match, adjustments: &[RatingAdjustment]) {
+        self.0.on_match_processed(match_, adjustments);
+        self.1.on_match_processed(match_, adjustments);
+    }
+
+    fn on_manual_adjustment(&mut self, adjustment: &RatingAdjustment) {
+        self.0.on_manual_adjustment(adjustment);
+        self.1.on_manual_adjustment(adjustment);
+    }
+
+    fn on_warning(&mut self, warning: &Warning) {
+        self.0.on_warning(warning);
+        self.1.on_warning(warning);
+    }
+}
@@ -1,23 +1,47 @@
 use crate::{
-    database::db_structs::{Game, GameScore, Match, PlayerRating, RatingAdjustment},
+    database::db_structs::{Game, GameScore, ManualAdjustment, Match, PlayerRating, RatingAdjustment},
     model::{
-        constants::{ABSOLUTE_RATING_FLOOR, DEFAULT_VOLATILITY, WEIGHT_A, WEIGHT_B},
+        clock::Clock,
+        constants::{ABSOLUTE_RATING_FLOOR, DEFAULT_VOLATILITY},
+        decay::RatingDecay,
+        model_config::ModelConfig,
+        observer::ProcessingObserver,
+        otr_model_builder::{OtrModelBuilder, RatingModel},
+        processing_result::{ProcessingResult, SkippedEntities},
         rating_tracker::RatingTracker,
-        structures::{rating_adjustment_type::RatingAdjustmentType, ruleset::Ruleset}
+        restrictions::Restrictions,
+        ruleset_policy::ruleset_policy,
+        simulation::{Lineup, ProjectedChange, SimulationError},
+        stats_accumulator::StatsAccumulator,
+        structures::{
+            dnf_policy::DnfPolicy, rating_adjustment_type::RatingAdjustmentType, rating_clamps::RatingClamps,
+            ruleset::Ruleset, short_match_policy::ShortMatchPolicy
+        }
     },
-    utils::progress_utils::progress_bar
+    report::warnings::Warning,
+    utils::progress_utils::{progress_bar, ProgressSpan}
 };
-use chrono::Utc;
 use itertools::Itertools;
-use openskill::{
-    constant::*,
-    model::{model::Model, plackett_luce::PlackettLuce},
-    rating::{Rating, TeamRating}
-};
-use std::collections::HashMap;
+use openskill::{constant::*, rating::Rating};
+use std::collections::{BTreeSet, HashMap};
 use strum::IntoEnumIterator;
+use thiserror::Error;
+
+use super::rating_utils::matches_played;
+
+/// Matches with at least this many games show a nested progress bar while being processed
+const LONG_MATCH_GAMES: usize = 12;
 
-use super::decay::DecaySystem;
+/// Inconsistencies of the input data which keep the model from rating a match
+#[derive(Error, Debug, PartialEq)]
+pub enum ModelError {
+    #[error("Player {player_id} of match {match_id} has no rating in ruleset {ruleset:?}")]
+    UnratedPlayer {
+        match_id: i32,
+        player_id: i32,
+        ruleset: Ruleset
+    }
+}
 
 /// o!TR Model Implementation
 ///
@@ -41,10 +65,25 @@ use super::decay::DecaySystem;
 ///    - Applied before processing new matches
 ///    - Applied as a final pass to ensure current ratings
 pub struct OtrModel {
-    /// The underlying PlackettLuce rating model
-    pub model: PlackettLuce,
+    /// The underlying rating model, PlackettLuce unless replaced through `OtrModelBuilder`
+    pub model: Box<dyn RatingModel>,
     /// Tracks and maintains all player ratings
-    pub rating_tracker: RatingTracker
+    pub rating_tracker: RatingTracker,
+    /// Tunable model parameters
+    pub config: ModelConfig,
+    /// Per-tournament processing totals
+    pub stats: StatsAccumulator,
+    /// Rating corrections applied at their timestamp during processing, in any order
+    pub manual_adjustments: Vec<ManualAdjustment>,
+    /// Periods during which players could not play, which pause their decay
+    pub restrictions: Restrictions,
+    /// Number of remaining matches each returning player is rated with a boosted volatility,
+    /// see `ModelConfig::returning_boost`
+    returning_players: HashMap<(i32, Ruleset), usize>,
+    /// Decays the ratings of inactive players
+    decay: Box<dyn RatingDecay>,
+    /// Time the final decay pass decays ratings to, unless `ModelConfig::decay_until` is set
+    clock: Box<dyn Clock>
 }
 
 impl OtrModel {
@@ -54,55 +93,223 @@ impl OtrModel {
     /// - A custom gamma function for volatility control
     /// - Default beta and kappa values from OpenSkill
     /// - Initial player ratings loaded into the tracker
+    /// - The default ModelConfig
     pub fn new(initial_player_ratings: &[PlayerRating], country_mapping: &HashMap<i32, String>) -> OtrModel {
-        let mut tracker = RatingTracker::new();
-        tracker.set_country_mapping(country_mapping.clone());
-        tracker.insert_or_update(initial_player_ratings);
+        Self::with_config(initial_player_ratings, country_mapping, ModelConfig::default())
+    }
 
-        OtrModel {
-            rating_tracker: tracker,
-            model: PlackettLuce::new(DEFAULT_BETA, KAPPA, Self::gamma_override)
-        }
+    /// Creates a new o!TR model instance using the provided ModelConfig
+    pub fn with_config(
+        initial_player_ratings: &[PlayerRating],
+        country_mapping: &HashMap<i32, String>,
+        config: ModelConfig
+    ) -> OtrModel {
+        OtrModelBuilder::new()
+            .ratings(initial_player_ratings)
+            .country_mapping(country_mapping.clone())
+            .config(config)
+            .build()
     }
 
-    /// Custom volatility control function for the PlackettLuce model.
-    ///
-    /// This function determines how quickly player volatility changes based on performance.
-    /// A higher gamma means volatility changes more slowly.
-    fn gamma_override(_: f64, k: f64, _: &TeamRating) -> f64 {
-        1.0 / k
+    /// Assembles a model from its parts, see `OtrModelBuilder::build`
+    pub(crate) fn from_parts(
+        model: Box<dyn RatingModel>,
+        rating_tracker: RatingTracker,
+        config: ModelConfig,
+        decay: Box<dyn RatingDecay>,
+        clock: Box<dyn Clock>
+    ) -> OtrModel {
+        OtrModel {
+            model,
+            rating_tracker,
+            config,
+            stats: StatsAccumulator::default(),
+            manual_adjustments: Vec::new(),
+            restrictions: Restrictions::default(),
+            returning_players: HashMap::new(),
+            decay,
+            clock
+        }
     }
 
     /// Processes a batch of matches chronologically, updating player ratings.
     ///
+    /// Matches are processed in order of start time regardless of their order in `matches`.
+    /// Matches starting at the same time are processed in order of id.
+    ///
     /// # Processing Steps
-    /// 1. Process each match individually, updating ratings
+    /// 1. Process each match individually, updating ratings. Games without scores and
+    ///    matches without any remaining games are skipped. Manual adjustments are applied
+    ///    before the first match starting at or after their timestamp.
     /// 2. Apply final decay pass to all players
     /// 3. Sort ratings and return the complete rating list
     ///
     /// # Returns
-    /// Returns a vector of all PlayerRatings after processing
-    pub fn process(&mut self, matches: &[Match]) -> Vec<PlayerRating> {
+    /// Returns all PlayerRatings after processing along with the skipped matches and games
+    ///
+    /// # Errors
+    /// Returns a `ModelError` naming the match and player if a participant has no rating in
+    /// the ruleset of their game
+    pub fn process(&mut self, matches: &[Match]) -> Result<ProcessingResult, ModelError> {
+        self.process_with_observer(matches, &mut ())
+    }
+
+    /// Processes matches like `process`, notifying `observer` of every processed match and
+    /// applied manual adjustment
+    pub fn process_with_observer(
+        &mut self,
+        matches: &[Match],
+        observer: &mut impl ProcessingObserver
+    ) -> Result<ProcessingResult, ModelError> {
+        let result = self.process_matches(matches, None, observer)?;
+        self.rating_tracker.sort();
+
+        Ok(ProcessingResult {
+            ratings: self.rating_tracker.get_all_ratings(),
+            ..result
+        })
+    }
+
+    /// Processes the matches of `ruleset` like `process_with_observer`, only applying the manual
+    /// adjustments and final decay of `ruleset`
+    ///
+    /// Rulesets are rated independently, so processing every ruleset in turn gives the same
+    /// ratings as processing all matches at once, while the ratings of a ruleset are final as
+    /// soon as it is processed.
+    ///
+    /// # Returns
+    /// The PlayerRatings of `ruleset` after processing along with the skipped matches and games
+    pub fn process_ruleset(
+        &mut self,
+        ruleset: Ruleset,
+        matches: &[Match],
+        observer: &mut impl ProcessingObserver
+    ) -> Result<ProcessingResult, ModelError> {
+        let matches = matches.iter().filter(|m| m.ruleset == ruleset).cloned().collect_vec();
+        let result = self.process_matches(&matches, Some(ruleset), observer)?;
+        self.rating_tracker.sort();
+
+        Ok(ProcessingResult {
+            ratings: self.rating_tracker.get_leaderboard(ruleset),
+            ..result
+        })
+    }
+
+    /// Processes `matches` and applies the manual adjustments and final decay of `ruleset`, or
+    /// of every ruleset if None, see `process_with_observer`
+    ///
+    /// # Returns
+    /// The number of processed matches and everything skipped, without any ratings
+    fn process_matches(
+        &mut self,
+        matches: &[Match],
+        ruleset: Option<Ruleset>,
+        observer: &mut impl ProcessingObserver
+    ) -> Result<ProcessingResult, ModelError> {
         let progress_bar = progress_bar(matches.len() as u64, "Processing match data".to_string());
+        let mut skipped = SkippedEntities::default();
+        let mut matches_processed = 0;
+        let mut manual_adjustments = self
+            .manual_adjustments
+            .iter()
+            .filter(|a| ruleset.is_none_or(|r| a.ruleset == r))
+            .sorted_by_key(|a| (a.timestamp, a.id))
+            .cloned()
+            .collect_vec()
+            .into_iter()
+            .peekable();
+
+        // Decay depends on the time between matches, so the order must not depend on the caller
+        for m in matches.iter().sorted_by_key(|m| (m.start_time, m.id)) {
+            while let Some(manual) = manual_adjustments.next_if(|a| a.timestamp <= m.start_time) {
+                if let Some(adjustment) = self.apply_manual_adjustment(&manual, &mut skipped) {
+                    observer.on_manual_adjustment(&adjustment);
+                }
+            }
 
-        for m in matches {
-            self.process_match(m);
-            if let Some(pb) = &progress_bar {
-                pb.inc(1);
+            let rateable = skipped
+                .filter_match(m)
+                .filter(|match_| self.check_short_match(match_, &mut skipped, observer));
+            if let Some(match_) = rateable {
+                // Long matches get their own bar so the overall progress does not appear stuck
+                let games = if match_.games.len() >= LONG_MATCH_GAMES {
+                    progress_bar.child(2 * match_.games.len() as u64, format!("Match {}", match_.id))
+                } else {
+                    ProgressSpan::default()
+                };
+                let adjustments = self.process_match(&match_, &games, observer)?;
+                observer.on_match_processed(&match_, &adjustments);
+                matches_processed += 1;
             }
+            progress_bar.inc(1);
         }
 
-        if let Some(pb) = &progress_bar {
-            pb.finish();
+        progress_bar.finish();
+
+        for manual in manual_adjustments {
+            if let Some(adjustment) = self.apply_manual_adjustment(&manual, &mut skipped) {
+                observer.on_manual_adjustment(&adjustment);
+            }
         }
 
-        self.final_decay_pass();
-        self.rating_tracker.sort();
-        self.rating_tracker.get_all_ratings()
+        self.final_decay_pass(ruleset);
+        Ok(ProcessingResult {
+            ratings: Vec::new(),
+            matches_processed,
+            skipped
+        })
     }
 
     // Match Processing Methods
 
+    /// Projects the rating changes of a hypothetical match without modifying any ratings.
+    ///
+    /// The lineup is rated exactly like a processed match, using the current ratings in the
+    /// tracker. Decay is not applied, so the projection reflects the ratings as they are now.
+    ///
+    /// # Returns
+    /// The projected change of every player in the lineup, in ascending order of player id
+    pub fn simulate_match(&self, lineup: &Lineup) -> Result<Vec<ProjectedChange>, SimulationError> {
+        if lineup.games.is_empty() || lineup.games.iter().any(|g| g.is_empty()) {
+            return Err(SimulationError::EmptyLineup);
+        }
+
+        let match_ = lineup.to_match();
+        let participants = self.get_match_participants(&match_);
+        if let Some(&player_id) = participants
+            .iter()
+            .find(|&&id| self.rating_tracker.get_rating(id, lineup.ruleset).is_none())
+        {
+            return Err(SimulationError::UnratedPlayer {
+                player_id,
+                ruleset: lineup.ruleset
+            });
+        }
+
+        let games = ProgressSpan::default();
+        let calc_standard = self.calc_a(self.generate_ratings_a(&match_, &games)?, &match_)?;
+        let calc_penalized = self.calc_b(self.generate_ratings_b(&match_, &games)?, &match_);
+        let (mut final_results, _) = self.calc_weighted_rating(&calc_standard, &calc_penalized, &match_);
+        self.dampen_short_match(&match_, &mut final_results)?;
+
+        participants
+            .into_iter()
+            .sorted()
+            .map(|player_id| {
+                let current = self.current_rating(player_id, lineup.ruleset, match_.id)?;
+                let result = &final_results[&player_id];
+
+                Ok(ProjectedChange {
+                    player_id,
+                    rating_before: current.rating,
+                    rating_after: result.mu,
+                    volatility_before: current.volatility,
+                    volatility_after: result.sigma
+                })
+            })
+            .collect()
+    }
+
     /// Processes a single match, calculating and applying rating changes for all participants.
     ///
     /// # Processing Steps
@@ -112,32 +319,181 @@ impl OtrModel {
     ///    - Method B: Assumes last place for unplayed games
     /// 3. Combine results using weighted average
     /// 4. Update player ratings in the tracker
-    fn process_match(&mut self, match_: &Match) {
-        self.apply_decay(match_);
+    ///
+    /// Rating exempt matches are only recorded in the stats: they produce no decay and no adjustments.
+    fn process_match(
+        &mut self,
+        match_: &Match,
+        games: &ProgressSpan,
+        observer: &mut impl ProcessingObserver
+    ) -> Result<Vec<RatingAdjustment>, ModelError> {
+        if match_.rating_exempt {
+            self.stats.record_match(match_, &[]);
+            return Ok(Vec::new());
+        }
 
-        let ratings_a = self.generate_ratings_a(match_);
-        let ratings_b = self.generate_ratings_b(match_);
+        self.apply_decay(match_, observer);
+        self.track_returning_players(match_);
 
-        let calc_standard = self.calc_a(ratings_a, match_);
+        let ratings_a = self.generate_ratings_a(match_, games)?;
+        let ratings_b = self.generate_ratings_b(match_, games)?;
+
+        let calc_standard = self.calc_a(ratings_a, match_)?;
         let calc_penalized = self.calc_b(ratings_b, match_);
-        let final_results = self.calc_weighted_rating(&calc_standard, &calc_penalized);
+        let (mut final_results, clamps) = self.calc_weighted_rating(&calc_standard, &calc_penalized, match_);
+        self.dampen_short_match(match_, &mut final_results)?;
+
+        let adjustments = self.apply_results(match_, &final_results, &clamps)?;
+        self.stats.record_match(match_, &adjustments);
+
+        for adjustment in &adjustments {
+            let key = (adjustment.player_id, adjustment.ruleset);
+            if let Some(remaining) = self.returning_players.get_mut(&key) {
+                *remaining -= 1;
+                if *remaining == 0 {
+                    self.returning_players.remove(&key);
+                }
+            }
+        }
+
+        Ok(adjustments)
+    }
+
+    /// Applies `ModelConfig::short_match_policy` before a match is rated. Rating exempt matches
+    /// are never affected.
+    ///
+    /// # Returns
+    /// Whether the match is rated, false if it has too few games and was skipped
+    fn check_short_match(
+        &self,
+        match_: &Match,
+        skipped: &mut SkippedEntities,
+        observer: &mut impl ProcessingObserver
+    ) -> bool {
+        if match_.rating_exempt {
+            return true;
+        }
+
+        match self.config.short_match_policy {
+            ShortMatchPolicy::MinGames(min_games) => skipped.require_games(match_, min_games),
+            ShortMatchPolicy::Flag if match_.games.len() == 1 => {
+                observer.on_warning(&Warning::ShortMatch { match_id: match_.id });
+                true
+            }
+            _ => true
+        }
+    }
+
+    /// Scales the rating and volatility change of every player of a single game match by the
+    /// factor of `ShortMatchPolicy::Dampen`, if selected
+    fn dampen_short_match(&self, match_: &Match, results: &mut HashMap<i32, Rating>) -> Result<(), ModelError> {
+        let ShortMatchPolicy::Dampen(factor) = self.config.short_match_policy else {
+            return Ok(());
+        };
+        if match_.games.len() != 1 {
+            return Ok(());
+        }
+
+        for (player_id, result) in results.iter_mut() {
+            let current = self.current_rating(*player_id, match_.ruleset, match_.id)?;
+            result.mu = current.rating + factor * (result.mu - current.rating);
+            result.sigma = current.volatility + factor * (result.sigma - current.volatility);
+        }
+
+        Ok(())
+    }
+
+    /// Starts the returning boost of every participant whose rating decayed since their
+    /// last match. Must be called after decay has been applied for the match.
+    fn track_returning_players(&mut self, match_: &Match) {
+        let Some(boost) = self.config.returning_boost else {
+            return;
+        };
+
+        for player_id in self.get_match_participants(match_) {
+            let Some(rating) = self.rating_tracker.get_rating(player_id, match_.ruleset) else {
+                continue;
+            };
+
+            let decayed = rating
+                .adjustments
+                .iter()
+                .rev()
+                .take_while(|a| a.adjustment_type != RatingAdjustmentType::Match)
+                .any(|a| a.adjustment_type == RatingAdjustmentType::Decay);
+            if decayed {
+                self.returning_players
+                    .insert((player_id, match_.ruleset), boost.matches);
+            }
+        }
+    }
+
+    /// The volatility a player is rated with, which is raised while they are returning or
+    /// provisional
+    fn rating_volatility(&self, rating: &PlayerRating) -> f64 {
+        let volatility = match self.config.returning_boost {
+            Some(boost) if self.returning_players.contains_key(&(rating.player_id, rating.ruleset)) => {
+                boost.apply(rating.volatility)
+            }
+            _ => rating.volatility
+        };
+
+        match self.config.provisional_period {
+            Some(period) => period.apply(volatility, matches_played(rating)),
+            None => volatility
+        }
+    }
+
+    /// The current rating of a participant of the match `match_id`
+    ///
+    /// # Errors
+    /// Returns `ModelError::UnratedPlayer` if the player has no rating in `ruleset`
+    fn current_rating(&self, player_id: i32, ruleset: Ruleset, match_id: i32) -> Result<&PlayerRating, ModelError> {
+        self.rating_tracker
+            .get_rating(player_id, ruleset)
+            .ok_or(ModelError::UnratedPlayer {
+                match_id,
+                player_id,
+                ruleset
+            })
+    }
 
-        self.apply_results(match_, &final_results)
+    /// Whether a player is still in their provisional period, see
+    /// `ModelConfig::provisional_period`
+    fn is_provisional(&self, rating: &PlayerRating) -> bool {
+        self.config
+            .provisional_period
+            .is_some_and(|period| period.is_provisional(matches_played(rating)))
     }
 
     /// Generates ratings for each player based on their actual game performances.
     ///
     /// This method only considers games that players actually participated in,
     /// providing a "pure" performance rating for each game played.
-    fn generate_ratings_a(&self, match_: &Match) -> HashMap<i32, Vec<Rating>> {
-        let mut map: HashMap<i32, Vec<Rating>> = HashMap::new();
-        for game in &match_.games {
-            let game_rating_result = self.rate(game);
+    fn generate_ratings_a(
+        &self,
+        match_: &Match,
+        games: &ProgressSpan
+    ) -> Result<HashMap<i32, Vec<(Rating, f64)>>, ModelError> {
+        self.rate_games(match_, &self.config.game_weights.weights(match_), games)
+    }
+
+    /// Rates every game of a match, pairing each game rating with the weight of its game
+    fn rate_games(
+        &self,
+        match_: &Match,
+        weights: &[f64],
+        games: &ProgressSpan
+    ) -> Result<HashMap<i32, Vec<(Rating, f64)>>, ModelError> {
+        let mut map: HashMap<i32, Vec<(Rating, f64)>> = HashMap::new();
+        for (game, &weight) in match_.games.iter().zip(weights) {
+            let game_rating_result = self.rate(game, match_.id)?;
             for (k, v) in game_rating_result {
-                map.entry(k).or_default().push(v);
+                map.entry(k).or_default().push((v, weight));
             }
+            games.inc(1);
         }
-        map
+        Ok(map)
     }
 
     /// Generates ratings with penalties for missed games.
@@ -145,11 +501,17 @@ impl OtrModel {
     /// This method assumes players who missed games would have placed last,
     /// providing a "worst-case" rating scenario for players who don't participate
     /// in all games of a match.
-    fn generate_ratings_b(&self, match_: &Match) -> HashMap<i32, Vec<Rating>> {
+    fn generate_ratings_b(
+        &self,
+        match_: &Match,
+        games: &ProgressSpan
+    ) -> Result<HashMap<i32, Vec<(Rating, f64)>>, ModelError> {
+        // Weighted by the games as played, before the added scores could change their winners
+        let weights = self.config.game_weights.weights(match_);
         let mut cloned_match = match_.clone();
         let participants = self.get_match_participants(&cloned_match);
         self.apply_tie_for_last_scores(&mut cloned_match, &participants);
-        self.generate_ratings_a(&cloned_match)
+        self.rate_games(&cloned_match, &weights, games)
     }
 
     /// Gets a unique list of all players who participated in any game of the match.
@@ -165,12 +527,20 @@ impl OtrModel {
     /// Adds last-place scores for players who missed specific games.
     ///
     /// For each game, players who didn't participate are given a score with:
-    /// - Placement one worse than the last-place finisher
+    /// - Placement one worse than the last-place finisher, or the placement of the game's
+    ///   DNFs if they are tied last (see `ModelConfig::dnf_policy`)
     /// - Score of 0
     fn apply_tie_for_last_scores(&self, match_: &mut Match, ids: &[i32]) {
         for game in &mut match_.games {
             let worst_placement = game.scores.iter().map(|f| f.placement).max().unwrap();
-            let tie_for_last_placement = worst_placement + 1;
+            let tied_dnf_placement = game
+                .scores
+                .iter()
+                .filter(|s| ruleset_policy(game.ruleset).is_dnf(s))
+                .map(|s| s.placement)
+                .min()
+                .filter(|_| self.config.dnf_policy == DnfPolicy::TiedLast);
+            let tie_for_last_placement = tied_dnf_placement.unwrap_or(worst_placement + 1);
 
             let missing_players = ids
                 .iter()
@@ -184,34 +554,28 @@ impl OtrModel {
                     player_id,
                     game_id: game.id,
                     score: 0,
-                    placement: tie_for_last_placement
+                    placement: tie_for_last_placement,
+                    team: 0
                 });
             }
         }
     }
 
-    /// Calculates ratings for a single game using the PlackettLuce model.
+    /// Calculates ratings for a single game using the rating model.
     ///
     /// # Returns
     /// Returns a mapping of player IDs to their calculated ratings for this game.
     ///
-    /// # Panics
-    /// Panics if a player doesn't have an existing rating for the game's ruleset.
-    fn rate(&self, game: &Game) -> HashMap<i32, Rating> {
+    /// # Errors
+    /// Returns `ModelError::UnratedPlayer` if a player doesn't have an existing rating for the
+    /// game's ruleset.
+    fn rate(&self, game: &Game, match_id: i32) -> Result<HashMap<i32, Rating>, ModelError> {
         let mut player_ratings = Vec::new();
         let mut placements = Vec::new();
 
         // Build input vectors maintaining index correlation
         for score in &game.scores {
-            let rating = self
-                .rating_tracker
-                .get_rating(score.player_id, game.ruleset)
-                .unwrap_or_else(|| {
-                    panic!(
-                        "Player {}: No rating found for ruleset {:?}",
-                        score.player_id, game.ruleset
-                    )
-                });
+            let rating = self.current_rating(score.player_id, game.ruleset, match_id)?;
 
             player_ratings.push(rating);
             placements.push(score.placement as usize);
@@ -223,20 +587,24 @@ impl OtrModel {
             .map(|r| {
                 vec![Rating {
                     mu: r.rating,
-                    sigma: r.volatility
+                    sigma: self.rating_volatility(r)
                 }]
             })
             .collect_vec();
 
-        // Calculate new ratings
-        let model_result = self.model.rate(model_input, placements);
+        // Calculate new ratings, with the lobby's beta if it is scaled
+        let beta = self
+            .config
+            .lobby_beta
+            .map_or(DEFAULT_BETA, |lobby_beta| lobby_beta.beta(placements.len()));
+        let model_result = self.model.rate(model_input, placements, beta);
 
         // Map results back to player IDs
-        player_ratings
+        Ok(player_ratings
             .iter()
             .enumerate()
             .map(|(i, r)| (r.player_id, model_result[i][0].clone()))
-            .collect()
+            .collect())
     }
 
     // Rating Calculation Methods
@@ -249,20 +617,21 @@ impl OtrModel {
     /// # Arguments
     /// * `rating_map` - Map of player IDs to their per-game ratings
     /// * `match_` - The match being processed
-    fn calc_a(&self, rating_map: HashMap<i32, Vec<Rating>>, match_: &Match) -> HashMap<i32, Rating> {
-        let total_games = match_.games.len();
+    fn calc_a(
+        &self,
+        rating_map: HashMap<i32, Vec<(Rating, f64)>>,
+        match_: &Match
+    ) -> Result<HashMap<i32, Rating>, ModelError> {
+        let total_weight = self.config.game_weights.weights(match_).iter().sum();
         rating_map
             .into_iter()
             .map(|(player_id, ratings)| {
-                let current = self
-                    .rating_tracker
-                    .get_rating(player_id, match_.ruleset)
-                    .expect("Player rating should exist");
+                let current = self.current_rating(player_id, match_.ruleset, match_.id)?;
 
-                (
+                Ok((
                     player_id,
-                    Self::calc_rating_a(&ratings, current.rating, current.volatility, total_games)
-                )
+                    Self::calc_rating_a(&ratings, current.rating, self.rating_volatility(current), total_weight)
+                ))
             })
             .collect()
     }
@@ -271,55 +640,87 @@ impl OtrModel {
     ///
     /// Method B uses the actual ratings calculated with missed games counted as losses,
     /// providing a more punitive rating change for partially played matches.
-    fn calc_b(&self, rating_map: HashMap<i32, Vec<Rating>>, match_: &Match) -> HashMap<i32, Rating> {
-        let total_games = match_.games.len();
+    fn calc_b(&self, rating_map: HashMap<i32, Vec<(Rating, f64)>>, match_: &Match) -> HashMap<i32, Rating> {
+        let total_weight = self.config.game_weights.weights(match_).iter().sum();
         rating_map
             .into_iter()
-            .map(|(player_id, ratings)| (player_id, Self::calc_rating_b(&ratings, total_games)))
+            .map(|(player_id, ratings)| (player_id, Self::calc_rating_b(&ratings, total_weight)))
             .collect()
     }
 
     /// Combines Method A and B ratings using weighted average.
     ///
     /// The final rating is calculated as:
-    /// - Rating = (WEIGHT_A × Method A) + (WEIGHT_B × Method B)
-    /// - Volatility = √(WEIGHT_A × σ²_A + WEIGHT_B × σ²_B)
+    /// - Rating = (W_A × Method A) + (W_B × Method B)
+    /// - Volatility = √(W_A × σ²_A + W_B × σ²_B)
+    ///
+    /// where W_A is given by ModelConfig::weights for the fraction of the match's games
+    /// the player participated in, and W_B = 1 - W_A.
     ///
     /// Ensures the final rating stays within system bounds:
     /// - Rating ≥ ABSOLUTE_RATING_FLOOR
-    /// - Volatility ≤ DEFAULT_VOLATILITY
-    fn calc_weighted_rating(&self, map_a: &HashMap<i32, Rating>, map_b: &HashMap<i32, Rating>) -> HashMap<i32, Rating> {
+    /// - ModelConfig::min_volatility ≤ Volatility ≤ DEFAULT_VOLATILITY
+    ///
+    /// # Returns
+    /// The final rating of every player, and the bounds it was clamped to
+    fn calc_weighted_rating(
+        &self,
+        map_a: &HashMap<i32, Rating>,
+        map_b: &HashMap<i32, Rating>,
+        match_: &Match
+    ) -> (HashMap<i32, Rating>, HashMap<i32, RatingClamps>) {
+        let total_games = match_.games.len() as f64;
+        let games_played = match_
+            .games
+            .iter()
+            .flat_map(|g| g.scores.iter().map(|s| s.player_id))
+            .counts();
+
         map_a
             .keys()
             .map(|&player_id| {
                 let result_a = map_a.get(&player_id).expect("Player should have Method A rating");
                 let result_b = map_b.get(&player_id).expect("Player should have Method B rating");
 
-                let rating = WEIGHT_A * result_a.mu + WEIGHT_B * result_b.mu;
-                let volatility = (WEIGHT_A * result_a.sigma.powf(2.0) + WEIGHT_B * result_b.sigma.powf(2.0)).sqrt();
+                let participation = games_played.get(&player_id).copied().unwrap_or(0) as f64 / total_games;
+                let weight_a = self.config.weights.weight_a(participation);
+                let weight_b = 1.0 - weight_a;
 
-                (
-                    player_id,
-                    Rating {
-                        mu: rating.max(ABSOLUTE_RATING_FLOOR),
-                        sigma: volatility.min(DEFAULT_VOLATILITY)
-                    }
-                )
+                let rating = weight_a * result_a.mu + weight_b * result_b.mu;
+                let volatility = (weight_a * result_a.sigma.powf(2.0) + weight_b * result_b.sigma.powf(2.0)).sqrt();
+
+                let mut clamps = RatingClamps::default();
+                clamps.set(RatingClamps::RATING_FLOOR, rating < ABSOLUTE_RATING_FLOOR);
+                clamps.set(RatingClamps::VOLATILITY_FLOOR, volatility < self.config.min_volatility);
+                clamps.set(RatingClamps::VOLATILITY_CAP, volatility > DEFAULT_VOLATILITY);
+
+                let result = Rating {
+                    mu: rating.max(ABSOLUTE_RATING_FLOOR),
+                    sigma: volatility.clamp(self.config.min_volatility, DEFAULT_VOLATILITY)
+                };
+                ((player_id, result), (player_id, clamps))
             })
-            .collect()
+            .unzip()
     }
 
     /// Calculates Method A rating for a player.
-    fn calc_rating_a(ratings: &[Rating], current_rating: f64, current_volatility: f64, total_games: usize) -> Rating {
-        let played_games = ratings.len();
-        let unplayed_games = total_games - played_games;
+    ///
+    /// Each game rating counts with the weight of its game, see `ModelConfig::game_weights`.
+    /// Games the player did not play keep their current rating.
+    fn calc_rating_a(
+        ratings: &[(Rating, f64)],
+        current_rating: f64,
+        current_volatility: f64,
+        total_weight: f64
+    ) -> Rating {
+        let played_weight: f64 = ratings.iter().map(|(_, w)| w).sum();
+        let unplayed_weight = total_weight - played_weight;
 
-        let rating_sum: f64 = ratings.iter().map(|r| r.mu).sum();
-        let rating = (rating_sum + current_rating * unplayed_games as f64) / total_games as f64;
+        let rating_sum: f64 = ratings.iter().map(|(r, w)| w * r.mu).sum();
+        let rating = (rating_sum + current_rating * unplayed_weight) / total_weight;
 
-        let volatility_sum: f64 = ratings.iter().map(|r| r.sigma.powf(2.0)).sum();
-        let volatility =
-            ((volatility_sum + current_volatility.powf(2.0) * unplayed_games as f64) / total_games as f64).sqrt();
+        let volatility_sum: f64 = ratings.iter().map(|(r, w)| w * r.sigma.powf(2.0)).sum();
+        let volatility = ((volatility_sum + current_volatility.powf(2.0) * unplayed_weight) / total_weight).sqrt();
 
         Rating {
             mu: rating,
@@ -330,9 +731,9 @@ impl OtrModel {
     /// Calculates Method B rating for a player.
     ///
     /// Note: Missing games are pre-calculated as losses in `generate_penalized_ratings`
-    fn calc_rating_b(ratings: &[Rating], total_games: usize) -> Rating {
-        let rating = ratings.iter().map(|r| r.mu).sum::<f64>() / total_games as f64;
-        let volatility = (ratings.iter().map(|r| r.sigma.powf(2.0)).sum::<f64>() / total_games as f64).sqrt();
+    fn calc_rating_b(ratings: &[(Rating, f64)], total_weight: f64) -> Rating {
+        let rating = ratings.iter().map(|(r, w)| w * r.mu).sum::<f64>() / total_weight;
+        let volatility = (ratings.iter().map(|(r, w)| w * r.sigma.powf(2.0)).sum::<f64>() / total_weight).sqrt();
 
         Rating {
             mu: rating,
@@ -342,15 +743,15 @@ impl OtrModel {
 
     // Decay Handling Methods
 
-    /// Applies the final decay pass to all players across all rulesets.
+    /// Applies the final decay pass to all players of `ruleset`, or across all rulesets if None.
     ///
-    /// This ensures that all player ratings are properly decayed to the current time,
-    /// even if they haven't participated in recent matches.
-    fn final_decay_pass(&mut self) {
-        let current_time = Utc::now().fixed_offset();
-        let decay_system = DecaySystem::new(current_time);
+    /// This ensures that all player ratings are properly decayed to the current time of the
+    /// clock (or `ModelConfig::decay_until`), even if they haven't participated in recent matches.
+    fn final_decay_pass(&mut self, ruleset: Option<Ruleset>) {
+        let current_time = self.config.decay_until.unwrap_or_else(|| self.clock.now());
 
         let leaderboards: Vec<Vec<PlayerRating>> = Ruleset::iter()
+            .filter(|r| ruleset.is_none_or(|ruleset| *r == ruleset))
             .map(|ruleset| self.rating_tracker.get_leaderboard(ruleset))
             .filter(|lb| !lb.is_empty())
             .collect();
@@ -361,23 +762,23 @@ impl OtrModel {
                 .map(|r| r.ruleset)
                 .expect("Leaderboard should not be empty");
 
+            let parameters = self.config.decay_parameters(ruleset);
             let progress = progress_bar(leaderboard.len() as u64, format!("Applying decay: [{:?}]", ruleset));
 
             let mut updated_ratings = Vec::new();
             for rating in leaderboard {
                 let mut current = rating.clone();
-                if let Ok(Some(updated)) = decay_system.decay(&mut current) {
+                if let Ok(Some(updated)) = self
+                    .decay
+                    .decay(&mut current, current_time, parameters, &self.restrictions)
+                {
                     updated_ratings.push(updated.clone());
                 }
 
-                if let Some(pb) = &progress {
-                    pb.inc(1);
-                }
+                progress.inc(1);
             }
 
-            if let Some(pb) = &progress {
-                pb.finish();
-            }
+            progress.finish();
 
             if !updated_ratings.is_empty() {
                 self.rating_tracker.insert_or_update(&updated_ratings);
@@ -386,33 +787,95 @@ impl OtrModel {
     }
 
     /// Applies decay to all players in a match before processing their results.
-    fn apply_decay(&mut self, match_: &Match) {
-        let decay_system = DecaySystem::new(match_.start_time);
+    fn apply_decay(&mut self, match_: &Match, observer: &mut impl ProcessingObserver) {
+        let parameters = self.config.decay_parameters(match_.ruleset);
         let player_ids: Vec<i32> = self.get_match_participants(match_);
 
         for player_id in player_ids {
             if let Some(rating) = self.rating_tracker.get_rating(player_id, match_.ruleset) {
                 let mut current = rating.clone();
-                if let Ok(Some(updated)) = decay_system.decay(&mut current) {
-                    self.rating_tracker.insert_or_update(&[updated.clone()]);
+                if let Ok(Some(updated)) =
+                    self.decay
+                        .decay(&mut current, match_.start_time, parameters, &self.restrictions)
+                {
+                    self.rating_tracker.insert_or_update(std::slice::from_ref(updated));
                 }
             } else {
-                log::warn!(
-                    "No rating found for player [Id: {} | Ruleset: {:?}]",
+                observer.on_warning(&Warning::RulesetMismatch {
                     player_id,
-                    match_.ruleset
-                );
+                    ruleset: match_.ruleset,
+                    match_id: match_.id
+                });
             }
         }
     }
 
+    /// Applies a manual adjustment to the player's current rating, recording it as a
+    /// `Manual` adjustment. Volatility is unchanged and the rating is kept at or above
+    /// ABSOLUTE_RATING_FLOOR.
+    ///
+    /// # Returns
+    /// The applied adjustment, or None if the player has no rating in the adjustment's
+    /// ruleset, in which case it is skipped
+    fn apply_manual_adjustment(
+        &mut self,
+        manual: &ManualAdjustment,
+        skipped: &mut SkippedEntities
+    ) -> Option<RatingAdjustment> {
+        let Some(current) = self.rating_tracker.get_rating(manual.player_id, manual.ruleset) else {
+            skipped.manual_adjustments_without_rating.push(manual.id);
+            return None;
+        };
+
+        let mut player_rating = current.clone();
+        let rating = manual.kind.apply(player_rating.rating, manual.value);
+        let rating_after = rating.max(ABSOLUTE_RATING_FLOOR);
+        let mut clamps = RatingClamps::default();
+        clamps.set(RatingClamps::RATING_FLOOR, rating < ABSOLUTE_RATING_FLOOR);
+
+        let adjustment = RatingAdjustment {
+            player_id: manual.player_id,
+            ruleset: manual.ruleset,
+            match_id: None,
+            rating_before: player_rating.rating,
+            rating_after,
+            volatility_before: player_rating.volatility,
+            volatility_after: player_rating.volatility,
+            timestamp: manual.timestamp,
+            adjustment_type: RatingAdjustmentType::Manual,
+            average_opponent_rating: None,
+            provisional: false,
+            percentile: None,
+            clamps
+        };
+        player_rating.adjustments.push(adjustment.clone());
+        player_rating.rating = rating_after;
+
+        self.rating_tracker
+            .insert_or_update(std::slice::from_ref(&player_rating));
+        Some(adjustment)
+    }
+
     /// Updates the RatingTracker with the results of the rating calculation
-    fn apply_results(&mut self, match_: &Match, rating_calc_result: &HashMap<i32, Rating>) {
+    ///
+    /// # Returns
+    /// The rating adjustments created for the match
+    fn apply_results(
+        &mut self,
+        match_: &Match,
+        rating_calc_result: &HashMap<i32, Rating>,
+        clamps: &HashMap<i32, RatingClamps>
+    ) -> Result<Vec<RatingAdjustment>, ModelError> {
+        // Opponents are averaged before any of the match's results are applied
+        let opponent_ratings = self.average_opponent_ratings(match_);
+
+        let mut adjustments = Vec::with_capacity(rating_calc_result.len());
         for (k, v) in rating_calc_result {
             // Get their current rating
-            let mut player_rating = self.rating_tracker.get_rating(*k, match_.ruleset).unwrap().clone();
+            let mut player_rating = self.current_rating(*k, match_.ruleset, match_.id)?.clone();
 
             // Create the adjustment
+            let provisional = self.is_provisional(&player_rating);
             let adjustment = RatingAdjustment {
                 player_id: *k,
                 ruleset: player_rating.ruleset,
@@ -422,9 +885,14 @@ impl OtrModel {
                 volatility_before: player_rating.volatility,
                 volatility_after: v.sigma,
                 timestamp: match_.start_time,
-                adjustment_type: RatingAdjustmentType::Match
+                adjustment_type: RatingAdjustmentType::Match,
+                average_opponent_rating: opponent_ratings.get(k).copied(),
+                provisional,
+                percentile: None,
+                clamps: clamps.get(k).copied().unwrap_or_default()
             };
 
+            adjustments.push(adjustment.clone());
             player_rating.adjustments.push(adjustment);
 
             // Update the player_rating values
@@ -434,6 +902,44 @@ impl OtrModel {
             // Save
             self.rating_tracker.insert_or_update(&[player_rating])
         }
+
+        Ok(adjustments)
+    }
+
+    /// Calculates the average current rating of the distinct opponents each player faced in
+    /// the games of a match
+    ///
+    /// Teammates (scores of the same nonzero team) are not opponents. Players without opponents
+    /// (e.g. who only played games alone) are left out. Opponents are summed in order of their
+    /// id, so the average does not depend on hashing.
+    fn average_opponent_ratings(&self, match_: &Match) -> HashMap<i32, f64> {
+        let mut opponents: HashMap<i32, BTreeSet<i32>> = HashMap::new();
+        for game in &match_.games {
+            for score in &game.scores {
+                opponents.entry(score.player_id).or_default().extend(
+                    game.scores
+                        .iter()
+                        .filter(|s| s.player_id != score.player_id && (score.team == 0 || s.team != score.team))
+                        .map(|s| s.player_id)
+                );
+            }
+        }
+
+        opponents
+            .into_iter()
+            .filter_map(|(player_id, opponent_ids)| {
+                let ratings = opponent_ids
+                    .iter()
+                    .filter_map(|&id| self.rating_tracker.get_rating(id, match_.ruleset))
+                    .map(|r| r.rating)
+                    .collect_vec();
+                if ratings.is_empty() {
+                    return None;
+                }
+
+                Some((player_id, ratings.iter().sum::<f64>() / ratings.len() as f64))
+            })
+            .collect()
     }
 
     /// Applies a scaled performance penalty to negative changes in rating.
@@ -457,15 +963,34 @@ impl OtrModel {
 mod tests {
     pub use crate::utils::test_utils::*;
     use crate::{
-        database::db_structs::{Game, PlayerPlacement, PlayerRating},
+        database::db_structs::{Game, ManualAdjustment, Match, PlayerPlacement, PlayerRating, RatingAdjustment},
         model::{
+            clock::FixedClock,
             constants::{ABSOLUTE_RATING_FLOOR, DEFAULT_VOLATILITY},
-            otr_model::OtrModel,
-            structures::{rating_adjustment_type::RatingAdjustmentType, ruleset::Ruleset::Osu}
-        }
+            decay::NoDecay,
+            model_config::ModelConfig,
+            observer::ProcessingObserver,
+            otr_model::{ModelError, OtrModel},
+            otr_model_builder::{OtrModelBuilder, RatingModel},
+            simulation::{Lineup, SimulationError},
+            structures::{
+                dnf_policy::DnfPolicy, game_weights::GameWeights, gamma_strategy::GammaStrategy,
+                lobby_beta::LobbyBeta, manual_adjustment_kind::ManualAdjustmentKind,
+                provisional_period::ProvisionalPeriod, rating_adjustment_type::RatingAdjustmentType,
+                rating_clamps::RatingClamps, returning_boost::ReturningBoost, ruleset::Ruleset::{Osu, Taiko},
+                short_match_policy::ShortMatchPolicy, weight_strategy::WeightStrategy
+            }
+        },
+        report::warnings::{WarningKind, WarningSink}
     };
     use approx::assert_abs_diff_eq;
-    use chrono::Utc;
+    use chrono::{TimeZone, Utc};
+    use itertools::Itertools;
+    use openskill::{constant::DEFAULT_BETA, rating::Rating};
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex}
+    };
 
     #[test]
     fn test_rate() {
@@ -488,7 +1013,7 @@ mod tests {
 
         let game = generate_game(1, &placements);
 
-        let rating_result = model.rate(&game);
+        let rating_result = model.rate(&game, 1).unwrap();
 
         // Compare the 3 rating values, ensure order is 2, 1, 3
         let result_1 = rating_result.get(&1).unwrap();
@@ -526,7 +1051,7 @@ mod tests {
         ];
 
         let matches = vec![generate_match(1, Osu, &games, Utc::now().fixed_offset())];
-        model.process(&matches);
+        model.process(&matches).unwrap();
         model.rating_tracker.sort();
 
         // Get final ratings and adjustments
@@ -539,7 +1064,7 @@ mod tests {
         for (player_id, rating) in [(1, rating_1), (2, rating_2), (3, rating_3), (4, rating_4)] {
             let adjustments = model
                 .rating_tracker
-                .get_rating_adjustments(player_id, Osu)
+                .adjustments(player_id, Osu)
                 .expect("Expected player to have adjustments");
 
             // Each player should have exactly 2 adjustments:
@@ -602,6 +1127,87 @@ mod tests {
         assert_eq!(rating_1.country_rank, 4);
     }
 
+    #[test]
+    fn test_average_opponent_rating() {
+        let player_ratings = vec![
+            generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None),
+            generate_player_rating(2, Osu, 1200.0, 100.0, 1, None, None),
+            generate_player_rating(3, Osu, 1400.0, 100.0, 1, None, None),
+        ];
+
+        let countries = generate_country_mapping_player_ratings(player_ratings.as_slice(), "US");
+        let mut model = OtrModel::new(player_ratings.as_slice(), &countries);
+
+        // Player 3 only plays against player 1
+        let games = vec![
+            generate_game(1, &[generate_placement(1, 2), generate_placement(2, 1)]),
+            generate_game(2, &[generate_placement(1, 1), generate_placement(3, 2)]),
+        ];
+
+        let matches = vec![generate_match(1, Osu, &games, Utc::now().fixed_offset())];
+        model.process(&matches).unwrap();
+
+        let average_opponent_rating = |player_id| {
+            model
+                .rating_tracker
+                .adjustments(player_id, Osu)
+                .unwrap()
+                .last()
+                .unwrap()
+                .average_opponent_rating
+        };
+
+        // Ratings from before the match are averaged
+        assert_eq!(average_opponent_rating(1), Some(1300.0));
+        assert_eq!(average_opponent_rating(2), Some(1000.0));
+        assert_eq!(average_opponent_rating(3), Some(1000.0));
+    }
+
+    #[test]
+    fn test_average_opponent_rating_excludes_teammates() {
+        let player_ratings = vec![
+            generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None),
+            generate_player_rating(2, Osu, 1200.0, 100.0, 1, None, None),
+            generate_player_rating(3, Osu, 1400.0, 100.0, 1, None, None),
+            generate_player_rating(4, Osu, 1600.0, 100.0, 1, None, None),
+        ];
+
+        let countries = generate_country_mapping_player_ratings(player_ratings.as_slice(), "US");
+        let mut model = OtrModel::new(player_ratings.as_slice(), &countries);
+
+        // Players 1 and 2 play against players 3 and 4
+        let mut game = generate_game(
+            1,
+            &[
+                generate_placement(1, 1),
+                generate_placement(2, 2),
+                generate_placement(3, 3),
+                generate_placement(4, 4)
+            ]
+        );
+        for score in &mut game.scores {
+            score.team = if score.player_id <= 2 { 1 } else { 2 };
+        }
+
+        let matches = vec![generate_match(1, Osu, &[game], Utc::now().fixed_offset())];
+        model.process(&matches).unwrap();
+
+        let average_opponent_rating = |player_id| {
+            model
+                .rating_tracker
+                .adjustments(player_id, Osu)
+                .unwrap()
+                .last()
+                .unwrap()
+                .average_opponent_rating
+        };
+
+        assert_eq!(average_opponent_rating(1), Some(1500.0));
+        assert_eq!(average_opponent_rating(2), Some(1500.0));
+        assert_eq!(average_opponent_rating(3), Some(1100.0));
+        assert_eq!(average_opponent_rating(4), Some(1100.0));
+    }
+
     /// Tests that the performance scaling system correctly reduces rating changes
     /// based on participation frequency.
     #[test]
@@ -670,7 +1276,7 @@ mod tests {
         let games: Vec<Game> = (1..=3).map(|id| generate_game(id, &placements)).collect();
 
         let matches = vec![generate_match(1, Osu, &games, time)];
-        model.process(&matches);
+        model.process(&matches).unwrap();
 
         // Verify rating bounds are enforced
         for player_id in 1..=4 {
@@ -694,5 +1300,893 @@ mod tests {
                 DEFAULT_VOLATILITY
             );
         }
+
+        // The clamps are recorded with the adjustments
+        let clamps = |player_id| {
+            model
+                .rating_tracker
+                .get_rating(player_id, Osu)
+                .unwrap()
+                .adjustments
+                .last()
+                .unwrap()
+                .clamps
+        };
+        assert!(!clamps(1).contains(RatingClamps::RATING_FLOOR));
+        assert!(clamps(4).contains(RatingClamps::RATING_FLOOR));
+        assert!((1..=4).all(|player_id| clamps(player_id).contains(RatingClamps::VOLATILITY_CAP)));
+    }
+
+    /// Tests that volatility never drops below the configured floor,
+    /// even for players who have converged to a very low volatility.
+    #[test]
+    fn test_min_volatility_enforcement() {
+        let time = Utc::now().fixed_offset();
+        let min_volatility = 50.0;
+
+        // Create 4 players whose volatility has already collapsed below the floor
+        let player_ratings: Vec<PlayerRating> = (1..=4)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, 1.0, 1, Some(time), Some(time)))
+            .collect();
+
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+        let config = ModelConfig {
+            min_volatility,
+            ..Default::default()
+        };
+        let mut model = OtrModel::with_config(&player_ratings, &countries, config);
+
+        let placements: Vec<PlayerPlacement> = (1..=4).map(|id| generate_placement(id, id)).collect();
+        let games: Vec<Game> = (1..=3).map(|id| generate_game(id, &placements)).collect();
+
+        model.process(&[generate_match(1, Osu, &games, time)]).unwrap();
+
+        for player_id in 1..=4 {
+            let rating = model
+                .rating_tracker
+                .get_rating(player_id, Osu)
+                .expect("Player rating should exist");
+
+            assert_abs_diff_eq!(rating.volatility, min_volatility);
+        }
+    }
+
+    /// Tests that a participant without a rating fails the run with the match and player
+    #[test]
+    fn test_unrated_participant_is_a_model_error() {
+        let time = Utc::now().fixed_offset();
+
+        let player_ratings: Vec<PlayerRating> = (1..=3)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, 100.0, 1, Some(time), Some(time)))
+            .collect();
+
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+        let mut model = OtrModel::new(&player_ratings, &countries);
+
+        let placements: Vec<PlayerPlacement> = (1..=4).map(|id| generate_placement(id, id)).collect();
+        let games: Vec<Game> = (1..=3).map(|id| generate_game(id, &placements)).collect();
+
+        let error = model.process(&[generate_match(7, Osu, &games, time)]).unwrap_err();
+        assert_eq!(
+            error,
+            ModelError::UnratedPlayer {
+                match_id: 7,
+                player_id: 4,
+                ruleset: Osu
+            }
+        );
+    }
+
+    /// Tests that exhibition matches leave ratings untouched
+    #[test]
+    fn test_rating_exempt_match_produces_no_adjustments() {
+        let time = Utc::now().fixed_offset();
+
+        let player_ratings: Vec<PlayerRating> = (1..=4)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, 100.0, 1, Some(time), Some(time)))
+            .collect();
+
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+        let mut model = OtrModel::new(&player_ratings, &countries);
+
+        let placements: Vec<PlayerPlacement> = (1..=4).map(|id| generate_placement(id, id)).collect();
+        let games: Vec<Game> = (1..=3).map(|id| generate_game(id, &placements)).collect();
+
+        let mut showmatch = generate_match(1, Osu, &games, time);
+        showmatch.rating_exempt = true;
+
+        model.process(&[showmatch]).unwrap();
+
+        for player_id in 1..=4 {
+            let rating = model
+                .rating_tracker
+                .get_rating(player_id, Osu)
+                .expect("Player rating should exist");
+
+            assert_abs_diff_eq!(rating.rating, 1000.0);
+            assert!(rating.adjustments.iter().all(|a| a.match_id.is_none()));
+        }
+    }
+
+    /// Tests that matches without games, matches with invalid placements and games without
+    /// scores are skipped and reported
+    #[test]
+    fn test_process_reports_skipped_entities() {
+        let time = Utc::now().fixed_offset();
+
+        let player_ratings: Vec<PlayerRating> = (1..=4)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, 100.0, 1, Some(time), Some(time)))
+            .collect();
+
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+        let mut model = OtrModel::new(&player_ratings, &countries);
+
+        let placements: Vec<PlayerPlacement> = (1..=4).map(|id| generate_placement(id, id)).collect();
+        let matches = vec![
+            generate_match(1, Osu, &[generate_game(1, &placements), generate_game(2, &[])], time),
+            generate_match(2, Osu, &[], time),
+            generate_match(3, Osu, &[generate_game(3, &[generate_placement(1, 5)])], time),
+        ];
+
+        let result = model.process(&matches).unwrap();
+
+        assert_eq!(result.skipped.matches_without_games, vec![2]);
+        assert_eq!(result.skipped.matches_with_invalid_placements, vec![3]);
+        assert_eq!(result.skipped.games_without_scores, vec![2]);
+        assert_eq!(result.matches_processed, 1);
+        assert_eq!(result.ratings.len(), 4);
+
+        // The rateable game of the first match is still processed
+        let summaries = model.stats.summaries();
+        assert_eq!(summaries[0].matches_processed, 1);
+        assert_eq!(summaries[0].games_processed, 1);
+    }
+
+    /// Processes the same matches with the given gamma strategy and returns the
+    /// final volatility of every player
+    fn volatility_after_matches(gamma: GammaStrategy) -> Vec<f64> {
+        let time = Utc::now().fixed_offset();
+        let player_ratings: Vec<PlayerRating> = (1..=4)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, DEFAULT_VOLATILITY, 1, Some(time), Some(time)))
+            .collect();
+
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+        let config = ModelConfig {
+            gamma,
+            ..Default::default()
+        };
+        let mut model = OtrModel::with_config(&player_ratings, &countries, config);
+
+        let placements: Vec<PlayerPlacement> = (1..=4).map(|id| generate_placement(id, id)).collect();
+        let games: Vec<Game> = (1..=3).map(|id| generate_game(id, &placements)).collect();
+        let matches = (1..=5)
+            .map(|id| generate_match(id, Osu, &games, time))
+            .collect::<Vec<_>>();
+
+        model.process(&matches).unwrap();
+
+        (1..=4)
+            .map(|id| model.rating_tracker.get_rating(id, Osu).unwrap().volatility)
+            .collect()
+    }
+
+    /// Tests that a larger gamma makes volatility converge faster
+    #[test]
+    fn test_gamma_controls_volatility_convergence() {
+        let slow = volatility_after_matches(GammaStrategy::Constant(0.05));
+        let fast = volatility_after_matches(GammaStrategy::Constant(0.5));
+
+        for (slow, fast) in slow.iter().zip(fast.iter()) {
+            assert!(slow < &DEFAULT_VOLATILITY);
+            assert!(fast < slow);
+        }
+    }
+
+    /// Volatility of every player of a lobby of `players` players after a match of 3 games
+    fn lobby_volatility_after_match(players: i32, lobby_beta: Option<LobbyBeta>) -> Vec<f64> {
+        let time = Utc::now().fixed_offset();
+        let player_ratings: Vec<PlayerRating> = (1..=players)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, DEFAULT_VOLATILITY, 1, Some(time), Some(time)))
+            .collect();
+
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+        let config = ModelConfig {
+            lobby_beta,
+            ..Default::default()
+        };
+        let mut model = OtrModel::with_config(&player_ratings, &countries, config);
+
+        let placements: Vec<PlayerPlacement> = (1..=players).map(|id| generate_placement(id, id)).collect();
+        let games: Vec<Game> = (1..=3).map(|id| generate_game(id, &placements)).collect();
+        model.process(&[generate_match(1, Osu, &games, time)]).unwrap();
+
+        (1..=players)
+            .map(|id| model.rating_tracker.get_rating(id, Osu).unwrap().volatility)
+            .collect()
+    }
+
+    /// Tests that a scaled beta keeps the volatility of large lobbies higher, leaving 1v1s unchanged
+    #[test]
+    fn test_lobby_beta_slows_large_lobby_convergence() {
+        let lobby_beta = Some(LobbyBeta { exponent: 0.5 });
+
+        let constant = lobby_volatility_after_match(16, None);
+        let scaled = lobby_volatility_after_match(16, lobby_beta);
+        for (constant, scaled) in constant.iter().zip(scaled.iter()) {
+            assert!(scaled > constant);
+        }
+
+        let constant = lobby_volatility_after_match(2, None);
+        let scaled = lobby_volatility_after_match(2, lobby_beta);
+        for (constant, scaled) in constant.iter().zip(scaled.iter()) {
+            assert_abs_diff_eq!(constant, scaled, epsilon = 1e-9);
+        }
+    }
+
+    /// Records the beta of every game it rates, leaving the ratings unchanged
+    struct BetaRecorder(Arc<Mutex<Vec<f64>>>);
+
+    impl RatingModel for BetaRecorder {
+        fn rate(&self, teams: Vec<Vec<Rating>>, _: Vec<usize>, beta: f64) -> Vec<Vec<Rating>> {
+            self.0.lock().unwrap().push(beta);
+            teams
+        }
+    }
+
+    /// Tests that an injected rating model rates with the scaled beta of each lobby
+    #[test]
+    fn test_lobby_beta_is_passed_to_injected_model() {
+        let time = Utc::now().fixed_offset();
+        let player_ratings: Vec<PlayerRating> = (1..=8)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, DEFAULT_VOLATILITY, 1, Some(time), Some(time)))
+            .collect();
+        let betas = Arc::new(Mutex::new(Vec::new()));
+        let mut model = OtrModelBuilder::new()
+            .ratings(&player_ratings)
+            .country_mapping(generate_country_mapping_player_ratings(&player_ratings, "US"))
+            .config(ModelConfig {
+                lobby_beta: Some(LobbyBeta { exponent: 0.5 }),
+                ..Default::default()
+            })
+            .rating_model(BetaRecorder(Arc::clone(&betas)))
+            .build();
+
+        let placements: Vec<PlayerPlacement> = (1..=8).map(|id| generate_placement(id, id)).collect();
+        let games: Vec<Game> = (1..=3).map(|id| generate_game(id, &placements)).collect();
+        model.process(&[generate_match(1, Osu, &games, time)]).unwrap();
+
+        let betas = betas.lock().unwrap();
+        assert!(!betas.is_empty());
+        for beta in betas.iter() {
+            assert_abs_diff_eq!(*beta, DEFAULT_BETA * 2.0);
+        }
+    }
+
+    /// Ratings of players 1 and 2 after a match of `games`, where player 1 wins the games
+    /// listed in `wins` and player 2 wins all others
+    fn ratings_after_games(games: i32, wins: &[i32], game_weights: GameWeights) -> (f64, f64) {
+        let time = Utc::now().fixed_offset();
+        let player_ratings: Vec<PlayerRating> = (1..=2)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, 200.0, 1, Some(time), Some(time)))
+            .collect();
+
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+        let config = ModelConfig {
+            game_weights,
+            ..Default::default()
+        };
+        let mut model = OtrModel::with_config(&player_ratings, &countries, config);
+
+        let games: Vec<Game> = (1..=games)
+            .map(|id| {
+                let (winner, loser) = if wins.contains(&id) { (1, 2) } else { (2, 1) };
+                generate_game(id, &[generate_placement(winner, 1), generate_placement(loser, 2)])
+            })
+            .collect();
+        model.process(&[generate_match(1, Osu, &games, time)]).unwrap();
+
+        let rating = |id| model.rating_tracker.get_rating(id, Osu).unwrap().rating;
+        (rating(1), rating(2))
+    }
+
+    /// Tests that a warmup with a weight of 0 does not count, and that a heavier tiebreaker
+    /// moves ratings towards its winner
+    #[test]
+    fn test_game_weights() {
+        let without_warmup = GameWeights {
+            warmup: 0.0,
+            ..GameWeights::default()
+        };
+        let (winner, loser) = ratings_after_games(3, &[2, 3], without_warmup);
+        let (winner_without_warmup, loser_without_warmup) = ratings_after_games(2, &[1, 2], GameWeights::default());
+        assert_abs_diff_eq!(winner, winner_without_warmup, epsilon = 1e-9);
+        assert_abs_diff_eq!(loser, loser_without_warmup, epsilon = 1e-9);
+
+        let heavy_tiebreaker = GameWeights {
+            tiebreaker: 2.0,
+            ..GameWeights::default()
+        };
+        let (equal, _) = ratings_after_games(3, &[1, 3], GameWeights::default());
+        let (weighted, _) = ratings_after_games(3, &[1, 3], heavy_tiebreaker);
+        assert!(weighted > equal);
+    }
+
+    /// Tests that the default strategy is equivalent to a constant gamma of 1 / k
+    #[test]
+    fn test_default_gamma_is_inverse_team_count() {
+        let default = volatility_after_matches(GammaStrategy::default());
+        let constant = volatility_after_matches(GammaStrategy::Constant(0.25));
+
+        for (default, constant) in default.iter().zip(constant.iter()) {
+            assert_abs_diff_eq!(default, constant, epsilon = 1e-9);
+        }
+    }
+
+    /// Rating of the given player after a match in which player 4 sits out two of three games
+    fn rating_with_sitter(weights: WeightStrategy, player_id: i32) -> f64 {
+        let time = Utc::now().fixed_offset();
+        let player_ratings: Vec<PlayerRating> = (1..=4)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, 200.0, 1, Some(time), Some(time)))
+            .collect();
+
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+        let config = ModelConfig {
+            weights,
+            ..Default::default()
+        };
+        let mut model = OtrModel::with_config(&player_ratings, &countries, config);
+
+        let full: Vec<PlayerPlacement> = (1..=4).map(|id| generate_placement(id, id)).collect();
+        let partial: Vec<PlayerPlacement> = (1..=3).map(|id| generate_placement(id, id)).collect();
+        let games = vec![
+            generate_game(1, &full),
+            generate_game(2, &partial),
+            generate_game(3, &partial),
+        ];
+
+        model.process(&[generate_match(1, Osu, &games, time)]).unwrap();
+        model.rating_tracker.get_rating(player_id, Osu).unwrap().rating
+    }
+
+    /// Tests that participation weights lean toward Method B for players who sit out games
+    #[test]
+    fn test_participation_weights_penalize_sitters() {
+        let constant = rating_with_sitter(WeightStrategy::Constant, 4);
+        let participation = rating_with_sitter(WeightStrategy::Participation, 4);
+
+        assert!(participation < constant);
+    }
+
+    /// Tests that a simulated match projects the same ratings as processing it, without
+    /// modifying the tracker
+    #[test]
+    fn test_simulate_match() {
+        let time = Utc::now().fixed_offset();
+        let player_ratings: Vec<PlayerRating> = (1..=3)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, 200.0, 2, Some(time), Some(time)))
+            .collect();
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+        let mut model = OtrModel::new(&player_ratings, &countries);
+
+        let placements: Vec<PlayerPlacement> = (1..=3).map(|id| generate_placement(id, id)).collect();
+        let lineup = Lineup {
+            ruleset: Osu,
+            games: vec![placements.clone(), placements.iter().rev().cloned().collect()]
+        };
+
+        let projected = model.simulate_match(&lineup).unwrap();
+        assert_eq!(projected.iter().map(|c| c.player_id).collect::<Vec<_>>(), vec![1, 2, 3]);
+        for change in &projected {
+            let current = model.rating_tracker.get_rating(change.player_id, Osu).unwrap();
+            assert_eq!(change.rating_before, current.rating);
+        }
+
+        // Process at the current time so the final decay pass has no effect
+        let mut match_ = lineup.to_match();
+        match_.start_time = time;
+        model.process(&[match_]).unwrap();
+        for change in &projected {
+            let processed = model.rating_tracker.get_rating(change.player_id, Osu).unwrap();
+            assert_abs_diff_eq!(change.rating_after, processed.rating, epsilon = 1e-9);
+            assert_abs_diff_eq!(change.volatility_after, processed.volatility, epsilon = 1e-9);
+        }
+    }
+
+    /// Tests that lineups which cannot be rated are rejected
+    #[test]
+    fn test_simulate_invalid_lineup() {
+        let player_ratings = vec![generate_player_rating(1, Osu, 1000.0, 200.0, 2, None, None)];
+        let model = OtrModel::new(&player_ratings, &HashMap::new());
+
+        let empty = Lineup {
+            ruleset: Osu,
+            games: vec![]
+        };
+        assert_eq!(model.simulate_match(&empty), Err(SimulationError::EmptyLineup));
+
+        let unrated = Lineup {
+            ruleset: Osu,
+            games: vec![vec![generate_placement(1, 1), generate_placement(2, 2)]]
+        };
+        assert_eq!(
+            model.simulate_match(&unrated),
+            Err(SimulationError::UnratedPlayer {
+                player_id: 2,
+                ruleset: Osu
+            })
+        );
+    }
+
+    /// Tests that manual adjustments are applied at their timestamp, between matches,
+    /// keeping the adjustment chain consistent
+    #[test]
+    fn test_manual_adjustments() {
+        let start = Utc::now().fixed_offset() - chrono::Duration::days(2);
+        let player_ratings: Vec<PlayerRating> = (1..=2)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, 200.0, 2, Some(start), Some(start)))
+            .collect();
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+        let mut model = OtrModel::new(&player_ratings, &countries);
+
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 2)];
+        let games = vec![generate_game(1, &placements)];
+        let matches = vec![
+            generate_match(1, Osu, &games, start + chrono::Duration::hours(1)),
+            generate_match(2, Osu, &games, start + chrono::Duration::hours(3)),
+        ];
+
+        let manual = |id, player_id, hours, kind, value| ManualAdjustment {
+            id,
+            player_id,
+            ruleset: Osu,
+            timestamp: start + chrono::Duration::hours(hours),
+            kind,
+            value
+        };
+        model.manual_adjustments = vec![
+            manual(2, 2, 4, ManualAdjustmentKind::Offset, -100.0),
+            manual(1, 1, 2, ManualAdjustmentKind::Override, 500.0),
+            // Player 3 is not rated
+            manual(3, 3, 2, ManualAdjustmentKind::Override, 500.0),
+        ];
+
+        let result = model.process(&matches).unwrap();
+        assert_eq!(result.skipped.manual_adjustments_without_rating, vec![3]);
+
+        let types = |player_id| {
+            let rating = model.rating_tracker.get_rating(player_id, Osu).unwrap();
+            for pair in rating.adjustments.windows(2) {
+                assert!(pair[0].timestamp <= pair[1].timestamp);
+                assert_abs_diff_eq!(pair[0].rating_after, pair[1].rating_before);
+            }
+
+            rating.adjustments.iter().map(|a| a.adjustment_type).collect::<Vec<_>>()
+        };
+
+        use RatingAdjustmentType::{Initial, Manual, Match};
+        assert_eq!(types(1), vec![Initial, Match, Match, Manual, Match]);
+        assert_eq!(types(2), vec![Initial, Match, Match, Match, Manual]);
+
+        // The override of player 1 took effect before the second match
+        let player_1 = model.rating_tracker.get_rating(1, Osu).unwrap();
+        assert_eq!(player_1.adjustments[3].rating_after, 500.0);
+        assert_eq!(player_1.adjustments[4].rating_before, 500.0);
+
+        let player_2 = model.rating_tracker.get_rating(2, Osu).unwrap();
+        let offset = &player_2.adjustments[4];
+        assert_abs_diff_eq!(offset.rating_after, offset.rating_before - 100.0);
+        assert_eq!(player_2.rating, offset.rating_after);
+    }
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        matches: Vec<(i32, usize)>,
+        manual: Vec<i32>
+    }
+
+    impl ProcessingObserver for RecordingObserver {
+        fn on_match_processed(&mut self, match_: &Match, adjustments: &[RatingAdjustment]) {
+            self.matches.push((match_.id, adjustments.len()));
+        }
+
+        fn on_manual_adjustment(&mut self, adjustment: &RatingAdjustment) {
+            self.manual.push(adjustment.player_id);
+        }
+    }
+
+    /// The observer is notified of every processed match, in order, along with the
+    /// adjustments it produced. Exempt matches produce no adjustments.
+    #[test]
+    fn test_process_with_observer() {
+        let start = Utc::now().fixed_offset() - chrono::Duration::days(2);
+        let player_ratings: Vec<PlayerRating> = (1..=3)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, 200.0, 2, Some(start), Some(start)))
+            .collect();
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+        let mut model = OtrModel::new(&player_ratings, &countries);
+
+        let placements = vec![
+            generate_placement(1, 1),
+            generate_placement(2, 2),
+            generate_placement(3, 3),
+        ];
+        let games = vec![generate_game(1, &placements)];
+        let mut exempt = generate_match(2, Osu, &games, start + chrono::Duration::hours(2));
+        exempt.rating_exempt = true;
+        let matches = vec![
+            generate_match(1, Osu, &games, start + chrono::Duration::hours(1)),
+            exempt,
+        ];
+        model.manual_adjustments = vec![ManualAdjustment {
+            id: 1,
+            player_id: 2,
+            ruleset: Osu,
+            timestamp: start + chrono::Duration::hours(3),
+            kind: ManualAdjustmentKind::Offset,
+            value: 50.0
+        }];
+
+        let mut observer = RecordingObserver::default();
+        model.process_with_observer(&matches, &mut observer).unwrap();
+
+        assert_eq!(observer.matches, vec![(1, 3), (2, 0)]);
+        assert_eq!(observer.manual, vec![2]);
+    }
+
+    /// Matches are processed chronologically regardless of their input order, breaking
+    /// ties in start time by match id
+    #[test]
+    fn test_process_unordered_matches() {
+        let start = Utc::now().fixed_offset() - chrono::Duration::days(400);
+        let player_ratings: Vec<PlayerRating> = (1..=2)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, 200.0, 2, Some(start), Some(start)))
+            .collect();
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 2)];
+        let reversed = vec![generate_placement(1, 2), generate_placement(2, 1)];
+        // Far enough apart for decay to occur between the matches
+        let matches = vec![
+            generate_match(
+                1,
+                Osu,
+                &[generate_game(1, &placements)],
+                start + chrono::Duration::days(1)
+            ),
+            generate_match(
+                2,
+                Osu,
+                &[generate_game(2, &reversed)],
+                start + chrono::Duration::days(200)
+            ),
+            generate_match(
+                3,
+                Osu,
+                &[generate_game(3, &placements)],
+                start + chrono::Duration::days(200)
+            ),
+        ];
+        let unordered = vec![matches[2].clone(), matches[1].clone(), matches[0].clone()];
+
+        let mut ordered_model = OtrModel::new(&player_ratings, &countries);
+        let ordered_result = ordered_model.process(&matches).unwrap();
+
+        let mut unordered_model = OtrModel::new(&player_ratings, &countries);
+        let mut observer = RecordingObserver::default();
+        let unordered_result = unordered_model
+            .process_with_observer(&unordered, &mut observer)
+            .unwrap();
+
+        assert_eq!(
+            observer.matches.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(ordered_result.ratings, unordered_result.ratings);
+    }
+
+    /// Tests that processing ruleset by ruleset gives the same ratings as processing all matches
+    /// at once
+    #[test]
+    fn test_process_ruleset_matches_process() {
+        let start = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let player_ratings = [Osu, Taiko]
+            .into_iter()
+            .flat_map(|ruleset| {
+                (1..=2).map(move |id| generate_player_rating(id, ruleset, 1000.0, 200.0, 2, Some(start), Some(start)))
+            })
+            .collect_vec();
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 2)];
+        let taiko_game = Game {
+            ruleset: Taiko,
+            ..generate_game(2, &placements)
+        };
+        let matches = vec![
+            generate_match(1, Osu, &[generate_game(1, &placements)], start + chrono::Duration::days(1)),
+            generate_match(2, Taiko, &[taiko_game], start + chrono::Duration::days(2)),
+        ];
+        let model = || {
+            OtrModelBuilder::new()
+                .ratings(&player_ratings)
+                .country_mapping(countries.clone())
+                .clock(FixedClock(start + chrono::Duration::days(400)))
+                .build()
+        };
+
+        let all = model().process(&matches).unwrap().ratings;
+        let mut by_ruleset = model();
+        let osu = by_ruleset.process_ruleset(Osu, &matches, &mut ()).unwrap().ratings;
+        let taiko = by_ruleset.process_ruleset(Taiko, &matches, &mut ()).unwrap().ratings;
+
+        assert_eq!(osu.len() + taiko.len(), all.len());
+        for rating in osu.iter().chain(&taiko) {
+            assert!(all.contains(rating));
+        }
+    }
+
+    /// Tests that the final decay pass only decays ratings up to `decay_until`
+    #[test]
+    fn test_decay_until() {
+        let match_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let player_ratings = (1..=2)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, 100.0, 1, None, None))
+            .collect_vec();
+        let countries = generate_country_mapping_player_ratings(player_ratings.as_slice(), "US");
+        let placements = [generate_placement(1, 1), generate_placement(2, 2)];
+        let matches = vec![generate_match(1, Osu, &[generate_game(1, &placements)], match_time)];
+
+        let decay_count = |decay_until| {
+            let config = ModelConfig {
+                decay_until,
+                ..Default::default()
+            };
+            let mut model = OtrModel::with_config(&player_ratings, &countries, config);
+            model.process(&matches).unwrap();
+
+            model
+                .rating_tracker
+                .adjustments(1, Osu)
+                .unwrap()
+                .iter()
+                .filter(|a| a.adjustment_type == RatingAdjustmentType::Decay)
+                .count()
+        };
+
+        // Years of inactivity up to now, but none a week after the match
+        assert!(decay_count(None) > 0);
+        assert_eq!(decay_count(Some(match_time + chrono::Duration::weeks(1))), 0);
+    }
+
+    /// Tests that the final decay pass decays ratings up to the time of the injected clock,
+    /// and that no decay applies with `NoDecay`
+    #[test]
+    fn test_builder_clock_and_decay() {
+        let match_time = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let player_ratings = (1..=2)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, 100.0, 1, None, None))
+            .collect_vec();
+        let placements = [generate_placement(1, 1), generate_placement(2, 2)];
+        let matches = vec![generate_match(1, Osu, &[generate_game(1, &placements)], match_time)];
+
+        let decay_count = |builder: OtrModelBuilder| {
+            let mut model = builder.ratings(&player_ratings).build();
+            model.process(&matches).unwrap();
+
+            model
+                .rating_tracker
+                .adjustments(1, Osu)
+                .unwrap()
+                .iter()
+                .filter(|a| a.adjustment_type == RatingAdjustmentType::Decay)
+                .count()
+        };
+
+        let in_a_week = FixedClock(match_time + chrono::Duration::weeks(1));
+        let in_a_year = FixedClock(match_time + chrono::Duration::days(365));
+        assert_eq!(decay_count(OtrModelBuilder::new().clock(in_a_week)), 0);
+        assert!(decay_count(OtrModelBuilder::new().clock(in_a_year)) > 0);
+        assert_eq!(decay_count(OtrModelBuilder::new().decay(NoDecay)), 0);
+    }
+
+    /// Players returning from decay are rated with a boosted volatility for the configured
+    /// number of matches, active players are unaffected
+    #[test]
+    fn test_returning_boost() {
+        let boost = ReturningBoost {
+            matches: 1,
+            volatility: 250.0
+        };
+        let config = ModelConfig {
+            returning_boost: Some(boost),
+            ..Default::default()
+        };
+
+        let now = Utc::now().fixed_offset();
+        let run = |config: ModelConfig, inactive_days: i64| {
+            let tournament = TournamentBuilder::new()
+                .matches(2)
+                .start_time(now - chrono::Duration::days(7));
+            let last_active = now - chrono::Duration::days(inactive_days);
+            let player_ratings: Vec<PlayerRating> = tournament
+                .player_ids()
+                .into_iter()
+                .map(|id| generate_player_rating(id, Osu, 1000.0, 100.0, 2, Some(last_active), Some(last_active)))
+                .collect();
+            let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+
+            let mut model = OtrModel::with_config(&player_ratings, &countries, config);
+            let mut observer = RecordingAdjustments::default();
+            model.process_with_observer(&tournament.build(), &mut observer).unwrap();
+            observer.adjustments
+        };
+
+        let boosted = run(config.clone(), 365);
+        let plain = run(ModelConfig::default(), 365);
+
+        // The first match back moves ratings further, the second is rated normally again
+        let change = |a: &RatingAdjustment| (a.rating_after - a.rating_before).abs();
+        assert!(change(&boosted[0][0]) > change(&plain[0][0]));
+        assert!(boosted[0][0].volatility_after > plain[0][0].volatility_after);
+        assert_abs_diff_eq!(boosted[1][0].volatility_before, boosted[0][0].volatility_after);
+
+        // Without decay there is no boost
+        assert_eq!(run(config, 30), run(ModelConfig::default(), 30));
+    }
+
+    /// New players are rated with a raised volatility during their provisional period, which
+    /// makes them move the ratings of established players less
+    #[test]
+    fn test_provisional_period() {
+        let period = ProvisionalPeriod {
+            matches: 2,
+            volatility: 300.0
+        };
+        let now = Utc::now().fixed_offset();
+
+        // Player 1 is established, player 2 only has an initial rating
+        let player_ratings = vec![
+            generate_player_rating(1, Osu, 1000.0, 100.0, 11, Some(now), Some(now)),
+            generate_player_rating(2, Osu, 1000.0, 100.0, 1, Some(now), Some(now)),
+        ];
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+        let placements = [generate_placement(1, 2), generate_placement(2, 1)];
+        let matches = (1..=3)
+            .map(|id| generate_match(id, Osu, &[generate_game(id, &placements)], now))
+            .collect_vec();
+
+        let run = |provisional_period| {
+            let config = ModelConfig {
+                provisional_period,
+                ..Default::default()
+            };
+            let mut model = OtrModel::with_config(&player_ratings, &countries, config);
+            model.process(&matches).unwrap();
+
+            [1, 2].map(|id| {
+                model
+                    .rating_tracker
+                    .get_rating_adjustments(id, Osu)
+                    .unwrap()
+                    .into_iter()
+                    // Only the processed matches have a match id
+                    .filter(|a| a.match_id.is_some())
+                    .collect_vec()
+            })
+        };
+
+        let [established, new] = run(Some(period));
+        let [plain_established, _] = run(None);
+
+        assert_eq!(new.iter().map(|a| a.provisional).collect_vec(), vec![true, true, false]);
+        assert!(established.iter().all(|a| !a.provisional));
+
+        let change = |a: &RatingAdjustment| (a.rating_after - a.rating_before).abs();
+        assert!(change(&established[0]) < change(&plain_established[0]));
+    }
+
+    #[test]
+    fn test_short_match_policy() {
+        let now = Utc::now().fixed_offset();
+        let player_ratings = vec![
+            generate_player_rating(1, Osu, 1000.0, 200.0, 1, Some(now), Some(now)),
+            generate_player_rating(2, Osu, 1000.0, 200.0, 1, Some(now), Some(now)),
+        ];
+        let countries = generate_country_mapping_player_ratings(&player_ratings, "US");
+        let placements = [generate_placement(1, 1), generate_placement(2, 2)];
+        let matches = vec![generate_match(1, Osu, &[generate_game(1, &placements)], now)];
+
+        let run = |short_match_policy| {
+            let config = ModelConfig {
+                short_match_policy,
+                ..Default::default()
+            };
+            let mut model = OtrModel::with_config(&player_ratings, &countries, config);
+            let mut warnings = WarningSink::new(&[WarningKind::ShortMatch]);
+            let result = model.process_with_observer(&matches, &mut warnings).unwrap();
+
+            let change = model
+                .rating_tracker
+                .adjustments(1, Osu)
+                .unwrap()
+                .iter()
+                .find(|a| a.match_id == Some(1))
+                .map(|a| {
+                    (
+                        a.rating_after - a.rating_before,
+                        a.volatility_after - a.volatility_before
+                    )
+                });
+            (change, result.skipped, warnings.violations().len())
+        };
+
+        let (rated, _, warned) = run(ShortMatchPolicy::Rate);
+        let (rating_change, volatility_change) = rated.unwrap();
+        assert!(rating_change > 0.0);
+        assert_eq!(warned, 0);
+
+        let (flagged, _, warned) = run(ShortMatchPolicy::Flag);
+        assert_eq!(flagged, rated);
+        assert_eq!(warned, 1);
+
+        let (dampened, _, _) = run(ShortMatchPolicy::Dampen(0.5));
+        let (dampened_rating, dampened_volatility) = dampened.unwrap();
+        assert_abs_diff_eq!(dampened_rating, rating_change * 0.5, epsilon = 1e-9);
+        assert_abs_diff_eq!(dampened_volatility, volatility_change * 0.5, epsilon = 1e-9);
+
+        let (skipped_change, skipped, _) = run(ShortMatchPolicy::MinGames(2));
+        assert_eq!(skipped_change, None);
+        assert_eq!(skipped.matches_with_too_few_games, vec![1]);
+        assert_eq!(skipped.quarantined_matches.len(), 1);
+    }
+
+    #[derive(Default)]
+    struct RecordingAdjustments {
+        adjustments: Vec<Vec<RatingAdjustment>>
+    }
+
+    impl ProcessingObserver for RecordingAdjustments {
+        fn on_match_processed(&mut self, _match_: &Match, adjustments: &[RatingAdjustment]) {
+            self.adjustments
+                .push(adjustments.iter().cloned().sorted_by_key(|a| a.player_id).collect());
+        }
+    }
+
+    #[test]
+    fn test_tie_for_last_follows_dnf_policy() {
+        // Player 3 did not finish game 1 and player 4 missed it
+        let mut game_1 = generate_game(
+            1,
+            &[
+                generate_placement(1, 1),
+                generate_placement(2, 2),
+                generate_placement(3, 3)
+            ]
+        );
+        for (score, value) in game_1.scores.iter_mut().zip([900_000, 700_000, 0]) {
+            score.score = value;
+        }
+        let game_2 = generate_game(2, &[generate_placement(4, 1)]);
+        let match_ = generate_match(1, Osu, &[game_1, game_2], Utc::now().fixed_offset());
+
+        let placement_of_missing = |dnf_policy| {
+            let config = ModelConfig {
+                dnf_policy,
+                ..Default::default()
+            };
+            let model = OtrModel::with_config(&[], &HashMap::new(), config);
+            let mut penalized = match_.clone();
+            model.apply_tie_for_last_scores(&mut penalized, &[1, 2, 3, 4]);
+
+            penalized.games[0]
+                .scores
+                .iter()
+                .find(|s| s.player_id == 4)
+                .unwrap()
+                .placement
+        };
+
+        assert_eq!(placement_of_missing(DnfPolicy::Rank), 4);
+        assert_eq!(placement_of_missing(DnfPolicy::TiedLast), 3);
     }
 }
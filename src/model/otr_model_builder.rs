@@ -0,0 +1,132 @@
+use super::{
+    clock::{Clock, SystemClock},
+    decay::{RatingDecay, StandardDecay},
+    model_config::ModelConfig,
+    otr_model::OtrModel,
+    rating_tracker::RatingTracker
+};
+use crate::{database::db_structs::PlayerRating, model::structures::gamma_strategy::GammaStrategy};
+use openskill::{
+    constant::KAPPA,
+    model::{model::Model, plackett_luce::PlackettLuce},
+    rating::Rating
+};
+use std::collections::HashMap;
+
+/// Rates the placements of a single game, see `OtrModelBuilder::rating_model`
+pub trait RatingModel: Send + Sync {
+    /// Rates `teams`, placed according to `ranks`, returning their new ratings in the same order
+    ///
+    /// `beta` is the performance variance of the game, see `ModelConfig::lobby_beta`.
+    fn rate(&self, teams: Vec<Vec<Rating>>, ranks: Vec<usize>, beta: f64) -> Vec<Vec<Rating>>;
+}
+
+/// Rates games with the PlackettLuce model of OpenSkill
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlackettLuceModel {
+    pub kappa: f64,
+    pub gamma: GammaStrategy
+}
+
+impl PlackettLuceModel {
+    pub fn new(gamma: GammaStrategy) -> PlackettLuceModel {
+        PlackettLuceModel { kappa: KAPPA, gamma }
+    }
+}
+
+impl RatingModel for PlackettLuceModel {
+    fn rate(&self, teams: Vec<Vec<Rating>>, ranks: Vec<usize>, beta: f64) -> Vec<Vec<Rating>> {
+        self.gamma
+            .with_function(|gamma| PlackettLuce::new(beta, self.kappa, gamma).rate(teams, ranks))
+    }
+}
+
+/// Builds an `OtrModel`, replacing any of its parts
+///
+/// Unless replaced, the model rates games with PlackettLuce using the gamma of the config,
+/// decays ratings with the `DecaySystem` and decays them up to the system time in the final
+/// decay pass.
+///
+/// ```
+/// # use otr_processor::model::{clock::FixedClock, decay::NoDecay, otr_model_builder::OtrModelBuilder};
+/// # use chrono::{TimeZone, Utc};
+/// let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+/// let model = OtrModelBuilder::new().clock(FixedClock(now)).decay(NoDecay).build();
+/// ```
+#[derive(Default)]
+pub struct OtrModelBuilder {
+    ratings: Vec<PlayerRating>,
+    country_mapping: HashMap<i32, String>,
+    config: ModelConfig,
+    rating_model: Option<Box<dyn RatingModel>>,
+    decay: Option<Box<dyn RatingDecay>>,
+    clock: Option<Box<dyn Clock>>,
+    tracker: Option<RatingTracker>
+}
+
+impl OtrModelBuilder {
+    pub fn new() -> OtrModelBuilder {
+        OtrModelBuilder::default()
+    }
+
+    /// Ratings the model starts from, added to the tracker
+    pub fn ratings(mut self, ratings: &[PlayerRating]) -> Self {
+        self.ratings = ratings.to_vec();
+        self
+    }
+
+    /// Maps player ids to their country codes
+    pub fn country_mapping(mut self, country_mapping: HashMap<i32, String>) -> Self {
+        self.country_mapping = country_mapping;
+        self
+    }
+
+    pub fn config(mut self, config: ModelConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn rating_model(mut self, rating_model: impl RatingModel + 'static) -> Self {
+        self.rating_model = Some(Box::new(rating_model));
+        self
+    }
+
+    pub fn decay(mut self, decay: impl RatingDecay + 'static) -> Self {
+        self.decay = Some(Box::new(decay));
+        self
+    }
+
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(Box::new(clock));
+        self
+    }
+
+    /// Tracker the ratings and country mapping are added to, instead of an empty one with room
+    /// for the ratings
+    pub fn tracker(mut self, tracker: RatingTracker) -> Self {
+        self.tracker = Some(tracker);
+        self
+    }
+
+    pub fn build(self) -> OtrModel {
+        let mut tracker = self
+            .tracker
+            .unwrap_or_else(|| RatingTracker::with_capacity(self.ratings.len()));
+        if !self.country_mapping.is_empty() {
+            tracker.set_country_mapping(self.country_mapping);
+        }
+        tracker.insert_or_update(&self.ratings);
+
+        let rating_model = self
+            .rating_model
+            .unwrap_or_else(|| Box::new(PlackettLuceModel::new(self.config.gamma)));
+
+        OtrModel::from_parts(
+            rating_model,
+            tracker,
+            self.config,
+            self.decay.unwrap_or_else(|| Box::new(StandardDecay)),
+            self.clock.unwrap_or_else(|| Box::new(SystemClock))
+        )
+    }
+}
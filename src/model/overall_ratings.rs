@@ -0,0 +1,88 @@
+use super::rating_utils::matches_played;
+use crate::database::db_structs::{OverallRating, PlayerRating};
+use itertools::Itertools;
+
+/// Blends the ratings of every player across rulesets into a single overall rating
+///
+/// Each ruleset rating is weighted by the number of matches the player was rated in within
+/// that ruleset, so the overall rating follows the rulesets a player actually competes in.
+/// Players who were never rated in a match have no overall rating.
+///
+/// # Returns
+/// The overall ratings in order of global rank. Ties are broken by player id.
+pub fn overall_ratings(ratings: &[PlayerRating]) -> Vec<OverallRating> {
+    let blended = ratings
+        .iter()
+        .into_group_map_by(|r| r.player_id)
+        .into_iter()
+        .filter_map(|(player_id, player_ratings)| {
+            let weighted = player_ratings
+                .iter()
+                .map(|r| (r.rating, matches_played(r)))
+                .filter(|(_, matches)| *matches > 0)
+                .collect_vec();
+            let matches_played = weighted.iter().map(|(_, matches)| matches).sum::<usize>();
+            if matches_played == 0 {
+                return None;
+            }
+
+            let rating = weighted
+                .iter()
+                .map(|(rating, matches)| rating * *matches as f64)
+                .sum::<f64>()
+                / matches_played as f64;
+            Some((player_id, rating, matches_played))
+        })
+        .sorted_by(|(a_id, a, _), (b_id, b, _)| b.total_cmp(a).then(a_id.cmp(b_id)))
+        .collect_vec();
+
+    let total = blended.len() as i32;
+    (1..)
+        .zip(blended)
+        .map(|(global_rank, (player_id, rating, matches_played))| OverallRating {
+            player_id,
+            rating,
+            global_rank,
+            percentile: (total - global_rank) as f64 / total as f64 * 100.0,
+            matches_played
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::overall_ratings;
+    use crate::{
+        model::structures::ruleset::Ruleset::{Mania4k, Osu, Taiko},
+        utils::test_utils::generate_player_rating
+    };
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_ratings_weighted_by_matches() {
+        // Player 1 played 3 osu! matches and 1 taiko match, player 2 was never rated in a
+        // mania match
+        let ratings = vec![
+            generate_player_rating(1, Osu, 1200.0, 100.0, 4, None, None),
+            generate_player_rating(1, Taiko, 800.0, 100.0, 2, None, None),
+            generate_player_rating(2, Osu, 1000.0, 100.0, 2, None, None),
+            generate_player_rating(2, Mania4k, 2000.0, 100.0, 1, None, None),
+            generate_player_rating(3, Taiko, 500.0, 100.0, 1, None, None),
+        ];
+
+        let overall = overall_ratings(&ratings);
+
+        assert_eq!(overall.len(), 2);
+
+        assert_eq!(overall[0].player_id, 1);
+        assert_abs_diff_eq!(overall[0].rating, 1100.0);
+        assert_eq!(overall[0].matches_played, 4);
+        assert_eq!(overall[0].global_rank, 1);
+        assert_abs_diff_eq!(overall[0].percentile, 50.0);
+
+        assert_eq!(overall[1].player_id, 2);
+        assert_abs_diff_eq!(overall[1].rating, 1000.0);
+        assert_eq!(overall[1].global_rank, 2);
+        assert_abs_diff_eq!(overall[1].percentile, 0.0);
+    }
+}
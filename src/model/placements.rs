@@ -0,0 +1,153 @@
+use super::{ruleset_policy::ruleset_policy, structures::dnf_policy::DnfPolicy};
+use crate::database::db_structs::{Game, Match};
+use itertools::Itertools;
+
+/// Recalculates the placements of every game in `matches` from their scores
+pub fn calculate_placements(matches: &mut [Match]) {
+    for game in matches.iter_mut().flat_map(|m| m.games.iter_mut()) {
+        calculate_game_placements(game);
+    }
+}
+
+/// Assigns each score of a game its placement, ranking scores in descending order
+///
+/// Tied scores share the best placement of the tie and the placements they occupy are
+/// skipped (e.g. 1, 2, 2, 4). Only verified scores are fetched for processing, so every
+/// score in the game is ranked. Recomputing is idempotent, making it safe to retry.
+pub fn calculate_game_placements(game: &mut Game) {
+    let values = game.scores.iter().map(|s| s.score).collect_vec();
+
+    for score in &mut game.scores {
+        score.placement = 1 + values.iter().filter(|&&other| other > score.score).count() as i32;
+    }
+}
+
+/// Places the DNF scores (scores of 0, unless the ruleset says otherwise) of every game in
+/// `matches` according to `policy`
+///
+/// # Returns
+/// The number of DNF scores found
+pub fn apply_dnf_policy(matches: &mut [Match], policy: DnfPolicy) -> usize {
+    matches
+        .iter_mut()
+        .flat_map(|m| m.games.iter_mut())
+        .map(|game| apply_game_dnf_policy(game, policy))
+        .sum()
+}
+
+/// Places the DNF scores of a game according to `policy`, see `apply_dnf_policy`
+///
+/// Tied-last DNFs are placed right after the other scores, so placements stay within the
+/// number of scores whichever way the other scores were placed.
+pub fn apply_game_dnf_policy(game: &mut Game, policy: DnfPolicy) -> usize {
+    let rules = ruleset_policy(game.ruleset);
+    let dnfs = game.scores.iter().filter(|s| rules.is_dnf(s)).count();
+
+    match policy {
+        DnfPolicy::Rank => {}
+        DnfPolicy::TiedLast => {
+            let last_placement = (game.scores.len() - dnfs) as i32 + 1;
+            for score in game.scores.iter_mut().filter(|s| rules.is_dnf(s)) {
+                score.placement = last_placement;
+            }
+        }
+        DnfPolicy::Exclude => game.scores.retain(|s| !rules.is_dnf(s))
+    }
+
+    dnfs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_game_dnf_policy, calculate_game_placements, calculate_placements};
+    use crate::{
+        database::db_structs::{Game, GameScore},
+        model::structures::{dnf_policy::DnfPolicy, ruleset::Ruleset::Osu},
+        utils::test_utils::generate_match
+    };
+    use chrono::Utc;
+
+    fn game(scores: &[(i32, i32)]) -> Game {
+        Game {
+            id: 1,
+            ruleset: Osu,
+            start_time: Default::default(),
+            end_time: Default::default(),
+            scores: scores
+                .iter()
+                .enumerate()
+                .map(|(i, &(player_id, score))| GameScore {
+                    id: i as i32,
+                    player_id,
+                    game_id: 1,
+                    score,
+                    placement: 0,
+                    team: 0
+                })
+                .collect()
+        }
+    }
+
+    fn placements(game: &Game) -> Vec<(i32, i32)> {
+        game.scores.iter().map(|s| (s.player_id, s.placement)).collect()
+    }
+
+    #[test]
+    fn test_placements_descending_by_score() {
+        let mut game = game(&[(1, 500_000), (2, 900_000), (3, 700_000)]);
+        calculate_game_placements(&mut game);
+
+        assert_eq!(placements(&game), vec![(1, 3), (2, 1), (3, 2)]);
+    }
+
+    #[test]
+    fn test_tied_scores_share_placement() {
+        let mut game = game(&[(1, 900_000), (2, 700_000), (3, 700_000), (4, 100_000)]);
+        calculate_game_placements(&mut game);
+
+        assert_eq!(placements(&game), vec![(1, 1), (2, 2), (3, 2), (4, 4)]);
+    }
+
+    #[test]
+    fn test_recalculation_is_idempotent() {
+        let mut game = game(&[(1, 100), (2, 200)]);
+        calculate_game_placements(&mut game);
+        let first = placements(&game);
+
+        calculate_game_placements(&mut game);
+        assert_eq!(placements(&game), first);
+    }
+
+    #[test]
+    fn test_calculate_placements_overrides_stored_values() {
+        let mut stored = game(&[(1, 100), (2, 200)]);
+        stored.scores[0].placement = 1;
+        stored.scores[1].placement = 2;
+
+        let mut matches = vec![generate_match(1, Osu, &[stored], Utc::now().fixed_offset())];
+        calculate_placements(&mut matches);
+
+        assert_eq!(placements(&matches[0].games[0]), vec![(1, 2), (2, 1)]);
+    }
+
+    #[test]
+    fn test_dnf_policies() {
+        // Placements as stored, with the DNFs of players 3 and 4 ranked by id
+        let mut stored = game(&[(1, 900_000), (2, 700_000), (3, 0), (4, 0)]);
+        for (score, placement) in stored.scores.iter_mut().zip([1, 2, 3, 4]) {
+            score.placement = placement;
+        }
+
+        let mut ranked = stored.clone();
+        assert_eq!(apply_game_dnf_policy(&mut ranked, DnfPolicy::Rank), 2);
+        assert_eq!(placements(&ranked), vec![(1, 1), (2, 2), (3, 3), (4, 4)]);
+
+        let mut tied = stored.clone();
+        apply_game_dnf_policy(&mut tied, DnfPolicy::TiedLast);
+        assert_eq!(placements(&tied), vec![(1, 1), (2, 2), (3, 3), (4, 3)]);
+
+        let mut excluded = stored.clone();
+        apply_game_dnf_policy(&mut excluded, DnfPolicy::Exclude);
+        assert_eq!(placements(&excluded), vec![(1, 1), (2, 2)]);
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{
+    database::db_structs::{Game, Match, PlayerRating},
+    model::structures::quarantine_reason::QuarantineReason
+};
+use serde::Serialize;
+use std::borrow::Cow;
+
+/// Output of `OtrModel::process`
+#[derive(Debug, Clone, Default)]
+pub struct ProcessingResult {
+    /// All player ratings after processing, sorted
+    pub ratings: Vec<PlayerRating>,
+    /// Number of matches processed by the model, including rating exempt matches but not
+    /// skipped ones
+    pub matches_processed: usize,
+    /// Matches and games which could not be rated
+    pub skipped: SkippedEntities
+}
+
+/// Matches and games left out of processing because they contain nothing to rate, and manual
+/// adjustments which could not be applied
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedEntities {
+    /// Ids of matches without any rateable games, in processing order
+    pub matches_without_games: Vec<i32>,
+    /// Ids of matches with a game whose placements are invalid, in processing order
+    pub matches_with_invalid_placements: Vec<i32>,
+    /// Ids of matches with fewer games than required by `ShortMatchPolicy::MinGames`, in
+    /// processing order
+    pub matches_with_too_few_games: Vec<i32>,
+    /// Ids of games without any scores, in processing order
+    pub games_without_scores: Vec<i32>,
+    /// Ids of manual adjustments for a player without a rating in the adjustment's ruleset,
+    /// in processing order
+    pub manual_adjustments_without_rating: Vec<i32>,
+    /// Every skipped match along with why it was skipped, in processing order
+    pub quarantined_matches: Vec<QuarantinedMatch>
+}
+
+/// A match left out of processing, stored so data admins know what needs fixing
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantinedMatch {
+    pub match_id: i32,
+    pub reason: QuarantineReason,
+    /// The offending games, if any
+    pub details: String
+}
+
+impl SkippedEntities {
+    /// Whether nothing was skipped
+    pub fn is_empty(&self) -> bool {
+        self.matches_without_games.is_empty()
+            && self.matches_with_invalid_placements.is_empty()
+            && self.matches_with_too_few_games.is_empty()
+            && self.games_without_scores.is_empty()
+            && self.manual_adjustments_without_rating.is_empty()
+    }
+
+    /// Adds everything skipped in `other`, e.g. while processing another ruleset
+    pub fn merge(&mut self, other: SkippedEntities) {
+        self.matches_without_games.extend(other.matches_without_games);
+        self.matches_with_invalid_placements
+            .extend(other.matches_with_invalid_placements);
+        self.matches_with_too_few_games.extend(other.matches_with_too_few_games);
+        self.games_without_scores.extend(other.games_without_scores);
+        self.manual_adjustments_without_rating
+            .extend(other.manual_adjustments_without_rating);
+        self.quarantined_matches.extend(other.quarantined_matches);
+    }
+
+    /// Returns the rateable part of `match_`, recording anything which cannot be rated.
+    ///
+    /// Games without scores are removed, cloning the match only if it has any. Returns None
+    /// if no games remain or if any game has invalid placements, in which case the whole
+    /// match is skipped and quarantined.
+    pub fn filter_match<'a>(&mut self, match_: &'a Match) -> Option<Cow<'a, Match>> {
+        let invalid_games = match_
+            .games
+            .iter()
+            .filter(|g| !has_valid_placements(g))
+            .map(|g| g.id)
+            .collect::<Vec<_>>();
+
+        if !invalid_games.is_empty() {
+            self.matches_with_invalid_placements.push(match_.id);
+            self.quarantine(
+                match_.id,
+                QuarantineReason::InvalidPlacements,
+                format!("games {:?}", invalid_games)
+            );
+            return None;
+        }
+
+        let empty_games = match_
+            .games
+            .iter()
+            .filter(|g| g.scores.is_empty())
+            .map(|g| g.id)
+            .collect::<Vec<_>>();
+
+        if empty_games.len() == match_.games.len() {
+            if empty_games.is_empty() {
+                self.quarantine(match_.id, QuarantineReason::NoGames, String::new());
+            } else {
+                self.quarantine(
+                    match_.id,
+                    QuarantineReason::EmptyScores,
+                    format!("games {:?}", empty_games)
+                );
+            }
+            self.games_without_scores.extend(empty_games);
+            self.matches_without_games.push(match_.id);
+            return None;
+        }
+
+        if empty_games.is_empty() {
+            return Some(Cow::Borrowed(match_));
+        }
+
+        let mut filtered = match_.clone();
+        filtered.games.retain(|g| !g.scores.is_empty());
+        self.games_without_scores.extend(empty_games);
+
+        Some(Cow::Owned(filtered))
+    }
+
+    /// Whether `match_` has at least `min_games` games, skipping and quarantining it otherwise
+    pub fn require_games(&mut self, match_: &Match, min_games: usize) -> bool {
+        if match_.games.len() >= min_games {
+            return true;
+        }
+
+        self.matches_with_too_few_games.push(match_.id);
+        self.quarantine(
+            match_.id,
+            QuarantineReason::TooFewGames,
+            format!("{} of {} games", match_.games.len(), min_games)
+        );
+        false
+    }
+
+    fn quarantine(&mut self, match_id: i32, reason: QuarantineReason, details: String) {
+        self.quarantined_matches.push(QuarantinedMatch {
+            match_id,
+            reason,
+            details
+        });
+    }
+}
+
+/// Whether every placement of `game` lies between 1 and its number of scores
+fn has_valid_placements(game: &Game) -> bool {
+    game.scores
+        .iter()
+        .all(|s| s.placement >= 1 && s.placement as usize <= game.scores.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QuarantinedMatch, SkippedEntities};
+    use crate::{
+        model::structures::{quarantine_reason::QuarantineReason, ruleset::Ruleset::Osu},
+        utils::test_utils::{generate_game, generate_match, generate_placement}
+    };
+    use chrono::Utc;
+
+    #[test]
+    fn test_filter_match() {
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 2)];
+        let time = Utc::now().fixed_offset();
+        let mut skipped = SkippedEntities::default();
+
+        let complete = generate_match(1, Osu, &[generate_game(1, &placements)], time);
+        assert_eq!(skipped.filter_match(&complete).unwrap().games.len(), 1);
+        assert!(skipped.is_empty());
+
+        let partial = generate_match(2, Osu, &[generate_game(2, &placements), generate_game(3, &[])], time);
+        let filtered = skipped.filter_match(&partial).unwrap();
+        assert_eq!(filtered.games.len(), 1);
+        assert_eq!(filtered.games[0].id, 2);
+
+        assert!(skipped.filter_match(&generate_match(3, Osu, &[], time)).is_none());
+        assert!(skipped
+            .filter_match(&generate_match(4, Osu, &[generate_game(4, &[])], time))
+            .is_none());
+
+        assert_eq!(skipped.matches_without_games, vec![3, 4]);
+        assert_eq!(skipped.games_without_scores, vec![3, 4]);
+        assert_eq!(
+            skipped.quarantined_matches,
+            vec![
+                QuarantinedMatch {
+                    match_id: 3,
+                    reason: QuarantineReason::NoGames,
+                    details: String::new()
+                },
+                QuarantinedMatch {
+                    match_id: 4,
+                    reason: QuarantineReason::EmptyScores,
+                    details: "games [4]".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_placements_are_quarantined() {
+        let time = Utc::now().fixed_offset();
+        let mut skipped = SkippedEntities::default();
+
+        // Placements must lie between 1 and the number of scores, ties are fine
+        let tied = [generate_placement(1, 1), generate_placement(2, 1)];
+        let unset = [generate_placement(1, 0), generate_placement(2, 0)];
+        let out_of_range = [generate_placement(1, 1), generate_placement(2, 3)];
+
+        assert!(skipped
+            .filter_match(&generate_match(1, Osu, &[generate_game(1, &tied)], time))
+            .is_some());
+        assert!(skipped
+            .filter_match(&generate_match(2, Osu, &[generate_game(2, &unset)], time))
+            .is_none());
+        assert!(skipped
+            .filter_match(&generate_match(
+                3,
+                Osu,
+                &[generate_game(3, &tied), generate_game(4, &out_of_range)],
+                time
+            ))
+            .is_none());
+
+        assert_eq!(skipped.matches_with_invalid_placements, vec![2, 3]);
+        assert_eq!(
+            skipped.quarantined_matches[1].reason,
+            QuarantineReason::InvalidPlacements
+        );
+        assert_eq!(skipped.quarantined_matches[1].details, "games [4]");
+    }
+}
@@ -0,0 +1,500 @@
+use crate::{
+    database::db_structs::{PercentileMilestone, PlayerHighestRank, PlayerRating},
+    model::structures::{rating_adjustment_type::RatingAdjustmentType, ruleset::Ruleset}
+};
+use chrono::{DateTime, FixedOffset};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// A player's rating as of an adjustment
+struct RatingEvent {
+    timestamp: DateTime<FixedOffset>,
+    player_id: i32,
+    rating: f64
+}
+
+/// Milestones of the global leaderboard, as the top percentage of players a player must
+/// be within to reach them
+pub const PERCENTILE_MILESTONES: [f64; 3] = [50.0, 10.0, 1.0];
+
+/// The best rank of a single player along with the time it was first reached
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BestRank {
+    rank: i32,
+    timestamp: DateTime<FixedOffset>
+}
+
+/// The best percentile of a single player along with the time it was first reached
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PeakPercentile {
+    percentile: f64,
+    timestamp: DateTime<FixedOffset>
+}
+
+/// Computes the best global and country rank each player reached during processing.
+///
+/// The adjustments of all players are replayed chronologically. After all adjustments
+/// sharing a timestamp have been applied, the rank of every player adjusted at that time
+/// is taken, and the earliest time each player reached their best rank is kept. Ranks are
+/// only sampled at a player's own adjustments, so a rank gained solely because other
+/// players dropped is not seen until the player's next adjustment.
+///
+/// The peak percentile is sampled the same way, using the number of players rated at the
+/// time. Players without a country in `country_mapping` have a country rank of 0.
+///
+/// # Returns
+/// The highest ranks of every (player, ruleset) with at least one adjustment. Ids are 0.
+pub fn highest_ranks(
+    ratings: &[PlayerRating],
+    country_mapping: &HashMap<i32, String>
+) -> HashMap<(i32, Ruleset), PlayerHighestRank> {
+    let mut highest = HashMap::new();
+
+    for (ruleset, ruleset_ratings) in ratings.iter().into_group_map_by(|r| r.ruleset) {
+        let mut global: HashMap<i32, BestRank> = HashMap::new();
+        let mut peaks: HashMap<i32, PeakPercentile> = HashMap::new();
+        replay(events(&ruleset_ratings), |player_id, rank, total, timestamp| {
+            keep_best_rank(&mut global, player_id, rank, timestamp);
+
+            let percentile = percentile(rank, total);
+            if peaks.get(&player_id).is_none_or(|p| percentile > p.percentile) {
+                peaks.insert(player_id, PeakPercentile { percentile, timestamp });
+            }
+        });
+
+        let by_country = ruleset_ratings
+            .iter()
+            .filter_map(|r| {
+                country_mapping
+                    .get(&r.player_id)
+                    .filter(|c| !c.is_empty())
+                    .map(|c| (c, *r))
+            })
+            .into_group_map();
+        let country = by_country
+            .into_values()
+            .flat_map(|members| best_ranks(events(&members)))
+            .collect::<HashMap<_, _>>();
+
+        for (player_id, best_global) in global {
+            let best_country = country.get(&player_id);
+            let peak = peaks[&player_id];
+
+            highest.insert(
+                (player_id, ruleset),
+                PlayerHighestRank {
+                    id: 0,
+                    ruleset,
+                    global_rank: best_global.rank,
+                    global_rank_date: best_global.timestamp,
+                    country_rank: best_country.map_or(0, |c| c.rank),
+                    country_rank_date: best_country.map_or(best_global.timestamp, |c| c.timestamp),
+                    percentile: peak.percentile,
+                    percentile_date: peak.timestamp,
+                    player_id
+                }
+            );
+        }
+    }
+
+    highest
+}
+
+/// Computes when each player first reached every milestone of `PERCENTILE_MILESTONES`.
+///
+/// Percentiles are sampled like the peak percentile of `highest_ranks`: at a player's own
+/// adjustments, after all adjustments sharing the timestamp have been applied. A player is
+/// within the top `p` percent once their percentile is at least `100 - p`.
+///
+/// # Returns
+/// The milestones reached, ordered by ruleset, player and milestone
+pub fn percentile_milestones(ratings: &[PlayerRating]) -> Vec<PercentileMilestone> {
+    let mut milestones = Vec::new();
+
+    for (ruleset, ruleset_ratings) in ratings
+        .iter()
+        .into_group_map_by(|r| r.ruleset)
+        .into_iter()
+        .sorted_by_key(|(ruleset, _)| *ruleset as i32)
+    {
+        let mut reached: HashMap<i32, Vec<PercentileMilestone>> = HashMap::new();
+        replay(events(&ruleset_ratings), |player_id, rank, total, timestamp| {
+            let percentile = percentile(rank, total);
+            let player_milestones = reached.entry(player_id).or_default();
+
+            for top_percent in PERCENTILE_MILESTONES {
+                if percentile >= 100.0 - top_percent && !player_milestones.iter().any(|m| m.top_percent == top_percent)
+                {
+                    player_milestones.push(PercentileMilestone {
+                        player_id,
+                        ruleset,
+                        top_percent,
+                        reached_at: timestamp
+                    });
+                }
+            }
+        });
+
+        for (_, player_milestones) in reached.into_iter().sorted_by_key(|(player_id, _)| *player_id) {
+            milestones.extend(
+                player_milestones
+                    .into_iter()
+                    .sorted_by(|a, b| b.top_percent.total_cmp(&a.top_percent))
+            );
+        }
+    }
+
+    milestones
+}
+
+/// Records the percentile each player had within their ruleset right after each of their
+/// match adjustments, so percentile history can be shown without replaying all ratings.
+///
+/// Percentiles are sampled like the peak percentile of `highest_ranks`: after all adjustments
+/// sharing the timestamp have been applied, among the players rated at the time. Adjustments
+/// which are not matches are left without a percentile.
+pub fn record_adjustment_percentiles(ratings: &mut [PlayerRating]) {
+    let mut percentiles = HashMap::new();
+    for (ruleset, ruleset_ratings) in ratings.iter().into_group_map_by(|r| r.ruleset) {
+        replay(events(&ruleset_ratings), |player_id, rank, total, timestamp| {
+            percentiles.insert((player_id, ruleset, timestamp), percentile(rank, total));
+        });
+    }
+
+    for rating in ratings.iter_mut() {
+        for adjustment in &mut rating.adjustments {
+            if adjustment.adjustment_type == RatingAdjustmentType::Match {
+                adjustment.percentile = percentiles
+                    .get(&(rating.player_id, rating.ruleset, adjustment.timestamp))
+                    .copied();
+            }
+        }
+    }
+}
+
+/// Combines a stored highest rank with one reached during processing, keeping the better
+/// global and country rank and percentile independently along with their dates. A country
+/// rank of 0 means unknown and never replaces a known rank.
+pub fn merge_highest_ranks(stored: &PlayerHighestRank, reached: &PlayerHighestRank) -> PlayerHighestRank {
+    let mut merged = stored.clone();
+
+    if reached.global_rank > 0 && (stored.global_rank <= 0 || reached.global_rank < stored.global_rank) {
+        merged.global_rank = reached.global_rank;
+        merged.global_rank_date = reached.global_rank_date;
+    }
+
+    if reached.country_rank > 0 && (stored.country_rank <= 0 || reached.country_rank < stored.country_rank) {
+        merged.country_rank = reached.country_rank;
+        merged.country_rank_date = reached.country_rank_date;
+    }
+
+    if reached.percentile > stored.percentile {
+        merged.percentile = reached.percentile;
+        merged.percentile_date = reached.percentile_date;
+    }
+
+    merged
+}
+
+/// All adjustments of the given ratings in chronological order
+fn events(ratings: &[&PlayerRating]) -> Vec<RatingEvent> {
+    ratings
+        .iter()
+        .flat_map(|r| {
+            r.adjustments.iter().map(|a| RatingEvent {
+                timestamp: a.timestamp,
+                player_id: r.player_id,
+                rating: a.rating_after
+            })
+        })
+        .sorted_by_key(|e| e.timestamp)
+        .collect()
+}
+
+/// Replays chronologically sorted events, returning the best rank of every player
+fn best_ranks(events: Vec<RatingEvent>) -> HashMap<i32, BestRank> {
+    let mut best = HashMap::new();
+    replay(events, |player_id, rank, _, timestamp| {
+        keep_best_rank(&mut best, player_id, rank, timestamp)
+    });
+
+    best
+}
+
+fn keep_best_rank(best: &mut HashMap<i32, BestRank>, player_id: i32, rank: i32, timestamp: DateTime<FixedOffset>) {
+    match best.get(&player_id) {
+        Some(b) if b.rank <= rank => {}
+        _ => {
+            best.insert(player_id, BestRank { rank, timestamp });
+        }
+    }
+}
+
+/// Percentile of `rank` among `total` players, as computed by the rating tracker
+fn percentile(rank: i32, total: i32) -> f64 {
+    (total - rank) as f64 / total as f64 * 100.0
+}
+
+/// Replays chronologically sorted events. After all events sharing a timestamp have been
+/// applied, `on_rank` is called with the player id, rank, number of rated players and
+/// timestamp of every player adjusted at that time.
+fn replay(events: Vec<RatingEvent>, mut on_rank: impl FnMut(i32, i32, i32, DateTime<FixedOffset>)) {
+    // Ratings are replaced by their index among all distinct ratings, in ascending order
+    let values = events
+        .iter()
+        .map(|e| e.rating)
+        .sorted_by(|a, b| a.total_cmp(b))
+        .dedup()
+        .collect_vec();
+
+    let mut counts = FenwickTree::new(values.len());
+    let mut current: HashMap<i32, usize> = HashMap::new();
+
+    for (timestamp, group) in &events.into_iter().group_by(|e| e.timestamp) {
+        let mut adjusted = Vec::new();
+
+        for event in group {
+            let index = values
+                .binary_search_by(|v| v.total_cmp(&event.rating))
+                .expect("Rating should be among the collected values");
+
+            if let Some(previous) = current.insert(event.player_id, index) {
+                counts.add(previous, -1);
+            }
+            counts.add(index, 1);
+            adjusted.push(event.player_id);
+        }
+
+        let total = current.len() as i32;
+        for player_id in adjusted {
+            // Rank is one more than the number of players with a strictly higher rating
+            let rank = total - counts.prefix_sum(current[&player_id]) + 1;
+            on_rank(player_id, rank, total, timestamp);
+        }
+    }
+}
+
+/// Counts values by index, supporting logarithmic updates and prefix sums
+struct FenwickTree {
+    tree: Vec<i32>
+}
+
+impl FenwickTree {
+    fn new(size: usize) -> FenwickTree {
+        FenwickTree {
+            tree: vec![0; size + 1]
+        }
+    }
+
+    fn add(&mut self, index: usize, delta: i32) {
+        let mut i = index + 1;
+        while i < self.tree.len() {
+            self.tree[i] += delta;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Sum of the counts at indices `0..=index`
+    fn prefix_sum(&self, index: usize) -> i32 {
+        let mut i = index + 1;
+        let mut sum = 0;
+        while i > 0 {
+            sum += self.tree[i];
+            i -= i & i.wrapping_neg();
+        }
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{highest_ranks, merge_highest_ranks, percentile_milestones, record_adjustment_percentiles};
+    use crate::{
+        database::db_structs::{PlayerHighestRank, PlayerRating, RatingAdjustment},
+        model::structures::{
+            rating_adjustment_type::RatingAdjustmentType, rating_clamps::RatingClamps, ruleset::Ruleset::Osu
+        }
+    };
+    use approx::assert_abs_diff_eq;
+    use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+    use std::collections::HashMap;
+
+    fn day(d: u32) -> DateTime<FixedOffset> {
+        Utc.with_ymd_and_hms(2024, 1, d, 0, 0, 0).unwrap().fixed_offset()
+    }
+
+    /// A rating whose history passes through the given (day, rating) points
+    fn rating(player_id: i32, history: &[(u32, f64)]) -> PlayerRating {
+        let adjustments = history
+            .iter()
+            .enumerate()
+            .map(|(i, &(d, rating))| RatingAdjustment {
+                player_id,
+                ruleset: Osu,
+                match_id: None,
+                rating_before: if i == 0 { rating } else { history[i - 1].1 },
+                rating_after: rating,
+                volatility_before: 100.0,
+                volatility_after: 100.0,
+                timestamp: day(d),
+                adjustment_type: if i == 0 {
+                    RatingAdjustmentType::Initial
+                } else {
+                    RatingAdjustmentType::Decay
+                },
+                average_opponent_rating: None,
+                provisional: false,
+                percentile: None,
+                clamps: RatingClamps::default()
+            })
+            .collect::<Vec<_>>();
+
+        PlayerRating {
+            id: player_id,
+            player_id,
+            ruleset: Osu,
+            rating: history.last().unwrap().1,
+            volatility: 100.0,
+            percentile: 0.0,
+            global_rank: 0,
+            country_rank: 0,
+            adjustments
+        }
+    }
+
+    #[test]
+    fn test_best_rank_is_dated_when_reached() {
+        // Player 1 is first on day 2, then decays below player 2 on day 10
+        let ratings = vec![
+            rating(1, &[(1, 900.0), (2, 1200.0), (10, 800.0)]),
+            rating(2, &[(1, 1000.0)]),
+        ];
+        let countries = HashMap::from([(1, "US".to_string()), (2, "US".to_string())]);
+
+        let highest = highest_ranks(&ratings, &countries);
+
+        let first = &highest[&(1, Osu)];
+        assert_eq!(first.global_rank, 1);
+        assert_eq!(first.global_rank_date, day(2));
+        assert_eq!(first.country_rank, 1);
+        assert_eq!(first.country_rank_date, day(2));
+
+        let second = &highest[&(2, Osu)];
+        assert_eq!(second.global_rank, 1);
+        assert_eq!(second.global_rank_date, day(1));
+    }
+
+    #[test]
+    fn test_ranks_use_all_adjustments_at_a_timestamp() {
+        // Both players are adjusted at the same time, so player 1 never ranks above player 2
+        let ratings = vec![rating(1, &[(1, 1000.0)]), rating(2, &[(1, 1100.0)])];
+        let countries = HashMap::from([(1, "US".to_string()), (2, "NZ".to_string())]);
+
+        let highest = highest_ranks(&ratings, &countries);
+
+        assert_eq!(highest[&(1, Osu)].global_rank, 2);
+        assert_eq!(highest[&(1, Osu)].country_rank, 1);
+        assert_eq!(highest[&(2, Osu)].global_rank, 1);
+    }
+
+    #[test]
+    fn test_adjustment_percentiles() {
+        let mut ratings = vec![
+            rating(1, &[(1, 900.0), (2, 1200.0), (3, 800.0)]),
+            rating(2, &[(1, 1000.0)]),
+            rating(3, &[(1, 1100.0)]),
+        ];
+        for adjustment in ratings[0].adjustments.iter_mut().skip(1) {
+            adjustment.adjustment_type = RatingAdjustmentType::Match;
+        }
+
+        record_adjustment_percentiles(&mut ratings);
+
+        let percentiles = ratings[0].adjustments.iter().map(|a| a.percentile).collect::<Vec<_>>();
+        assert_eq!(percentiles[0], None);
+        assert_abs_diff_eq!(percentiles[1].unwrap(), 2.0 / 3.0 * 100.0);
+        assert_abs_diff_eq!(percentiles[2].unwrap(), 0.0);
+        assert_eq!(ratings[1].adjustments[0].percentile, None);
+    }
+
+    #[test]
+    fn test_unknown_country_has_no_country_rank() {
+        let ratings = vec![rating(1, &[(1, 1000.0)])];
+
+        let highest = highest_ranks(&ratings, &HashMap::new());
+
+        assert_eq!(highest[&(1, Osu)].global_rank, 1);
+        assert_eq!(highest[&(1, Osu)].country_rank, 0);
+    }
+
+    #[test]
+    fn test_merge_keeps_better_ranks() {
+        let stored = PlayerHighestRank {
+            id: 7,
+            ruleset: Osu,
+            global_rank: 10,
+            global_rank_date: day(1),
+            country_rank: 0,
+            country_rank_date: day(1),
+            percentile: 90.0,
+            percentile_date: day(1),
+            player_id: 1
+        };
+        let reached = PlayerHighestRank {
+            id: 0,
+            global_rank: 12,
+            global_rank_date: day(5),
+            country_rank: 3,
+            country_rank_date: day(6),
+            percentile: 95.0,
+            percentile_date: day(7),
+            ..stored.clone()
+        };
+
+        let merged = merge_highest_ranks(&stored, &reached);
+
+        assert_eq!(merged.id, 7);
+        assert_eq!(merged.global_rank, 10);
+        assert_eq!(merged.global_rank_date, day(1));
+        assert_eq!(merged.country_rank, 3);
+        assert_eq!(merged.country_rank_date, day(6));
+        assert_eq!(merged.percentile, 95.0);
+        assert_eq!(merged.percentile_date, day(7));
+
+        assert_eq!(merge_highest_ranks(&merged, &reached), merged);
+    }
+
+    #[test]
+    fn test_peak_percentile() {
+        // Player 1 is second of two on day 1, first of three on day 3 and last on day 10
+        let ratings = vec![
+            rating(1, &[(1, 900.0), (3, 1200.0), (10, 800.0)]),
+            rating(2, &[(1, 1000.0)]),
+            rating(3, &[(2, 1100.0)]),
+        ];
+
+        let highest = highest_ranks(&ratings, &HashMap::new());
+
+        let first = &highest[&(1, Osu)];
+        assert_abs_diff_eq!(first.percentile, 200.0 / 3.0, epsilon = 1e-9);
+        assert_eq!(first.percentile_date, day(3));
+    }
+
+    #[test]
+    fn test_percentile_milestones() {
+        // 100 players rated on day 1, player 1 climbs into the top 10% on day 2 and to
+        // first place on day 3
+        let mut ratings = (2..=100)
+            .map(|id| rating(id, &[(1, 1000.0 + id as f64)]))
+            .collect::<Vec<_>>();
+        ratings.push(rating(1, &[(1, 1000.0), (2, 1095.5), (3, 2000.0)]));
+
+        let milestones = percentile_milestones(&ratings)
+            .into_iter()
+            .filter(|m| m.player_id == 1)
+            .map(|m| (m.top_percent, m.reached_at))
+            .collect::<Vec<_>>();
+
+        assert_eq!(milestones, vec![(50.0, day(2)), (10.0, day(2)), (1.0, day(3))]);
+    }
+}
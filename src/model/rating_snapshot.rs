@@ -0,0 +1,130 @@
+use crate::{
+    database::db_structs::{PlayerRating, RatingAdjustment},
+    model::{rating_tracker::Leaderboard, structures::ruleset::Ruleset}
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// A read-only view of the ratings of a `RatingTracker`, taken with `RatingTracker::snapshot`
+///
+/// The ratings are shared behind an `Arc`, so a snapshot is cheap to clone and can be read
+/// from several threads at once (e.g. by exporters) while the results are being saved. Later
+/// changes to the tracker are not reflected in the snapshot.
+#[derive(Debug, Clone)]
+pub struct RatingSnapshot {
+    inner: Arc<SnapshotData>
+}
+
+#[derive(Debug)]
+struct SnapshotData {
+    /// Key: (player_id, ruleset), in the tracker's leaderboard order
+    leaderboard: Leaderboard,
+    country_mapping: HashMap<i32, String>
+}
+
+impl RatingSnapshot {
+    pub(crate) fn new(leaderboard: Leaderboard, country_mapping: HashMap<i32, String>) -> RatingSnapshot {
+        RatingSnapshot {
+            inner: Arc::new(SnapshotData {
+                leaderboard,
+                country_mapping
+            })
+        }
+    }
+
+    /// Returns all player ratings across all rulesets
+    pub fn get_all_ratings(&self) -> impl Iterator<Item = &PlayerRating> {
+        self.inner.leaderboard.values()
+    }
+
+    /// Returns the leaderboard of a specific ruleset, in the tracker's leaderboard order
+    pub fn get_leaderboard(&self, ruleset: Ruleset) -> impl Iterator<Item = &PlayerRating> {
+        self.inner
+            .leaderboard
+            .values()
+            .filter(move |player_rating| player_rating.ruleset == ruleset)
+    }
+
+    /// Retrieves a player's rating for a specific ruleset
+    ///
+    /// # Returns
+    /// Returns None if the player has no rating for the specified ruleset
+    pub fn get_rating(&self, player_id: i32, ruleset: Ruleset) -> Option<&PlayerRating> {
+        self.inner.leaderboard.get(&(player_id, ruleset))
+    }
+
+    /// Gets a player's country code
+    ///
+    /// Returns None if the player is not in the country mapping or their country is empty
+    pub fn get_country(&self, player_id: i32) -> Option<&String> {
+        self.inner
+            .country_mapping
+            .get(&player_id)
+            .filter(|country| !country.is_empty())
+    }
+
+    /// Retrieves a player's rating adjustment history for a specific ruleset
+    pub fn get_rating_adjustments(&self, player_id: i32, ruleset: Ruleset) -> Option<&[RatingAdjustment]> {
+        self.get_rating(player_id, ruleset)
+            .map(|rating| rating.adjustments.as_slice())
+    }
+
+    /// Number of ratings across all rulesets
+    pub fn len(&self) -> usize {
+        self.inner.leaderboard.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.leaderboard.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        model::{
+            rating_tracker::RatingTracker,
+            structures::ruleset::Ruleset::{Osu, Taiko}
+        },
+        utils::test_utils::generate_player_rating
+    };
+    use std::{collections::HashMap, thread};
+
+    #[test]
+    fn test_snapshot_is_unaffected_by_later_updates() {
+        let mut tracker = RatingTracker::new();
+        tracker.set_country_mapping(HashMap::from([(1, "US".to_string()), (2, String::new())]));
+        tracker.insert_or_update(&[
+            generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None),
+            generate_player_rating(2, Osu, 900.0, 100.0, 1, None, None),
+            generate_player_rating(1, Taiko, 800.0, 100.0, 1, None, None)
+        ]);
+
+        let snapshot = tracker.snapshot();
+        tracker.insert_or_update(&[generate_player_rating(1, Osu, 2000.0, 100.0, 1, None, None)]);
+
+        assert_eq!(snapshot.len(), 3);
+        assert_eq!(snapshot.get_rating(1, Osu).unwrap().rating, 1000.0);
+        assert_eq!(snapshot.get_leaderboard(Osu).count(), 2);
+        assert_eq!(snapshot.get_country(1), Some(&"US".to_string()));
+        assert_eq!(snapshot.get_country(2), None);
+        assert!(snapshot.get_rating(2, Taiko).is_none());
+    }
+
+    #[test]
+    fn test_snapshot_shared_across_threads() {
+        let mut tracker = RatingTracker::new();
+        tracker.insert_or_update(&[generate_player_rating(1, Osu, 1000.0, 100.0, 3, None, None)]);
+        let snapshot = tracker.snapshot();
+
+        let handles = (0..4)
+            .map(|_| {
+                let snapshot = snapshot.clone();
+                thread::spawn(move || snapshot.get_rating_adjustments(1, Osu).map(|a| a.len()))
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), Some(3));
+        }
+    }
+}
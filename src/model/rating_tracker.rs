@@ -1,11 +1,16 @@
 use std::collections::HashMap;
 
+use ahash::{AHashMap, RandomState};
 use indexmap::IndexMap;
 use itertools::Itertools;
 
 use crate::database::db_structs::{PlayerRating, RatingAdjustment};
 
-use super::structures::ruleset::Ruleset;
+use super::{rating_snapshot::RatingSnapshot, structures::ruleset::Ruleset};
+
+/// Ratings keyed by (player_id, ruleset), hashed with aHash as every player of every game is
+/// looked up in it
+pub(crate) type Leaderboard = IndexMap<(i32, Ruleset), PlayerRating, RandomState>;
 
 /// Manages and tracks player ratings across all rulesets
 ///
@@ -17,28 +22,46 @@ use super::structures::ruleset::Ruleset;
 /// - Managing rating history through adjustments
 ///
 /// # Implementation Details
-/// - Uses IndexMap for ordered storage of ratings
+/// - Uses IndexMap for ordered storage of ratings, preallocated with `with_capacity` when the
+///   number of ratings is known
 /// - Maintains separate country leaderboards
 /// - Updates rankings efficiently through batch processing
-/// - Ensures consistency between global and country rankings
 pub struct RatingTracker {
     /// Global leaderboard storing all player ratings
     /// Key: (player_id, ruleset)
     ///
     /// This is the source of truth for current ratings
-    leaderboard: IndexMap<(i32, Ruleset), PlayerRating>,
+    leaderboard: Leaderboard,
 
     /// Per-country leaderboards for country ranking calculations
-    /// Key: country_code
+    /// Key: (country_code, ruleset)
+    ///
+    /// Each entry holds the (leaderboard index, rating) pairs of the country's players,
+    /// sorted by descending rating after `sort()`
+    country_leaderboards: AHashMap<(String, Ruleset), Vec<(usize, f64)>>,
+
+    /// Rated players whose country is unknown (absent from the country mapping or empty)
+    /// Key: (player_id, ruleset)
     ///
-    /// These leaderboards mirror the global leaderboard but are
-    /// filtered by country for efficient country rank calculations
-    country_leaderboards: HashMap<String, IndexMap<(i32, Ruleset), PlayerRating>>,
+    /// These players are excluded from country rankings and have a country rank of 0
+    unknown_country_players: Vec<(i32, Ruleset)>,
 
     /// Maps player IDs to their country codes
     country_mapping: HashMap<i32, String>
 }
 
+/// The state of a RatingTracker which can be serialized and restored, e.g. to share
+/// tracker states between o!TR tools without going through the database
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RatingTrackerSnapshot {
+    /// Every rating in leaderboard order
+    pub ratings: Vec<PlayerRating>,
+    /// Maps player IDs to their country codes
+    pub country_mapping: HashMap<i32, String>
+}
+
 impl Default for RatingTracker {
     fn default() -> Self {
         Self::new()
@@ -48,9 +71,19 @@ impl Default for RatingTracker {
 impl RatingTracker {
     /// Creates a new, empty RatingTracker
     pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates a new, empty RatingTracker with room for `capacity` ratings, e.g. the number of
+    /// players times the number of rulesets they are rated in
+    ///
+    /// Bulk inserts into a tracker without enough capacity rehash the leaderboard every time
+    /// it grows.
+    pub fn with_capacity(capacity: usize) -> Self {
         RatingTracker {
-            leaderboard: IndexMap::new(),
-            country_leaderboards: HashMap::new(),
+            leaderboard: Leaderboard::with_capacity_and_hasher(capacity, RandomState::new()),
+            country_leaderboards: AHashMap::new(),
+            unknown_country_players: Vec::new(),
             country_mapping: HashMap::new()
         }
     }
@@ -116,16 +149,76 @@ impl RatingTracker {
     }
 
     /// Gets a player's country code
+    ///
+    /// Returns None if the player is not in the country mapping or their country is empty
     pub fn get_country(&self, player_id: i32) -> Option<&String> {
-        self.country_mapping.get(&player_id)
+        self.country_mapping
+            .get(&player_id)
+            .filter(|country| !country.is_empty())
+    }
+
+    /// Returns the (player_id, ruleset) keys of all rated players without a known country
+    ///
+    /// Only accurate after `sort()` has been called
+    pub fn unknown_country_players(&self) -> &[(i32, Ruleset)] {
+        &self.unknown_country_players
     }
 
-    /// Retrieves a player's rating adjustment history for a specific ruleset
+    /// Returns the number of rated players of every (country, ruleset) pair with at least one
+    /// rated player, ordered by country and ruleset
+    ///
+    /// Only accurate after `sort()` has been called
+    pub fn country_sizes(&self) -> Vec<(String, Ruleset, usize)> {
+        self.country_leaderboards
+            .iter()
+            .map(|((country, ruleset), board)| (country.clone(), *ruleset, board.len()))
+            .sorted_by_key(|(country, ruleset, _)| (country.clone(), *ruleset as i32))
+            .collect()
+    }
+
+    /// Borrows a player's rating adjustment history for a specific ruleset
+    pub fn adjustments(&self, player_id: i32, ruleset: Ruleset) -> Option<&[RatingAdjustment]> {
+        self.get_rating(player_id, ruleset)
+            .map(|rating| rating.adjustments.as_slice())
+    }
+
+    /// Retrieves an owned copy of a player's rating adjustment history for a specific ruleset
+    ///
+    /// Clones the full history, prefer `adjustments` unless the history must outlive the tracker.
     pub fn get_rating_adjustments(&self, player_id: i32, ruleset: Ruleset) -> Option<Vec<RatingAdjustment>> {
         self.get_rating(player_id, ruleset)
             .map(|rating| rating.adjustments.clone())
     }
 
+    /// Takes a read-only snapshot of the ratings and country mapping, which can be shared
+    /// between threads
+    ///
+    /// Call `sort()` beforehand for the snapshot to contain accurate ranks and percentiles.
+    pub fn snapshot(&self) -> RatingSnapshot {
+        RatingSnapshot::new(self.leaderboard.clone(), self.country_mapping.clone())
+    }
+
+    /// Captures the ratings and country mapping of the tracker
+    #[cfg(feature = "serde")]
+    pub fn to_snapshot(&self) -> RatingTrackerSnapshot {
+        RatingTrackerSnapshot {
+            ratings: self.get_all_ratings(),
+            country_mapping: self.country_mapping.clone()
+        }
+    }
+
+    /// Restores a tracker from a snapshot
+    ///
+    /// Ratings keep the ranks and percentiles they were captured with. Country leaderboards
+    /// and unknown country players are not part of the snapshot, call `sort()` to rebuild them.
+    #[cfg(feature = "serde")]
+    pub fn from_snapshot(snapshot: RatingTrackerSnapshot) -> RatingTracker {
+        let mut tracker = RatingTracker::with_capacity(snapshot.ratings.len());
+        tracker.set_country_mapping(snapshot.country_mapping);
+        tracker.insert_or_update(&snapshot.ratings);
+        tracker
+    }
+
     /// Updates all rankings, percentiles, and sorts leaderboards
     ///
     /// This is the main ranking calculation function, which:
@@ -141,20 +234,18 @@ impl RatingTracker {
     ///    - Calculate percentiles
     ///
     /// 2. Country Rankings:
-    ///    - Group players by country
+    ///    - Classify every rated player by country (players without a known country
+    ///      are collected separately and receive a country rank of 0)
     ///    - Sort within each country/ruleset combination
     ///    - Assign country ranks
-    ///
-    /// 3. Final Update:
-    ///    - Ensure all leaderboards are consistent
-    ///    - Update all player records
     pub fn sort(&mut self) {
         let rulesets = [
             Ruleset::Osu,
             Ruleset::Taiko,
             Ruleset::Catch,
             Ruleset::ManiaOther,
-            Ruleset::Mania4k
+            Ruleset::Mania4k,
+            Ruleset::Mania7k
         ];
 
         // Process global rankings for each ruleset
@@ -164,91 +255,129 @@ impl RatingTracker {
         self.rebuild_country_leaderboards(&rulesets);
 
         // Process country rankings
-        self.update_country_rankings(&rulesets);
-
-        // Final consistency update
-        self.ensure_leaderboard_consistency(&rulesets);
+        self.update_country_rankings();
     }
 
     /// Updates global rankings and percentiles for all rulesets
+    ///
+    /// Ranking works on compact (leaderboard index, rating) pairs: the pairs of each ruleset
+    /// are sorted and the resulting ranks are written back by index, so the ratings themselves
+    /// are never moved or cloned.
     fn update_global_rankings(&mut self, rulesets: &[Ruleset]) {
-        for ruleset in rulesets {
-            let mut global_rank = 1;
+        let mut boards: AHashMap<Ruleset, Vec<(usize, f64)>> = AHashMap::new();
+        for (index, rating) in self.leaderboard.values().enumerate() {
+            if rulesets.contains(&rating.ruleset) {
+                boards.entry(rating.ruleset).or_default().push((index, rating.rating));
+            }
+        }
 
-            // Get and sort players for this ruleset
-            let ruleset_leaderboard: Vec<_> = self
-                .leaderboard
-                .iter_mut()
-                .filter(|(_, rating)| rating.ruleset == *ruleset)
-                .sorted_by(|(_, a), (_, b)| b.rating.partial_cmp(&a.rating).unwrap_or(std::cmp::Ordering::Equal))
-                .collect();
+        for mut board in boards.into_values() {
+            Self::sort_board(&mut board);
+            let total_players = board.len() as i32;
 
-            let total_players = ruleset_leaderboard.len() as i32;
+            for (global_rank, (index, _)) in (1..).zip(board) {
+                let (_, rating) = self
+                    .leaderboard
+                    .get_index_mut(index)
+                    .expect("Leaderboard index should be valid");
 
-            // Update rankings and percentiles
-            for (_, rating) in ruleset_leaderboard {
                 rating.global_rank = global_rank;
                 rating.percentile =
                     Self::calculate_percentile(global_rank, total_players).expect("Invalid rank/total combination");
-                global_rank += 1;
             }
         }
     }
 
     /// Rebuilds country leaderboards with current rating data
+    ///
+    /// Every rated player is classified: players with a known country are added to the
+    /// leaderboard of their country and ruleset, all others are collected as unknown and have
+    /// their country rank reset to 0.
     fn rebuild_country_leaderboards(&mut self, rulesets: &[Ruleset]) {
         // Clear existing country leaderboards
         self.country_leaderboards.clear();
+        self.unknown_country_players.clear();
 
-        // Rebuild country leaderboards from main leaderboard
-        for (player_id, country) in &self.country_mapping {
-            for ruleset in rulesets {
-                if let Some(rating) = self.leaderboard.get(&(*player_id, *ruleset)) {
-                    let country_board = self.country_leaderboards.entry(country.clone()).or_default();
-                    country_board.insert((*player_id, *ruleset), rating.clone());
+        for (index, (key, rating)) in self.leaderboard.iter_mut().enumerate() {
+            if !rulesets.contains(&rating.ruleset) {
+                continue;
+            }
+
+            match self.country_mapping.get(&rating.player_id).filter(|c| !c.is_empty()) {
+                Some(country) => {
+                    self.country_leaderboards
+                        .entry((country.clone(), rating.ruleset))
+                        .or_default()
+                        .push((index, rating.rating));
+                }
+                None => {
+                    rating.country_rank = 0;
+                    self.unknown_country_players.push(*key);
                 }
             }
         }
     }
 
-    /// Updates country rankings for all countries and rulesets
-    fn update_country_rankings(&mut self, rulesets: &[Ruleset]) {
-        for country_leaderboard in self.country_leaderboards.values() {
-            for ruleset in rulesets {
-                let mut country_rank = 1;
-
-                // Sort players within country by rating
-                let country_ruleset_board: Vec<_> = country_leaderboard
-                    .iter()
-                    .filter(|(_, rating)| rating.ruleset == *ruleset)
-                    .sorted_by(|(_, a), (_, b)| b.rating.partial_cmp(&a.rating).unwrap_or(std::cmp::Ordering::Equal))
-                    .collect();
-
-                // Update country ranks in main leaderboard
-                for (_, rating) in country_ruleset_board {
-                    if let Some(main_entry) = self.leaderboard.get_mut(&(rating.player_id, rating.ruleset)) {
-                        main_entry.country_rank = country_rank;
-                        country_rank += 1;
-                    }
-                }
+    /// Recomputes the country ranks of `country` only, leaving every other rank untouched
+    ///
+    /// Players who left the country keep their current country rank, recompute the country
+    /// they moved to as well.
+    ///
+    /// # Returns
+    /// The ratings of every player of the country, with their updated country rank
+    pub fn update_country_ranks(&mut self, country: &str) -> Vec<&PlayerRating> {
+        let mut boards: AHashMap<Ruleset, Vec<(usize, f64)>> = AHashMap::new();
+        for (index, rating) in self.leaderboard.values().enumerate() {
+            if self.get_country(rating.player_id).is_some_and(|c| c == country) {
+                boards.entry(rating.ruleset).or_default().push((index, rating.rating));
             }
         }
+
+        let mut indices = Vec::new();
+        for (ruleset, mut board) in boards {
+            Self::sort_board(&mut board);
+
+            for (country_rank, (index, _)) in (1..).zip(board.iter()) {
+                let (_, rating) = self
+                    .leaderboard
+                    .get_index_mut(*index)
+                    .expect("Leaderboard index should be valid");
+
+                rating.country_rank = country_rank;
+                indices.push(*index);
+            }
+            self.country_leaderboards.insert((country.to_string(), ruleset), board);
+        }
+
+        indices
+            .into_iter()
+            .map(|index| &self.leaderboard[index])
+            .collect()
     }
 
-    /// Ensures all leaderboards are consistent after updates
-    fn ensure_leaderboard_consistency(&mut self, rulesets: &[Ruleset]) {
-        for ruleset in rulesets {
-            let updates: Vec<PlayerRating> = self
-                .leaderboard
-                .values()
-                .filter(|rating| rating.ruleset == *ruleset)
-                .cloned()
-                .collect();
+    /// Sorts every country leaderboard and assigns country ranks
+    fn update_country_rankings(&mut self) {
+        for board in self.country_leaderboards.values_mut() {
+            Self::sort_board(board);
+
+            for (country_rank, (index, _)) in (1..).zip(board.iter()) {
+                let (_, rating) = self
+                    .leaderboard
+                    .get_index_mut(*index)
+                    .expect("Leaderboard index should be valid");
 
-            self.insert_or_update(&updates);
+                rating.country_rank = country_rank;
+            }
         }
     }
 
+    /// Sorts (leaderboard index, rating) pairs by descending rating.
+    ///
+    /// The sort is stable, so players with equal ratings keep their leaderboard order.
+    fn sort_board(board: &mut [(usize, f64)]) {
+        board.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    }
+
     /// Calculates percentile for a given rank and total player count
     ///
     /// # Formula
@@ -290,6 +419,7 @@ mod tests {
         utils::test_utils::{generate_country_mapping_player_ratings, generate_player_rating}
     };
     use approx::assert_abs_diff_eq;
+    use itertools::Itertools;
 
     #[test]
     fn test_sort() {
@@ -506,9 +636,10 @@ mod tests {
         }
 
         // Verify adjustment history
-        let adjustments = tracker.get_rating_adjustments(1, Ruleset::Osu).unwrap();
+        let adjustments = tracker.adjustments(1, Ruleset::Osu).unwrap();
         assert_eq!(adjustments.len(), 4);
         assert!(adjustments.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+        assert_eq!(tracker.get_rating_adjustments(1, Ruleset::Osu).unwrap(), adjustments);
     }
 
     #[test]
@@ -600,4 +731,148 @@ mod tests {
         assert_eq!(tracker.get_rating(1, Ruleset::Osu).unwrap().country_rank, 1);
         assert_eq!(tracker.get_rating(2, Ruleset::Osu).unwrap().country_rank, 2);
     }
+
+    #[test]
+    fn test_unknown_country_players_classified() {
+        let mut tracker = RatingTracker::new();
+
+        // Players 1 and 2 are known, player 3 has an empty country and
+        // player 4 is a fallback-rated player who was never in the players table
+        let mut country_mapping = HashMap::new();
+        country_mapping.insert(1, "US".to_string());
+        country_mapping.insert(2, "US".to_string());
+        country_mapping.insert(3, String::new());
+        tracker.set_country_mapping(country_mapping);
+
+        tracker.insert_or_update(&[
+            generate_player_rating(1, Ruleset::Osu, 1000.0, 100.0, 1, None, None),
+            generate_player_rating(2, Ruleset::Osu, 1100.0, 100.0, 1, None, None),
+            generate_player_rating(3, Ruleset::Osu, 1200.0, 100.0, 1, None, None),
+            generate_player_rating(4, Ruleset::Osu, FALLBACK_RATING, DEFAULT_VOLATILITY, 1, None, None)
+        ]);
+        tracker.sort();
+
+        assert_eq!(tracker.get_rating(2, Ruleset::Osu).unwrap().country_rank, 1);
+        assert_eq!(tracker.get_rating(1, Ruleset::Osu).unwrap().country_rank, 2);
+        assert_eq!(tracker.get_rating(3, Ruleset::Osu).unwrap().country_rank, 0);
+        assert_eq!(tracker.get_rating(4, Ruleset::Osu).unwrap().country_rank, 0);
+
+        // Unknown players are still ranked globally
+        assert_eq!(tracker.get_rating(3, Ruleset::Osu).unwrap().global_rank, 1);
+        assert!(tracker.get_rating(4, Ruleset::Osu).unwrap().global_rank > 0);
+
+        let mut unknown = tracker.unknown_country_players().to_vec();
+        unknown.sort_by_key(|(player_id, _)| *player_id);
+        assert_eq!(unknown, vec![(3, Ruleset::Osu), (4, Ruleset::Osu)]);
+
+        assert!(tracker.get_country(3).is_none());
+        assert!(tracker.get_country(4).is_none());
+    }
+
+    #[test]
+    fn test_country_sizes() {
+        let mut tracker = RatingTracker::new();
+
+        let mut country_mapping = HashMap::new();
+        country_mapping.insert(1, "US".to_string());
+        country_mapping.insert(2, "US".to_string());
+        country_mapping.insert(3, "NZ".to_string());
+        country_mapping.insert(4, String::new());
+        tracker.set_country_mapping(country_mapping);
+
+        tracker.insert_or_update(&[
+            generate_player_rating(1, Ruleset::Osu, 1000.0, 100.0, 1, None, None),
+            generate_player_rating(2, Ruleset::Osu, 1100.0, 100.0, 1, None, None),
+            generate_player_rating(2, Ruleset::Taiko, 1100.0, 100.0, 1, None, None),
+            generate_player_rating(3, Ruleset::Osu, 1200.0, 100.0, 1, None, None),
+            generate_player_rating(4, Ruleset::Osu, 1300.0, 100.0, 1, None, None)
+        ]);
+        tracker.sort();
+
+        // Players without a known country are not counted
+        assert_eq!(
+            tracker.country_sizes(),
+            vec![
+                ("NZ".to_string(), Ruleset::Osu, 1),
+                ("US".to_string(), Ruleset::Osu, 2),
+                ("US".to_string(), Ruleset::Taiko, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_country_rank_reset_after_mapping_change() {
+        let mut tracker = RatingTracker::new();
+
+        let mut country_mapping = HashMap::new();
+        country_mapping.insert(1, "US".to_string());
+        tracker.set_country_mapping(country_mapping);
+        tracker.insert_or_update(&[generate_player_rating(1, Ruleset::Osu, 1000.0, 100.0, 1, None, None)]);
+        tracker.sort();
+
+        assert_eq!(tracker.get_rating(1, Ruleset::Osu).unwrap().country_rank, 1);
+
+        // Removing the mapping must not leave a stale country rank behind
+        tracker.set_country_mapping(HashMap::new());
+        tracker.sort();
+
+        assert_eq!(tracker.get_rating(1, Ruleset::Osu).unwrap().country_rank, 0);
+        assert_eq!(tracker.unknown_country_players(), &[(1, Ruleset::Osu)]);
+    }
+
+    #[test]
+    fn test_update_country_ranks() {
+        let mut tracker = RatingTracker::new();
+        tracker.set_country_mapping(HashMap::from([
+            (1, "US".to_string()),
+            (2, "US".to_string()),
+            (3, "DE".to_string())
+        ]));
+        tracker.insert_or_update(&[
+            generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None),
+            generate_player_rating(2, Osu, 1200.0, 100.0, 1, None, None),
+            generate_player_rating(3, Osu, 1100.0, 100.0, 1, None, None)
+        ]);
+        tracker.sort();
+
+        // Player 3 turns out to be from the US
+        tracker.set_country_mapping(HashMap::from([
+            (1, "US".to_string()),
+            (2, "US".to_string()),
+            (3, "US".to_string())
+        ]));
+        let updated = tracker
+            .update_country_ranks("US")
+            .into_iter()
+            .map(|r| (r.player_id, r.country_rank))
+            .sorted()
+            .collect_vec();
+        assert_eq!(updated, vec![(1, 3), (2, 1), (3, 2)]);
+
+        // Global ranks are untouched
+        assert_eq!(tracker.get_rating(3, Osu).unwrap().global_rank, 2);
+        assert!(tracker.update_country_ranks("DE").is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut tracker = RatingTracker::new();
+        tracker.set_country_mapping(HashMap::from([(1, "US".to_string()), (2, String::new())]));
+        tracker.insert_or_update(&[
+            generate_player_rating(1, Osu, 1000.0, 100.0, 3, None, None),
+            generate_player_rating(2, Osu, 1100.0, 100.0, 2, None, None)
+        ]);
+        tracker.sort();
+
+        let json = serde_json::to_string(&tracker.to_snapshot()).unwrap();
+        let mut restored = RatingTracker::from_snapshot(serde_json::from_str(&json).unwrap());
+
+        assert_eq!(restored.get_all_ratings(), tracker.get_all_ratings());
+        assert_eq!(restored.get_country(1), Some(&"US".to_string()));
+
+        restored.sort();
+        assert_eq!(restored.unknown_country_players(), tracker.unknown_country_players());
+        assert_eq!(restored.country_sizes(), tracker.country_sizes());
+    }
 }
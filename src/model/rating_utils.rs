@@ -1,18 +1,35 @@
 use super::constants::FALLBACK_RATING;
 use crate::{
-    database::db_structs::{Match, Player, PlayerRating, RatingAdjustment},
+    database::db_structs::{Match, Player, PlayerRating, RankHistoryPoint, RatingAdjustment},
     model::{
         constants,
         constants::{DEFAULT_VOLATILITY, MULTIPLIER, OSU_INITIAL_RATING_CEILING},
-        structures::{rating_adjustment_type::RatingAdjustmentType, ruleset::Ruleset}
+        structures::{
+            fallback_strategy::FallbackStrategy, rating_adjustment_type::RatingAdjustmentType,
+            rating_clamps::RatingClamps, ruleset::Ruleset
+        }
     },
     utils::progress_utils::progress_bar
 };
 use chrono::{DateTime, Duration, FixedOffset};
 use constants::OSU_INITIAL_RATING_FLOOR;
-use std::{collections::HashMap, ops::Sub};
+use itertools::Itertools;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Sub
+};
 
-pub fn create_initial_ratings(players: &[Player], matches: &[Match]) -> Vec<PlayerRating> {
+/// Creates an initial rating for every player in each ruleset they have played in
+///
+/// Players without rank data for a ruleset receive the fallback rating chosen by `fallback`,
+/// which may be derived from `known_ratings` (e.g. seeded ratings) and the ratings estimated
+/// for the other players. Ratings are not validated here, see `bootstrap::bootstrap`
+pub fn create_initial_ratings(
+    players: &[Player],
+    matches: &[Match],
+    known_ratings: &[PlayerRating],
+    fallback: FallbackStrategy
+) -> Vec<PlayerRating> {
     // Identify which players have played in each ruleset
     let mut ruleset_activity: HashMap<Ruleset, HashMap<i32, DateTime<FixedOffset>>> = HashMap::new();
 
@@ -35,76 +52,172 @@ pub fn create_initial_ratings(players: &[Player], matches: &[Match]) -> Vec<Play
             }
         }
 
-        if let Some(bar) = &p_bar {
-            bar.inc(1);
-        }
+        p_bar.inc(1);
     }
 
-    if let Some(bar) = &p_bar {
-        bar.finish_with_message("Initial ratings created");
-    }
+    p_bar.finish_with_message("Initial ratings created");
 
-    let mut ratings = Vec::new();
+    // (player id, ruleset, first appearance, rating estimated from rank data)
+    let mut estimates = Vec::new();
     for player in players {
-        for ruleset in ruleset_activity.keys() {
-            if let Some(ruleset_entry) = ruleset_activity.get(ruleset) {
-                if ruleset_entry.get(&player.id).is_none() {
-                    // Player has not played in this ruleset
-                    continue;
-                }
+        for (ruleset, activity) in &ruleset_activity {
+            if let Some(timestamp) = activity.get(&player.id) {
+                estimates.push((
+                    player.id,
+                    *ruleset,
+                    *timestamp,
+                    initial_rating(player, ruleset, *timestamp)
+                ));
             }
+        }
+    }
 
-            let rating = initial_rating(player, ruleset);
-            if let Some(timestamp) = ruleset_activity.get(ruleset).unwrap().get(&player.id) {
-                let adjustment = RatingAdjustment {
-                    player_id: player.id,
-                    ruleset: *ruleset,
-                    match_id: None,
-                    rating_before: 0.0,
-                    rating_after: rating,
-                    volatility_before: 0.0,
-                    volatility_after: DEFAULT_VOLATILITY,
-                    timestamp: timestamp.sub(Duration::seconds(1)),
-                    adjustment_type: RatingAdjustmentType::Initial
+    let known = known_ratings.iter().map(|r| (r.ruleset, r.rating)).chain(
+        estimates
+            .iter()
+            .filter_map(|(_, ruleset, _, rating)| rating.map(|r| (*ruleset, r)))
+    );
+    let fallback_ratings = fallback_ratings(fallback, known);
+
+    estimates
+        .into_iter()
+        .map(|(player_id, ruleset, timestamp, estimate)| {
+            let rating = estimate.unwrap_or_else(|| fallback_ratings.get(&ruleset).copied().unwrap_or(FALLBACK_RATING));
+            let adjustment = RatingAdjustment {
+                player_id,
+                ruleset,
+                match_id: None,
+                rating_before: 0.0,
+                rating_after: rating,
+                volatility_before: 0.0,
+                volatility_after: DEFAULT_VOLATILITY,
+                timestamp: timestamp.sub(Duration::seconds(1)),
+                adjustment_type: RatingAdjustmentType::Initial,
+                average_opponent_rating: None,
+                provisional: false,
+                percentile: None,
+                clamps: RatingClamps::default()
+            };
+
+            PlayerRating {
+                id: 0, // database id, leave default
+                player_id,
+                ruleset,
+                rating,
+                volatility: DEFAULT_VOLATILITY,
+                // percentile, global_rank, and country_rank
+                // are managed by the rating_tracker
+                percentile: 0.0,
+                global_rank: 0,
+                country_rank: 0,
+                adjustments: vec![adjustment]
+            }
+        })
+        .collect()
+}
+
+/// Number of matches a rating was adjusted by
+pub fn matches_played(rating: &PlayerRating) -> usize {
+    rating
+        .adjustments
+        .iter()
+        .filter(|a| a.adjustment_type == RatingAdjustmentType::Match)
+        .count()
+}
+
+/// Computes the fallback rating of each ruleset with known ratings
+///
+/// Rulesets without known ratings are absent and use `FALLBACK_RATING`.
+fn fallback_ratings(strategy: FallbackStrategy, known: impl Iterator<Item = (Ruleset, f64)>) -> HashMap<Ruleset, f64> {
+    match strategy {
+        FallbackStrategy::Constant => HashMap::new(),
+        FallbackStrategy::Median => known
+            .into_group_map()
+            .into_iter()
+            .map(|(ruleset, mut ratings)| {
+                ratings.sort_by(|a, b| a.total_cmp(b));
+                let mid = ratings.len() / 2;
+                let median = if ratings.len() % 2 == 0 {
+                    (ratings[mid - 1] + ratings[mid]) / 2.0
+                } else {
+                    ratings[mid]
                 };
 
-                if rating.is_nan() || rating <= 0.0 {
-                    panic!("Initial rating is NaN or <= 0.0 for player: {:?}", player);
-                }
-
-                ratings.push(PlayerRating {
-                    id: 0, // database id, leave default
-                    player_id: player.id,
-                    ruleset: *ruleset,
-                    rating,
-                    volatility: DEFAULT_VOLATILITY,
-                    // percentile, global_rank, and country_rank
-                    // are managed by the rating_tracker
-                    percentile: 0.0,
-                    global_rank: 0,
-                    country_rank: 0,
-                    adjustments: vec![adjustment]
-                });
-            }
-        }
+                (ruleset, median)
+            })
+            .collect()
     }
+}
 
-    ratings
+/// Combines ratings reconstructed from stored history with freshly created initial ratings.
+///
+/// Seeded ratings take precedence: an initial rating is only kept for a (player, ruleset)
+/// pair which has no seeded rating, i.e. a player who is new to that ruleset.
+pub fn merge_seeded_ratings(seeded: Vec<PlayerRating>, initial: Vec<PlayerRating>) -> Vec<PlayerRating> {
+    let seeded_keys: HashSet<(i32, Ruleset)> = seeded.iter().map(|r| (r.player_id, r.ruleset)).collect();
+
+    seeded
+        .into_iter()
+        .chain(
+            initial
+                .into_iter()
+                .filter(|r| !seeded_keys.contains(&(r.player_id, r.ruleset)))
+        )
+        .collect()
 }
 
-fn initial_rating(player: &Player, ruleset: &Ruleset) -> f64 {
-    match &player.ruleset_data {
-        Some(data) => {
-            let ruleset_data = data.iter().find(|rd| rd.ruleset == *ruleset);
-            let rank = ruleset_data.and_then(|rd| rd.earliest_global_rank.or(Some(rd.global_rank)));
+/// Estimates a player's initial rating from their global rank
+///
+/// When rank history is available, the rank at `first_appearance` (the start of the player's
+/// first match in the ruleset) is used. Otherwise the earliest known rank is used, falling
+/// back to the current rank.
+///
+/// # Returns
+/// None if the player has no rank data for the ruleset
+fn initial_rating(player: &Player, ruleset: &Ruleset, first_appearance: DateTime<FixedOffset>) -> Option<f64> {
+    let ruleset_data = player.ruleset_data.as_ref()?.iter().find(|rd| rd.ruleset == *ruleset)?;
+    let rank = rank_at(&ruleset_data.rank_history, first_appearance)
+        .or(ruleset_data.earliest_global_rank)
+        .unwrap_or(ruleset_data.global_rank);
+
+    Some(mu_from_rank(rank, *ruleset))
+}
 
-            match rank {
-                Some(r) => mu_from_rank(r, *ruleset),
-                None => FALLBACK_RATING
-            }
-        }
-        None => FALLBACK_RATING
+/// Interpolates a player's global rank at `timestamp` from their rank history
+///
+/// Ranks are interpolated geometrically between the surrounding history points, since rank
+/// changes are roughly proportional to the rank itself. Timestamps outside the recorded
+/// history use the nearest point.
+///
+/// # Returns
+/// None if the history is empty
+fn rank_at(history: &[RankHistoryPoint], timestamp: DateTime<FixedOffset>) -> Option<i32> {
+    let first = history.first()?;
+    let last = history.last()?;
+
+    if timestamp <= first.timestamp {
+        return Some(first.global_rank);
+    }
+    if timestamp >= last.timestamp {
+        return Some(last.global_rank);
     }
+
+    let (before, after) = history
+        .windows(2)
+        .map(|w| (w[0], w[1]))
+        .find(|(_, after)| after.timestamp >= timestamp)?;
+
+    let span = (after.timestamp - before.timestamp).num_seconds() as f64;
+    let progress = if span > 0.0 {
+        (timestamp - before.timestamp).num_seconds() as f64 / span
+    } else {
+        1.0
+    };
+
+    let log_rank =
+        (before.global_rank.max(1) as f64).ln() * (1.0 - progress) + (after.global_rank.max(1) as f64).ln() * progress;
+
+    Some(log_rank.exp().round() as i32)
 }
 
 fn mu_from_rank(rank: i32, ruleset: Ruleset) -> f64 {
@@ -149,14 +262,23 @@ fn std_dev_from_ruleset(ruleset: Ruleset) -> f64 {
 #[cfg(test)]
 mod tests {
     use crate::{
-        database::db_structs::Player,
+        database::db_structs::{Player, RankHistoryPoint},
         model::{
-            constants::{OSU_INITIAL_RATING_CEILING, OSU_INITIAL_RATING_FLOOR},
-            rating_utils::{mu_from_rank, std_dev_from_ruleset},
-            structures::ruleset::Ruleset::{Catch, Mania4k, ManiaOther, Osu, Taiko}
+            constants::{FALLBACK_RATING, OSU_INITIAL_RATING_CEILING, OSU_INITIAL_RATING_FLOOR},
+            rating_utils::{
+                create_initial_ratings, initial_rating, merge_seeded_ratings, mu_from_rank, rank_at,
+                std_dev_from_ruleset
+            },
+            structures::{
+                fallback_strategy::FallbackStrategy,
+                ruleset::Ruleset::{Catch, Mania4k, ManiaOther, Osu, Taiko}
+            }
         },
-        utils::test_utils::generate_ruleset_data
+        utils::test_utils::{
+            generate_game, generate_match, generate_placement, generate_player_rating, generate_ruleset_data
+        }
     };
+    use chrono::{DateTime, FixedOffset, TimeZone, Utc};
 
     #[test]
     fn test_ruleset_stddev_osu() {
@@ -244,17 +366,17 @@ mod tests {
             ])
         };
 
-        let expected_osu = mu_from_rank(1, Osu);
-        let expected_taiko = mu_from_rank(1, Taiko);
-        let expected_catch = mu_from_rank(1, Catch);
-        let expected_mania4k = mu_from_rank(1, ManiaOther);
-        let expected_mania7k = mu_from_rank(1, Mania4k);
+        let expected_osu = Some(mu_from_rank(1, Osu));
+        let expected_taiko = Some(mu_from_rank(1, Taiko));
+        let expected_catch = Some(mu_from_rank(1, Catch));
+        let expected_mania4k = Some(mu_from_rank(1, ManiaOther));
+        let expected_mania7k = Some(mu_from_rank(1, Mania4k));
 
-        let actual_osu = super::initial_rating(&player, &Osu);
-        let actual_taiko = super::initial_rating(&player, &Taiko);
-        let actual_catch = super::initial_rating(&player, &Catch);
-        let actual_mania_4k = super::initial_rating(&player, &ManiaOther);
-        let actual_mania_7k = super::initial_rating(&player, &Mania4k);
+        let actual_osu = super::initial_rating(&player, &Osu, day(1));
+        let actual_taiko = super::initial_rating(&player, &Taiko, day(1));
+        let actual_catch = super::initial_rating(&player, &Catch, day(1));
+        let actual_mania_4k = super::initial_rating(&player, &ManiaOther, day(1));
+        let actual_mania_7k = super::initial_rating(&player, &Mania4k, day(1));
 
         assert_eq!(expected_osu, actual_osu);
         assert_eq!(expected_taiko, actual_taiko);
@@ -262,4 +384,123 @@ mod tests {
         assert_eq!(expected_mania4k, actual_mania_4k);
         assert_eq!(expected_mania7k, actual_mania_7k);
     }
+
+    #[test]
+    fn test_merge_seeded_ratings_prefers_seeded() {
+        let seeded = vec![generate_player_rating(1, Osu, 1500.0, 100.0, 3, None, None)];
+        let initial = vec![
+            generate_player_rating(1, Osu, 900.0, 200.0, 1, None, None),
+            generate_player_rating(1, Taiko, 900.0, 200.0, 1, None, None),
+            generate_player_rating(2, Osu, 900.0, 200.0, 1, None, None),
+        ];
+
+        let merged = merge_seeded_ratings(seeded, initial);
+
+        assert_eq!(merged.len(), 3);
+
+        let osu_1 = merged.iter().find(|r| r.player_id == 1 && r.ruleset == Osu).unwrap();
+        assert_eq!(osu_1.rating, 1500.0);
+        assert_eq!(osu_1.adjustments.len(), 3);
+
+        assert!(merged.iter().any(|r| r.player_id == 1 && r.ruleset == Taiko));
+        assert!(merged.iter().any(|r| r.player_id == 2 && r.ruleset == Osu));
+    }
+
+    fn day(d: u32) -> DateTime<FixedOffset> {
+        Utc.with_ymd_and_hms(2024, 1, d, 0, 0, 0).unwrap().fixed_offset()
+    }
+
+    fn history() -> Vec<RankHistoryPoint> {
+        vec![
+            RankHistoryPoint {
+                timestamp: day(1),
+                global_rank: 10_000
+            },
+            RankHistoryPoint {
+                timestamp: day(11),
+                global_rank: 100
+            },
+        ]
+    }
+
+    #[test]
+    fn test_rank_at_interpolates_geometrically() {
+        let history = history();
+
+        assert_eq!(rank_at(&[], day(5)), None);
+        assert_eq!(rank_at(&history, day(1)), Some(10_000));
+        assert_eq!(rank_at(&history, day(6)), Some(1_000));
+        assert_eq!(rank_at(&history, day(11)), Some(100));
+
+        // Outside the recorded history the nearest point is used
+        assert_eq!(rank_at(&history[..1], day(20)), Some(10_000));
+        assert_eq!(rank_at(&history, day(20)), Some(100));
+    }
+
+    #[test]
+    fn test_initial_rating_uses_rank_at_first_appearance() {
+        let mut data = generate_ruleset_data(Osu, 100, Some(10_000));
+        let player = |data| Player {
+            id: 1,
+            username: None,
+            country: None,
+            ruleset_data: Some(vec![data])
+        };
+
+        // Without history, the earliest rank is used
+        assert_eq!(
+            initial_rating(&player(data.clone()), &Osu, day(6)),
+            Some(mu_from_rank(10_000, Osu))
+        );
+
+        data.rank_history = history();
+        assert_eq!(
+            initial_rating(&player(data), &Osu, day(6)),
+            Some(mu_from_rank(1_000, Osu))
+        );
+    }
+
+    #[test]
+    fn test_median_fallback_per_ruleset() {
+        let ranked = |id, rank| Player {
+            id,
+            username: None,
+            country: None,
+            ruleset_data: Some(vec![generate_ruleset_data(Catch, rank, None)])
+        };
+        let unranked = Player {
+            id: 4,
+            username: None,
+            country: None,
+            ruleset_data: None
+        };
+        let players = vec![ranked(1, 10), ranked(2, 1_000), ranked(3, 100_000), unranked];
+
+        let placements = (1..=4).map(|id| generate_placement(id, id)).collect::<Vec<_>>();
+        let mut game = generate_game(1, &placements);
+        game.ruleset = Catch;
+        let matches = vec![generate_match(1, Catch, &[game], day(1))];
+        let fallback_of = |strategy| {
+            create_initial_ratings(&players, &matches, &[], strategy)
+                .into_iter()
+                .find(|r| r.player_id == 4)
+                .unwrap()
+                .rating
+        };
+
+        assert_eq!(fallback_of(FallbackStrategy::Constant), FALLBACK_RATING);
+        assert_eq!(fallback_of(FallbackStrategy::Median), mu_from_rank(1_000, Catch));
+
+        // Known ratings, e.g. seeded ones, are part of the distribution
+        let known = vec![generate_player_rating(5, Catch, 0.0, 100.0, 2, None, None)];
+        let median = create_initial_ratings(&players, &matches, &known, FallbackStrategy::Median)
+            .into_iter()
+            .find(|r| r.player_id == 4)
+            .unwrap()
+            .rating;
+        assert_eq!(
+            median,
+            (mu_from_rank(100_000, Catch) + mu_from_rank(1_000, Catch)) / 2.0
+        );
+    }
 }
@@ -0,0 +1,155 @@
+use crate::database::db_structs::PlayerRestriction;
+use chrono::{DateTime, Duration, FixedOffset};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// A restricted period as `(start, end)`, without an end while it is ongoing
+type Period = (DateTime<FixedOffset>, Option<DateTime<FixedOffset>>);
+
+/// The periods during which each player was restricted from playing, e.g. by a ban
+///
+/// Restricted players could not have played, so time spent restricted does not count towards
+/// their inactivity, see `DecaySystem::with_restrictions`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Restrictions {
+    /// Non-overlapping periods of every player in chronological order
+    periods: HashMap<i32, Vec<Period>>
+}
+
+impl Restrictions {
+    /// Indexes the restrictions by player, merging overlapping periods
+    pub fn new(restrictions: &[PlayerRestriction]) -> Restrictions {
+        let periods = restrictions
+            .iter()
+            .into_group_map_by(|r| r.player_id)
+            .into_iter()
+            .map(|(player_id, player_restrictions)| {
+                let mut merged: Vec<Period> = Vec::new();
+                for r in player_restrictions.into_iter().sorted_by_key(|r| r.starts_at) {
+                    match merged.last_mut() {
+                        Some((_, end)) if (*end).is_none_or(|end| r.starts_at <= end) => {
+                            *end = end.zip(r.ends_at).map(|(a, b)| a.max(b));
+                        }
+                        _ => merged.push((r.starts_at, r.ends_at))
+                    }
+                }
+
+                (player_id, merged)
+            })
+            .collect();
+
+        Restrictions { periods }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.periods.is_empty()
+    }
+
+    /// The time between `from` and `to` during which the player was not restricted
+    pub fn unrestricted_time(
+        &self,
+        player_id: i32,
+        from: DateTime<FixedOffset>,
+        to: DateTime<FixedOffset>
+    ) -> Duration {
+        let restricted = self
+            .periods(player_id)
+            .iter()
+            .map(|&(start, end)| {
+                let start = start.max(from);
+                let end = end.map_or(to, |end| end.min(to));
+                (end - start).max(Duration::zero())
+            })
+            .sum::<Duration>();
+
+        (to - from) - restricted
+    }
+
+    /// The earliest time after which the player spent `duration` unrestricted since `from`
+    ///
+    /// # Returns
+    /// None if the player is restricted indefinitely before that
+    pub fn after_unrestricted(
+        &self,
+        player_id: i32,
+        from: DateTime<FixedOffset>,
+        duration: Duration
+    ) -> Option<DateTime<FixedOffset>> {
+        let mut time = from;
+        let mut remaining = duration;
+
+        for &(start, end) in self.periods(player_id) {
+            if end.is_some_and(|end| end <= time) {
+                continue;
+            }
+            if start > time {
+                if remaining <= start - time {
+                    break;
+                }
+                remaining -= start - time;
+            }
+
+            time = end?.max(time);
+        }
+
+        Some(time + remaining)
+    }
+
+    fn periods(&self, player_id: i32) -> &[Period] {
+        self.periods.get(&player_id).map_or(&[], |periods| periods.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Restrictions;
+    use crate::database::db_structs::PlayerRestriction;
+    use chrono::{DateTime, Duration, FixedOffset, TimeZone, Utc};
+
+    fn day(day: u32) -> DateTime<FixedOffset> {
+        Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap().fixed_offset()
+    }
+
+    fn restriction(starts_at: u32, ends_at: Option<u32>) -> PlayerRestriction {
+        PlayerRestriction {
+            player_id: 1,
+            starts_at: day(starts_at),
+            ends_at: ends_at.map(day)
+        }
+    }
+
+    #[test]
+    fn test_unrestricted_time() {
+        let restrictions = Restrictions::new(&[restriction(5, Some(10)), restriction(8, Some(12))]);
+
+        // Overlapping periods are only counted once
+        assert_eq!(restrictions.unrestricted_time(1, day(1), day(21)), Duration::days(13));
+        assert_eq!(restrictions.unrestricted_time(1, day(6), day(9)), Duration::zero());
+        assert_eq!(restrictions.unrestricted_time(2, day(1), day(21)), Duration::days(20));
+    }
+
+    #[test]
+    fn test_after_unrestricted() {
+        let restrictions = Restrictions::new(&[restriction(5, Some(10)), restriction(20, None)]);
+
+        assert_eq!(
+            restrictions.after_unrestricted(1, day(1), Duration::days(3)),
+            Some(day(4))
+        );
+        assert_eq!(
+            restrictions.after_unrestricted(1, day(1), Duration::days(4)),
+            Some(day(5))
+        );
+        assert_eq!(
+            restrictions.after_unrestricted(1, day(1), Duration::days(6)),
+            Some(day(12))
+        );
+        assert_eq!(
+            restrictions.after_unrestricted(1, day(7), Duration::days(1)),
+            Some(day(11))
+        );
+
+        // The second restriction never ends
+        assert_eq!(restrictions.after_unrestricted(1, day(1), Duration::days(20)), None);
+    }
+}
@@ -0,0 +1,63 @@
+use super::{beatmaps::Mods, structures::ruleset::Ruleset};
+use crate::database::db_structs::GameScore;
+
+/// Rules which differ between rulesets
+///
+/// Every place in the model which treats a ruleset differently consults the policy of the
+/// ruleset instead of matching on it, so exceptions live in one place. The defaults apply to
+/// osu! and taiko.
+pub trait RulesetPolicy: Send + Sync {
+    /// Mods a player may add without changing the mod pool of a game, see
+    /// `mod_detection::classify_games`
+    fn optional_mods(&self) -> Mods {
+        Mods::default()
+    }
+
+    /// Whether Hard Rock and Easy scale the circle size of a beatmap, see `Beatmap::with_mods`
+    fn scales_circle_size(&self) -> bool {
+        true
+    }
+
+    /// Whether a score means the player did not finish the game, see
+    /// `placements::apply_dnf_policy`
+    fn is_dnf(&self, score: &GameScore) -> bool {
+        score.score == 0
+    }
+}
+
+/// Rules of osu! and taiko
+pub struct DefaultPolicy;
+
+impl RulesetPolicy for DefaultPolicy {}
+
+/// Rules of osu!catch
+///
+/// Hidden is commonly allowed as a free pick on the other mod pools of catch tournaments, so
+/// players who add it to a game don't turn it into free mod.
+pub struct CatchPolicy;
+
+impl RulesetPolicy for CatchPolicy {
+    fn optional_mods(&self) -> Mods {
+        Mods(Mods::HIDDEN)
+    }
+}
+
+/// Rules of every mania ruleset
+///
+/// The circle size of a mania beatmap is its key count, which no mod changes.
+pub struct ManiaPolicy;
+
+impl RulesetPolicy for ManiaPolicy {
+    fn scales_circle_size(&self) -> bool {
+        false
+    }
+}
+
+/// The rules of `ruleset`
+pub fn ruleset_policy(ruleset: Ruleset) -> &'static dyn RulesetPolicy {
+    match ruleset {
+        Ruleset::Osu | Ruleset::Taiko => &DefaultPolicy,
+        Ruleset::Catch => &CatchPolicy,
+        Ruleset::ManiaOther | Ruleset::Mania4k | Ruleset::Mania7k => &ManiaPolicy
+    }
+}
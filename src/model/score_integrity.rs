@@ -0,0 +1,274 @@
+use super::{exclusions::rerank_placements, structures::integrity_action::IntegrityAction};
+use crate::database::db_structs::{Game, GameScore, Match};
+use itertools::Itertools;
+use serde::Serialize;
+use std::{collections::HashSet, fmt};
+use thiserror::Error;
+
+/// Default largest score considered possible. Scores above it are corrupted or overflowed.
+pub const DEFAULT_MAX_SCORE: i32 = 1_000_000_000;
+
+/// How each kind of integrity issue is handled, see `check_score_integrity`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntegrityPolicy {
+    /// Largest score considered possible
+    pub max_score: i32,
+    /// Applied to negative scores and scores above `max_score`
+    pub invalid_score: IntegrityAction,
+    /// Applied to every score of a player after their first one in the same game
+    pub duplicate_player: IntegrityAction,
+    /// Applied to games left with a single score. Dropping the score leaves the game without
+    /// scores, so it is skipped.
+    pub single_participant: IntegrityAction
+}
+
+impl Default for IntegrityPolicy {
+    fn default() -> Self {
+        IntegrityPolicy {
+            max_score: DEFAULT_MAX_SCORE,
+            invalid_score: IntegrityAction::DropScore,
+            duplicate_player: IntegrityAction::DropScore,
+            single_participant: IntegrityAction::DropGame
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IntegrityIssueKind {
+    InvalidScore,
+    DuplicatePlayer,
+    SingleParticipant
+}
+
+/// A score which failed an integrity check
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityIssue {
+    pub kind: IntegrityIssueKind,
+    pub match_id: i32,
+    pub game_id: i32,
+    pub score_id: i32,
+    pub player_id: i32
+}
+
+impl fmt::Display for IntegrityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let issue = match self.kind {
+            IntegrityIssueKind::InvalidScore => "has an impossible value",
+            IntegrityIssueKind::DuplicatePlayer => "duplicates an earlier score of its player",
+            IntegrityIssueKind::SingleParticipant => "is the only score of its game"
+        };
+        write!(
+            f,
+            "score {} of player {} in game {} (match {}) {}",
+            self.score_id, self.player_id, self.game_id, self.match_id, issue
+        )
+    }
+}
+
+/// Integrity issues whose policy is to fail the run
+#[derive(Error, Debug, PartialEq)]
+#[error("{} scores failed integrity checks: {}", .issues.len(), .issues.iter().join(", "))]
+pub struct IntegrityError {
+    pub issues: Vec<IntegrityIssue>
+}
+
+/// Checks the scores of every game before rating, handling each issue as set by `policy`
+///
+/// Scores with impossible values are checked first, then players entered more than once in
+/// a game, whose first score is kept, and finally games left with a single score. Placements
+/// of games which lost scores are re-ranked, see `exclude_players`. Games which lost all their
+/// scores are kept and skipped during processing.
+///
+/// # Returns
+/// Every issue found, or the issues whose action is `Fail` if there are any
+pub fn check_score_integrity(
+    matches: &mut [Match],
+    policy: &IntegrityPolicy
+) -> Result<Vec<IntegrityIssue>, IntegrityError> {
+    let mut issues = Vec::new();
+    let mut failures = Vec::new();
+
+    for match_ in matches.iter_mut() {
+        let match_id = match_.id;
+        match_.games.retain_mut(|game| {
+            let game_id = game.id;
+            let mut raise = |kind: IntegrityIssueKind, score: &GameScore, action: IntegrityAction| {
+                let issue = IntegrityIssue {
+                    kind,
+                    match_id,
+                    game_id,
+                    score_id: score.id,
+                    player_id: score.player_id
+                };
+                if action == IntegrityAction::Fail {
+                    failures.push(issue.clone());
+                }
+                issues.push(issue);
+                action
+            };
+
+            check_game(game, policy, &mut raise)
+        });
+    }
+
+    match failures.is_empty() {
+        true => Ok(issues),
+        false => Err(IntegrityError { issues: failures })
+    }
+}
+
+/// Checks the scores of a single game, raising every issue found
+///
+/// # Returns
+/// Whether the game is kept
+fn check_game(
+    game: &mut Game,
+    policy: &IntegrityPolicy,
+    raise: &mut impl FnMut(IntegrityIssueKind, &GameScore, IntegrityAction) -> IntegrityAction
+) -> bool {
+    let before = game.scores.len();
+    let mut keep = vec![true; before];
+    let mut drop_game = false;
+    let mut apply = |action: IntegrityAction, keep: &mut bool| match action {
+        IntegrityAction::DropScore => *keep = false,
+        IntegrityAction::DropGame => drop_game = true,
+        IntegrityAction::Fail => {}
+    };
+
+    for (i, score) in game.scores.iter().enumerate() {
+        if score.score < 0 || score.score > policy.max_score {
+            let action = raise(IntegrityIssueKind::InvalidScore, score, policy.invalid_score);
+            apply(action, &mut keep[i]);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for (i, score) in game.scores.iter().enumerate() {
+        if keep[i] && !seen.insert(score.player_id) {
+            let action = raise(IntegrityIssueKind::DuplicatePlayer, score, policy.duplicate_player);
+            apply(action, &mut keep[i]);
+        }
+    }
+
+    if drop_game {
+        return false;
+    }
+
+    let mut kept = keep.into_iter();
+    game.scores.retain(|_| kept.next().unwrap());
+
+    if game.scores.len() == 1 {
+        match raise(
+            IntegrityIssueKind::SingleParticipant,
+            &game.scores[0],
+            policy.single_participant
+        ) {
+            IntegrityAction::DropScore => game.scores.clear(),
+            IntegrityAction::DropGame => return false,
+            IntegrityAction::Fail => {}
+        }
+    }
+
+    if game.scores.len() != before {
+        rerank_placements(game);
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_score_integrity, IntegrityIssueKind, IntegrityPolicy};
+    use crate::{
+        database::db_structs::{Game, Match},
+        model::structures::{integrity_action::IntegrityAction, ruleset::Ruleset::Osu},
+        utils::test_utils::{generate_game, generate_match, generate_placement}
+    };
+    use chrono::Utc;
+
+    /// A match with a single game of the given `(player_id, score)` entries, placed by score
+    fn single_game_match(scores: &[(i32, i32)]) -> Vec<Match> {
+        let placements = scores
+            .iter()
+            .map(|&(player_id, score)| {
+                generate_placement(
+                    player_id,
+                    1 + scores.iter().filter(|&&(_, other)| other > score).count() as i32
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut game = generate_game(1, &placements);
+        for (i, (score, &(_, value))) in game.scores.iter_mut().zip(scores).enumerate() {
+            score.id = i as i32 + 1;
+            score.score = value;
+        }
+
+        vec![generate_match(1, Osu, &[game], Utc::now().fixed_offset())]
+    }
+
+    fn scores(game: &Game) -> Vec<(i32, i32)> {
+        game.scores.iter().map(|s| (s.player_id, s.placement)).collect()
+    }
+
+    #[test]
+    fn test_invalid_and_duplicate_scores_are_dropped() {
+        let mut matches = single_game_match(&[(1, 500_000), (2, -5), (3, 400_000), (1, 500_000), (4, i32::MAX)]);
+
+        let issues = check_score_integrity(&mut matches, &IntegrityPolicy::default()).unwrap();
+
+        let kinds = issues.iter().map(|i| (i.kind, i.score_id)).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (IntegrityIssueKind::InvalidScore, 2),
+                (IntegrityIssueKind::InvalidScore, 5),
+                (IntegrityIssueKind::DuplicatePlayer, 4),
+            ]
+        );
+        assert_eq!(scores(&matches[0].games[0]), vec![(1, 1), (3, 2)]);
+    }
+
+    #[test]
+    fn test_single_participant_games_are_dropped() {
+        let mut matches = single_game_match(&[(1, 500_000), (1, 400_000)]);
+
+        let issues = check_score_integrity(&mut matches, &IntegrityPolicy::default()).unwrap();
+
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[1].kind, IntegrityIssueKind::SingleParticipant);
+        assert!(matches[0].games.is_empty());
+    }
+
+    #[test]
+    fn test_drop_game_and_fail_actions() {
+        let policy = IntegrityPolicy {
+            duplicate_player: IntegrityAction::DropGame,
+            ..IntegrityPolicy::default()
+        };
+        let mut matches = single_game_match(&[(1, 500_000), (2, 400_000), (2, 400_000)]);
+        check_score_integrity(&mut matches, &policy).unwrap();
+        assert!(matches[0].games.is_empty());
+
+        let policy = IntegrityPolicy {
+            invalid_score: IntegrityAction::Fail,
+            ..IntegrityPolicy::default()
+        };
+        let mut matches = single_game_match(&[(1, 500_000), (2, -1), (3, 1)]);
+        let error = check_score_integrity(&mut matches, &policy).unwrap_err();
+        assert_eq!(error.issues.len(), 1);
+        assert_eq!(error.issues[0].player_id, 2);
+    }
+
+    #[test]
+    fn test_valid_games_are_untouched() {
+        let mut matches = single_game_match(&[(1, 500_000), (2, 400_000), (3, 400_000)]);
+        let before = scores(&matches[0].games[0]);
+
+        assert!(check_score_integrity(&mut matches, &IntegrityPolicy::default())
+            .unwrap()
+            .is_empty());
+        assert_eq!(scores(&matches[0].games[0]), before);
+    }
+}
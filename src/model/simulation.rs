@@ -0,0 +1,95 @@
+use crate::{
+    database::db_structs::{Game, GameScore, Match, PlayerPlacement},
+    model::{otr_model::ModelError, structures::ruleset::Ruleset}
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+use thiserror::Error;
+
+/// A hypothetical match to simulate, e.g. a planned lineup
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lineup {
+    pub ruleset: Ruleset,
+    /// The placements of every game, in the order the games are played
+    pub games: Vec<Vec<PlayerPlacement>>
+}
+
+impl Lineup {
+    /// Reads a lineup from a JSON file
+    pub fn read(path: &Path) -> io::Result<Lineup> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(io::Error::from)
+    }
+
+    /// Builds the match the model would process for this lineup. Ids and times are placeholders.
+    pub(crate) fn to_match(&self) -> Match {
+        let games = (1..)
+            .zip(&self.games)
+            .map(|(id, placements)| Game {
+                id,
+                ruleset: self.ruleset,
+                start_time: Default::default(),
+                end_time: Default::default(),
+                scores: placements
+                    .iter()
+                    .map(|p| GameScore {
+                        id: 0,
+                        player_id: p.player_id,
+                        game_id: id,
+                        score: 0,
+                        placement: p.placement,
+                        team: 0
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Match {
+            id: 0,
+            tournament_id: 0,
+            name: "Simulated match".to_string(),
+            start_time: Default::default(),
+            end_time: Default::default(),
+            ruleset: self.ruleset,
+            rating_exempt: false,
+            games
+        }
+    }
+}
+
+/// The rating change a player would receive from a simulated match
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectedChange {
+    pub player_id: i32,
+    pub rating_before: f64,
+    pub rating_after: f64,
+    pub volatility_before: f64,
+    pub volatility_after: f64
+}
+
+impl ProjectedChange {
+    pub fn rating_delta(&self) -> f64 {
+        self.rating_after - self.rating_before
+    }
+}
+
+/// Reasons a lineup cannot be simulated
+#[derive(Error, Debug, PartialEq)]
+pub enum SimulationError {
+    #[error("Lineup has no games, or a game without placements")]
+    EmptyLineup,
+    #[error("Player {player_id} has no rating in ruleset {ruleset:?}")]
+    UnratedPlayer { player_id: i32, ruleset: Ruleset }
+}
+
+impl From<ModelError> for SimulationError {
+    fn from(error: ModelError) -> Self {
+        match error {
+            ModelError::UnratedPlayer { player_id, ruleset, .. } => {
+                SimulationError::UnratedPlayer { player_id, ruleset }
+            }
+        }
+    }
+}
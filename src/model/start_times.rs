@@ -0,0 +1,154 @@
+use crate::database::db_structs::Match;
+use std::{collections::HashSet, fmt, str::FromStr};
+
+/// What to do with matches whose start time is missing from the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingStartTimePolicy {
+    /// Leave the match unprocessed
+    #[default]
+    Skip,
+    /// Use the earliest start time of the match's games
+    Impute
+}
+
+/// Outcome of resolving missing match start times
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StartTimeResolution {
+    /// Ids of matches left unprocessed, in ascending order
+    pub skipped: Vec<i32>,
+    /// Ids of matches whose start time was imputed, in ascending order
+    pub imputed: Vec<i32>
+}
+
+/// Applies `policy` to every match in `missing`, the ids of matches fetched without a start time.
+///
+/// Imputed matches start at the earliest start time of their games. If their end time is
+/// missing as well (or precedes the imputed start), it is set to the latest game end time.
+/// Matches without any games cannot be imputed and are always skipped.
+pub fn resolve_missing_start_times(
+    matches: &mut Vec<Match>,
+    missing: &HashSet<i32>,
+    policy: MissingStartTimePolicy
+) -> StartTimeResolution {
+    let mut resolution = StartTimeResolution::default();
+
+    matches.retain_mut(|match_| {
+        if !missing.contains(&match_.id) {
+            return true;
+        }
+
+        let start = match_.games.iter().map(|g| g.start_time).min();
+        let end = match_.games.iter().map(|g| g.end_time).max();
+
+        match (policy, start, end) {
+            (MissingStartTimePolicy::Impute, Some(start), Some(end)) => {
+                match_.start_time = start;
+                if match_.end_time < start {
+                    match_.end_time = end.max(start);
+                }
+
+                resolution.imputed.push(match_.id);
+                true
+            }
+            _ => {
+                resolution.skipped.push(match_.id);
+                false
+            }
+        }
+    });
+
+    resolution.skipped.sort();
+    resolution.imputed.sort();
+    resolution
+}
+
+impl FromStr for MissingStartTimePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(MissingStartTimePolicy::Skip),
+            "impute" => Ok(MissingStartTimePolicy::Impute),
+            _ => Err(format!("'{}' is not a start time policy (expected skip or impute)", s))
+        }
+    }
+}
+
+impl fmt::Display for MissingStartTimePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MissingStartTimePolicy::Skip => write!(f, "skip"),
+            MissingStartTimePolicy::Impute => write!(f, "impute")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve_missing_start_times, MissingStartTimePolicy};
+    use crate::{
+        database::db_structs::Match,
+        model::structures::ruleset::Ruleset::Osu,
+        utils::test_utils::{generate_game, generate_match, generate_placement}
+    };
+    use chrono::{DateTime, Duration, FixedOffset, TimeZone, Utc};
+    use std::collections::HashSet;
+
+    fn time(hour: u32) -> DateTime<FixedOffset> {
+        Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).unwrap().fixed_offset()
+    }
+
+    /// A match as fetched without start or end time, with games starting at the given hours
+    fn match_without_times(id: i32, game_hours: &[u32]) -> Match {
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 2)];
+        let games = game_hours
+            .iter()
+            .enumerate()
+            .map(|(i, &hour)| {
+                let mut game = generate_game(i as i32, &placements);
+                game.start_time = time(hour);
+                game.end_time = time(hour) + Duration::minutes(5);
+                game
+            })
+            .collect::<Vec<_>>();
+
+        let mut match_ = generate_match(id, Osu, &games, DateTime::default());
+        match_.end_time = DateTime::default();
+        match_
+    }
+
+    #[test]
+    fn test_skip_policy() {
+        let mut matches = vec![generate_match(1, Osu, &[], time(1)), match_without_times(2, &[3, 2])];
+
+        let resolution = resolve_missing_start_times(&mut matches, &HashSet::from([2]), MissingStartTimePolicy::Skip);
+
+        assert_eq!(resolution.skipped, vec![2]);
+        assert!(resolution.imputed.is_empty());
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].id, 1);
+    }
+
+    #[test]
+    fn test_impute_policy() {
+        let mut matches = vec![match_without_times(1, &[3, 2]), match_without_times(2, &[])];
+
+        let resolution =
+            resolve_missing_start_times(&mut matches, &HashSet::from([1, 2]), MissingStartTimePolicy::Impute);
+
+        // Matches without games cannot be imputed
+        assert_eq!(resolution.skipped, vec![2]);
+        assert_eq!(resolution.imputed, vec![1]);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].start_time, time(2));
+        assert_eq!(matches[0].end_time, time(3) + Duration::minutes(5));
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        assert_eq!("skip".parse(), Ok(MissingStartTimePolicy::Skip));
+        assert_eq!("impute".parse(), Ok(MissingStartTimePolicy::Impute));
+        assert!("guess".parse::<MissingStartTimePolicy>().is_err());
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{
+    database::db_structs::{Match, PlayerLobbyStats, RatingAdjustment},
+    model::structures::ruleset::Ruleset
+};
+use itertools::Itertools;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex
+};
+
+/// Processing totals of a single tournament
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentStats {
+    pub tournament_id: i32,
+    pub matches_processed: usize,
+    pub games_processed: usize,
+    /// Number of match rating adjustments produced
+    pub rating_changes: usize,
+    /// Mean of `rating_after - rating_before` over all match rating adjustments
+    pub average_rating_change: f64,
+    /// Number of distinct participants who were rated using the fallback rating
+    pub fallback_players: usize
+}
+
+#[derive(Debug, Default)]
+struct Totals {
+    matches: usize,
+    games: usize,
+    rating_changes: usize,
+    rating_change_sum: f64,
+    fallback_players: HashSet<i32>
+}
+
+#[derive(Debug, Default)]
+struct LobbyTotals {
+    games: usize,
+    lobby_size_sum: usize,
+    team_size_sum: usize
+}
+
+/// Aggregates per-tournament totals and the lobby sizes of every player while matches are
+/// processed
+///
+/// Recording only requires a shared reference, so the accumulator can be shared between
+/// threads processing matches concurrently.
+#[derive(Debug, Default)]
+pub struct StatsAccumulator {
+    /// (player, ruleset) pairs which started from the fallback rating
+    fallback_ratings: HashSet<(i32, Ruleset)>,
+    totals: Mutex<HashMap<i32, Totals>>,
+    /// Key: (player_id, ruleset)
+    lobbies: Mutex<HashMap<(i32, Ruleset), LobbyTotals>>
+}
+
+impl StatsAccumulator {
+    /// Creates an accumulator which counts participants in `fallback_ratings` as fallback players
+    pub fn new(fallback_ratings: HashSet<(i32, Ruleset)>) -> StatsAccumulator {
+        StatsAccumulator {
+            fallback_ratings,
+            totals: Mutex::new(HashMap::new()),
+            lobbies: Mutex::new(HashMap::new())
+        }
+    }
+
+    /// Records a processed match along with the rating adjustments it produced
+    ///
+    /// The lobbies of rating exempt matches are not recorded, as they are never rated.
+    pub fn record_match(&self, match_: &Match, adjustments: &[RatingAdjustment]) {
+        if !match_.rating_exempt {
+            self.record_lobbies(match_);
+        }
+
+        let mut totals = self
+            .totals
+            .lock()
+            .expect("Stats accumulator lock should not be poisoned");
+        let entry = totals.entry(match_.tournament_id).or_default();
+
+        entry.matches += 1;
+        entry.games += match_.games.len();
+        entry.rating_changes += adjustments.len();
+        entry.rating_change_sum += adjustments
+            .iter()
+            .map(|a| a.rating_after - a.rating_before)
+            .sum::<f64>();
+        entry.fallback_players.extend(
+            match_
+                .games
+                .iter()
+                .flat_map(|g| g.scores.iter())
+                .map(|s| s.player_id)
+                .filter(|&id| self.fallback_ratings.contains(&(id, match_.ruleset)))
+        );
+    }
+
+    fn record_lobbies(&self, match_: &Match) {
+        let mut lobbies = self
+            .lobbies
+            .lock()
+            .expect("Stats accumulator lock should not be poisoned");
+
+        for game in &match_.games {
+            let teams = game.scores.iter().map(|s| s.team).counts();
+            for score in &game.scores {
+                let entry = lobbies.entry((score.player_id, match_.ruleset)).or_default();
+                entry.games += 1;
+                entry.lobby_size_sum += game.scores.len();
+                entry.team_size_sum += if score.team == 0 { 1 } else { teams[&score.team] };
+            }
+        }
+    }
+
+    /// Returns the lobby sizes of every player recorded so far, ordered by player id and ruleset
+    pub fn lobby_stats(&self) -> Vec<PlayerLobbyStats> {
+        let lobbies = self
+            .lobbies
+            .lock()
+            .expect("Stats accumulator lock should not be poisoned");
+
+        lobbies
+            .iter()
+            .sorted_by_key(|((player_id, ruleset), _)| (*player_id, *ruleset as i32))
+            .map(|(&(player_id, ruleset), l)| PlayerLobbyStats {
+                player_id,
+                ruleset,
+                games: l.games as i32,
+                average_lobby_size: l.lobby_size_sum as f64 / l.games as f64,
+                average_team_size: l.team_size_sum as f64 / l.games as f64
+            })
+            .collect()
+    }
+
+    /// Returns the totals of every tournament recorded so far, ordered by tournament id
+    pub fn summaries(&self) -> Vec<TournamentStats> {
+        let totals = self
+            .totals
+            .lock()
+            .expect("Stats accumulator lock should not be poisoned");
+
+        totals
+            .iter()
+            .sorted_by_key(|(id, _)| **id)
+            .map(|(&tournament_id, t)| TournamentStats {
+                tournament_id,
+                matches_processed: t.matches,
+                games_processed: t.games,
+                rating_changes: t.rating_changes,
+                average_rating_change: if t.rating_changes == 0 {
+                    0.0
+                } else {
+                    t.rating_change_sum / t.rating_changes as f64
+                },
+                fallback_players: t.fallback_players.len()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatsAccumulator;
+    use crate::{
+        database::db_structs::RatingAdjustment,
+        model::structures::{
+            rating_adjustment_type::RatingAdjustmentType, rating_clamps::RatingClamps, ruleset::Ruleset::Osu
+        },
+        utils::test_utils::{generate_game, generate_match, generate_placement, TournamentBuilder}
+    };
+    use approx::assert_abs_diff_eq;
+    use chrono::Utc;
+    use std::{collections::HashSet, sync::Arc, thread};
+
+    fn adjustment(player_id: i32, change: f64) -> RatingAdjustment {
+        RatingAdjustment {
+            player_id,
+            ruleset: Osu,
+            match_id: Some(1),
+            rating_before: 1000.0,
+            rating_after: 1000.0 + change,
+            volatility_before: 100.0,
+            volatility_after: 100.0,
+            timestamp: Utc::now().fixed_offset(),
+            adjustment_type: RatingAdjustmentType::Match,
+            average_opponent_rating: None,
+            provisional: false,
+            percentile: None,
+            clamps: RatingClamps::default()
+        }
+    }
+
+    #[test]
+    fn test_totals_per_tournament() {
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 2)];
+        let games = vec![generate_game(1, &placements), generate_game(2, &placements)];
+        let time = Utc::now().fixed_offset();
+
+        let first = generate_match(1, Osu, &games, time);
+        let mut second = generate_match(2, Osu, &games[..1], time);
+        second.tournament_id = 2;
+
+        let accumulator = StatsAccumulator::new(HashSet::from([(2, Osu)]));
+        accumulator.record_match(&first, &[adjustment(1, 10.0), adjustment(2, -20.0)]);
+        accumulator.record_match(&first, &[adjustment(1, 4.0), adjustment(2, -2.0)]);
+        accumulator.record_match(&second, &[]);
+
+        let summaries = accumulator.summaries();
+        assert_eq!(summaries.len(), 2);
+
+        let tournament = &summaries[0];
+        assert_eq!(tournament.tournament_id, first.tournament_id);
+        assert_eq!(tournament.matches_processed, 2);
+        assert_eq!(tournament.games_processed, 4);
+        assert_eq!(tournament.rating_changes, 4);
+        assert_abs_diff_eq!(tournament.average_rating_change, -2.0);
+        assert_eq!(tournament.fallback_players, 1);
+
+        let exempt = &summaries[1];
+        assert_eq!(exempt.games_processed, 1);
+        assert_abs_diff_eq!(exempt.average_rating_change, 0.0);
+    }
+
+    #[test]
+    fn test_lobby_stats() {
+        // Two teams of two over two games, with player 3 missing the first game
+        let mut matches = TournamentBuilder::new()
+            .team_size(2)
+            .games(2)
+            .matches(2)
+            .with_absent_player(3, &[1])
+            .build();
+        matches[1].rating_exempt = true;
+
+        let accumulator = StatsAccumulator::default();
+        for match_ in &matches {
+            accumulator.record_match(match_, &[]);
+        }
+
+        let stats = accumulator.lobby_stats();
+        assert_eq!(stats.iter().map(|s| s.player_id).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+
+        assert_eq!(stats[0].games, 2);
+        assert_abs_diff_eq!(stats[0].average_lobby_size, 3.5);
+        assert_abs_diff_eq!(stats[0].average_team_size, 2.0);
+
+        assert_eq!(stats[2].games, 1);
+        assert_abs_diff_eq!(stats[2].average_team_size, 2.0);
+
+        // Player 4 played the first game without their teammate
+        assert_abs_diff_eq!(stats[3].average_team_size, 1.5);
+    }
+
+    #[test]
+    fn test_concurrent_recording() {
+        let placements = vec![generate_placement(1, 1)];
+        let match_ = generate_match(1, Osu, &[generate_game(1, &placements)], Utc::now().fixed_offset());
+        let accumulator = Arc::new(StatsAccumulator::default());
+
+        let handles = (0..4)
+            .map(|_| {
+                let accumulator = Arc::clone(&accumulator);
+                let match_ = match_.clone();
+                thread::spawn(move || {
+                    for _ in 0..25 {
+                        accumulator.record_match(&match_, &[adjustment(1, 1.0)]);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let summaries = accumulator.summaries();
+        assert_eq!(summaries[0].matches_processed, 100);
+        assert_eq!(summaries[0].rating_changes, 100);
+    }
+}
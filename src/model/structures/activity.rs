@@ -0,0 +1,25 @@
+use serde_repr::Serialize_repr;
+use std::fmt;
+
+/// Whether a player still competes in a ruleset, as judged by the decay rules
+#[derive(Serialize_repr, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum Activity {
+    /// Played a match within the inactivity period
+    Active = 0,
+    /// Inactive and still losing rating to decay
+    Decaying = 1,
+    /// Inactive with a rating at its decay floor, or never rated in a match
+    Dormant = 2
+}
+
+impl fmt::Display for Activity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let activity = match self {
+            Activity::Active => "active",
+            Activity::Decaying => "decaying",
+            Activity::Dormant => "dormant"
+        };
+        write!(f, "{}", activity)
+    }
+}
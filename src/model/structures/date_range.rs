@@ -0,0 +1,64 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+/// An optionally bounded window of time used to restrict which matches are processed
+///
+/// Both bounds are inclusive. A missing bound leaves that side of the window open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DateRange {
+    pub from: Option<DateTime<FixedOffset>>,
+    pub to: Option<DateTime<FixedOffset>>
+}
+
+impl DateRange {
+    pub fn new(from: Option<DateTime<FixedOffset>>, to: Option<DateTime<FixedOffset>>) -> Self {
+        DateRange { from, to }
+    }
+
+    /// Whether neither bound is set, i.e. the range covers all of time
+    pub fn is_unbounded(&self) -> bool {
+        self.from.is_none() && self.to.is_none()
+    }
+
+    /// Whether the given timestamp falls within the range
+    pub fn contains(&self, timestamp: DateTime<FixedOffset>) -> bool {
+        self.from.is_none_or(|from| timestamp >= from) && self.to.is_none_or(|to| timestamp <= to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DateRange;
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_unbounded_contains_everything() {
+        let range = DateRange::default();
+        let time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+
+        assert!(range.is_unbounded());
+        assert!(range.contains(time));
+    }
+
+    #[test]
+    fn test_bounds_are_inclusive() {
+        let from = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let to = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap().fixed_offset();
+        let range = DateRange::new(Some(from), Some(to));
+
+        assert!(!range.is_unbounded());
+        assert!(range.contains(from));
+        assert!(range.contains(to));
+        assert!(!range.contains(from - Duration::seconds(1)));
+        assert!(!range.contains(to + Duration::seconds(1)));
+    }
+
+    #[test]
+    fn test_open_ended() {
+        let from = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let range = DateRange::new(Some(from), None);
+
+        assert!(range.contains(from + Duration::weeks(520)));
+        assert!(!range.contains(from - Duration::days(1)));
+    }
+}
@@ -0,0 +1,85 @@
+use crate::model::{decay::DecayParameters, structures::ruleset::Ruleset};
+use std::{fmt, str::FromStr};
+
+/// Decay parameters replacing the defaults for one ruleset
+///
+/// Parsed from `<ruleset>:<inactivity days>:<rate>:<minimum>`, e.g. `catch:240:1.8:900`.
+/// The volatility interval is shared by all rulesets, see `ModelConfig::decay_parameters`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecayOverride {
+    pub ruleset: Ruleset,
+    pub parameters: DecayParameters
+}
+
+impl FromStr for DecayOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "'{}' is not a decay override (expected <ruleset>:<inactivity days>:<rate>:<minimum>)",
+                s
+            )
+        };
+
+        let parts = s.split(':').collect::<Vec<_>>();
+        let [ruleset, days, rate, minimum] = parts.as_slice() else {
+            return Err(invalid());
+        };
+
+        let parameters = DecayParameters {
+            inactivity_days: days.parse().map_err(|_| invalid())?,
+            rate: rate.parse().map_err(|_| invalid())?,
+            minimum: minimum.parse().map_err(|_| invalid())?,
+            ..DecayParameters::default()
+        };
+        if !parameters.rate.is_finite() || parameters.rate < 0.0 || !parameters.minimum.is_finite() {
+            return Err(invalid());
+        }
+
+        Ok(DecayOverride {
+            ruleset: ruleset.parse()?,
+            parameters
+        })
+    }
+}
+
+impl fmt::Display for DecayOverride {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}",
+            self.ruleset as i32, self.parameters.inactivity_days, self.parameters.rate, self.parameters.minimum
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DecayOverride;
+    use crate::model::{decay::DecayParameters, structures::ruleset::Ruleset};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse() {
+        let parsed = DecayOverride::from_str("catch:240:1.8:900").unwrap();
+        assert_eq!(
+            parsed,
+            DecayOverride {
+                ruleset: Ruleset::Catch,
+                parameters: DecayParameters {
+                    inactivity_days: 240,
+                    rate: 1.8,
+                    minimum: 900.0,
+                    ..DecayParameters::default()
+                }
+            }
+        );
+        assert_eq!(DecayOverride::from_str(&parsed.to_string()), Ok(parsed));
+
+        assert!(DecayOverride::from_str("catch:240:1.8").is_err());
+        assert!(DecayOverride::from_str("catch:-1:1.8:900").is_err());
+        assert!(DecayOverride::from_str("catch:240:-1:900").is_err());
+        assert!(DecayOverride::from_str("fruits:240:1.8:900").is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use itertools::Itertools;
+use std::{fmt, str::FromStr};
+
+/// Maps internal ratings onto the scale shown to players, e.g. 100 to 3500
+///
+/// Each anchor pins the rating found at a percentile of a ruleset's leaderboard to a display
+/// value. Ratings between two anchors are interpolated linearly, ratings beyond the outermost
+/// anchors are clamped to them.
+///
+/// Parsed from comma separated `<percentile>:<display>` anchors in increasing order,
+/// e.g. `0:100,50:1000,99:2500,100:3500`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayScale {
+    pub anchors: Vec<DisplayAnchor>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayAnchor {
+    /// Percentile (0 to 100) of the leaderboard the anchor is placed at
+    pub percentile: f64,
+    pub display: f64
+}
+
+impl FromStr for DisplayScale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "'{}' is not a display scale (expected at least two <percentile>:<display> anchors, \
+                increasing in both)",
+                s
+            )
+        };
+
+        let anchors = s
+            .split(',')
+            .map(|anchor| {
+                let (percentile, display) = anchor.split_once(':').ok_or_else(invalid)?;
+                Ok(DisplayAnchor {
+                    percentile: percentile.trim().parse().map_err(|_| invalid())?,
+                    display: display.trim().parse().map_err(|_| invalid())?
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let in_range = anchors
+            .iter()
+            .all(|a| (0.0..=100.0).contains(&a.percentile) && a.display.is_finite());
+        let increasing = anchors
+            .iter()
+            .tuple_windows()
+            .all(|(a, b)| a.percentile < b.percentile && a.display <= b.display);
+        if anchors.len() < 2 || !in_range || !increasing {
+            return Err(invalid());
+        }
+
+        Ok(DisplayScale { anchors })
+    }
+}
+
+impl fmt::Display for DisplayScale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let anchors = self
+            .anchors
+            .iter()
+            .map(|a| format!("{}:{}", a.percentile, a.display))
+            .join(",");
+        write!(f, "{}", anchors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DisplayAnchor, DisplayScale};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse() {
+        let parsed = DisplayScale::from_str("0:100,50:1000,100:3500").unwrap();
+        assert_eq!(
+            parsed.anchors[1],
+            DisplayAnchor {
+                percentile: 50.0,
+                display: 1000.0
+            }
+        );
+        assert_eq!(DisplayScale::from_str(&parsed.to_string()), Ok(parsed));
+
+        assert!(DisplayScale::from_str("0:100").is_err());
+        assert!(DisplayScale::from_str("50:1000,0:100").is_err());
+        assert!(DisplayScale::from_str("0:1000,100:100").is_err());
+        assert!(DisplayScale::from_str("0:100,150:3500").is_err());
+        assert!(DisplayScale::from_str("0:100;100:3500").is_err());
+    }
+}
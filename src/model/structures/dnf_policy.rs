@@ -0,0 +1,56 @@
+use std::{fmt, str::FromStr};
+
+/// Determines how scores of 0, usually players who disconnected or did not finish (DNF), are
+/// placed within their game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DnfPolicy {
+    /// Ranked by score like any other score, i.e. tied below every score above 0
+    #[default]
+    Rank,
+    /// Tied last below every score above 0, also when placements are read from the database.
+    /// Players who missed the game are tied with them in method B.
+    TiedLast,
+    /// Removed from the game, as if the player had not played it
+    Exclude
+}
+
+impl FromStr for DnfPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rank" => Ok(DnfPolicy::Rank),
+            "tied-last" => Ok(DnfPolicy::TiedLast),
+            "exclude" => Ok(DnfPolicy::Exclude),
+            _ => Err(format!(
+                "'{}' is not a DNF policy (expected rank, tied-last or exclude)",
+                s
+            ))
+        }
+    }
+}
+
+impl fmt::Display for DnfPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DnfPolicy::Rank => write!(f, "rank"),
+            DnfPolicy::TiedLast => write!(f, "tied-last"),
+            DnfPolicy::Exclude => write!(f, "exclude")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DnfPolicy;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_round_trip() {
+        for policy in [DnfPolicy::Rank, DnfPolicy::TiedLast, DnfPolicy::Exclude] {
+            assert_eq!(DnfPolicy::from_str(&policy.to_string()), Ok(policy));
+        }
+
+        assert!(DnfPolicy::from_str("ignore").is_err());
+    }
+}
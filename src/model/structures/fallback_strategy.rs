@@ -0,0 +1,51 @@
+use std::{fmt, str::FromStr};
+
+/// Determines the initial rating of players without rank data for a ruleset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FallbackStrategy {
+    /// `FALLBACK_RATING` in every ruleset
+    #[default]
+    Constant,
+    /// The median of the known ratings in the player's ruleset, or `FALLBACK_RATING` if the
+    /// ruleset has no known ratings
+    Median
+}
+
+impl FromStr for FallbackStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "constant" => Ok(FallbackStrategy::Constant),
+            "median" => Ok(FallbackStrategy::Median),
+            _ => Err(format!(
+                "'{}' is not a fallback strategy (expected constant or median)",
+                s
+            ))
+        }
+    }
+}
+
+impl fmt::Display for FallbackStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FallbackStrategy::Constant => write!(f, "constant"),
+            FallbackStrategy::Median => write!(f, "median")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FallbackStrategy;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_round_trip() {
+        for strategy in [FallbackStrategy::Constant, FallbackStrategy::Median] {
+            assert_eq!(FallbackStrategy::from_str(&strategy.to_string()), Ok(strategy));
+        }
+
+        assert!(FallbackStrategy::from_str("mean").is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use crate::database::db_structs::Match;
+use itertools::Itertools;
+use std::{fmt, str::FromStr};
+
+/// Multiplies the weight of warmups and tiebreakers when the ratings of a match's games are
+/// aggregated, so that e.g. warmups don't count and tiebreakers count more
+///
+/// The first game of a match with more than one game is taken to be its warmup. The last game
+/// is taken to be a tiebreaker if the games before it, not counting a weighted warmup, were won
+/// equally often by two players. This always holds for the tiebreaker of a 1v1 but may also
+/// hold for other games in team matches, where each game is won by the highest scoring player.
+/// Every other game has a weight of 1.
+///
+/// Parsed from `warmup=<weight>,tiebreaker=<weight>`, e.g. `warmup=0,tiebreaker=1.5`. Either
+/// may be left out, keeping a weight of 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameWeights {
+    pub warmup: f64,
+    pub tiebreaker: f64
+}
+
+impl Default for GameWeights {
+    fn default() -> Self {
+        GameWeights {
+            warmup: 1.0,
+            tiebreaker: 1.0
+        }
+    }
+}
+
+impl GameWeights {
+    /// The weight of every game of `match_`, in order of its games
+    pub fn weights(&self, match_: &Match) -> Vec<f64> {
+        let mut weights = vec![1.0; match_.games.len()];
+        if weights.len() > 1 {
+            weights[0] *= self.warmup;
+        }
+        if self.is_tiebreaker(match_) {
+            *weights.last_mut().expect("A match with a tiebreaker has games") *= self.tiebreaker;
+        }
+        weights
+    }
+
+    /// Whether the last game of `match_` was played with the wins of the games before it split
+    /// equally between two players
+    fn is_tiebreaker(&self, match_: &Match) -> bool {
+        let Some((_, decided)) = match_.games.split_last() else {
+            return false;
+        };
+        let decided = match decided.split_first() {
+            Some((_, after_warmup)) if self.warmup != 1.0 => after_warmup,
+            _ => decided
+        };
+        if decided.len() < 2 {
+            return false;
+        }
+
+        let wins = decided
+            .iter()
+            .flat_map(|game| {
+                let first = game.scores.iter().map(|s| s.placement).min();
+                game.scores
+                    .iter()
+                    .filter(move |s| Some(s.placement) == first)
+                    .map(|s| s.player_id)
+            })
+            .counts();
+
+        match wins.values().collect_vec()[..] {
+            [a, b] => a == b,
+            _ => false
+        }
+    }
+}
+
+impl FromStr for GameWeights {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "'{}' are not game weights (expected warmup=<weight>,tiebreaker=<weight>)",
+                s
+            )
+        };
+
+        let mut weights = GameWeights::default();
+        for part in s.split(',') {
+            let (name, weight) = part.split_once('=').ok_or_else(invalid)?;
+            let weight = weight
+                .parse::<f64>()
+                .ok()
+                .filter(|w| w.is_finite() && *w >= 0.0)
+                .ok_or_else(invalid)?;
+            match name {
+                "warmup" => weights.warmup = weight,
+                "tiebreaker" => weights.tiebreaker = weight,
+                _ => return Err(invalid())
+            }
+        }
+
+        Ok(weights)
+    }
+}
+
+impl fmt::Display for GameWeights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "warmup={},tiebreaker={}", self.warmup, self.tiebreaker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GameWeights;
+    use crate::{
+        model::structures::ruleset::Ruleset::Osu,
+        utils::test_utils::{generate_game, generate_match, generate_placement}
+    };
+    use chrono::Utc;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse() {
+        let parsed = GameWeights::from_str("warmup=0,tiebreaker=1.5").unwrap();
+        assert_eq!(
+            parsed,
+            GameWeights {
+                warmup: 0.0,
+                tiebreaker: 1.5
+            }
+        );
+        assert_eq!(GameWeights::from_str(&parsed.to_string()), Ok(parsed));
+        assert_eq!(
+            GameWeights::from_str("tiebreaker=2").unwrap(),
+            GameWeights {
+                warmup: 1.0,
+                tiebreaker: 2.0
+            }
+        );
+
+        assert!(GameWeights::from_str("warmup").is_err());
+        assert!(GameWeights::from_str("warmup=-1").is_err());
+        assert!(GameWeights::from_str("opener=0").is_err());
+    }
+
+    #[test]
+    fn test_weights_of_warmup_and_tiebreaker() {
+        let weights = GameWeights {
+            warmup: 0.0,
+            tiebreaker: 1.5
+        };
+        let game =
+            |id, winner, loser| generate_game(id, &[generate_placement(winner, 1), generate_placement(loser, 2)]);
+        let now = Utc::now().fixed_offset();
+
+        // Player 1 and 2 won a game each after the warmup, so the last game decides the match
+        let tied = generate_match(1, Osu, &[game(1, 1, 2), game(2, 1, 2), game(3, 2, 1), game(4, 1, 2)], now);
+        assert_eq!(weights.weights(&tied), vec![0.0, 1.0, 1.0, 1.5]);
+
+        let decided = generate_match(2, Osu, &[game(1, 1, 2), game(2, 1, 2), game(3, 1, 2), game(4, 1, 2)], now);
+        assert_eq!(weights.weights(&decided), vec![0.0, 1.0, 1.0, 1.0]);
+
+        let single = generate_match(3, Osu, &[game(1, 1, 2)], now);
+        assert_eq!(weights.weights(&single), vec![1.0]);
+
+        // Without weighted warmups, the first game counts towards the tie
+        let best_of_three = generate_match(4, Osu, &[game(1, 1, 2), game(2, 2, 1), game(3, 1, 2)], now);
+        let tiebreaker_only = GameWeights {
+            tiebreaker: 1.5,
+            ..GameWeights::default()
+        };
+        assert_eq!(tiebreaker_only.weights(&best_of_three), vec![1.0, 1.0, 1.5]);
+        assert_eq!(weights.weights(&best_of_three), vec![0.0, 1.0, 1.0]);
+    }
+}
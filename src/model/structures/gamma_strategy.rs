@@ -0,0 +1,140 @@
+use crate::model::constants::DEFAULT_VOLATILITY;
+use openskill::rating::{default_gamma, GammaFunc, TeamRating};
+use std::{cell::Cell, fmt, str::FromStr};
+
+thread_local! {
+    /// Value returned by the constant gamma function, only set within `with_function`.
+    ///
+    /// `GammaFunc` is a plain function pointer and cannot capture a value.
+    static CONSTANT_GAMMA: Cell<f64> = const { Cell::new(1.0) };
+}
+
+/// Determines how strongly volatility is reduced after each game
+///
+/// The gamma value scales the volatility update of the PlackettLuce model: a higher
+/// gamma makes volatility converge faster.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GammaStrategy {
+    /// `1 / k` where `k` is the number of teams in the game
+    #[default]
+    InverseTeamCount,
+    /// The OpenSkill default, `sigma / c`
+    OpenSkill,
+    /// The team's volatility relative to the default volatility
+    SigmaProportional,
+    /// A fixed value regardless of the game
+    Constant(f64)
+}
+
+impl GammaStrategy {
+    /// Calls `f` with the gamma function to pass to `PlackettLuce::new`, which must not be
+    /// used after `f` returns
+    pub fn with_function<R>(&self, f: impl FnOnce(GammaFunc) -> R) -> R {
+        match self {
+            GammaStrategy::InverseTeamCount => f(inverse_team_count_gamma),
+            GammaStrategy::OpenSkill => f(default_gamma),
+            GammaStrategy::SigmaProportional => f(sigma_proportional_gamma),
+            GammaStrategy::Constant(value) => {
+                // Restored afterwards, so strategies rating on the same thread never see each other's value
+                let previous = CONSTANT_GAMMA.replace(*value);
+                let result = f(constant_gamma);
+                CONSTANT_GAMMA.set(previous);
+                result
+            }
+        }
+    }
+}
+
+fn inverse_team_count_gamma(_: f64, k: f64, _: &TeamRating) -> f64 {
+    1.0 / k
+}
+
+fn sigma_proportional_gamma(_: f64, _: f64, team: &TeamRating) -> f64 {
+    team.sigma_sq.sqrt() / DEFAULT_VOLATILITY
+}
+
+fn constant_gamma(_: f64, _: f64, _: &TeamRating) -> f64 {
+    CONSTANT_GAMMA.with(|gamma| gamma.get())
+}
+
+impl FromStr for GammaStrategy {
+    type Err = String;
+
+    /// Parses `inverse-team-count`, `openskill`, `sigma-proportional` or a number,
+    /// which selects a constant gamma
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inverse-team-count" => Ok(GammaStrategy::InverseTeamCount),
+            "openskill" => Ok(GammaStrategy::OpenSkill),
+            "sigma-proportional" => Ok(GammaStrategy::SigmaProportional),
+            _ => match s.parse::<f64>() {
+                Ok(value) if value.is_finite() && value > 0.0 => Ok(GammaStrategy::Constant(value)),
+                _ => Err(format!(
+                    "'{}' is not a gamma strategy (expected inverse-team-count, openskill, \
+                    sigma-proportional or a positive number)",
+                    s
+                ))
+            }
+        }
+    }
+}
+
+impl fmt::Display for GammaStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GammaStrategy::InverseTeamCount => write!(f, "inverse-team-count"),
+            GammaStrategy::OpenSkill => write!(f, "openskill"),
+            GammaStrategy::SigmaProportional => write!(f, "sigma-proportional"),
+            GammaStrategy::Constant(value) => write!(f, "{}", value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GammaStrategy;
+    use openskill::rating::TeamRating;
+    use std::str::FromStr;
+
+    fn team(sigma: f64) -> TeamRating {
+        TeamRating {
+            members: Vec::new(),
+            mu: 1000.0,
+            sigma_sq: sigma * sigma,
+            rank: 1
+        }
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        for strategy in [
+            GammaStrategy::InverseTeamCount,
+            GammaStrategy::OpenSkill,
+            GammaStrategy::SigmaProportional,
+            GammaStrategy::Constant(2.0)
+        ] {
+            assert_eq!(GammaStrategy::from_str(&strategy.to_string()), Ok(strategy));
+        }
+
+        assert!(GammaStrategy::from_str("sometimes").is_err());
+        assert!(GammaStrategy::from_str("-1").is_err());
+    }
+
+    #[test]
+    fn test_gamma_values() {
+        let team = team(150.0);
+        let gamma = |strategy: GammaStrategy| strategy.with_function(|gamma| gamma(10.0, 4.0, &team));
+
+        assert_eq!(gamma(GammaStrategy::InverseTeamCount), 0.25);
+        assert_eq!(gamma(GammaStrategy::OpenSkill), 15.0);
+        assert_eq!(gamma(GammaStrategy::SigmaProportional), 0.5);
+        assert_eq!(gamma(GammaStrategy::Constant(2.0)), 2.0);
+
+        // Nested constants see their own value and restore the outer one
+        let nested = GammaStrategy::Constant(2.0).with_function(|outer| {
+            let inner = gamma(GammaStrategy::Constant(3.0));
+            (outer(10.0, 4.0, &team), inner)
+        });
+        assert_eq!(nested, (2.0, 3.0));
+    }
+}
@@ -0,0 +1,57 @@
+use std::{fmt, str::FromStr};
+
+/// What to do with the scores failing an integrity check, see `check_score_integrity`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrityAction {
+    /// Remove the offending scores from their game
+    DropScore,
+    /// Remove the whole game the offending scores belong to
+    DropGame,
+    /// Abort the run before anything is saved
+    Fail
+}
+
+impl FromStr for IntegrityAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop-score" => Ok(IntegrityAction::DropScore),
+            "drop-game" => Ok(IntegrityAction::DropGame),
+            "fail" => Ok(IntegrityAction::Fail),
+            _ => Err(format!(
+                "'{}' is not an integrity action (expected drop-score, drop-game or fail)",
+                s
+            ))
+        }
+    }
+}
+
+impl fmt::Display for IntegrityAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityAction::DropScore => write!(f, "drop-score"),
+            IntegrityAction::DropGame => write!(f, "drop-game"),
+            IntegrityAction::Fail => write!(f, "fail")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IntegrityAction;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_round_trip() {
+        for action in [
+            IntegrityAction::DropScore,
+            IntegrityAction::DropGame,
+            IntegrityAction::Fail
+        ] {
+            assert_eq!(IntegrityAction::from_str(&action.to_string()), Ok(action));
+        }
+
+        assert!(IntegrityAction::from_str("ignore").is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use openskill::constant::DEFAULT_BETA;
+use std::{fmt, str::FromStr};
+
+/// Scales the beta of the PlackettLuce model by the number of players in each game
+///
+/// Beta is the performance variance of a single game. Placements in large lobbies are noisier
+/// than the result of a 1v1, so a positive exponent rates them with a larger beta, which
+/// moves ratings less and reduces volatility more slowly. Games with `n` players are rated
+/// with `DEFAULT_BETA * (n / 2) ^ exponent`, leaving 1v1s unchanged.
+///
+/// Parsed from the exponent, e.g. `0.5`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LobbyBeta {
+    pub exponent: f64
+}
+
+impl LobbyBeta {
+    /// The beta to rate a game of `players` players with
+    pub fn beta(&self, players: usize) -> f64 {
+        DEFAULT_BETA * (players.max(2) as f64 / 2.0).powf(self.exponent)
+    }
+}
+
+impl FromStr for LobbyBeta {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<f64>() {
+            Ok(exponent) if exponent.is_finite() => Ok(LobbyBeta { exponent }),
+            _ => Err(format!("'{}' is not a lobby beta exponent (expected a number)", s))
+        }
+    }
+}
+
+impl fmt::Display for LobbyBeta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.exponent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LobbyBeta;
+    use approx::assert_abs_diff_eq;
+    use openskill::constant::DEFAULT_BETA;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse() {
+        let parsed = LobbyBeta::from_str("0.5").unwrap();
+        assert_eq!(parsed, LobbyBeta { exponent: 0.5 });
+        assert_eq!(LobbyBeta::from_str(&parsed.to_string()), Ok(parsed));
+
+        assert!(LobbyBeta::from_str("large").is_err());
+        assert!(LobbyBeta::from_str("inf").is_err());
+    }
+
+    #[test]
+    fn test_beta_grows_with_lobby_size() {
+        let lobby_beta = LobbyBeta { exponent: 0.5 };
+
+        assert_abs_diff_eq!(lobby_beta.beta(2), DEFAULT_BETA);
+        assert_abs_diff_eq!(lobby_beta.beta(8), DEFAULT_BETA * 2.0);
+        assert_abs_diff_eq!(lobby_beta.beta(1), DEFAULT_BETA);
+    }
+}
@@ -0,0 +1,51 @@
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use std::convert::TryFrom;
+
+/// How a manual adjustment changes a player's rating
+#[derive(Deserialize_repr, Serialize_repr, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ManualAdjustmentKind {
+    /// Replaces the rating with the adjustment's value
+    Override = 0,
+    /// Adds the adjustment's value to the rating
+    Offset = 1
+}
+
+impl ManualAdjustmentKind {
+    /// The rating after applying an adjustment of this kind with `value` to `rating`
+    pub fn apply(&self, rating: f64, value: f64) -> f64 {
+        match self {
+            ManualAdjustmentKind::Override => value,
+            ManualAdjustmentKind::Offset => rating + value
+        }
+    }
+}
+
+impl TryFrom<i32> for ManualAdjustmentKind {
+    type Error = ();
+    fn try_from(v: i32) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(ManualAdjustmentKind::Override),
+            1 => Ok(ManualAdjustmentKind::Offset),
+            _ => Err(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ManualAdjustmentKind;
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(ManualAdjustmentKind::try_from(0), Ok(ManualAdjustmentKind::Override));
+        assert_eq!(ManualAdjustmentKind::try_from(1), Ok(ManualAdjustmentKind::Offset));
+        assert_eq!(ManualAdjustmentKind::try_from(2), Err(()));
+    }
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(ManualAdjustmentKind::Override.apply(1000.0, 800.0), 800.0);
+        assert_eq!(ManualAdjustmentKind::Offset.apply(1000.0, -150.0), 850.0);
+    }
+}
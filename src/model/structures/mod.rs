@@ -1,2 +1,22 @@
+pub mod activity;
+pub mod date_range;
+pub mod decay_override;
+pub mod display_scale;
+pub mod dnf_policy;
+pub mod fallback_strategy;
+pub mod game_weights;
+pub mod gamma_strategy;
+pub mod integrity_action;
+pub mod lobby_beta;
+pub mod manual_adjustment_kind;
+pub mod mod_category;
+pub mod provisional_period;
+pub mod quarantine_reason;
 pub mod rating_adjustment_type;
+pub mod rating_clamps;
+pub mod returning_boost;
 pub mod ruleset;
+pub mod ruleset_filter;
+pub mod short_match_policy;
+pub mod verification_status;
+pub mod weight_strategy;
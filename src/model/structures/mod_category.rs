@@ -0,0 +1,26 @@
+use serde_repr::Serialize_repr;
+use std::fmt;
+
+/// The mod pool a game was played from, as detected from the mods actually played
+#[derive(Serialize_repr, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum ModCategory {
+    NoMod = 0,
+    Hidden = 1,
+    HardRock = 2,
+    DoubleTime = 3,
+    FreeMod = 4
+}
+
+impl fmt::Display for ModCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let abbreviation = match self {
+            ModCategory::NoMod => "NM",
+            ModCategory::Hidden => "HD",
+            ModCategory::HardRock => "HR",
+            ModCategory::DoubleTime => "DT",
+            ModCategory::FreeMod => "FM"
+        };
+        write!(f, "{}", abbreviation)
+    }
+}
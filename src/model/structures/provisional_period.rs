@@ -0,0 +1,100 @@
+use std::{fmt, str::FromStr};
+
+/// Rates new players with a raised volatility so that their rating converges faster, while
+/// their uncertainty limits how much they move the ratings of established players
+///
+/// A player's first match is rated as if their volatility were at least `volatility`. The
+/// raise shrinks linearly with every match played, ending after `matches` matches. The
+/// stored volatility before each of these matches is unchanged.
+///
+/// Parsed from `<matches>:<volatility>`, e.g. `5:300`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProvisionalPeriod {
+    pub matches: usize,
+    pub volatility: f64
+}
+
+impl ProvisionalPeriod {
+    /// Whether a player who played `matches_played` matches is still provisional
+    pub fn is_provisional(&self, matches_played: usize) -> bool {
+        matches_played < self.matches
+    }
+
+    /// The volatility to rate a player with after `matches_played` matches
+    pub fn apply(&self, volatility: f64, matches_played: usize) -> f64 {
+        if !self.is_provisional(matches_played) {
+            return volatility;
+        }
+
+        let remaining = (self.matches - matches_played) as f64 / self.matches as f64;
+        volatility + (self.volatility - volatility).max(0.0) * remaining
+    }
+}
+
+impl FromStr for ProvisionalPeriod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{}' is not a provisional period (expected <matches>:<volatility>)", s);
+
+        let Some((matches, volatility)) = s.split_once(':') else {
+            return Err(invalid());
+        };
+
+        let period = ProvisionalPeriod {
+            matches: matches.parse().map_err(|_| invalid())?,
+            volatility: volatility.parse().map_err(|_| invalid())?
+        };
+        if period.matches == 0 || !period.volatility.is_finite() || period.volatility <= 0.0 {
+            return Err(invalid());
+        }
+
+        Ok(period)
+    }
+}
+
+impl fmt::Display for ProvisionalPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.matches, self.volatility)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProvisionalPeriod;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse() {
+        let parsed = ProvisionalPeriod::from_str("5:300").unwrap();
+        assert_eq!(
+            parsed,
+            ProvisionalPeriod {
+                matches: 5,
+                volatility: 300.0
+            }
+        );
+        assert_eq!(ProvisionalPeriod::from_str(&parsed.to_string()), Ok(parsed));
+
+        assert!(ProvisionalPeriod::from_str("5").is_err());
+        assert!(ProvisionalPeriod::from_str("0:300").is_err());
+        assert!(ProvisionalPeriod::from_str("5:nan").is_err());
+    }
+
+    #[test]
+    fn test_raise_shrinks_with_matches() {
+        let period = ProvisionalPeriod {
+            matches: 4,
+            volatility: 300.0
+        };
+
+        assert_eq!(period.apply(200.0, 0), 300.0);
+        assert_eq!(period.apply(200.0, 2), 250.0);
+        assert_eq!(period.apply(200.0, 3), 225.0);
+        assert_eq!(period.apply(200.0, 4), 200.0);
+        assert!(!period.is_provisional(4));
+
+        // Volatilities above the provisional volatility are never lowered
+        assert_eq!(period.apply(350.0, 0), 350.0);
+    }
+}
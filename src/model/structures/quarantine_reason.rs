@@ -0,0 +1,28 @@
+use serde_repr::Serialize_repr;
+use std::fmt;
+
+/// Why a match was left out of processing, see `SkippedEntities::quarantined_matches`
+#[derive(Serialize_repr, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum QuarantineReason {
+    /// The match has no games
+    NoGames = 0,
+    /// None of the games of the match have scores
+    EmptyScores = 1,
+    /// A game of the match has placements outside of 1 to its number of scores
+    InvalidPlacements = 2,
+    /// The match has fewer games than required by `ShortMatchPolicy::MinGames`
+    TooFewGames = 3
+}
+
+impl fmt::Display for QuarantineReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            QuarantineReason::NoGames => "no games",
+            QuarantineReason::EmptyScores => "empty scores",
+            QuarantineReason::InvalidPlacements => "invalid placements",
+            QuarantineReason::TooFewGames => "too few games"
+        };
+        write!(f, "{}", reason)
+    }
+}
@@ -6,7 +6,11 @@ use std::convert::TryFrom;
 pub enum RatingAdjustmentType {
     Initial = 0,
     Decay = 1,
-    Match = 2
+    Match = 2,
+    /// Inserted by moderation to correct a player's rating, see `ManualAdjustment`
+    Manual = 3,
+    /// Replaces the match adjustments of a tournament when stored, see `settle_tournaments`
+    TournamentSettlement = 4
 }
 
 impl TryFrom<i32> for RatingAdjustmentType {
@@ -16,6 +20,8 @@ impl TryFrom<i32> for RatingAdjustmentType {
             0 => Ok(RatingAdjustmentType::Initial),
             1 => Ok(RatingAdjustmentType::Decay),
             2 => Ok(RatingAdjustmentType::Match),
+            3 => Ok(RatingAdjustmentType::Manual),
+            4 => Ok(RatingAdjustmentType::TournamentSettlement),
             _ => Err(())
         }
     }
@@ -41,8 +47,21 @@ mod tests {
         assert_eq!(RatingAdjustmentType::try_from(2), Ok(RatingAdjustmentType::Match));
     }
 
+    #[test]
+    fn test_convert_manual() {
+        assert_eq!(RatingAdjustmentType::try_from(3), Ok(RatingAdjustmentType::Manual));
+    }
+
+    #[test]
+    fn test_convert_tournament_settlement() {
+        assert_eq!(
+            RatingAdjustmentType::try_from(4),
+            Ok(RatingAdjustmentType::TournamentSettlement)
+        );
+    }
+
     #[test]
     fn test_convert_error() {
-        assert_eq!(RatingAdjustmentType::try_from(3), Err(()));
+        assert_eq!(RatingAdjustmentType::try_from(5), Err(()));
     }
 }
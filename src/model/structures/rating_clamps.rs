@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// The bounds a rating adjustment was clamped to, as bitflags
+///
+/// Stored with every adjustment so that it can be measured how often the bounds bind, e.g.
+/// to decide whether they need retuning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct RatingClamps(pub i32);
+
+impl RatingClamps {
+    /// The rating was raised to `ABSOLUTE_RATING_FLOOR`
+    pub const RATING_FLOOR: i32 = 1 << 0;
+    /// The volatility was raised to `ModelConfig::min_volatility`
+    pub const VOLATILITY_FLOOR: i32 = 1 << 1;
+    /// The volatility was lowered to `DEFAULT_VOLATILITY`
+    pub const VOLATILITY_CAP: i32 = 1 << 2;
+
+    pub fn contains(&self, flag: i32) -> bool {
+        self.0 & flag != 0
+    }
+
+    /// Adds `flag` if `clamped`
+    pub fn set(&mut self, flag: i32, clamped: bool) {
+        if clamped {
+            self.0 |= flag;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
@@ -0,0 +1,84 @@
+use std::{fmt, str::FromStr};
+
+/// Raises the volatility of players returning from inactivity so that their rating converges
+/// to their current skill faster
+///
+/// For the first `matches` matches after a player's rating decayed, the player is rated as if
+/// their volatility were at least `volatility`. The stored volatility before each of these
+/// matches is unchanged.
+///
+/// Parsed from `<matches>:<volatility>`, e.g. `3:250`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReturningBoost {
+    pub matches: usize,
+    pub volatility: f64
+}
+
+impl ReturningBoost {
+    /// The volatility to rate a returning player with
+    pub fn apply(&self, volatility: f64) -> f64 {
+        volatility.max(self.volatility)
+    }
+}
+
+impl FromStr for ReturningBoost {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{}' is not a returning boost (expected <matches>:<volatility>)", s);
+
+        let Some((matches, volatility)) = s.split_once(':') else {
+            return Err(invalid());
+        };
+
+        let boost = ReturningBoost {
+            matches: matches.parse().map_err(|_| invalid())?,
+            volatility: volatility.parse().map_err(|_| invalid())?
+        };
+        if boost.matches == 0 || !boost.volatility.is_finite() || boost.volatility <= 0.0 {
+            return Err(invalid());
+        }
+
+        Ok(boost)
+    }
+}
+
+impl fmt::Display for ReturningBoost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.matches, self.volatility)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReturningBoost;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse() {
+        let parsed = ReturningBoost::from_str("3:250").unwrap();
+        assert_eq!(
+            parsed,
+            ReturningBoost {
+                matches: 3,
+                volatility: 250.0
+            }
+        );
+        assert_eq!(ReturningBoost::from_str(&parsed.to_string()), Ok(parsed));
+
+        assert!(ReturningBoost::from_str("3").is_err());
+        assert!(ReturningBoost::from_str("0:250").is_err());
+        assert!(ReturningBoost::from_str("3:-250").is_err());
+    }
+
+    #[test]
+    fn test_apply_only_raises() {
+        let boost = ReturningBoost {
+            matches: 3,
+            volatility: 250.0
+        };
+
+        assert_eq!(boost.apply(100.0), 250.0);
+        assert_eq!(boost.apply(300.0), 300.0);
+    }
+}
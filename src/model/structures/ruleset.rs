@@ -1,5 +1,5 @@
 use serde_repr::{Deserialize_repr, Serialize_repr};
-use std::convert::TryFrom;
+use std::{convert::TryFrom, str::FromStr};
 use strum_macros::EnumIter;
 
 #[derive(Deserialize_repr, Serialize_repr, Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter)]
@@ -29,6 +29,27 @@ impl TryFrom<i32> for Ruleset {
     }
 }
 
+impl FromStr for Ruleset {
+    type Err = String;
+
+    /// Parses a ruleset from its lowercase name (e.g. `mania4k`) or its id
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "osu" => Ok(Ruleset::Osu),
+            "taiko" => Ok(Ruleset::Taiko),
+            "catch" => Ok(Ruleset::Catch),
+            "maniaother" => Ok(Ruleset::ManiaOther),
+            "mania4k" => Ok(Ruleset::Mania4k),
+            "mania7k" => Ok(Ruleset::Mania7k),
+            _ => s
+                .parse::<i32>()
+                .ok()
+                .and_then(|id| Ruleset::try_from(id).ok())
+                .ok_or_else(|| format!("'{}' is not a ruleset", s))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::model::structures::ruleset::Ruleset;
@@ -84,4 +105,13 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("catch".parse(), Ok(Ruleset::Catch));
+        assert_eq!("mania7k".parse(), Ok(Ruleset::Mania7k));
+        assert_eq!("4".parse(), Ok(Ruleset::Mania4k));
+        assert!("6".parse::<Ruleset>().is_err());
+        assert!("fruits".parse::<Ruleset>().is_err());
+    }
 }
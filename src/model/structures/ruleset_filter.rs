@@ -0,0 +1,59 @@
+use super::ruleset::Ruleset;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+/// The rulesets a run is restricted to, used like `DateRange` to scope fetching and saving
+///
+/// An empty filter selects every ruleset.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RulesetFilter {
+    rulesets: Vec<Ruleset>
+}
+
+impl RulesetFilter {
+    pub fn new(rulesets: &[Ruleset]) -> Self {
+        RulesetFilter {
+            rulesets: rulesets.iter().copied().unique().collect()
+        }
+    }
+
+    /// Whether every ruleset is selected
+    pub fn is_unrestricted(&self) -> bool {
+        self.rulesets.is_empty()
+    }
+
+    /// Whether the given ruleset is selected
+    pub fn contains(&self, ruleset: Ruleset) -> bool {
+        self.is_unrestricted() || self.rulesets.contains(&ruleset)
+    }
+
+    /// The ids of the selected rulesets as a query parameter, None if every ruleset is selected
+    pub fn ids(&self) -> Option<Vec<i32>> {
+        (!self.is_unrestricted()).then(|| self.rulesets.iter().map(|&r| r as i32).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RulesetFilter;
+    use crate::model::structures::ruleset::Ruleset::{Mania4k, Osu, Taiko};
+
+    #[test]
+    fn test_empty_filter_selects_everything() {
+        let filter = RulesetFilter::default();
+
+        assert!(filter.is_unrestricted());
+        assert!(filter.contains(Mania4k));
+        assert_eq!(filter.ids(), None);
+    }
+
+    #[test]
+    fn test_restricted_filter() {
+        let filter = RulesetFilter::new(&[Osu, Taiko, Osu]);
+
+        assert!(!filter.is_unrestricted());
+        assert!(filter.contains(Taiko));
+        assert!(!filter.contains(Mania4k));
+        assert_eq!(filter.ids(), Some(vec![0, 1]));
+    }
+}
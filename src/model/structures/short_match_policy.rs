@@ -0,0 +1,80 @@
+use std::{fmt, str::FromStr};
+
+/// How matches with a single game are rated
+///
+/// The rating change of a match is spread over its games, so a single game carries the weight
+/// of a whole match and produces unusually large changes.
+///
+/// Parsed from `rate`, `flag`, `dampen:<factor>` or `min-games:<games>`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ShortMatchPolicy {
+    /// Rate them like any other match
+    #[default]
+    Rate,
+    /// Rate them like any other match, raising a `short-match` warning for each
+    Flag,
+    /// Scale the rating and volatility change of every player by a factor in (0, 1]
+    Dampen(f64),
+    /// Skip and quarantine matches with fewer games than this, which may be more than one
+    MinGames(usize)
+}
+
+impl FromStr for ShortMatchPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "'{}' is not a short match policy (expected rate, flag, dampen:<factor> or min-games:<games>)",
+                s
+            )
+        };
+
+        match s.split_once(':') {
+            None if s == "rate" => Ok(ShortMatchPolicy::Rate),
+            None if s == "flag" => Ok(ShortMatchPolicy::Flag),
+            Some(("dampen", factor)) => match factor.parse::<f64>() {
+                Ok(factor) if factor > 0.0 && factor <= 1.0 => Ok(ShortMatchPolicy::Dampen(factor)),
+                _ => Err(invalid())
+            },
+            Some(("min-games", games)) => match games.parse::<usize>() {
+                Ok(games) if games > 1 => Ok(ShortMatchPolicy::MinGames(games)),
+                _ => Err(invalid())
+            },
+            _ => Err(invalid())
+        }
+    }
+}
+
+impl fmt::Display for ShortMatchPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShortMatchPolicy::Rate => write!(f, "rate"),
+            ShortMatchPolicy::Flag => write!(f, "flag"),
+            ShortMatchPolicy::Dampen(factor) => write!(f, "dampen:{}", factor),
+            ShortMatchPolicy::MinGames(games) => write!(f, "min-games:{}", games)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ShortMatchPolicy;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_round_trip() {
+        for policy in [
+            ShortMatchPolicy::Rate,
+            ShortMatchPolicy::Flag,
+            ShortMatchPolicy::Dampen(0.5),
+            ShortMatchPolicy::MinGames(3)
+        ] {
+            assert_eq!(ShortMatchPolicy::from_str(&policy.to_string()), Ok(policy));
+        }
+
+        for invalid in ["skip", "dampen:0", "dampen:1.5", "dampen", "min-games:1", "flag:2"] {
+            assert!(ShortMatchPolicy::from_str(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
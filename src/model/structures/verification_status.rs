@@ -0,0 +1,49 @@
+use std::convert::TryFrom;
+
+/// Verification status of tournaments, matches, games and scores, as stored by the o!TR API
+///
+/// Only verified games and scores are rated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum VerificationStatus {
+    None = 0,
+    PreRejected = 1,
+    PreVerified = 2,
+    Rejected = 3,
+    Verified = 4
+}
+
+impl TryFrom<i32> for VerificationStatus {
+    type Error = ();
+
+    fn try_from(v: i32) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(VerificationStatus::None),
+            1 => Ok(VerificationStatus::PreRejected),
+            2 => Ok(VerificationStatus::PreVerified),
+            3 => Ok(VerificationStatus::Rejected),
+            4 => Ok(VerificationStatus::Verified),
+            _ => Err(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VerificationStatus;
+
+    #[test]
+    fn test_round_trip() {
+        for status in [
+            VerificationStatus::None,
+            VerificationStatus::PreRejected,
+            VerificationStatus::PreVerified,
+            VerificationStatus::Rejected,
+            VerificationStatus::Verified
+        ] {
+            assert_eq!(VerificationStatus::try_from(status as i32), Ok(status));
+        }
+
+        assert!(VerificationStatus::try_from(5).is_err());
+    }
+}
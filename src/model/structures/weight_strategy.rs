@@ -0,0 +1,95 @@
+use crate::model::constants::{MAX_PARTICIPATION_WEIGHT_A, MIN_PARTICIPATION_WEIGHT_A, WEIGHT_A};
+use std::{fmt, str::FromStr};
+
+/// Determines how Method A and Method B ratings are blended for each player
+///
+/// Method B's weight is always `1 - weight_a`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WeightStrategy {
+    /// `WEIGHT_A` for every player
+    #[default]
+    Constant,
+    /// Scales linearly with the fraction of games the player participated in, from
+    /// `MIN_PARTICIPATION_WEIGHT_A` (no games) to `MAX_PARTICIPATION_WEIGHT_A` (every game)
+    Participation
+}
+
+impl WeightStrategy {
+    /// The weight of Method A for a player who played `participation` (0 to 1) of a match's games
+    pub fn weight_a(&self, participation: f64) -> f64 {
+        match self {
+            WeightStrategy::Constant => WEIGHT_A,
+            WeightStrategy::Participation => {
+                let participation = participation.clamp(0.0, 1.0);
+                MIN_PARTICIPATION_WEIGHT_A + (MAX_PARTICIPATION_WEIGHT_A - MIN_PARTICIPATION_WEIGHT_A) * participation
+            }
+        }
+    }
+}
+
+impl FromStr for WeightStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "constant" => Ok(WeightStrategy::Constant),
+            "participation" => Ok(WeightStrategy::Participation),
+            _ => Err(format!(
+                "'{}' is not a weight strategy (expected constant or participation)",
+                s
+            ))
+        }
+    }
+}
+
+impl fmt::Display for WeightStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeightStrategy::Constant => write!(f, "constant"),
+            WeightStrategy::Participation => write!(f, "participation")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WeightStrategy;
+    use crate::model::constants::{MAX_PARTICIPATION_WEIGHT_A, MIN_PARTICIPATION_WEIGHT_A, WEIGHT_A};
+    use approx::assert_abs_diff_eq;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse_round_trip() {
+        for strategy in [WeightStrategy::Constant, WeightStrategy::Participation] {
+            assert_eq!(WeightStrategy::from_str(&strategy.to_string()), Ok(strategy));
+        }
+
+        assert!(WeightStrategy::from_str("dynamic").is_err());
+    }
+
+    #[test]
+    fn test_constant_ignores_participation() {
+        assert_abs_diff_eq!(WeightStrategy::Constant.weight_a(0.0), WEIGHT_A);
+        assert_abs_diff_eq!(WeightStrategy::Constant.weight_a(1.0), WEIGHT_A);
+    }
+
+    #[test]
+    fn test_participation_curve() {
+        let strategy = WeightStrategy::Participation;
+
+        assert_abs_diff_eq!(strategy.weight_a(0.0), MIN_PARTICIPATION_WEIGHT_A);
+        assert_abs_diff_eq!(strategy.weight_a(1.0), MAX_PARTICIPATION_WEIGHT_A);
+        assert_abs_diff_eq!(
+            strategy.weight_a(0.5),
+            (MIN_PARTICIPATION_WEIGHT_A + MAX_PARTICIPATION_WEIGHT_A) / 2.0
+        );
+
+        // The weight of Method A never decreases as participation grows
+        let weights = (0..=10).map(|i| strategy.weight_a(i as f64 / 10.0)).collect::<Vec<_>>();
+        assert!(weights.windows(2).all(|w| w[0] <= w[1]));
+
+        // Out of range participation is clamped
+        assert_abs_diff_eq!(strategy.weight_a(1.5), MAX_PARTICIPATION_WEIGHT_A);
+        assert_abs_diff_eq!(strategy.weight_a(-1.0), MIN_PARTICIPATION_WEIGHT_A);
+    }
+}
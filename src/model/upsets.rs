@@ -0,0 +1,151 @@
+use super::observer::ProcessingObserver;
+use crate::database::db_structs::{Match, MatchUpset, RatingAdjustment};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// Measures how surprising the outcome of a match was given the ratings of its players
+/// before the match
+///
+/// Within every game, each pair of players placed differently is compared. A pair is
+/// inverted when the player placed ahead had the lower rating, and contributes the gap
+/// between both ratings. The upset factor is the sum of these gaps divided by the number of
+/// compared pairs, so it is 0 if every game ended in rating order and grows with both the
+/// number and the size of the inversions.
+///
+/// # Returns
+/// None if no pair of rated players could be compared, e.g. for rating exempt matches
+pub fn match_upset(match_: &Match, adjustments: &[RatingAdjustment]) -> Option<MatchUpset> {
+    let ratings: HashMap<i32, f64> = adjustments.iter().map(|a| (a.player_id, a.rating_before)).collect();
+
+    let mut compared_pairs = 0;
+    let mut inverted_pairs = 0;
+    let mut inverted_gaps = 0.0;
+    for game in &match_.games {
+        let placed = game
+            .scores
+            .iter()
+            .filter_map(|s| ratings.get(&s.player_id).map(|&rating| (s.placement, rating)))
+            .collect_vec();
+
+        for ((placement_a, rating_a), (placement_b, rating_b)) in placed.iter().tuple_combinations() {
+            if placement_a == placement_b {
+                continue;
+            }
+
+            compared_pairs += 1;
+            let (ahead, behind) = match placement_a < placement_b {
+                true => (rating_a, rating_b),
+                false => (rating_b, rating_a)
+            };
+            if ahead < behind {
+                inverted_pairs += 1;
+                inverted_gaps += behind - ahead;
+            }
+        }
+    }
+
+    (compared_pairs > 0).then(|| MatchUpset {
+        match_id: match_.id,
+        upset_factor: inverted_gaps / compared_pairs as f64,
+        inverted_pairs,
+        compared_pairs
+    })
+}
+
+/// Collects the upset factor of every processed match, see `match_upset`
+#[derive(Debug, Default)]
+pub struct UpsetTracker {
+    pub upsets: Vec<MatchUpset>
+}
+
+impl ProcessingObserver for UpsetTracker {
+    fn on_match_processed(&mut self, match_: &Match, adjustments: &[RatingAdjustment]) {
+        self.upsets.extend(match_upset(match_, adjustments));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{match_upset, UpsetTracker};
+    use crate::{
+        database::db_structs::RatingAdjustment,
+        model::{otr_model::OtrModel, structures::ruleset::Ruleset::Osu},
+        utils::test_utils::{generate_game, generate_match, generate_placement, generate_player_rating}
+    };
+    use approx::assert_abs_diff_eq;
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    fn rated(player_id: i32, rating_before: f64) -> RatingAdjustment {
+        RatingAdjustment {
+            player_id,
+            rating_before,
+            ..generate_player_rating(player_id, Osu, rating_before, 100.0, 1, None, None).adjustments[0].clone()
+        }
+    }
+
+    #[test]
+    fn test_expected_outcome_is_no_upset() {
+        let game = generate_game(1, &[generate_placement(1, 1), generate_placement(2, 2)]);
+        let match_ = generate_match(1, Osu, &[game], Utc::now().fixed_offset());
+
+        let upset = match_upset(&match_, &[rated(1, 1500.0), rated(2, 1000.0)]).unwrap();
+        assert_eq!((upset.inverted_pairs, upset.compared_pairs), (0, 1));
+        assert_eq!(upset.upset_factor, 0.0);
+    }
+
+    #[test]
+    fn test_inversions_are_weighted_by_rating_gap() {
+        let games = [
+            // Player 3 beats both higher rated players
+            generate_game(
+                1,
+                &[
+                    generate_placement(3, 1),
+                    generate_placement(1, 2),
+                    generate_placement(2, 3)
+                ]
+            ),
+            // Ties are not compared, unrated players are ignored
+            generate_game(
+                2,
+                &[
+                    generate_placement(1, 1),
+                    generate_placement(2, 1),
+                    generate_placement(4, 2)
+                ]
+            )
+        ];
+        let match_ = generate_match(1, Osu, &games, Utc::now().fixed_offset());
+
+        let upset = match_upset(&match_, &[rated(1, 1500.0), rated(2, 1200.0), rated(3, 1000.0)]).unwrap();
+        assert_eq!((upset.inverted_pairs, upset.compared_pairs), (2, 3));
+        assert_abs_diff_eq!(upset.upset_factor, (500.0 + 200.0) / 3.0);
+    }
+
+    #[test]
+    fn test_unrated_matches_have_no_upset() {
+        let game = generate_game(1, &[generate_placement(1, 1), generate_placement(2, 2)]);
+        let match_ = generate_match(1, Osu, &[game], Utc::now().fixed_offset());
+
+        assert_eq!(match_upset(&match_, &[]), None);
+    }
+
+    #[test]
+    fn test_tracker_uses_pre_match_ratings() {
+        let ratings = vec![
+            generate_player_rating(1, Osu, 1500.0, 100.0, 1, None, None),
+            generate_player_rating(2, Osu, 1000.0, 100.0, 1, None, None),
+        ];
+        let game = generate_game(1, &[generate_placement(2, 1), generate_placement(1, 2)]);
+        let matches = vec![generate_match(1, Osu, &[game], Utc::now().fixed_offset())];
+
+        let mut tracker = UpsetTracker::default();
+        OtrModel::new(&ratings, &HashMap::new())
+            .process_with_observer(&matches, &mut tracker)
+            .unwrap();
+
+        assert_eq!(tracker.upsets.len(), 1);
+        assert_abs_diff_eq!(tracker.upsets[0].upset_factor, 500.0);
+    }
+}
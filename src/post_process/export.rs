@@ -0,0 +1,58 @@
+use super::post_processor::{PostProcessContext, PostProcessError, PostProcessor};
+use crate::dto::PlayerRatingDto;
+use std::{fs, path::PathBuf};
+
+/// Writes the final ratings, without their adjustments, to a JSON file
+pub struct ExportPostProcessor {
+    pub path: PathBuf
+}
+
+impl PostProcessor for ExportPostProcessor {
+    fn name(&self) -> &'static str {
+        "export"
+    }
+
+    fn run(&self, context: &mut PostProcessContext) -> Result<(), PostProcessError> {
+        let exported = context.ratings.iter().map(PlayerRatingDto::from).collect::<Vec<_>>();
+        let json = serde_json::to_string(&exported).expect("Exported ratings should be serializable");
+
+        fs::write(&self.path, json).map_err(|source| PostProcessError::Io {
+            path: self.path.clone(),
+            source
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExportPostProcessor;
+    use crate::{
+        model::{otr_model::OtrModel, structures::ruleset::Ruleset::Osu},
+        post_process::post_processor::{PostProcessContext, PostProcessor},
+        report::run_report::RunReport,
+        utils::test_utils::generate_player_rating
+    };
+    use std::{collections::HashMap, env, fs};
+
+    #[test]
+    fn test_export_ratings() {
+        let path = env::temp_dir().join(format!("otr-export-{}.json", std::process::id()));
+        let ratings = vec![generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None)];
+        let model = OtrModel::new(&[], &HashMap::new());
+        let mut report = RunReport::default();
+
+        ExportPostProcessor { path: path.clone() }
+            .run(&mut PostProcessContext {
+                ratings: &ratings,
+                model: &model,
+                report: &mut report
+            })
+            .unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(json[0]["playerId"], 1);
+        assert_eq!(json[0]["rating"], 1000.0);
+    }
+}
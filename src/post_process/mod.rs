@@ -0,0 +1,4 @@
+pub mod export;
+pub mod post_processor;
+pub mod stats;
+pub mod validation;
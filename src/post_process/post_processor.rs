@@ -0,0 +1,158 @@
+use super::{export::ExportPostProcessor, stats::StatsPostProcessor, validation::ValidationPostProcessor};
+use crate::{database::db_structs::PlayerRating, model::otr_model::OtrModel, report::run_report::RunReport};
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+    str::FromStr
+};
+use thiserror::Error;
+
+/// Errors which abort a run before its results are saved
+#[derive(Error, Debug)]
+pub enum PostProcessError {
+    #[error("Validation failed: {0}")]
+    Validation(String),
+    #[error("Failed to write {path}: {source}")]
+    Io { path: PathBuf, source: io::Error }
+}
+
+/// Everything a post processor has access to
+pub struct PostProcessContext<'a> {
+    /// The final ratings returned by `OtrModel::process`
+    pub ratings: &'a [PlayerRating],
+    /// The model after processing
+    pub model: &'a OtrModel,
+    /// The report of the current run
+    pub report: &'a mut RunReport
+}
+
+/// A step run after the model has processed all matches and before the results are saved
+pub trait PostProcessor {
+    /// Name used to select the processor and in log messages
+    fn name(&self) -> &'static str;
+
+    /// Runs the step. Returning an error aborts the run without saving.
+    fn run(&self, context: &mut PostProcessContext) -> Result<(), PostProcessError>;
+}
+
+/// The built-in post processors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostProcessorKind {
+    Validate,
+    Stats,
+    Export
+}
+
+impl PostProcessorKind {
+    /// Creates the post processor, using `export_path` as the destination of exports
+    pub fn build(&self, export_path: &Path) -> Box<dyn PostProcessor> {
+        match self {
+            PostProcessorKind::Validate => Box::new(ValidationPostProcessor),
+            PostProcessorKind::Stats => Box::new(StatsPostProcessor),
+            PostProcessorKind::Export => Box::new(ExportPostProcessor {
+                path: export_path.to_path_buf()
+            })
+        }
+    }
+}
+
+impl FromStr for PostProcessorKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "validate" => Ok(PostProcessorKind::Validate),
+            "stats" => Ok(PostProcessorKind::Stats),
+            "export" => Ok(PostProcessorKind::Export),
+            _ => Err(format!(
+                "'{}' is not a post processor (expected validate, stats or export)",
+                s
+            ))
+        }
+    }
+}
+
+impl fmt::Display for PostProcessorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PostProcessorKind::Validate => write!(f, "validate"),
+            PostProcessorKind::Stats => write!(f, "stats"),
+            PostProcessorKind::Export => write!(f, "export")
+        }
+    }
+}
+
+/// Runs each post processor in order, stopping at the first error
+pub fn run_post_processors(
+    processors: &[Box<dyn PostProcessor>],
+    context: &mut PostProcessContext
+) -> Result<(), PostProcessError> {
+    for processor in processors {
+        println!("Running post processor [{}]", processor.name());
+        processor.run(context)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_post_processors, PostProcessContext, PostProcessError, PostProcessor, PostProcessorKind};
+    use crate::{model::otr_model::OtrModel, report::run_report::RunReport};
+    use std::{collections::HashMap, str::FromStr};
+
+    struct Failing;
+
+    impl PostProcessor for Failing {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        fn run(&self, _: &mut PostProcessContext) -> Result<(), PostProcessError> {
+            Err(PostProcessError::Validation("always fails".to_string()))
+        }
+    }
+
+    struct CountMatches;
+
+    impl PostProcessor for CountMatches {
+        fn name(&self) -> &'static str {
+            "count"
+        }
+
+        fn run(&self, context: &mut PostProcessContext) -> Result<(), PostProcessError> {
+            context.report.matches_processed += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_parse_kind() {
+        for kind in [
+            PostProcessorKind::Validate,
+            PostProcessorKind::Stats,
+            PostProcessorKind::Export
+        ] {
+            assert_eq!(PostProcessorKind::from_str(&kind.to_string()), Ok(kind));
+        }
+
+        assert!(PostProcessorKind::from_str("upload").is_err());
+    }
+
+    #[test]
+    fn test_run_stops_at_first_error() {
+        let model = OtrModel::new(&[], &HashMap::new());
+        let mut report = RunReport::default();
+        let mut context = PostProcessContext {
+            ratings: &[],
+            model: &model,
+            report: &mut report
+        };
+
+        let processors: Vec<Box<dyn PostProcessor>> =
+            vec![Box::new(CountMatches), Box::new(Failing), Box::new(CountMatches)];
+
+        assert!(run_post_processors(&processors, &mut context).is_err());
+        assert_eq!(report.matches_processed, 1);
+    }
+}
@@ -0,0 +1,47 @@
+use super::post_processor::{PostProcessContext, PostProcessError, PostProcessor};
+use crate::report::run_report::VolatilityStats;
+
+/// Adds the volatility distribution and per-tournament totals to the run report
+pub struct StatsPostProcessor;
+
+impl PostProcessor for StatsPostProcessor {
+    fn name(&self) -> &'static str {
+        "stats"
+    }
+
+    fn run(&self, context: &mut PostProcessContext) -> Result<(), PostProcessError> {
+        context.report.volatility = VolatilityStats::from_ratings(context.ratings, context.model.config.min_volatility);
+        context.report.tournaments = context.model.stats.summaries();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatsPostProcessor;
+    use crate::{
+        model::{otr_model::OtrModel, structures::ruleset::Ruleset::Osu},
+        post_process::post_processor::{PostProcessContext, PostProcessor},
+        report::run_report::RunReport,
+        utils::test_utils::{generate_country_mapping_player_ratings, generate_player_rating}
+    };
+
+    #[test]
+    fn test_fills_volatility_stats() {
+        let ratings = vec![generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None)];
+        let model = OtrModel::new(&ratings, &generate_country_mapping_player_ratings(&ratings, "US"));
+        let mut report = RunReport::default();
+
+        StatsPostProcessor
+            .run(&mut PostProcessContext {
+                ratings: &ratings,
+                model: &model,
+                report: &mut report
+            })
+            .unwrap();
+
+        assert_eq!(report.volatility.len(), 1);
+        assert_eq!(report.volatility[0].count, 1);
+    }
+}
@@ -0,0 +1,77 @@
+use super::post_processor::{PostProcessContext, PostProcessError, PostProcessor};
+use crate::{database::db_structs::PlayerRating, model::structures::ruleset::Ruleset};
+use std::collections::HashSet;
+
+/// Rejects results containing non-finite or non-positive values, or duplicate ratings
+pub struct ValidationPostProcessor;
+
+impl PostProcessor for ValidationPostProcessor {
+    fn name(&self) -> &'static str {
+        "validate"
+    }
+
+    fn run(&self, context: &mut PostProcessContext) -> Result<(), PostProcessError> {
+        validate_ratings(context.ratings).map_err(PostProcessError::Validation)
+    }
+}
+
+fn validate_ratings(ratings: &[PlayerRating]) -> Result<(), String> {
+    let mut seen: HashSet<(i32, Ruleset)> = HashSet::new();
+
+    for rating in ratings {
+        if !seen.insert((rating.player_id, rating.ruleset)) {
+            return Err(format!(
+                "Duplicate rating for player {} in ruleset {:?}",
+                rating.player_id, rating.ruleset
+            ));
+        }
+
+        if !rating.rating.is_finite() || rating.rating <= 0.0 {
+            return Err(format!(
+                "Invalid rating {} for player {} in ruleset {:?}",
+                rating.rating, rating.player_id, rating.ruleset
+            ));
+        }
+
+        if !rating.volatility.is_finite() || rating.volatility <= 0.0 {
+            return Err(format!(
+                "Invalid volatility {} for player {} in ruleset {:?}",
+                rating.volatility, rating.player_id, rating.ruleset
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_ratings;
+    use crate::{model::structures::ruleset::Ruleset::Osu, utils::test_utils::generate_player_rating};
+
+    #[test]
+    fn test_valid_ratings() {
+        let ratings = vec![
+            generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None),
+            generate_player_rating(2, Osu, 1000.0, 100.0, 1, None, None),
+        ];
+
+        assert!(validate_ratings(&ratings).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let nan = generate_player_rating(1, Osu, f64::NAN, 100.0, 1, None, None);
+        assert!(validate_ratings(&[nan]).is_err());
+
+        let zero_volatility = generate_player_rating(1, Osu, 1000.0, 0.0, 1, None, None);
+        assert!(validate_ratings(&[zero_volatility]).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_ratings() {
+        let rating = generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None);
+
+        assert!(validate_ratings(&[rating.clone(), rating]).is_err());
+    }
+}
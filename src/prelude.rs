@@ -0,0 +1,64 @@
+//! Stable re-exports of the types most commonly needed by downstream tools.
+//!
+//! Prefer importing from here over deep module paths, which may change as the
+//! crate is refactored.
+
+pub use crate::{
+    database::{
+        db::DbClient,
+        db_structs::{
+            Beatmap, DisplayRating, Game, GameModCategory, GameScore, Match, MatchUpset, OverallRating,
+            PercentileMilestone, PlayedMods, Player, PlayerHighestRank, PlayerLobbyStats, PlayerRating,
+            PlayerRestriction, RatingAdjustment, RulesetData
+        },
+        error::DatabaseError,
+        result_store::{ResultStore, RunRecord},
+        sqlite::SqliteStore
+    },
+    dto::{PlayerRatingDto, RatingAdjustmentDto, RatingHistoryDto, RunSummaryDto},
+    error::{Entity, ProcessorError, Stage},
+    model::{
+        beatmaps::{GameBeatmap, Mods},
+        clock::{Clock, FixedClock, SystemClock},
+        countries::InvalidCountry,
+        decay::{DecayError, DecayParameters, DecaySystem, NoDecay, RatingDecay, StandardDecay},
+        leaderboard_checks::LeaderboardError,
+        model_config::ModelConfig,
+        observer::ProcessingObserver,
+        otr_model::OtrModel,
+        otr_model_builder::{OtrModelBuilder, PlackettLuceModel, RatingModel},
+        processing_result::{ProcessingResult, SkippedEntities},
+        rating_snapshot::RatingSnapshot,
+        rating_tracker::RatingTracker,
+        restrictions::Restrictions,
+        ruleset_policy::{ruleset_policy, RulesetPolicy},
+        score_integrity::{IntegrityError, IntegrityIssue, IntegrityPolicy},
+        simulation::{Lineup, ProjectedChange, SimulationError},
+        stats_accumulator::{StatsAccumulator, TournamentStats},
+        structures::{
+            date_range::DateRange, display_scale::DisplayScale, dnf_policy::DnfPolicy,
+            fallback_strategy::FallbackStrategy, game_weights::GameWeights, gamma_strategy::GammaStrategy,
+            integrity_action::IntegrityAction, lobby_beta::LobbyBeta, mod_category::ModCategory,
+            rating_adjustment_type::RatingAdjustmentType, rating_clamps::RatingClamps,
+            returning_boost::ReturningBoost, ruleset::Ruleset, ruleset_filter::RulesetFilter,
+            short_match_policy::ShortMatchPolicy, weight_strategy::WeightStrategy
+        },
+        upsets::UpsetTracker
+    },
+    report::{
+        continuity::{ContinuityCheck, ContinuityDivergence},
+        memory::{MemoryMonitor, MemoryUsage},
+        rank_changes::RankChange,
+        rating_shift::{RatingShift, ShiftGuard},
+        run_report::{
+            ActivityCounts, ClampCounts, CountrySize, DecaySummary, RunReport, VolatilityStats, WarningCount
+        },
+        shards::{ShardReport, ShardedRunReport},
+        slow_log::{SlowLog, SlowOperation},
+        updated_players::UpdateThresholds,
+        warnings::{Warning, WarningKind, WarningSink}
+    }
+};
+
+#[cfg(feature = "serde")]
+pub use crate::model::rating_tracker::RatingTrackerSnapshot;
@@ -0,0 +1,231 @@
+use crate::{
+    database::db_structs::{PlayerRating, RatingAdjustment},
+    model::structures::{rating_adjustment_type::RatingAdjustmentType, ruleset::Ruleset}
+};
+use itertools::Itertools;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Largest difference between a stored and a recomputed rating for both to count as identical
+pub const CONTINUITY_TOLERANCE: f64 = 1e-6;
+
+/// Result of comparing the recomputed rating history of sampled players against the stored one
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinuityCheck {
+    /// Number of (player, ruleset) chains which share at least one match with the stored chain
+    pub chains_compared: usize,
+    /// Number of stored match adjustments compared
+    pub matches_compared: usize,
+    /// The first point at which each diverging chain differs from the stored one
+    pub divergences: Vec<ContinuityDivergence>
+}
+
+/// A stored match adjustment which the run no longer reproduces
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinuityDivergence {
+    pub player_id: i32,
+    pub ruleset: Ruleset,
+    pub match_id: i32,
+    pub stored_rating: f64,
+    /// None if the run did not rate the player in this match at the same point of their history
+    pub computed_rating: Option<f64>
+}
+
+impl ContinuityCheck {
+    pub fn is_continuous(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+/// Picks up to `size` players with a computed rating, spread evenly over their ids
+///
+/// The sample only depends on the rated players, so consecutive runs verify the same players
+/// unless new ones were rated.
+pub fn sample_players(ratings: &[PlayerRating], size: usize) -> Vec<i32> {
+    let player_ids = ratings.iter().map(|r| r.player_id).sorted().dedup().collect_vec();
+    if size == 0 || player_ids.is_empty() {
+        return Vec::new();
+    }
+
+    let step = (player_ids.len() / size).max(1);
+    player_ids.into_iter().step_by(step).take(size).collect()
+}
+
+/// Compares the match adjustments of every `stored` chain against the chain computed for the
+/// same player and ruleset, up to the last match found in both
+///
+/// Stored history is immutable, so the chains must be identical up to that match: the same
+/// matches in the same order with the same ratings before and after each of them. A
+/// difference means the inputs of already rated matches changed, e.g. through an upstream
+/// edit of their scores. Matches after the last common match are new and are not compared.
+///
+/// Chains without a computed counterpart or without a common match are skipped.
+pub fn check_continuity(stored: &[RatingAdjustment], computed: &[PlayerRating]) -> ContinuityCheck {
+    let computed_map: HashMap<(i32, Ruleset), &PlayerRating> =
+        computed.iter().map(|r| ((r.player_id, r.ruleset), r)).collect();
+
+    let mut check = ContinuityCheck::default();
+    for ((player_id, ruleset), stored_chain) in stored
+        .iter()
+        .into_group_map_by(|a| (a.player_id, a.ruleset))
+        .into_iter()
+        .sorted_by_key(|&((player_id, ruleset), _)| (player_id, ruleset as i32))
+    {
+        let Some(rating) = computed_map.get(&(player_id, ruleset)) else {
+            continue;
+        };
+
+        let stored_matches = match_adjustments(stored_chain.into_iter());
+        let computed_matches = match_adjustments(rating.adjustments.iter());
+        let Some(last_common) = stored_matches
+            .iter()
+            .rposition(|s| computed_matches.iter().any(|c| c.match_id == s.match_id))
+        else {
+            continue;
+        };
+
+        check.chains_compared += 1;
+        check.matches_compared += last_common + 1;
+
+        let diverged = stored_matches[..=last_common].iter().enumerate().find_map(|(i, s)| {
+            let c = computed_matches.get(i).filter(|c| c.match_id == s.match_id);
+            let identical = c.is_some_and(|c| {
+                (c.rating_before - s.rating_before).abs() <= CONTINUITY_TOLERANCE
+                    && (c.rating_after - s.rating_after).abs() <= CONTINUITY_TOLERANCE
+            });
+
+            (!identical).then(|| ContinuityDivergence {
+                player_id,
+                ruleset,
+                match_id: s.match_id.unwrap(),
+                stored_rating: s.rating_after,
+                computed_rating: c.map(|c| c.rating_after)
+            })
+        });
+        check.divergences.extend(diverged);
+    }
+
+    check
+}
+
+/// The match adjustments of a chain in chronological order
+fn match_adjustments<'a>(adjustments: impl Iterator<Item = &'a RatingAdjustment>) -> Vec<&'a RatingAdjustment> {
+    adjustments
+        .filter(|a| a.adjustment_type == RatingAdjustmentType::Match && a.match_id.is_some())
+        .sorted_by_key(|a| a.timestamp)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_continuity, sample_players};
+    use crate::{
+        database::db_structs::{PlayerRating, RatingAdjustment},
+        model::structures::{
+            rating_adjustment_type::RatingAdjustmentType,
+            rating_clamps::RatingClamps,
+            ruleset::Ruleset::{Osu, Taiko}
+        },
+        utils::test_utils::generate_player_rating
+    };
+    use chrono::{Duration, TimeZone, Utc};
+    use itertools::Itertools;
+
+    /// A chain of match adjustments, one per `(match_id, rating_after)` a day apart
+    fn chain(player_id: i32, matches: &[(i32, f64)]) -> Vec<RatingAdjustment> {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let mut rating = 1000.0;
+
+        matches
+            .iter()
+            .enumerate()
+            .map(|(i, &(match_id, rating_after))| {
+                let adjustment = RatingAdjustment {
+                    player_id,
+                    ruleset: Osu,
+                    match_id: Some(match_id),
+                    rating_before: rating,
+                    rating_after,
+                    volatility_before: 100.0,
+                    volatility_after: 100.0,
+                    timestamp: start + Duration::days(i as i64),
+                    adjustment_type: RatingAdjustmentType::Match,
+                    average_opponent_rating: None,
+                    provisional: false,
+                    percentile: None,
+                    clamps: RatingClamps::default()
+                };
+                rating = rating_after;
+                adjustment
+            })
+            .collect()
+    }
+
+    fn rating(player_id: i32, adjustments: Vec<RatingAdjustment>) -> PlayerRating {
+        PlayerRating {
+            adjustments,
+            ..generate_player_rating(player_id, Osu, 1000.0, 100.0, 1, None, None)
+        }
+    }
+
+    #[test]
+    fn test_new_matches_are_continuous() {
+        let stored = chain(1, &[(1, 1010.0), (2, 1020.0)]);
+        let computed = vec![rating(1, chain(1, &[(1, 1010.0), (2, 1020.0), (3, 1030.0)]))];
+
+        let check = check_continuity(&stored, &computed);
+        assert!(check.is_continuous());
+        assert_eq!(check.chains_compared, 1);
+        assert_eq!(check.matches_compared, 2);
+    }
+
+    #[test]
+    fn test_edited_match_diverges() {
+        let stored = chain(1, &[(1, 1010.0), (2, 1020.0), (3, 1030.0)]);
+        let computed = vec![rating(1, chain(1, &[(1, 1010.0), (2, 1025.0), (3, 1035.0)]))];
+
+        let check = check_continuity(&stored, &computed);
+        assert_eq!(check.divergences.len(), 1);
+        assert_eq!(check.divergences[0].match_id, 2);
+        assert_eq!(check.divergences[0].computed_rating, Some(1025.0));
+    }
+
+    #[test]
+    fn test_inserted_and_removed_matches_diverge() {
+        let stored = chain(1, &[(1, 1010.0), (2, 1020.0)]);
+
+        // Match 5 was inserted before match 2
+        let inserted = vec![rating(1, chain(1, &[(1, 1010.0), (5, 1015.0), (2, 1020.0)]))];
+        let check = check_continuity(&stored, &inserted);
+        assert_eq!(check.divergences[0].match_id, 2);
+        assert_eq!(check.divergences[0].computed_rating, None);
+
+        // Match 1 was removed
+        let removed = vec![rating(1, chain(1, &[(2, 1020.0)]))];
+        let check = check_continuity(&stored, &removed);
+        assert_eq!(check.divergences[0].match_id, 1);
+    }
+
+    #[test]
+    fn test_unmatched_chains_are_skipped() {
+        let stored = [chain(1, &[(1, 1010.0)]), chain(2, &[(1, 990.0)])].concat();
+        let mut taiko = rating(1, chain(1, &[(1, 1010.0)]));
+        taiko.ruleset = Taiko;
+        let computed = vec![taiko, rating(2, chain(2, &[(2, 990.0)]))];
+
+        let check = check_continuity(&stored, &computed);
+        assert!(check.is_continuous());
+        assert_eq!(check.chains_compared, 0);
+    }
+
+    #[test]
+    fn test_sample_players() {
+        let ratings = (1..=10).map(|id| rating(id, Vec::new())).collect_vec();
+
+        assert_eq!(sample_players(&ratings, 3), vec![1, 4, 7]);
+        assert_eq!(sample_players(&ratings, 20).len(), 10);
+        assert!(sample_players(&ratings, 0).is_empty());
+    }
+}
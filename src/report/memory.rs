@@ -0,0 +1,207 @@
+use serde::Serialize;
+use std::{
+    fmt, fs,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex
+    },
+    thread,
+    time::Duration
+};
+
+/// Default interval between two background samples of the resident memory
+pub const DEFAULT_MEMORY_SAMPLE_INTERVAL_MS: u64 = 1000;
+
+/// Resident memory of the process when a pipeline stage completed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemorySample {
+    pub stage: String,
+    pub rss_mb: u64
+}
+
+/// Memory usage of a run, as listed in the run report
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryUsage {
+    /// Highest resident memory seen by any sample, including the background ones
+    pub peak_rss_mb: u64,
+    pub stages: Vec<MemorySample>
+}
+
+/// The resident memory of the process reached the configured ceiling
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryCeilingExceeded {
+    pub rss_mb: u64,
+    pub ceiling_mb: u64
+}
+
+impl fmt::Display for MemoryCeilingExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "resident memory of {} MB reached the memory ceiling of {} MB",
+            self.rss_mb, self.ceiling_mb
+        )
+    }
+}
+
+/// Samples the resident memory of the process, logging it per stage and enforcing an
+/// optional ceiling
+///
+/// The ceiling lets a run abort with a clear error while nothing has been saved, rather than
+/// being killed by the OOM killer without a trace. Once results start being saved, aborting
+/// would leave them partially written, so the ceiling is no longer enforced from then on.
+#[derive(Debug)]
+pub struct MemoryMonitor {
+    /// None only samples memory without ever aborting
+    ceiling_mb: Option<u64>,
+    enforced: AtomicBool,
+    peak_mb: AtomicU64,
+    stages: Mutex<Vec<MemorySample>>
+}
+
+impl MemoryMonitor {
+    pub fn new(ceiling_mb: Option<u64>) -> MemoryMonitor {
+        MemoryMonitor {
+            ceiling_mb,
+            enforced: AtomicBool::new(true),
+            peak_mb: AtomicU64::new(0),
+            stages: Mutex::new(Vec::new())
+        }
+    }
+
+    /// Samples the resident memory, see `observe`
+    pub fn sample(&self) -> Result<(), MemoryCeilingExceeded> {
+        match resident_set_mb() {
+            Some(rss_mb) => self.observe(rss_mb),
+            None => Ok(())
+        }
+    }
+
+    /// Samples and logs the resident memory after `stage` completed, see `observe`
+    pub fn sample_stage(&self, stage: &str) -> Result<(), MemoryCeilingExceeded> {
+        let Some(rss_mb) = resident_set_mb() else {
+            return Ok(());
+        };
+
+        println!("Memory: stage={} rss_mb={}", stage, rss_mb);
+        self.stages.lock().unwrap().push(MemorySample {
+            stage: stage.to_string(),
+            rss_mb
+        });
+
+        self.observe(rss_mb)
+    }
+
+    /// Records a sample of the resident memory
+    ///
+    /// # Returns
+    /// An error if the ceiling is enforced and the sample reached it
+    pub fn observe(&self, rss_mb: u64) -> Result<(), MemoryCeilingExceeded> {
+        self.peak_mb.fetch_max(rss_mb, Ordering::Relaxed);
+
+        match self.ceiling_mb {
+            Some(ceiling_mb) if rss_mb >= ceiling_mb && self.enforced.load(Ordering::Relaxed) => {
+                Err(MemoryCeilingExceeded { rss_mb, ceiling_mb })
+            }
+            _ => Ok(())
+        }
+    }
+
+    /// Stops enforcing the ceiling, keeping sampling for the report
+    pub fn stop_enforcing(&self) {
+        self.enforced.store(false, Ordering::Relaxed);
+    }
+
+    /// Samples the resident memory every `interval` on a background thread, calling
+    /// `on_exceeded` whenever a sample reaches the enforced ceiling
+    ///
+    /// Stages such as processing the matches run for a long time without yielding, so the
+    /// stage samples alone would not notice them growing past the ceiling.
+    pub fn spawn_sampler<F>(self: &Arc<Self>, interval: Duration, on_exceeded: F)
+    where
+        F: Fn(MemoryCeilingExceeded) + Send + 'static
+    {
+        let monitor = self.clone();
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            if let Err(e) = monitor.sample() {
+                on_exceeded(e);
+            }
+        });
+    }
+
+    /// The stage samples and the peak seen so far
+    pub fn usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            peak_rss_mb: self.peak_mb.load(Ordering::Relaxed),
+            stages: self.stages.lock().unwrap().clone()
+        }
+    }
+}
+
+/// The resident set size of the process in MB, None where `/proc/self/status` is unavailable
+/// (i.e. outside of Linux)
+pub fn resident_set_mb() -> Option<u64> {
+    fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| parse_vm_rss_kb(&status))
+        .map(|kb| kb / 1024)
+}
+
+/// Parses the `VmRSS` line of `/proc/self/status`, which is given in kB
+fn parse_vm_rss_kb(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.trim().strip_suffix("kB"))
+        .and_then(|kb| kb.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_vm_rss_kb, resident_set_mb, MemoryCeilingExceeded, MemoryMonitor};
+
+    #[test]
+    fn test_parse_vm_rss() {
+        let status = "Name:\totr-processor-cli\nVmPeak:\t  204800 kB\nVmRSS:\t  102400 kB\nThreads:\t4\n";
+
+        assert_eq!(parse_vm_rss_kb(status), Some(102400));
+        assert_eq!(parse_vm_rss_kb("Name:\totr-processor-cli\n"), None);
+    }
+
+    #[test]
+    fn test_ceiling() {
+        let monitor = MemoryMonitor::new(Some(512));
+
+        assert!(monitor.observe(511).is_ok());
+        assert_eq!(
+            monitor.observe(600),
+            Err(MemoryCeilingExceeded {
+                rss_mb: 600,
+                ceiling_mb: 512
+            })
+        );
+
+        // Samples are still tracked once the ceiling is no longer enforced
+        monitor.stop_enforcing();
+        assert!(monitor.observe(700).is_ok());
+        assert_eq!(monitor.usage().peak_rss_mb, 700);
+    }
+
+    #[test]
+    fn test_stage_samples() {
+        let monitor = MemoryMonitor::new(None);
+        monitor.sample_stage("process").unwrap();
+
+        let usage = monitor.usage();
+        if resident_set_mb().is_some() {
+            assert_eq!(usage.stages.len(), 1);
+            assert_eq!(usage.stages[0].stage, "process");
+            assert_eq!(usage.peak_rss_mb, usage.stages[0].rss_mb);
+        } else {
+            assert!(usage.stages.is_empty());
+        }
+    }
+}
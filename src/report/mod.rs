@@ -0,0 +1,9 @@
+pub mod continuity;
+pub mod memory;
+pub mod rank_changes;
+pub mod rating_shift;
+pub mod run_report;
+pub mod shards;
+pub mod slow_log;
+pub mod updated_players;
+pub mod warnings;
@@ -0,0 +1,131 @@
+use crate::{database::db_structs::PlayerRating, model::structures::ruleset::Ruleset};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Default global ranks a player must cross for their rank change to be notified
+pub const DEFAULT_NOTABLE_RANKS: [i32; 3] = [100, 500, 1000];
+
+/// A player entering or leaving a notable part of a ruleset's leaderboard
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankChange {
+    pub player_id: i32,
+    pub ruleset: Ruleset,
+    /// None if the player was not rated before the run
+    pub old_rank: Option<i32>,
+    pub new_rank: i32,
+    /// Number of ranks climbed, negative if the player dropped. None if they were not rated
+    /// before the run.
+    pub delta: Option<i32>,
+    /// The most notable (smallest) rank crossed
+    pub threshold: i32
+}
+
+/// Lists the players whose global rank crossed one of the `notable_ranks` in either direction
+///
+/// A player crosses rank `n` when they were ranked below `n` (or not rated) before the run and
+/// are ranked `n` or above after it, or the other way round. Players who are no longer rated
+/// are not listed.
+///
+/// # Returns
+/// The rank changes ordered by ruleset and new rank
+pub fn rank_changes(previous: &[PlayerRating], current: &[PlayerRating], notable_ranks: &[i32]) -> Vec<RankChange> {
+    let previous_ranks: HashMap<(i32, Ruleset), i32> = previous
+        .iter()
+        .map(|r| ((r.player_id, r.ruleset), r.global_rank))
+        .collect();
+
+    let mut changes = current
+        .iter()
+        .filter_map(|rating| {
+            let old_rank = previous_ranks.get(&(rating.player_id, rating.ruleset)).copied();
+            let new_rank = rating.global_rank;
+            let within = |rank: Option<i32>, threshold: i32| rank.is_some_and(|rank| rank <= threshold);
+
+            let threshold = notable_ranks
+                .iter()
+                .copied()
+                .filter(|&threshold| within(old_rank, threshold) != within(Some(new_rank), threshold))
+                .min()?;
+
+            Some(RankChange {
+                player_id: rating.player_id,
+                ruleset: rating.ruleset,
+                old_rank,
+                new_rank,
+                delta: old_rank.map(|old_rank| old_rank - new_rank),
+                threshold
+            })
+        })
+        .collect::<Vec<_>>();
+
+    changes.sort_by_key(|c| (c.ruleset as i32, c.new_rank, c.player_id));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rank_changes, DEFAULT_NOTABLE_RANKS};
+    use crate::{
+        database::db_structs::PlayerRating,
+        model::structures::ruleset::Ruleset::{Osu, Taiko},
+        utils::test_utils::generate_player_rating
+    };
+
+    fn ranked(player_id: i32, global_rank: i32) -> PlayerRating {
+        PlayerRating {
+            global_rank,
+            ..generate_player_rating(player_id, Osu, 1000.0, 100.0, 1, None, None)
+        }
+    }
+
+    #[test]
+    fn test_rank_changes() {
+        let previous = vec![
+            ranked(1, 150),
+            ranked(2, 90),
+            ranked(3, 600),
+            ranked(4, 1200),
+            ranked(5, 20),
+        ];
+        let current = vec![
+            // Entered the top 100
+            ranked(1, 80),
+            // Left the top 100
+            ranked(2, 110),
+            // Moved within the top 1000
+            ranked(3, 550),
+            // Entered both the top 1000 and the top 500
+            ranked(4, 400),
+            // Newly rated in the top 1000
+            ranked(6, 700),
+        ];
+
+        let changes = rank_changes(&previous, &current, &DEFAULT_NOTABLE_RANKS)
+            .iter()
+            .map(|c| (c.player_id, c.old_rank, c.delta, c.threshold))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            vec![
+                (1, Some(150), Some(70), 100),
+                (2, Some(90), Some(-20), 100),
+                (4, Some(1200), Some(800), 500),
+                (6, None, None, 1000),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rulesets_are_compared_separately() {
+        let previous = vec![ranked(1, 50)];
+        let mut taiko = ranked(1, 50);
+        taiko.ruleset = Taiko;
+
+        let changes = rank_changes(&previous, &[taiko], &DEFAULT_NOTABLE_RANKS);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].ruleset, Taiko);
+        assert_eq!(changes[0].old_rank, None);
+    }
+}
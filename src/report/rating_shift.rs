@@ -0,0 +1,121 @@
+use crate::{database::db_structs::PlayerRating, model::structures::ruleset::Ruleset};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Default minimum rating change for a rating to count as shifted
+pub const DEFAULT_SHIFT_THRESHOLD: f64 = 100.0;
+/// Default largest fraction of compared ratings which may shift before a run is aborted
+pub const DEFAULT_MAX_SHIFT_FRACTION: f64 = 0.1;
+
+/// Limits how much a run may change the stored ratings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShiftGuard {
+    /// Minimum absolute rating change for a rating to count as shifted
+    pub threshold: f64,
+    /// Largest allowed fraction of shifted ratings
+    pub max_fraction: f64
+}
+
+impl Default for ShiftGuard {
+    fn default() -> Self {
+        ShiftGuard {
+            threshold: DEFAULT_SHIFT_THRESHOLD,
+            max_fraction: DEFAULT_MAX_SHIFT_FRACTION
+        }
+    }
+}
+
+/// How many stored ratings a run would shift
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RatingShift {
+    /// Number of (player, ruleset) ratings present both before and after the run
+    pub ratings_compared: usize,
+    /// Number of compared ratings which changed by at least the threshold
+    pub ratings_shifted: usize,
+    /// `ratings_shifted / ratings_compared`, or 0 if nothing was compared
+    pub fraction_shifted: f64,
+    /// Whether the shift exceeds the guard's maximum fraction
+    pub exceeds_limit: bool
+}
+
+impl ShiftGuard {
+    /// Measures how many of the `previous` ratings are shifted in `current`.
+    ///
+    /// Ratings only present on one side are new or removed and are not compared.
+    pub fn measure(&self, previous: &[PlayerRating], current: &[PlayerRating]) -> RatingShift {
+        let current_map: HashMap<(i32, Ruleset), f64> =
+            current.iter().map(|r| ((r.player_id, r.ruleset), r.rating)).collect();
+
+        let changes = previous
+            .iter()
+            .filter_map(|before| {
+                current_map
+                    .get(&(before.player_id, before.ruleset))
+                    .map(|after| (after - before.rating).abs())
+            })
+            .collect::<Vec<_>>();
+
+        let ratings_compared = changes.len();
+        let ratings_shifted = changes.iter().filter(|&&change| change >= self.threshold).count();
+        let fraction_shifted = if ratings_compared == 0 {
+            0.0
+        } else {
+            ratings_shifted as f64 / ratings_compared as f64
+        };
+
+        RatingShift {
+            ratings_compared,
+            ratings_shifted,
+            fraction_shifted,
+            exceeds_limit: fraction_shifted > self.max_fraction
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ShiftGuard;
+    use crate::{model::structures::ruleset::Ruleset::Osu, utils::test_utils::generate_player_rating};
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_measure_shift() {
+        let previous = (1..=4)
+            .map(|id| generate_player_rating(id, Osu, 1000.0, 100.0, 2, None, None))
+            .collect::<Vec<_>>();
+        let current = vec![
+            generate_player_rating(1, Osu, 1150.0, 100.0, 2, None, None),
+            generate_player_rating(2, Osu, 1050.0, 100.0, 2, None, None),
+            generate_player_rating(3, Osu, 1000.0, 100.0, 2, None, None),
+            // New players are not compared
+            generate_player_rating(5, Osu, 2000.0, 100.0, 2, None, None),
+        ];
+
+        let guard = ShiftGuard {
+            threshold: 100.0,
+            max_fraction: 0.25
+        };
+        let shift = guard.measure(&previous, &current);
+
+        assert_eq!(shift.ratings_compared, 3);
+        assert_eq!(shift.ratings_shifted, 1);
+        assert_abs_diff_eq!(shift.fraction_shifted, 1.0 / 3.0);
+        assert!(shift.exceeds_limit);
+
+        let lenient = ShiftGuard {
+            max_fraction: 0.5,
+            ..guard
+        };
+        assert!(!lenient.measure(&previous, &current).exceeds_limit);
+    }
+
+    #[test]
+    fn test_nothing_to_compare() {
+        let current = vec![generate_player_rating(1, Osu, 1000.0, 100.0, 2, None, None)];
+        let shift = ShiftGuard::default().measure(&[], &current);
+
+        assert_eq!(shift.ratings_compared, 0);
+        assert!(!shift.exceeds_limit);
+    }
+}
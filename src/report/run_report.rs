@@ -0,0 +1,541 @@
+use super::{
+    continuity::ContinuityCheck, memory::MemoryUsage, rank_changes::RankChange, rating_shift::RatingShift,
+    slow_log::SlowOperation, warnings::WarningKind
+};
+use crate::{
+    cli::effective_config::EffectiveConfig,
+    database::db_structs::{PlayerActivity, PlayerRating},
+    model::{
+        constants::DEFAULT_VOLATILITY,
+        countries::InvalidCountry,
+        decay::DecaySystem,
+        model_config::ModelConfig,
+        processing_result::SkippedEntities,
+        rating_tracker::RatingTracker,
+        score_integrity::IntegrityIssue,
+        stats_accumulator::TournamentStats,
+        structures::{
+            activity::Activity, date_range::DateRange, rating_adjustment_type::RatingAdjustmentType,
+            rating_clamps::RatingClamps, ruleset::Ruleset
+        }
+    }
+};
+use itertools::Itertools;
+use serde::Serialize;
+use std::{fs, io, path::Path};
+use strum::IntoEnumIterator;
+
+/// Default minimum number of rated players a country needs in a ruleset for its
+/// country ranks to be considered meaningful
+pub const DEFAULT_MIN_COUNTRY_SIZE: usize = 5;
+
+/// Summary of a processing run
+///
+/// Populated by the pipeline as processing progresses and written out once the run
+/// completes, giving operators a single place to inspect data quality and model behavior.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunReport {
+    /// Configuration the run was started with
+    pub config: Option<EffectiveConfig>,
+    /// Number of matches processed
+    pub matches_processed: usize,
+    /// Matches and games which could not be rated
+    pub skipped: SkippedEntities,
+    /// Number of processed matches which were exempt from rating
+    pub rating_exempt_matches: usize,
+    /// Ids of matches left unprocessed because they have no start time
+    pub skipped_without_start_time: Vec<i32>,
+    /// Ids of matches whose missing start time was imputed from their games
+    pub imputed_start_time: Vec<i32>,
+    /// Scores which failed an integrity check and were handled as set by the integrity policy
+    pub integrity_issues: Vec<IntegrityIssue>,
+    /// Number of scores removed because they belong to excluded players
+    pub excluded_scores: usize,
+    /// Ids of players referenced by scores but missing from the players table
+    pub missing_players: Vec<i32>,
+    /// Number of ratings belonging to players without a known country
+    pub unknown_country_players: usize,
+    /// Stored country codes which are not valid ISO 3166-1 alpha-2 codes. Their players are
+    /// treated as having an unknown country.
+    pub invalid_countries: Vec<InvalidCountry>,
+    /// Number of rated players of every country and ruleset. Country ranks are computed for
+    /// all of them, but ranks of flagged countries should not be presented as meaningful.
+    pub countries: Vec<CountrySize>,
+    /// Ids of players whose stored rating or rank changed beyond the update thresholds,
+    /// allowing consumers to selectively invalidate cached player data
+    pub updated_players: Vec<i32>,
+    /// Players whose global rank crossed one of the notable ranks, see `rank_changes`
+    pub rank_changes: Vec<RankChange>,
+    /// How many stored ratings the run shifted beyond the shift guard's threshold
+    pub rating_shift: Option<RatingShift>,
+    /// How the recomputed history of the players sampled by `--verify-continuity` compares
+    /// against their stored history
+    pub continuity: Option<ContinuityCheck>,
+    /// Number of warnings raised of every kind, see `WarningSink`
+    pub warnings: Vec<WarningCount>,
+    /// Number of active, decaying and dormant players of every ruleset
+    pub activity: Vec<ActivityCounts>,
+    /// Decay applied within the processed date range of every ruleset
+    pub decay: Vec<DecaySummary>,
+    /// How often match and manual adjustments within the processed date range were clamped to
+    /// the rating and volatility bounds, for every ruleset
+    pub clamps: Vec<ClampCounts>,
+    /// Distribution of final volatility values per ruleset
+    pub volatility: Vec<VolatilityStats>,
+    /// Processing totals of each tournament
+    pub tournaments: Vec<TournamentStats>,
+    /// Queries and pipeline stages which exceeded the slow log threshold
+    pub slow_operations: Vec<SlowOperation>,
+    /// Resident memory after each pipeline stage and at its peak, None where it cannot be
+    /// measured
+    pub memory: Option<MemoryUsage>
+}
+
+impl RunReport {
+    /// Serializes the report as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Run report should be serializable")
+    }
+
+    /// Writes the report as JSON to the given path
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+}
+
+/// Number of rated players of a country within a single ruleset
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CountrySize {
+    pub country: String,
+    pub ruleset: Ruleset,
+    pub players: usize,
+    /// Whether the country has fewer rated players than the configured minimum
+    pub below_minimum: bool
+}
+
+impl CountrySize {
+    /// Lists the size of every (country, ruleset) pair in the tracker, flagging those with
+    /// fewer than `min_players` rated players
+    ///
+    /// Only accurate after the tracker has been sorted
+    pub fn from_tracker(tracker: &RatingTracker, min_players: usize) -> Vec<CountrySize> {
+        tracker
+            .country_sizes()
+            .into_iter()
+            .map(|(country, ruleset, players)| CountrySize {
+                country,
+                ruleset,
+                players,
+                below_minimum: players < min_players
+            })
+            .collect()
+    }
+}
+
+/// Number of warnings of a single kind raised during a run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarningCount {
+    pub kind: WarningKind,
+    pub count: usize
+}
+
+/// Number of players of each activity class within a single ruleset
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityCounts {
+    pub ruleset: Ruleset,
+    pub active: usize,
+    pub decaying: usize,
+    pub dormant: usize
+}
+
+impl ActivityCounts {
+    /// Counts the activity classes of every ruleset with at least one player
+    pub fn from_activities(activities: &[PlayerActivity]) -> Vec<ActivityCounts> {
+        Ruleset::iter()
+            .filter_map(|ruleset| {
+                let mut counts = ActivityCounts {
+                    ruleset,
+                    active: 0,
+                    decaying: 0,
+                    dormant: 0
+                };
+                for activity in activities.iter().filter(|a| a.ruleset == ruleset) {
+                    match activity.activity {
+                        Activity::Active => counts.active += 1,
+                        Activity::Decaying => counts.decaying += 1,
+                        Activity::Dormant => counts.dormant += 1
+                    }
+                }
+
+                (counts.active + counts.decaying + counts.dormant > 0).then_some(counts)
+            })
+            .collect()
+    }
+}
+
+/// Decay applied to the players of a single ruleset
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecaySummary {
+    pub ruleset: Ruleset,
+    /// Number of players who lost rating to decay
+    pub players_decayed: usize,
+    /// Rating lost to decay by all players combined
+    pub rating_lost: f64,
+    /// Number of players whose rating decayed down to their decay floor
+    pub reached_floor: usize,
+    /// Number of players whose volatility grew to the cap during decay
+    pub volatility_capped: usize
+}
+
+impl DecaySummary {
+    /// Summarizes the decay adjustments within `range`, for every ruleset with at least one
+    ///
+    /// The decay floor of each adjustment is derived from the peak rating reached before it,
+    /// with the decay parameters of its ruleset in `config`.
+    pub fn from_ratings(ratings: &[PlayerRating], config: &ModelConfig, range: &DateRange) -> Vec<DecaySummary> {
+        Ruleset::iter()
+            .filter_map(|ruleset| {
+                let system = DecaySystem::with_parameters(config.decay_time(), config.decay_parameters(ruleset));
+                let mut summary = DecaySummary {
+                    ruleset,
+                    players_decayed: 0,
+                    rating_lost: 0.0,
+                    reached_floor: 0,
+                    volatility_capped: 0
+                };
+                let mut any_decay = false;
+
+                for rating in ratings.iter().filter(|r| r.ruleset == ruleset) {
+                    let (mut lost, mut reached_floor, mut capped) = (0.0, false, false);
+                    let mut peak_rating = f64::NEG_INFINITY;
+                    for adjustment in &rating.adjustments {
+                        peak_rating = peak_rating.max(adjustment.rating_after);
+                        if adjustment.adjustment_type != RatingAdjustmentType::Decay
+                            || !range.contains(adjustment.timestamp)
+                        {
+                            continue;
+                        }
+
+                        any_decay = true;
+                        let loss = adjustment.rating_before - adjustment.rating_after;
+                        lost += loss;
+                        reached_floor |=
+                            loss > 0.0 && adjustment.rating_after <= system.decay_floor_of_peak(peak_rating);
+                        capped |= adjustment.volatility_after >= DEFAULT_VOLATILITY;
+                    }
+
+                    if lost > 0.0 {
+                        summary.players_decayed += 1;
+                        summary.rating_lost += lost;
+                    }
+                    summary.reached_floor += reached_floor as usize;
+                    summary.volatility_capped += capped as usize;
+                }
+
+                any_decay.then_some(summary)
+            })
+            .collect()
+    }
+}
+
+/// Number of adjustments of a single ruleset clamped to each bound, see `RatingClamps`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClampCounts {
+    pub ruleset: Ruleset,
+    /// Number of match and manual adjustments, clamped or not
+    pub adjustments: usize,
+    pub rating_floor: usize,
+    pub volatility_floor: usize,
+    pub volatility_cap: usize
+}
+
+impl ClampCounts {
+    /// Counts the clamped match and manual adjustments within `range`, for every ruleset with
+    /// at least one
+    pub fn from_ratings(ratings: &[PlayerRating], range: &DateRange) -> Vec<ClampCounts> {
+        Ruleset::iter()
+            .filter_map(|ruleset| {
+                let clamps = ratings
+                    .iter()
+                    .filter(|r| r.ruleset == ruleset)
+                    .flat_map(|r| &r.adjustments)
+                    .filter(|a| {
+                        matches!(
+                            a.adjustment_type,
+                            RatingAdjustmentType::Match | RatingAdjustmentType::Manual
+                        ) && range.contains(a.timestamp)
+                    })
+                    .map(|a| a.clamps)
+                    .collect_vec();
+                if clamps.is_empty() {
+                    return None;
+                }
+
+                let count = |flag| clamps.iter().filter(|c| c.contains(flag)).count();
+                Some(ClampCounts {
+                    ruleset,
+                    adjustments: clamps.len(),
+                    rating_floor: count(RatingClamps::RATING_FLOOR),
+                    volatility_floor: count(RatingClamps::VOLATILITY_FLOOR),
+                    volatility_cap: count(RatingClamps::VOLATILITY_CAP)
+                })
+            })
+            .collect()
+    }
+}
+
+/// Distribution statistics of player volatility within a single ruleset
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VolatilityStats {
+    pub ruleset: Ruleset,
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub p10: f64,
+    pub median: f64,
+    pub p90: f64,
+    /// Number of players whose volatility is at the configured floor
+    pub at_floor: usize
+}
+
+impl VolatilityStats {
+    /// Computes volatility statistics for every ruleset with at least one rating
+    pub fn from_ratings(ratings: &[PlayerRating], min_volatility: f64) -> Vec<VolatilityStats> {
+        Ruleset::iter()
+            .filter_map(|ruleset| {
+                let volatilities = ratings
+                    .iter()
+                    .filter(|r| r.ruleset == ruleset)
+                    .map(|r| r.volatility)
+                    .sorted_by(|a, b| a.total_cmp(b))
+                    .collect_vec();
+
+                Self::from_sorted(ruleset, &volatilities, min_volatility)
+            })
+            .collect()
+    }
+
+    fn from_sorted(ruleset: Ruleset, sorted: &[f64], min_volatility: f64) -> Option<VolatilityStats> {
+        if sorted.is_empty() {
+            return None;
+        }
+
+        Some(VolatilityStats {
+            ruleset,
+            count: sorted.len(),
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            p10: percentile(sorted, 10.0),
+            median: percentile(sorted, 50.0),
+            p90: percentile(sorted, 90.0),
+            at_floor: sorted.iter().filter(|&&v| v <= min_volatility).count()
+        })
+    }
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{percentile, ActivityCounts, ClampCounts, CountrySize, DecaySummary, RunReport, VolatilityStats};
+    use crate::{
+        database::db_structs::PlayerActivity,
+        model::{
+            constants::DECAY_DAYS,
+            decay::DecaySystem,
+            model_config::ModelConfig,
+            rating_tracker::RatingTracker,
+            structures::{
+                activity::Activity,
+                date_range::DateRange,
+                rating_clamps::RatingClamps,
+                ruleset::Ruleset::{Osu, Taiko}
+            }
+        },
+        utils::test_utils::generate_player_rating
+    };
+    use approx::assert_abs_diff_eq;
+    use chrono::{Duration, TimeZone, Utc};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_decay_summary() {
+        let last_played = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let config = ModelConfig::default();
+        let rating = |player_id, volatility, weeks| {
+            let mut rating =
+                generate_player_rating(player_id, Osu, 1500.0, volatility, 2, Some(last_played), Some(last_played));
+            let decay_time = last_played + Duration::days(DECAY_DAYS as i64) + Duration::weeks(weeks);
+            DecaySystem::new(decay_time).decay(&mut rating).unwrap();
+            rating
+        };
+        // Two weeks of decay, and enough weeks to reach the floor and the volatility cap
+        let ratings = vec![
+            rating(1, 200.0, 2),
+            rating(2, 290.0, 1000),
+            generate_player_rating(3, Taiko, 1500.0, 200.0, 2, None, None),
+        ];
+
+        let summaries = DecaySummary::from_ratings(&ratings, &config, &DateRange::default());
+        assert_eq!(summaries.len(), 1);
+        let summary = &summaries[0];
+        assert_eq!(summary.ruleset, Osu);
+        assert_eq!(summary.players_decayed, 2);
+        let lost = ratings[..2]
+            .iter()
+            .map(|r| r.adjustments[1].rating_after - r.rating)
+            .sum::<f64>();
+        assert_abs_diff_eq!(summary.rating_lost, lost, epsilon = 1e-6);
+        assert_eq!((summary.reached_floor, summary.volatility_capped), (1, 1));
+
+        // Decay outside of the processed range is not part of the run
+        let range = DateRange::new(Some(last_played + Duration::weeks(10_000)), None);
+        assert!(DecaySummary::from_ratings(&ratings, &config, &range).is_empty());
+    }
+
+    #[test]
+    fn test_activity_counts() {
+        let activities = [
+            (1, Osu, Activity::Active),
+            (2, Osu, Activity::Dormant),
+            (1, Taiko, Activity::Active)
+        ]
+        .map(|(player_id, ruleset, activity)| PlayerActivity {
+            player_id,
+            ruleset,
+            activity
+        });
+
+        assert_eq!(
+            ActivityCounts::from_activities(&activities),
+            vec![
+                ActivityCounts {
+                    ruleset: Osu,
+                    active: 1,
+                    decaying: 0,
+                    dormant: 1
+                },
+                ActivityCounts {
+                    ruleset: Taiko,
+                    active: 1,
+                    decaying: 0,
+                    dormant: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_percentile() {
+        let values = (1..=10).map(|v| v as f64).collect::<Vec<_>>();
+
+        assert_abs_diff_eq!(percentile(&values, 10.0), 1.0);
+        assert_abs_diff_eq!(percentile(&values, 50.0), 5.0);
+        assert_abs_diff_eq!(percentile(&values, 90.0), 9.0);
+        assert_abs_diff_eq!(percentile(&values, 100.0), 10.0);
+        assert_abs_diff_eq!(percentile(&[3.0], 10.0), 3.0);
+    }
+
+    #[test]
+    fn test_volatility_stats() {
+        let ratings = vec![
+            generate_player_rating(1, Osu, 1000.0, 60.0, 1, None, None),
+            generate_player_rating(2, Osu, 1000.0, 100.0, 1, None, None),
+            generate_player_rating(3, Osu, 1000.0, 200.0, 1, None, None),
+            generate_player_rating(4, Taiko, 1000.0, 150.0, 1, None, None),
+        ];
+
+        let stats = VolatilityStats::from_ratings(&ratings, 60.0);
+
+        assert_eq!(stats.len(), 2);
+
+        let osu = &stats[0];
+        assert_eq!(osu.ruleset, Osu);
+        assert_eq!(osu.count, 3);
+        assert_abs_diff_eq!(osu.min, 60.0);
+        assert_abs_diff_eq!(osu.max, 200.0);
+        assert_abs_diff_eq!(osu.mean, 120.0);
+        assert_abs_diff_eq!(osu.median, 100.0);
+        assert_eq!(osu.at_floor, 1);
+
+        let taiko = &stats[1];
+        assert_eq!(taiko.ruleset, Taiko);
+        assert_eq!(taiko.count, 1);
+        assert_eq!(taiko.at_floor, 0);
+    }
+
+    #[test]
+    fn test_clamp_counts() {
+        let mut floored = generate_player_rating(1, Osu, 100.0, 200.0, 3, None, None);
+        floored.adjustments[1].clamps = RatingClamps(RatingClamps::RATING_FLOOR | RatingClamps::VOLATILITY_CAP);
+        floored.adjustments[2].clamps = RatingClamps(RatingClamps::RATING_FLOOR);
+        // Initial adjustments are never clamped by the model, so they are not counted
+        floored.adjustments[0].clamps = RatingClamps(RatingClamps::VOLATILITY_FLOOR);
+        let ratings = vec![floored, generate_player_rating(2, Osu, 1000.0, 200.0, 2, None, None)];
+
+        let counts = ClampCounts::from_ratings(&ratings, &DateRange::default());
+
+        assert_eq!(
+            counts,
+            vec![ClampCounts {
+                ruleset: Osu,
+                adjustments: 3,
+                rating_floor: 2,
+                volatility_floor: 0,
+                volatility_cap: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn test_country_sizes_flag_small_countries() {
+        let mut tracker = RatingTracker::new();
+        tracker.set_country_mapping(HashMap::from([
+            (1, "US".to_string()),
+            (2, "US".to_string()),
+            (3, "IS".to_string())
+        ]));
+        tracker.insert_or_update(&[
+            generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None),
+            generate_player_rating(2, Osu, 1100.0, 100.0, 1, None, None),
+            generate_player_rating(3, Osu, 1200.0, 100.0, 1, None, None)
+        ]);
+        tracker.sort();
+
+        let sizes = CountrySize::from_tracker(&tracker, 2);
+
+        assert_eq!(sizes.len(), 2);
+        assert_eq!(sizes[0].country, "IS");
+        assert!(sizes[0].below_minimum);
+        assert_eq!(sizes[1].players, 2);
+        assert!(!sizes[1].below_minimum);
+
+        // The single player is still ranked within their country
+        assert_eq!(tracker.get_rating(3, Osu).unwrap().country_rank, 1);
+    }
+
+    #[test]
+    fn test_report_serializes_camel_case() {
+        let report = RunReport {
+            matches_processed: 5,
+            ..Default::default()
+        };
+
+        let json = report.to_json();
+        assert!(json.contains("\"matchesProcessed\": 5"));
+        assert!(json.contains("\"unknownCountryPlayers\": 0"));
+    }
+}
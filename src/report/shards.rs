@@ -0,0 +1,136 @@
+use super::rank_changes::RankChange;
+use crate::model::structures::ruleset::Ruleset;
+use itertools::Itertools;
+use serde::Serialize;
+use serde_json::Value;
+use std::{fs, io, path::Path};
+
+/// The run report stored by the shard processing a single ruleset, see `--shard`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShardReport {
+    pub ruleset: Ruleset,
+    pub report: Value
+}
+
+/// The run reports of all shards of a run split by ruleset, merged once every shard finished
+///
+/// Only the totals consumers act on are merged. Everything else describes a single shard and is
+/// found in its own report.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShardedRunReport {
+    /// Number of matches processed by all shards
+    pub matches_processed: u64,
+    /// Ids of the players updated by any shard
+    pub updated_players: Vec<i32>,
+    /// Rank changes of all shards, ordered by ruleset and new rank
+    pub rank_changes: Vec<RankChange>,
+    pub shards: Vec<ShardReport>
+}
+
+impl ShardedRunReport {
+    pub fn merge(mut shards: Vec<ShardReport>) -> ShardedRunReport {
+        shards.sort_by_key(|shard| shard.ruleset as i32);
+
+        let mut merged = ShardedRunReport::default();
+        for shard in &shards {
+            let field = |name: &str| shard.report.get(name).cloned().unwrap_or_default();
+
+            merged.matches_processed += field("matchesProcessed").as_u64().unwrap_or_default();
+            merged
+                .updated_players
+                .extend(serde_json::from_value::<Vec<i32>>(field("updatedPlayers")).unwrap_or_default());
+            merged
+                .rank_changes
+                .extend(serde_json::from_value::<Vec<RankChange>>(field("rankChanges")).unwrap_or_default());
+        }
+        merged.updated_players = merged.updated_players.into_iter().sorted().dedup().collect();
+        merged.shards = shards;
+
+        merged
+    }
+
+    /// Serializes the report as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Sharded run report should be serializable")
+    }
+
+    /// Writes the report as JSON to the given path
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+}
+
+/// The rulesets of `expected` without a shard report
+pub fn missing_shards(expected: &[Ruleset], reports: &[ShardReport]) -> Vec<Ruleset> {
+    expected
+        .iter()
+        .copied()
+        .filter(|&ruleset| reports.iter().all(|report| report.ruleset != ruleset))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{missing_shards, ShardReport, ShardedRunReport};
+    use crate::{
+        model::structures::ruleset::{
+            Ruleset,
+            Ruleset::{Catch, Osu, Taiko}
+        },
+        report::{rank_changes::RankChange, run_report::RunReport}
+    };
+
+    fn shard(ruleset: Ruleset, report: RunReport) -> ShardReport {
+        ShardReport {
+            ruleset,
+            report: serde_json::from_str(&report.to_json()).unwrap()
+        }
+    }
+
+    fn rank_change(player_id: i32, ruleset: Ruleset) -> RankChange {
+        RankChange {
+            player_id,
+            ruleset,
+            old_rank: None,
+            new_rank: 50,
+            delta: None,
+            threshold: 100
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let osu = RunReport {
+            matches_processed: 10,
+            updated_players: vec![3, 1],
+            rank_changes: vec![rank_change(1, Osu)],
+            ..Default::default()
+        };
+        let taiko = RunReport {
+            matches_processed: 5,
+            updated_players: vec![1, 2],
+            rank_changes: vec![rank_change(2, Taiko)],
+            ..Default::default()
+        };
+
+        let merged = ShardedRunReport::merge(vec![shard(Taiko, taiko), shard(Osu, osu)]);
+
+        assert_eq!(merged.matches_processed, 15);
+        assert_eq!(merged.updated_players, vec![1, 2, 3]);
+        assert_eq!(merged.rank_changes, vec![rank_change(1, Osu), rank_change(2, Taiko)]);
+        assert_eq!(
+            merged.shards.iter().map(|s| s.ruleset).collect::<Vec<_>>(),
+            vec![Osu, Taiko]
+        );
+    }
+
+    #[test]
+    fn test_missing_shards() {
+        let reports = vec![shard(Osu, RunReport::default())];
+
+        assert_eq!(missing_shards(&[Osu, Taiko, Catch], &reports), vec![Taiko, Catch]);
+        assert!(missing_shards(&[Osu], &reports).is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+use serde::Serialize;
+use std::{
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant}
+};
+
+/// Default duration after which a query or stage is reported as slow
+pub const DEFAULT_SLOW_THRESHOLD_MS: u64 = 5000;
+
+/// Whether a slow operation was a database query or a pipeline stage
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OperationKind {
+    Query,
+    Stage
+}
+
+impl fmt::Display for OperationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OperationKind::Query => write!(f, "query"),
+            OperationKind::Stage => write!(f, "stage")
+        }
+    }
+}
+
+/// A query or stage which took at least the slow log's threshold
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlowOperation {
+    pub kind: OperationKind,
+    pub name: String,
+    pub duration_ms: u64,
+    /// Number of rows read or written by a query, or items handled by a stage
+    pub rows: usize
+}
+
+/// Collects the queries and stages of a run which exceed a duration threshold
+///
+/// Every slow operation is logged as a warning when it completes and kept for the run
+/// report. Shared between the database client and the pipeline, hence the interior
+/// mutability.
+#[derive(Debug, Default)]
+pub struct SlowLog {
+    /// Operations taking at least this long are slow. None disables the log.
+    threshold: Option<Duration>,
+    operations: Mutex<Vec<SlowOperation>>
+}
+
+impl SlowLog {
+    pub fn new(threshold: Duration) -> SlowLog {
+        SlowLog {
+            threshold: Some(threshold),
+            operations: Mutex::new(Vec::new())
+        }
+    }
+
+    /// Starts timing a database query
+    pub fn query(&self, name: &str) -> SlowTimer<'_> {
+        self.start(OperationKind::Query, name)
+    }
+
+    /// Starts timing a pipeline stage
+    pub fn stage(&self, name: &str) -> SlowTimer<'_> {
+        self.start(OperationKind::Stage, name)
+    }
+
+    fn start(&self, kind: OperationKind, name: &str) -> SlowTimer<'_> {
+        SlowTimer {
+            log: self,
+            kind,
+            name: name.to_string(),
+            start: Instant::now()
+        }
+    }
+
+    /// Records an operation which took `duration`, logging it if it is slow
+    ///
+    /// # Returns
+    /// Whether the operation was slow
+    pub fn record(&self, kind: OperationKind, name: &str, duration: Duration, rows: usize) -> bool {
+        if self.threshold.is_none_or(|threshold| duration < threshold) {
+            return false;
+        }
+
+        let operation = SlowOperation {
+            kind,
+            name: name.to_string(),
+            duration_ms: duration.as_millis() as u64,
+            rows
+        };
+        eprintln!(
+            "Slow {}: name={} duration_ms={} rows={}",
+            operation.kind, operation.name, operation.duration_ms, operation.rows
+        );
+        self.operations.lock().unwrap().push(operation);
+
+        true
+    }
+
+    /// The slow operations recorded so far, in order of completion
+    pub fn operations(&self) -> Vec<SlowOperation> {
+        self.operations.lock().unwrap().clone()
+    }
+}
+
+/// Times a single operation, see `SlowLog::query` and `SlowLog::stage`
+#[must_use = "the operation is only recorded once the timer is finished"]
+pub struct SlowTimer<'a> {
+    log: &'a SlowLog,
+    kind: OperationKind,
+    name: String,
+    start: Instant
+}
+
+impl SlowTimer<'_> {
+    /// Stops the timer and records the operation along with the number of rows or items
+    /// it handled
+    pub fn finish(self, rows: usize) {
+        self.log.record(self.kind, &self.name, self.start.elapsed(), rows);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OperationKind, SlowLog, SlowOperation};
+    use std::time::Duration;
+
+    #[test]
+    fn test_records_operations_at_threshold() {
+        let log = SlowLog::new(Duration::from_millis(100));
+
+        assert!(!log.record(OperationKind::Query, "get_players", Duration::from_millis(99), 10));
+        assert!(log.record(OperationKind::Query, "get_matches", Duration::from_millis(100), 20));
+        assert!(log.record(OperationKind::Stage, "process", Duration::from_secs(2), 5));
+
+        assert_eq!(
+            log.operations(),
+            vec![
+                SlowOperation {
+                    kind: OperationKind::Query,
+                    name: "get_matches".to_string(),
+                    duration_ms: 100,
+                    rows: 20
+                },
+                SlowOperation {
+                    kind: OperationKind::Stage,
+                    name: "process".to_string(),
+                    duration_ms: 2000,
+                    rows: 5
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disabled_log_records_nothing() {
+        let log = SlowLog::default();
+
+        assert!(!log.record(OperationKind::Stage, "process", Duration::from_secs(3600), 1));
+        log.stage("save").finish(1);
+        assert!(log.operations().is_empty());
+    }
+}
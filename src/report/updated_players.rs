@@ -0,0 +1,106 @@
+use crate::{database::db_structs::PlayerRating, model::structures::ruleset::Ruleset};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// Minimum absolute rating change for a player to be considered updated
+pub const DEFAULT_RATING_THRESHOLD: f64 = 1.0;
+/// Minimum absolute global rank change for a player to be considered updated
+pub const DEFAULT_RANK_THRESHOLD: i32 = 10;
+
+/// How much a stored rating must change for its player to be reported as updated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UpdateThresholds {
+    pub rating: f64,
+    pub global_rank: i32
+}
+
+impl Default for UpdateThresholds {
+    fn default() -> Self {
+        UpdateThresholds {
+            rating: DEFAULT_RATING_THRESHOLD,
+            global_rank: DEFAULT_RANK_THRESHOLD
+        }
+    }
+}
+
+/// Compares the ratings stored before a run against the newly computed ratings
+///
+/// A player is updated if, in any ruleset, they gained or lost a rating, their rating
+/// changed by at least `thresholds.rating`, or their global rank changed by at least
+/// `thresholds.global_rank`.
+///
+/// # Returns
+/// The ids of all updated players in ascending order
+pub fn find_updated_players(
+    previous: &[PlayerRating],
+    current: &[PlayerRating],
+    thresholds: &UpdateThresholds
+) -> Vec<i32> {
+    let previous_map: HashMap<(i32, Ruleset), &PlayerRating> =
+        previous.iter().map(|r| ((r.player_id, r.ruleset), r)).collect();
+    let current_map: HashMap<(i32, Ruleset), &PlayerRating> =
+        current.iter().map(|r| ((r.player_id, r.ruleset), r)).collect();
+
+    let changed = current
+        .iter()
+        .filter(|r| match previous_map.get(&(r.player_id, r.ruleset)) {
+            Some(before) => {
+                (r.rating - before.rating).abs() >= thresholds.rating
+                    || (r.global_rank - before.global_rank).abs() >= thresholds.global_rank
+            }
+            None => true
+        });
+    let removed = previous
+        .iter()
+        .filter(|r| !current_map.contains_key(&(r.player_id, r.ruleset)));
+
+    changed.chain(removed).map(|r| r.player_id).unique().sorted().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_updated_players, UpdateThresholds};
+    use crate::{
+        model::structures::ruleset::Ruleset::{Osu, Taiko},
+        utils::test_utils::generate_player_rating
+    };
+
+    #[test]
+    fn test_find_updated_players() {
+        let thresholds = UpdateThresholds {
+            rating: 5.0,
+            global_rank: 10
+        };
+
+        let mut previous = vec![
+            generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None),
+            generate_player_rating(2, Osu, 1000.0, 100.0, 1, None, None),
+            generate_player_rating(3, Osu, 1000.0, 100.0, 1, None, None),
+            generate_player_rating(4, Taiko, 1000.0, 100.0, 1, None, None),
+        ];
+        previous[2].global_rank = 20;
+
+        let mut current = vec![
+            // Below both thresholds
+            generate_player_rating(1, Osu, 1004.0, 100.0, 1, None, None),
+            // Rating changed
+            generate_player_rating(2, Osu, 1005.0, 100.0, 1, None, None),
+            // Global rank changed
+            generate_player_rating(3, Osu, 1000.0, 100.0, 1, None, None),
+            // Newly rated
+            generate_player_rating(5, Osu, 1000.0, 100.0, 1, None, None),
+        ];
+        current[0].global_rank = 5;
+        current[2].global_rank = 30;
+
+        // Player 4 is no longer rated
+        assert_eq!(find_updated_players(&previous, &current, &thresholds), vec![2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_no_changes() {
+        let ratings = vec![generate_player_rating(1, Osu, 1000.0, 100.0, 1, None, None)];
+
+        assert!(find_updated_players(&ratings, &ratings, &UpdateThresholds::default()).is_empty());
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{
+    model::{
+        bootstrap::Bootstrap, observer::ProcessingObserver, rating_tracker::RatingTracker, structures::ruleset::Ruleset
+    },
+    report::run_report::WarningCount
+};
+use itertools::Itertools;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    str::FromStr
+};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+/// A data quality issue which processing worked around
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// A player was given the fallback rating, as no rank data was available for the ruleset
+    FallbackRatingUsed { player_id: i32, ruleset: Ruleset },
+    /// A game without any scores was skipped
+    EmptyGame { game_id: i32 },
+    /// A rated player has no known country, so no country rank is meaningful
+    MissingCountry { player_id: i32, ruleset: Ruleset },
+    /// A stored country code is not a valid ISO 3166-1 alpha-2 code
+    InvalidCountry { code: String },
+    /// A participant of a match has no rating in the match's ruleset, so decay was not applied
+    /// to them before the match
+    RulesetMismatch {
+        player_id: i32,
+        ruleset: Ruleset,
+        match_id: i32
+    },
+    /// A match with a single game was rated, see `ShortMatchPolicy::Flag`
+    ShortMatch { match_id: i32 }
+}
+
+/// The type of a `Warning`, used to count warnings and to select fail-on-warning policies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WarningKind {
+    FallbackRatingUsed,
+    EmptyGame,
+    MissingCountry,
+    InvalidCountry,
+    RulesetMismatch,
+    ShortMatch
+}
+
+impl Warning {
+    pub fn kind(&self) -> WarningKind {
+        match self {
+            Warning::FallbackRatingUsed { .. } => WarningKind::FallbackRatingUsed,
+            Warning::EmptyGame { .. } => WarningKind::EmptyGame,
+            Warning::MissingCountry { .. } => WarningKind::MissingCountry,
+            Warning::InvalidCountry { .. } => WarningKind::InvalidCountry,
+            Warning::RulesetMismatch { .. } => WarningKind::RulesetMismatch,
+            Warning::ShortMatch { .. } => WarningKind::ShortMatch
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::FallbackRatingUsed { player_id, ruleset } => {
+                write!(
+                    f,
+                    "Fallback rating used for player [Id: {} | Ruleset: {:?}]",
+                    player_id, ruleset
+                )
+            }
+            Warning::EmptyGame { game_id } => write!(f, "Game {} has no scores", game_id),
+            Warning::MissingCountry { player_id, ruleset } => {
+                write!(
+                    f,
+                    "No country known for player [Id: {} | Ruleset: {:?}]",
+                    player_id, ruleset
+                )
+            }
+            Warning::InvalidCountry { code } => write!(f, "Invalid country code '{}'", code),
+            Warning::RulesetMismatch {
+                player_id,
+                ruleset,
+                match_id
+            } => write!(
+                f,
+                "No rating found for player [Id: {} | Ruleset: {:?}] in match {}",
+                player_id, ruleset, match_id
+            ),
+            Warning::ShortMatch { match_id } => write!(f, "Match {} has a single game", match_id)
+        }
+    }
+}
+
+impl FromStr for WarningKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WarningKind::iter().find(|kind| kind.to_string() == s).ok_or_else(|| {
+            format!(
+                "'{}' is not a warning (expected one of {})",
+                s,
+                WarningKind::iter()
+                    .map(|kind| kind.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+    }
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            WarningKind::FallbackRatingUsed => "fallback-rating-used",
+            WarningKind::EmptyGame => "empty-game",
+            WarningKind::MissingCountry => "missing-country",
+            WarningKind::InvalidCountry => "invalid-country",
+            WarningKind::RulesetMismatch => "ruleset-mismatch",
+            WarningKind::ShortMatch => "short-match"
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// Collects the warnings raised throughout a run, counting them per kind
+///
+/// Warnings are logged at the `log` crate's warn level. Kinds selected as fail-on-warning
+/// policies are reported by `violations` once raised, so the run can be aborted before
+/// anything is saved.
+#[derive(Debug, Clone, Default)]
+pub struct WarningSink {
+    counts: BTreeMap<WarningKind, usize>,
+    fail_on: BTreeSet<WarningKind>
+}
+
+impl WarningSink {
+    /// # Arguments
+    /// * `fail_on` - Kinds of warnings which must not be raised for the run to succeed
+    pub fn new(fail_on: &[WarningKind]) -> WarningSink {
+        WarningSink {
+            counts: BTreeMap::new(),
+            fail_on: fail_on.iter().copied().collect()
+        }
+    }
+
+    pub fn warn(&mut self, warning: Warning) {
+        log::warn!("{}", warning);
+        *self.counts.entry(warning.kind()).or_default() += 1;
+    }
+
+    /// Raises the warnings of the gaps found while bootstrapping the model
+    ///
+    /// Untracked players are left out, as processing raises a `RulesetMismatch` for each match
+    /// they play without a rating.
+    pub fn warn_bootstrap(&mut self, bootstrap: &Bootstrap) {
+        for (player_id, ruleset) in bootstrap
+            .fallback_ratings()
+            .into_iter()
+            .sorted_by_key(|&(player_id, ruleset)| (player_id, ruleset as i32))
+        {
+            self.warn(Warning::FallbackRatingUsed { player_id, ruleset });
+        }
+        for &game_id in &bootstrap.issues.empty_games {
+            self.warn(Warning::EmptyGame { game_id });
+        }
+        for country in &bootstrap.issues.invalid_countries {
+            self.warn(Warning::InvalidCountry {
+                code: country.code.clone()
+            });
+        }
+    }
+
+    /// Raises a `MissingCountry` for every rating of a player without a known country
+    pub fn warn_unknown_countries(&mut self, tracker: &RatingTracker) {
+        for &(player_id, ruleset) in tracker.unknown_country_players() {
+            self.warn(Warning::MissingCountry { player_id, ruleset });
+        }
+    }
+
+    /// Number of warnings raised of every kind which was raised, in a stable order
+    pub fn counts(&self) -> Vec<WarningCount> {
+        self.counts
+            .iter()
+            .map(|(&kind, &count)| WarningCount { kind, count })
+            .collect()
+    }
+
+    /// The raised warnings whose kind is a fail-on-warning policy
+    pub fn violations(&self) -> Vec<WarningCount> {
+        self.counts()
+            .into_iter()
+            .filter(|c| self.fail_on.contains(&c.kind))
+            .collect()
+    }
+}
+
+impl ProcessingObserver for WarningSink {
+    fn on_warning(&mut self, warning: &Warning) {
+        self.warn(warning.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Warning, WarningKind, WarningSink};
+    use crate::{
+        database::db_structs::Player,
+        model::{
+            bootstrap::bootstrap,
+            otr_model::OtrModel,
+            structures::{fallback_strategy::FallbackStrategy, ruleset::Ruleset::Osu}
+        },
+        utils::test_utils::{generate_game, generate_match, generate_placement, generate_ruleset_data}
+    };
+    use chrono::Utc;
+    use std::str::FromStr;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_parse_kind() {
+        for kind in WarningKind::iter() {
+            assert_eq!(WarningKind::from_str(&kind.to_string()), Ok(kind));
+        }
+
+        assert!(WarningKind::from_str("EmptyGame").is_err());
+    }
+
+    #[test]
+    fn test_counts_and_violations() {
+        let mut sink = WarningSink::new(&[WarningKind::EmptyGame, WarningKind::RulesetMismatch]);
+        sink.warn(Warning::EmptyGame { game_id: 1 });
+        sink.warn(Warning::EmptyGame { game_id: 2 });
+        sink.warn(Warning::InvalidCountry { code: "XX".to_string() });
+
+        let counts = sink.counts().iter().map(|c| (c.kind, c.count)).collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![(WarningKind::EmptyGame, 2), (WarningKind::InvalidCountry, 1)]
+        );
+
+        let violations = sink.violations().iter().map(|c| c.kind).collect::<Vec<_>>();
+        assert_eq!(violations, vec![WarningKind::EmptyGame]);
+    }
+
+    #[test]
+    fn test_bootstrap_and_processing_warnings() {
+        // Player 2 is missing from the players list, so has neither rank data nor a country
+        let players = vec![Player {
+            id: 1,
+            username: None,
+            country: Some("US".to_string()),
+            ruleset_data: Some(vec![generate_ruleset_data(Osu, 1000, None)])
+        }];
+        let placements = [generate_placement(1, 1), generate_placement(2, 2)];
+        let matches = vec![generate_match(
+            1,
+            Osu,
+            &[generate_game(1, &placements), generate_game(2, &[])],
+            Utc::now().fixed_offset()
+        )];
+        let bootstrap = bootstrap(&players, &matches, Vec::new(), FallbackStrategy::Constant, false).unwrap();
+
+        let mut sink = WarningSink::new(&[WarningKind::MissingCountry]);
+        sink.warn_bootstrap(&bootstrap);
+        let mut model = OtrModel::new(&bootstrap.initial_ratings, &bootstrap.country_mapping);
+        model.process_with_observer(&matches, &mut sink).unwrap();
+        sink.warn_unknown_countries(&model.rating_tracker);
+
+        let counts = sink.counts().iter().map(|c| (c.kind, c.count)).collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![
+                (WarningKind::FallbackRatingUsed, 1),
+                (WarningKind::EmptyGame, 1),
+                (WarningKind::MissingCountry, 1)
+            ]
+        );
+        assert_eq!(sink.violations().len(), 1);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{database::db_structs::RatingAdjustment, model::structures::rating_adjustment_type::RatingAdjustmentType};
+use chrono::{DateTime, FixedOffset};
+
+/// A rating adjustment as stored, possibly summarizing several consecutive decay adjustments
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressedAdjustment {
+    /// Holds the before values of the first and the after values and timestamp of the last
+    /// summarized adjustment
+    pub adjustment: RatingAdjustment,
+    /// Timestamp of the first summarized adjustment
+    pub first_timestamp: DateTime<FixedOffset>,
+    /// Number of summarized adjustments
+    pub count: i32
+}
+
+impl From<&RatingAdjustment> for CompressedAdjustment {
+    fn from(adjustment: &RatingAdjustment) -> Self {
+        CompressedAdjustment {
+            adjustment: adjustment.clone(),
+            first_timestamp: adjustment.timestamp,
+            count: 1
+        }
+    }
+}
+
+/// Coalesces each run of consecutive decay adjustments into a single summary adjustment
+///
+/// Non-decay adjustments are kept as-is. Because a summary ends with the values and timestamp
+/// of the last adjustment of its run, replaying the compressed history yields the same
+/// ratings at every non-decay adjustment as the full history.
+///
+/// # Arguments
+/// * `adjustments` - The adjustments of a single player rating, in chronological order
+pub fn compress_decay_adjustments(adjustments: &[RatingAdjustment]) -> Vec<CompressedAdjustment> {
+    let mut compressed: Vec<CompressedAdjustment> = Vec::new();
+
+    for adjustment in adjustments {
+        match compressed.last_mut() {
+            Some(previous)
+                if adjustment.adjustment_type == RatingAdjustmentType::Decay
+                    && previous.adjustment.adjustment_type == RatingAdjustmentType::Decay =>
+            {
+                previous.adjustment.rating_after = adjustment.rating_after;
+                previous.adjustment.volatility_after = adjustment.volatility_after;
+                previous.adjustment.timestamp = adjustment.timestamp;
+                previous.count += 1;
+            }
+            _ => compressed.push(CompressedAdjustment::from(adjustment))
+        }
+    }
+
+    compressed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compress_decay_adjustments;
+    use crate::{
+        database::db_structs::RatingAdjustment,
+        model::structures::{
+            rating_adjustment_type::RatingAdjustmentType::{self, Decay, Initial, Match},
+            rating_clamps::RatingClamps,
+            ruleset::Ruleset::Osu
+        }
+    };
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn adjustments(types: &[RatingAdjustmentType]) -> Vec<RatingAdjustment> {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+
+        types
+            .iter()
+            .enumerate()
+            .map(|(i, &adjustment_type)| RatingAdjustment {
+                player_id: 1,
+                ruleset: Osu,
+                match_id: None,
+                rating_before: 1000.0 - i as f64,
+                rating_after: 999.0 - i as f64,
+                volatility_before: 100.0 + i as f64,
+                volatility_after: 101.0 + i as f64,
+                timestamp: start + Duration::weeks(i as i64),
+                adjustment_type,
+                average_opponent_rating: None,
+                provisional: false,
+                percentile: None,
+                clamps: RatingClamps::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_consecutive_decays_are_coalesced() {
+        let original = adjustments(&[Initial, Decay, Decay, Decay, Match, Decay]);
+        let compressed = compress_decay_adjustments(&original);
+
+        assert_eq!(compressed.len(), 4);
+
+        let summary = &compressed[1];
+        assert_eq!(summary.count, 3);
+        assert_eq!(summary.first_timestamp, original[1].timestamp);
+        assert_eq!(summary.adjustment.timestamp, original[3].timestamp);
+        assert_eq!(summary.adjustment.rating_before, original[1].rating_before);
+        assert_eq!(summary.adjustment.rating_after, original[3].rating_after);
+        assert_eq!(summary.adjustment.volatility_before, original[1].volatility_before);
+        assert_eq!(summary.adjustment.volatility_after, original[3].volatility_after);
+
+        assert_eq!(compressed[2].adjustment, original[4]);
+        assert_eq!(compressed[3].count, 1);
+    }
+
+    #[test]
+    fn test_non_decay_adjustments_are_kept() {
+        let original = adjustments(&[Initial, Match, Match]);
+        let compressed = compress_decay_adjustments(&original);
+
+        assert_eq!(compressed.len(), 3);
+        assert!(compressed.iter().all(|c| c.count == 1));
+    }
+}
@@ -0,0 +1,177 @@
+use super::{
+    adjustment_compression::{compress_decay_adjustments, CompressedAdjustment},
+    tournament_settlement::{settle_tournaments, TournamentSettlements}
+};
+use crate::{
+    database::db_structs::{PlayerRating, RatingAdjustment},
+    model::structures::date_range::DateRange
+};
+use chrono::{DateTime, FixedOffset};
+use itertools::Itertools;
+use std::borrow::Cow;
+
+/// The rows a single player rating contributes to the rating_adjustments table
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatingRows {
+    /// Id of the stored player rating the rows belong to
+    pub player_rating_id: i32,
+    /// In chronological order
+    pub rows: Vec<CompressedAdjustment>,
+    /// The match adjustments replaced by tournament settlements, along with their tournament
+    /// and the time they were settled at
+    pub settled_matches: Vec<(i32, DateTime<FixedOffset>, RatingAdjustment)>
+}
+
+/// Builds the stored rows of every player rating, ordered by player, ruleset and time
+///
+/// Ratings arrive in leaderboard order, which depends on how ties were broken during
+/// processing. Writing them by player instead makes identical runs write identical rows.
+///
+/// # Arguments
+/// * `player_ratings` - The ratings to store, in any order
+/// * `player_rating_ids` - The stored id of each rating in `player_ratings`
+/// * `range` - Only adjustments within the range are stored
+/// * `compress_decay` - Whether consecutive decay adjustments are summarized, see
+///   `compress_decay_adjustments`
+/// * `settlements` - Whether and how match adjustments are settled per tournament, see
+///   `settle_tournaments`
+pub fn adjustment_rows<'a>(
+    player_ratings: &'a [PlayerRating],
+    player_rating_ids: &'a [i32],
+    range: &'a DateRange,
+    compress_decay: bool,
+    settlements: Option<&'a TournamentSettlements>
+) -> impl Iterator<Item = RatingRows> + 'a {
+    player_ratings
+        .iter()
+        .zip(player_rating_ids)
+        .sorted_by_key(|(rating, _)| (rating.player_id, rating.ruleset as i32))
+        .map(move |(rating, &player_rating_id)| {
+            let mut adjustments = adjustments_in_range(rating, range);
+            let mut settled_matches = Vec::new();
+            if let Some(settlements) = settlements {
+                adjustments = Cow::Owned(
+                    settle_tournaments(&adjustments, settlements)
+                        .into_iter()
+                        .map(|settled| {
+                            if let Some(tournament_id) = settled.tournament_id {
+                                settled_matches.extend(
+                                    settled
+                                        .matches
+                                        .into_iter()
+                                        .map(|m| (tournament_id, settled.adjustment.timestamp, m))
+                                );
+                            }
+                            settled.adjustment
+                        })
+                        .collect()
+                );
+            }
+
+            let rows = if compress_decay {
+                compress_decay_adjustments(&adjustments)
+            } else {
+                adjustments.iter().map(CompressedAdjustment::from).collect_vec()
+            };
+
+            RatingRows {
+                player_rating_id,
+                rows,
+                settled_matches
+            }
+        })
+}
+
+/// The adjustments of `rating` within `range`, borrowed when all of them are within it
+fn adjustments_in_range<'a>(rating: &'a PlayerRating, range: &DateRange) -> Cow<'a, [RatingAdjustment]> {
+    if rating.adjustments.iter().all(|a| range.contains(a.timestamp)) {
+        Cow::Borrowed(&rating.adjustments)
+    } else {
+        Cow::Owned(
+            rating
+                .adjustments
+                .iter()
+                .filter(|a| range.contains(a.timestamp))
+                .cloned()
+                .collect()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::adjustment_rows;
+    use crate::{
+        database::db_structs::PlayerRating,
+        model::{model_config::ModelConfig, otr_model::OtrModel, structures::date_range::DateRange},
+        utils::test_utils::{generate_country_mapping_player_ratings, TournamentBuilder}
+    };
+    use chrono::{DateTime, Duration, FixedOffset, TimeZone, Utc};
+    use itertools::Itertools;
+
+    fn run(tournament: &TournamentBuilder, decay_until: DateTime<FixedOffset>) -> Vec<PlayerRating> {
+        let initial_ratings = tournament.initial_ratings(1000.0, 200.0);
+        let countries = generate_country_mapping_player_ratings(&initial_ratings, "US");
+
+        // Decay up to a fixed time rather than now, which differs between the runs
+        let config = ModelConfig {
+            decay_until: Some(decay_until),
+            ..Default::default()
+        };
+        let mut model = OtrModel::with_config(&initial_ratings, &countries, config);
+        model.process(&tournament.build()).unwrap().ratings
+    }
+
+    /// Rows as they are written to the COPY stream, one per line
+    fn stream(ratings: &[PlayerRating], compress_decay: bool) -> String {
+        let ids = ratings
+            .iter()
+            .map(|r| r.player_id * 10 + r.ruleset as i32)
+            .collect_vec();
+
+        let rows = adjustment_rows(ratings, &ids, &DateRange::default(), compress_decay, None).collect_vec();
+        rows.into_iter()
+            .flat_map(|rating_rows| {
+                rating_rows
+                    .rows
+                    .into_iter()
+                    .map(move |row| format!("{} {:?}", rating_rows.player_rating_id, row))
+            })
+            .join("\n")
+    }
+
+    #[test]
+    fn test_identical_runs_write_identical_rows() {
+        let start_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let tournament = TournamentBuilder::new()
+            .teams(4)
+            .team_size(2)
+            .matches(6)
+            .seed(7)
+            .start_time(start_time);
+        let decay_until = start_time + Duration::days(365);
+        let first = run(&tournament, decay_until);
+        let mut second = run(&tournament, decay_until);
+
+        // Equal ratings may be ordered differently on the leaderboard
+        second.reverse();
+
+        for compress_decay in [false, true] {
+            assert_eq!(stream(&first, compress_decay), stream(&second, compress_decay));
+        }
+    }
+
+    #[test]
+    fn test_rows_are_ordered_by_player() {
+        let tournament = TournamentBuilder::new().teams(3).matches(2);
+        let ratings = run(&tournament, Utc::now().fixed_offset());
+        let ids = vec![0; ratings.len()];
+
+        let players = adjustment_rows(&ratings, &ids, &DateRange::default(), false, None)
+            .map(|rating_rows| rating_rows.rows[0].adjustment.player_id)
+            .collect_vec();
+
+        assert!(players.is_sorted());
+        assert_eq!(players.len(), ratings.len());
+    }
+}
@@ -0,0 +1,209 @@
+use crate::database::db_structs::{ManualAdjustment, Match, Player, PlayerRestriction};
+use itertools::Itertools;
+use sha2::{Digest, Sha256};
+
+/// Computes a SHA-256 fingerprint of all processing inputs
+///
+/// The hash covers every field of the fetched matches, games, scores, players, manual
+/// adjustments and player restrictions which can influence the produced ratings, along with
+/// a caller-provided `config` string (arguments, version, etc.). Entities are visited in a
+/// fixed order so the result does not depend on the order in which the database returned rows.
+///
+/// # Returns
+/// The hex-encoded digest
+pub fn compute_input_hash(
+    matches: &[Match],
+    players: &[Player],
+    manual_adjustments: &[ManualAdjustment],
+    restrictions: &[PlayerRestriction],
+    config: &str
+) -> String {
+    let mut hasher = Sha256::new();
+
+    hasher.update(config.as_bytes());
+
+    for match_ in matches.iter().sorted_by_key(|m| m.id) {
+        hasher.update(b"match");
+        hasher.update(match_.id.to_le_bytes());
+        hasher.update(match_.name.as_bytes());
+        hasher.update((match_.ruleset as i32).to_le_bytes());
+        hasher.update(match_.start_time.timestamp().to_le_bytes());
+        hasher.update(match_.end_time.timestamp().to_le_bytes());
+        hasher.update([match_.rating_exempt as u8]);
+
+        for game in match_.games.iter().sorted_by_key(|g| g.id) {
+            hasher.update(b"game");
+            hasher.update(game.id.to_le_bytes());
+            hasher.update((game.ruleset as i32).to_le_bytes());
+            hasher.update(game.start_time.timestamp().to_le_bytes());
+            hasher.update(game.end_time.timestamp().to_le_bytes());
+
+            for score in game.scores.iter().sorted_by_key(|s| s.id) {
+                hasher.update(b"score");
+                hasher.update(score.id.to_le_bytes());
+                hasher.update(score.player_id.to_le_bytes());
+                hasher.update(score.score.to_le_bytes());
+                hasher.update(score.placement.to_le_bytes());
+                hasher.update(score.team.to_le_bytes());
+            }
+        }
+    }
+
+    // Usernames do not influence ratings and are intentionally excluded
+    for player in players.iter().sorted_by_key(|p| p.id) {
+        hasher.update(b"player");
+        hasher.update(player.id.to_le_bytes());
+        hasher.update(player.country.as_deref().unwrap_or_default().as_bytes());
+
+        for data in player.ruleset_data.iter().flatten().sorted_by_key(|d| d.ruleset as i32) {
+            hasher.update((data.ruleset as i32).to_le_bytes());
+            hasher.update(data.global_rank.to_le_bytes());
+            hasher.update(data.earliest_global_rank.unwrap_or(-1).to_le_bytes());
+            for point in &data.rank_history {
+                hasher.update(point.timestamp.timestamp().to_le_bytes());
+                hasher.update(point.global_rank.to_le_bytes());
+            }
+        }
+    }
+
+    for manual in manual_adjustments.iter().sorted_by_key(|m| m.id) {
+        hasher.update(b"manual");
+        hasher.update(manual.id.to_le_bytes());
+        hasher.update(manual.player_id.to_le_bytes());
+        hasher.update((manual.ruleset as i32).to_le_bytes());
+        hasher.update(manual.timestamp.timestamp().to_le_bytes());
+        hasher.update([manual.kind as u8]);
+        hasher.update(manual.value.to_le_bytes());
+    }
+
+    // Restrictions have no id, so they are ordered by all of their fields
+    for restriction in restrictions
+        .iter()
+        .sorted_by_key(|r| (r.player_id, r.starts_at, r.ends_at))
+    {
+        hasher.update(b"restriction");
+        hasher.update(restriction.player_id.to_le_bytes());
+        hasher.update(restriction.starts_at.timestamp().to_le_bytes());
+        hasher.update(restriction.ends_at.map_or(-1, |e| e.timestamp()).to_le_bytes());
+    }
+
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compute_input_hash;
+    use crate::{
+        database::db_structs::{ManualAdjustment, Player, PlayerRestriction},
+        model::structures::{manual_adjustment_kind::ManualAdjustmentKind, ruleset::Ruleset::Osu},
+        utils::test_utils::{generate_game, generate_match, generate_placement, generate_ruleset_data}
+    };
+    use chrono::Utc;
+
+    fn player(id: i32, rank: i32) -> Player {
+        Player {
+            id,
+            username: Some(format!("Player {}", id)),
+            country: Some("US".to_string()),
+            ruleset_data: Some(vec![generate_ruleset_data(Osu, rank, None)])
+        }
+    }
+
+    #[test]
+    fn test_hash_is_order_independent() {
+        let time = Utc::now().fixed_offset();
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 2)];
+        let matches = vec![
+            generate_match(1, Osu, &[generate_game(1, &placements)], time),
+            generate_match(2, Osu, &[generate_game(2, &placements)], time),
+        ];
+        let players = vec![player(1, 100), player(2, 200)];
+
+        let reversed_matches = matches.iter().rev().cloned().collect::<Vec<_>>();
+        let reversed_players = players.iter().rev().cloned().collect::<Vec<_>>();
+
+        assert_eq!(
+            compute_input_hash(&matches, &players, &[], &[], "config"),
+            compute_input_hash(&reversed_matches, &reversed_players, &[], &[], "config")
+        );
+    }
+
+    #[test]
+    fn test_hash_detects_changes() {
+        let time = Utc::now().fixed_offset();
+        let placements = vec![generate_placement(1, 1), generate_placement(2, 2)];
+        let matches = vec![generate_match(1, Osu, &[generate_game(1, &placements)], time)];
+        let players = vec![player(1, 100), player(2, 200)];
+
+        let original = compute_input_hash(&matches, &players, &[], &[], "config");
+
+        let swapped = vec![generate_placement(1, 2), generate_placement(2, 1)];
+        let changed_matches = vec![generate_match(1, Osu, &[generate_game(1, &swapped)], time)];
+        assert_ne!(
+            original,
+            compute_input_hash(&changed_matches, &players, &[], &[], "config")
+        );
+
+        let changed_players = vec![player(1, 100), player(2, 300)];
+        assert_ne!(
+            original,
+            compute_input_hash(&matches, &changed_players, &[], &[], "config")
+        );
+
+        assert_ne!(
+            original,
+            compute_input_hash(&matches, &players, &[], &[], "other config")
+        );
+
+        let mut teams = matches.clone();
+        teams[0].games[0].scores[0].team = 1;
+        assert_ne!(original, compute_input_hash(&teams, &players, &[], &[], "config"));
+    }
+
+    #[test]
+    fn test_hash_detects_moderation_inputs() {
+        let time = Utc::now().fixed_offset();
+        let players = vec![player(1, 100)];
+        let original = compute_input_hash(&[], &players, &[], &[], "config");
+
+        let manual = ManualAdjustment {
+            id: 1,
+            player_id: 1,
+            ruleset: Osu,
+            timestamp: time,
+            kind: ManualAdjustmentKind::Offset,
+            value: -100.0
+        };
+        let adjusted = compute_input_hash(&[], &players, std::slice::from_ref(&manual), &[], "config");
+        assert_ne!(original, adjusted);
+
+        let corrected = ManualAdjustment { value: -50.0, ..manual };
+        assert_ne!(adjusted, compute_input_hash(&[], &players, &[corrected], &[], "config"));
+
+        let restriction = PlayerRestriction {
+            player_id: 1,
+            starts_at: time,
+            ends_at: None
+        };
+        let restricted = compute_input_hash(&[], &players, &[], std::slice::from_ref(&restriction), "config");
+        assert_ne!(original, restricted);
+
+        let lifted = PlayerRestriction {
+            ends_at: Some(time),
+            ..restriction
+        };
+        assert_ne!(restricted, compute_input_hash(&[], &players, &[], &[lifted], "config"));
+    }
+
+    #[test]
+    fn test_hash_ignores_username() {
+        let players = vec![player(1, 100)];
+        let mut renamed = players.clone();
+        renamed[0].username = Some("Renamed".to_string());
+
+        assert_eq!(
+            compute_input_hash(&[], &players, &[], &[], "config"),
+            compute_input_hash(&[], &renamed, &[], &[], "config")
+        );
+    }
+}
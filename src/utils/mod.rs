@@ -1,2 +1,9 @@
-pub mod progress_utils;
+pub mod adjustment_compression;
+pub mod adjustment_rows;
+pub mod input_hash;
+pub mod processor_state;
+pub(crate) mod progress_utils;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_data_builder;
 pub mod test_utils;
+pub mod tournament_settlement;
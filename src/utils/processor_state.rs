@@ -0,0 +1,102 @@
+use crate::{
+    database::db_structs::PlayerRating,
+    dto::RatingHistoryDto,
+    model::structures::{date_range::DateRange, ruleset_filter::RulesetFilter},
+    utils::tournament_settlement::TournamentSettlements
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, io, path::Path};
+
+/// The results of a run which only computed them (`--output-state`), for a later run to save
+/// them (`--input-state`)
+///
+/// Holds everything needed to save the results which is not read from the database again.
+/// The processed matches keep awaiting processing until the state is saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessorState {
+    /// Version of the processor which computed the results
+    pub version: String,
+    /// Input hash of the computing run, stored with the run which saves the results
+    pub input_hash: String,
+    /// Effective configuration of the computing run as JSON
+    pub config: String,
+    pub date_range: DateRange,
+    pub rulesets: RulesetFilter,
+    /// Maps player IDs to their country codes, used to compute the highest ranks
+    pub country_mapping: HashMap<i32, String>,
+    /// Set if match adjustments are stored as tournament settlements
+    pub settlements: Option<TournamentSettlements>,
+    /// Ids of the processed matches, marked as processed once the results are saved
+    pub match_ids: Vec<i32>,
+    pub ratings: Vec<RatingHistoryDto>
+}
+
+impl ProcessorState {
+    /// The computed ratings, along with their adjustments
+    pub fn player_ratings(&self) -> Vec<PlayerRating> {
+        self.ratings.iter().map(PlayerRating::from).collect()
+    }
+
+    /// Reads a state from a JSON file
+    pub fn read(path: &Path) -> io::Result<ProcessorState> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(io::Error::from)
+    }
+
+    /// Writes the state as JSON to the given path
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(
+            path,
+            serde_json::to_string(self).expect("Processor state should be serializable")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProcessorState;
+    use crate::{
+        dto::RatingHistoryDto,
+        model::structures::{
+            date_range::DateRange,
+            ruleset::Ruleset::{Osu, Taiko},
+            ruleset_filter::RulesetFilter
+        },
+        utils::test_utils::generate_player_rating
+    };
+    use chrono::{TimeZone, Utc};
+    use std::{collections::HashMap, env, fs};
+
+    #[test]
+    fn test_state_round_trips() {
+        let from = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let ratings = [
+            generate_player_rating(1, Osu, 1000.0, 100.0, 3, None, None),
+            generate_player_rating(2, Taiko, 1200.0, 150.0, 2, None, None)
+        ];
+        let state = ProcessorState {
+            version: "1.0.0".to_string(),
+            input_hash: "abc".to_string(),
+            config: "{}".to_string(),
+            date_range: DateRange::new(Some(from), None),
+            rulesets: RulesetFilter::new(&[Osu, Taiko]),
+            country_mapping: HashMap::from([(1, "US".to_string())]),
+            settlements: None,
+            match_ids: vec![1, 2],
+            ratings: ratings.iter().map(RatingHistoryDto::from).collect()
+        };
+
+        let path = env::temp_dir().join(format!("otr-state-{}.json", std::process::id()));
+        state.write(&path).unwrap();
+        let read = ProcessorState::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(read.date_range, state.date_range);
+        assert_eq!(read.rulesets, state.rulesets);
+        assert_eq!(read.country_mapping, state.country_mapping);
+        assert_eq!(read.match_ids, state.match_ids);
+        assert_eq!(read.ratings, state.ratings);
+        assert_eq!(read.player_ratings().len(), 2);
+    }
+}
@@ -1,48 +1,138 @@
-use indicatif::ProgressBar;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use lazy_static::lazy_static;
+use std::io::{stderr, IsTerminal};
 
-pub fn progress_bar(len: u64, msg: String) -> Option<ProgressBar> {
-    if cfg!(test) {
-        return None;
+lazy_static! {
+    /// Every visible progress bar is drawn through this, so nested bars render together
+    static ref MULTI_PROGRESS: MultiProgress = MultiProgress::with_draw_target(ProgressDrawTarget::stderr());
+}
+
+/// A progress bar which may be hidden. The default span is hidden.
+///
+/// Bars are hidden in tests and when stderr is not a terminal (e.g. in CI), in which case
+/// every method is a no-op. Child spans are drawn below their parent and removed when
+/// finished or dropped.
+#[derive(Default)]
+pub(crate) struct ProgressSpan {
+    bar: Option<ProgressBar>,
+    nested: bool
+}
+
+impl ProgressSpan {
+    fn new(bar: ProgressBar, style: ProgressStyle) -> ProgressSpan {
+        if !progress_enabled() {
+            return ProgressSpan::default();
+        }
+
+        bar.set_style(style);
+        ProgressSpan {
+            bar: Some(MULTI_PROGRESS.add(bar)),
+            nested: false
+        }
     }
 
-    let bar = ProgressBar::new(len).with_message(msg);
-    bar.set_style(
-        indicatif::ProgressStyle::default_bar()
-            .template("[{elapsed_precise} / {eta_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
-            .unwrap()
-            .progress_chars("##-")
-    );
+    /// Creates a bar drawn directly below this one, e.g. the games of the current match.
+    /// The child is removed once it is finished or dropped.
+    pub fn child(&self, len: u64, msg: String) -> ProgressSpan {
+        let Some(parent) = &self.bar else {
+            return ProgressSpan::default();
+        };
 
-    Some(bar)
-}
+        let bar = ProgressBar::new(len).with_message(msg);
+        bar.set_style(bar_style());
 
-pub fn progress_bar_spinner(len: u64, msg: String) -> Option<ProgressBar> {
-    if cfg!(test) {
-        return None;
+        ProgressSpan {
+            bar: Some(MULTI_PROGRESS.insert_after(parent, bar)),
+            nested: true
+        }
     }
 
-    let bar = ProgressBar::new(len).with_message(msg);
-    bar.set_style(
-        indicatif::ProgressStyle::default_spinner()
-            .template("[{elapsed_precise} / {eta_precise}] {spinner:.green} {msg}")
-            .unwrap()
-    );
+    pub fn inc(&self, delta: u64) {
+        if let Some(bar) = &self.bar {
+            bar.inc(delta);
+        }
+    }
+
+    pub fn set_message(&self, msg: &'static str) {
+        if let Some(bar) = &self.bar {
+            bar.set_message(msg);
+        }
+    }
 
-    Some(bar)
+    pub fn finish(&self) {
+        if let Some(bar) = &self.bar {
+            if self.nested {
+                bar.finish_and_clear();
+                MULTI_PROGRESS.remove(bar);
+            } else {
+                bar.finish();
+            }
+        }
+    }
+
+    pub fn finish_with_message(&self, msg: &'static str) {
+        if let Some(bar) = &self.bar {
+            bar.finish_with_message(msg);
+        }
+    }
 }
 
-pub fn indeterminate_bar(msg: String) -> Option<ProgressBar> {
-    if cfg!(test) {
-        return None;
+impl Drop for ProgressSpan {
+    fn drop(&mut self) {
+        if self.bar.as_ref().is_some_and(|bar| !bar.is_finished()) {
+            self.finish();
+        }
     }
+}
+
+/// Whether progress bars should be drawn at all
+fn progress_enabled() -> bool {
+    !cfg!(test) && stderr().is_terminal()
+}
+
+fn bar_style() -> ProgressStyle {
+    ProgressStyle::default_bar()
+        .template("[{elapsed_precise} / {eta_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
+        .unwrap()
+        .progress_chars("##-")
+}
 
-    let bar = ProgressBar::new_spinner().with_message(msg);
+pub(crate) fn progress_bar(len: u64, msg: String) -> ProgressSpan {
+    ProgressSpan::new(ProgressBar::new(len).with_message(msg), bar_style())
+}
 
-    bar.set_style(
-        indicatif::ProgressStyle::default_spinner()
+pub(crate) fn progress_bar_spinner(len: u64, msg: String) -> ProgressSpan {
+    ProgressSpan::new(
+        ProgressBar::new(len).with_message(msg),
+        ProgressStyle::default_spinner()
+            .template("[{elapsed_precise} / {eta_precise}] {spinner:.green} {msg}")
+            .unwrap()
+    )
+}
+
+pub(crate) fn indeterminate_bar(msg: String) -> ProgressSpan {
+    ProgressSpan::new(
+        ProgressBar::new_spinner().with_message(msg),
+        ProgressStyle::default_spinner()
             .template("[{elapsed_precise}] {spinner:.green} {msg}")
             .unwrap()
-    );
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::progress_bar;
 
-    Some(bar)
+    #[test]
+    fn test_hidden_span_is_noop() {
+        let span = progress_bar(10, "Parent".to_string());
+        assert!(span.bar.is_none());
+
+        let child = span.child(5, "Child".to_string());
+        assert!(child.bar.is_none());
+
+        child.inc(1);
+        child.finish();
+        span.finish_with_message("Done");
+    }
 }
@@ -0,0 +1,299 @@
+use super::test_utils::{generate_ruleset_data, TournamentBuilder};
+use crate::{
+    database::db_structs::{Game, GameScore, Match, Player},
+    model::{exclusions::rerank_placements, structures::verification_status::VerificationStatus}
+};
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashMap};
+
+/// Bad input a `TestDataBuilder` writes into a game, for tests of how it is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeCase {
+    /// The first score is negative
+    InvalidScore,
+    /// The first player has a second score
+    DuplicatePlayer,
+    /// Only the first score is left
+    SingleParticipant,
+    /// The last score belongs to a player who is not in the player list
+    UnknownPlayer,
+    /// Every score is removed
+    EmptyGame
+}
+
+/// Matches and players seeded by a `TestDataBuilder`
+#[derive(Debug, Clone)]
+pub struct TestData {
+    /// Matches as fetched for processing, i.e. without unverified games and scores
+    pub matches: Vec<Match>,
+    pub players: Vec<Player>
+}
+
+/// Seeds a consistent dataset of several tournaments and their players
+///
+/// Tournaments are numbered from 1 in the order they are added, and matches from 1 across all
+/// tournaments. Game `n` of a match has id `match_id * 1000 + n` and scores are numbered from 1
+/// across the whole dataset, so every id is unique. Players keep the ids given by their
+/// tournament, so tournaments of the same size share their players.
+///
+/// Games and scores can be given a verification status other than verified, in which case they
+/// are left out as the processor's query would. Edge cases are written into games after that
+/// and are not re-ranked, so the data looks as it would before the processor cleans it up.
+#[derive(Debug, Clone, Default)]
+pub struct TestDataBuilder {
+    tournaments: Vec<TournamentBuilder>,
+    /// Keyed by (match, game number, player), where no player stands for the whole game
+    statuses: HashMap<(i32, i32, Option<i32>), VerificationStatus>,
+    edge_cases: Vec<(i32, i32, EdgeCase)>
+}
+
+impl TestDataBuilder {
+    pub fn new() -> TestDataBuilder {
+        TestDataBuilder::default()
+    }
+
+    /// Adds a tournament, whose tournament id is replaced by its position in the dataset
+    pub fn tournament(mut self, tournament: TournamentBuilder) -> Self {
+        self.tournaments.push(tournament);
+        self
+    }
+
+    /// Sets the verification status of game `game` (numbered from 1) of match `match_id`
+    pub fn with_game_status(mut self, match_id: i32, game: i32, status: VerificationStatus) -> Self {
+        self.statuses.insert((match_id, game, None), status);
+        self
+    }
+
+    /// Sets the verification status of the score of `player_id` in game `game` of match `match_id`
+    pub fn with_score_status(mut self, match_id: i32, game: i32, player_id: i32, status: VerificationStatus) -> Self {
+        self.statuses.insert((match_id, game, Some(player_id)), status);
+        self
+    }
+
+    /// Writes an edge case into game `game` (numbered from 1) of match `match_id`
+    pub fn with_edge_case(mut self, match_id: i32, game: i32, edge_case: EdgeCase) -> Self {
+        self.edge_cases.push((match_id, game, edge_case));
+        self
+    }
+
+    /// Players of every tournament, with ruleset data for each ruleset they played
+    pub fn players(&self) -> Vec<Player> {
+        let mut rulesets = BTreeMap::new();
+        for tournament in &self.tournaments {
+            let matches = tournament.build();
+            for player_id in tournament.player_ids() {
+                rulesets
+                    .entry(player_id)
+                    .or_insert_with(Vec::new)
+                    .extend(matches.first().map(|m| m.ruleset));
+            }
+        }
+
+        rulesets
+            .into_iter()
+            .map(|(id, rulesets)| Player {
+                id,
+                username: Some(format!("Player {}", id)),
+                country: Some("US".to_string()),
+                ruleset_data: Some(
+                    rulesets
+                        .into_iter()
+                        .unique()
+                        .map(|ruleset| generate_ruleset_data(ruleset, 1000 + id, None))
+                        .collect()
+                )
+            })
+            .collect()
+    }
+
+    pub fn build(&self) -> TestData {
+        let players = self.players();
+        let unknown_player_id = players.last().map_or(1, |p| p.id + 1);
+
+        let mut matches: Vec<Match> = Vec::new();
+        let mut score_id = 0;
+        for (i, tournament) in self.tournaments.iter().enumerate() {
+            for mut match_ in tournament.clone().tournament_id(i as i32 + 1).build() {
+                match_.id = matches.len() as i32 + 1;
+                for game in &mut match_.games {
+                    game.id = match_.id * 1000 + game.id % 1000;
+                    for score in &mut game.scores {
+                        score_id += 1;
+                        score.id = score_id;
+                        score.game_id = game.id;
+                    }
+                }
+                matches.push(match_);
+            }
+        }
+
+        for match_ in &mut matches {
+            let match_id = match_.id;
+            match_.games.retain_mut(|game| {
+                let number = game.id % 1000;
+                let verified = |player_id: Option<i32>| {
+                    self.statuses
+                        .get(&(match_id, number, player_id))
+                        .is_none_or(|&status| status == VerificationStatus::Verified)
+                };
+                if !verified(None) {
+                    return false;
+                }
+
+                let before = game.scores.len();
+                game.scores.retain(|score| verified(Some(score.player_id)));
+                if game.scores.len() != before {
+                    rerank_placements(game);
+                }
+                true
+            });
+        }
+
+        for &(match_id, number, edge_case) in &self.edge_cases {
+            let game = matches
+                .iter_mut()
+                .find(|m| m.id == match_id)
+                .and_then(|m| m.games.iter_mut().find(|g| g.id % 1000 == number));
+            if let Some(game) = game {
+                score_id += 1;
+                apply_edge_case(game, edge_case, score_id, unknown_player_id);
+            }
+        }
+
+        TestData { matches, players }
+    }
+}
+
+fn apply_edge_case(game: &mut Game, edge_case: EdgeCase, new_score_id: i32, unknown_player_id: i32) {
+    match edge_case {
+        EdgeCase::InvalidScore => {
+            if let Some(score) = game.scores.first_mut() {
+                score.score = -1;
+            }
+        }
+        EdgeCase::DuplicatePlayer => {
+            if let Some(score) = game.scores.first() {
+                game.scores.push(GameScore {
+                    id: new_score_id,
+                    ..score.clone()
+                });
+            }
+        }
+        EdgeCase::SingleParticipant => game.scores.truncate(1),
+        EdgeCase::UnknownPlayer => {
+            if let Some(score) = game.scores.last_mut() {
+                score.player_id = unknown_player_id;
+            }
+        }
+        EdgeCase::EmptyGame => game.scores.clear()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EdgeCase, TestDataBuilder};
+    use crate::{
+        model::{
+            score_integrity::{check_score_integrity, IntegrityIssueKind, IntegrityPolicy},
+            structures::{
+                ruleset::Ruleset::{Osu, Taiko},
+                verification_status::VerificationStatus
+            }
+        },
+        utils::test_utils::TournamentBuilder
+    };
+    use itertools::Itertools;
+
+    fn two_tournaments() -> TestDataBuilder {
+        TestDataBuilder::new()
+            .tournament(TournamentBuilder::new().matches(2).games(3))
+            .tournament(TournamentBuilder::new().ruleset(Taiko).team_size(2).games(3))
+    }
+
+    #[test]
+    fn test_ids_are_unique_across_tournaments() {
+        let data = two_tournaments().build();
+
+        let matches = data.matches.iter().map(|m| (m.id, m.tournament_id)).collect_vec();
+        assert_eq!(matches, vec![(1, 1), (2, 1), (3, 2)]);
+
+        let games = data.matches.iter().flat_map(|m| &m.games).collect_vec();
+        assert!(games.iter().map(|g| g.id).all_unique());
+        let scores = games.iter().flat_map(|g| &g.scores).collect_vec();
+        assert!(scores.iter().map(|s| s.id).all_unique());
+        assert!(games.iter().all(|g| g.scores.iter().all(|s| s.game_id == g.id)));
+    }
+
+    #[test]
+    fn test_players_cover_every_ruleset_played() {
+        let players = two_tournaments().players();
+
+        let rulesets = players
+            .iter()
+            .map(|p| {
+                let data = p.ruleset_data.as_ref().unwrap();
+                (p.id, data.iter().map(|d| d.ruleset).collect_vec())
+            })
+            .collect_vec();
+        assert_eq!(
+            rulesets,
+            vec![
+                (1, vec![Osu, Taiko]),
+                (2, vec![Osu, Taiko]),
+                (3, vec![Taiko]),
+                (4, vec![Taiko]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unverified_data_is_left_out() {
+        let data = two_tournaments()
+            .with_game_status(1, 2, VerificationStatus::Rejected)
+            .with_score_status(3, 1, 4, VerificationStatus::PreVerified)
+            .build();
+
+        let games = data.matches[0].games.iter().map(|g| g.id).collect_vec();
+        assert_eq!(games, vec![1001, 1003]);
+
+        let game = &data.matches[2].games[0];
+        assert_eq!(game.scores.len(), 3);
+        assert!(game.scores.iter().all(|s| s.player_id != 4));
+        assert_eq!(
+            game.scores.iter().map(|s| s.placement).sorted().collect_vec(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_edge_cases_are_caught_by_integrity_checks() {
+        let mut data = two_tournaments()
+            .with_edge_case(1, 2, EdgeCase::DuplicatePlayer)
+            .with_edge_case(2, 1, EdgeCase::SingleParticipant)
+            .with_edge_case(2, 2, EdgeCase::EmptyGame)
+            .with_edge_case(3, 1, EdgeCase::InvalidScore)
+            .build();
+
+        assert!(data.matches[1].games[1].scores.is_empty());
+
+        let issues = check_score_integrity(&mut data.matches, &IntegrityPolicy::default()).unwrap();
+        let kinds = issues.iter().map(|i| (i.kind, i.game_id)).collect_vec();
+        assert_eq!(
+            kinds,
+            vec![
+                (IntegrityIssueKind::DuplicatePlayer, 1002),
+                (IntegrityIssueKind::SingleParticipant, 2001),
+                (IntegrityIssueKind::InvalidScore, 3001),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_player() {
+        let data = two_tournaments().with_edge_case(3, 1, EdgeCase::UnknownPlayer).build();
+
+        let player_id = data.matches[2].games[0].scores.last().unwrap().player_id;
+        assert_eq!(player_id, 5);
+        assert!(data.players.iter().all(|p| p.id != player_id));
+    }
+}
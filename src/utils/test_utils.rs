@@ -1,8 +1,9 @@
 use crate::{
-    database::db_structs::{
-        Game, GameScore, Match, Player, PlayerPlacement, PlayerRating, RatingAdjustment, RulesetData
-    },
-    model::structures::{rating_adjustment_type::RatingAdjustmentType, ruleset::Ruleset}
+    database::db_structs::{Game, GameScore, Match, PlayerPlacement, PlayerRating, RatingAdjustment, RulesetData},
+    model::{
+        placements::calculate_game_placements,
+        structures::{rating_adjustment_type::RatingAdjustmentType, rating_clamps::RatingClamps, ruleset::Ruleset}
+    }
 };
 use chrono::{DateTime, Duration, FixedOffset, Utc};
 use rand::{Rng, SeedableRng};
@@ -73,7 +74,11 @@ pub fn generate_player_rating(
             rating_after: next_rating,
             volatility_before: volatility,
             volatility_after: volatility,
-            timestamp
+            timestamp,
+            average_opponent_rating: None,
+            provisional: false,
+            percentile: None,
+            clamps: RatingClamps::default()
         });
     }
 
@@ -94,7 +99,8 @@ pub fn generate_ruleset_data(ruleset: Ruleset, global_rank: i32, earliest_global
     RulesetData {
         ruleset,
         global_rank,
-        earliest_global_rank
+        earliest_global_rank,
+        rank_history: Vec::new()
     }
 }
 
@@ -110,7 +116,8 @@ pub fn generate_game(id: i32, placements: &[PlayerPlacement]) -> Game {
             player_id: p.player_id,
             game_id: id,
             score: 0,
-            placement: p.placement
+            placement: p.placement,
+            team: 0
         })
         .collect();
 
@@ -132,20 +139,13 @@ pub fn generate_country_mapping_player_ratings(player_ratings: &[PlayerRating],
     mapping
 }
 
-pub fn generate_country_mapping_players(players: &[Player]) -> HashMap<i32, String> {
-    let mut mapping: HashMap<i32, String> = HashMap::new();
-    for p in players {
-        mapping.insert(p.id, p.country.clone().unwrap_or_default());
-    }
-
-    mapping
-}
-
 pub fn generate_match(id: i32, ruleset: Ruleset, games: &[Game], start_time: DateTime<FixedOffset>) -> Match {
     Match {
         id,
+        tournament_id: 1,
         name: "Test Match".to_string(),
         ruleset,
+        rating_exempt: false,
         start_time,
         end_time: start_time.add(chrono::Duration::hours(1)),
         games: games.to_vec()
@@ -181,12 +181,205 @@ fn random_placements(player_ids: &[i32]) -> Vec<PlayerPlacement> {
 
     // Select random placements for each player (1 to size)
     for (i, id) in player_ids.iter().enumerate() {
-        placements.push(generate_placement(*id, i as i32));
+        placements.push(generate_placement(*id, i as i32 + 1));
     }
 
     placements
 }
 
+/// Builds the matches of a tournament for scenarios which would be tedious to assemble by hand
+///
+/// Every match is played by all teams, and every player plays every game unless marked
+/// absent. Scores are drawn from a seeded RNG and placements are calculated from them, so a
+/// builder always produces the same matches. Players are numbered from 1, team by team.
+///
+/// ```
+/// # use otr_processor::{model::structures::ruleset::Ruleset::Taiko, utils::test_utils::TournamentBuilder};
+/// let tournament = TournamentBuilder::new().ruleset(Taiko).team_size(2).matches(5);
+/// let ratings = tournament.initial_ratings(1000.0, 200.0);
+/// let matches = tournament.with_absent_player(3, &[2, 4]).build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct TournamentBuilder {
+    tournament_id: i32,
+    ruleset: Ruleset,
+    teams: i32,
+    team_size: i32,
+    matches: i32,
+    games: i32,
+    start_time: DateTime<FixedOffset>,
+    match_interval: Duration,
+    /// Games (numbered from 1 within each match) each player sits out
+    absences: HashMap<i32, Vec<i32>>,
+    seed: u64
+}
+
+impl Default for TournamentBuilder {
+    fn default() -> Self {
+        TournamentBuilder {
+            tournament_id: 1,
+            ruleset: Ruleset::Osu,
+            teams: 2,
+            team_size: 1,
+            matches: 1,
+            games: 7,
+            start_time: Utc::now().fixed_offset() - Duration::days(7),
+            match_interval: Duration::days(1),
+            absences: HashMap::new(),
+            seed: 42
+        }
+    }
+}
+
+impl TournamentBuilder {
+    /// A single 1v1 osu! match of 7 games, starting a week ago
+    pub fn new() -> TournamentBuilder {
+        TournamentBuilder::default()
+    }
+
+    pub fn tournament_id(mut self, tournament_id: i32) -> Self {
+        self.tournament_id = tournament_id;
+        self
+    }
+
+    pub fn ruleset(mut self, ruleset: Ruleset) -> Self {
+        self.ruleset = ruleset;
+        self
+    }
+
+    pub fn teams(mut self, teams: i32) -> Self {
+        self.teams = teams;
+        self
+    }
+
+    pub fn team_size(mut self, team_size: i32) -> Self {
+        self.team_size = team_size;
+        self
+    }
+
+    pub fn matches(mut self, matches: i32) -> Self {
+        self.matches = matches;
+        self
+    }
+
+    /// Number of games of every match
+    pub fn games(mut self, games: i32) -> Self {
+        self.games = games;
+        self
+    }
+
+    pub fn start_time(mut self, start_time: DateTime<FixedOffset>) -> Self {
+        self.start_time = start_time;
+        self
+    }
+
+    /// Time between the start of consecutive matches
+    pub fn match_interval(mut self, match_interval: Duration) -> Self {
+        self.match_interval = match_interval;
+        self
+    }
+
+    /// Removes the player from the given games, numbered from 1, of every match
+    pub fn with_absent_player(mut self, player_id: i32, games: &[i32]) -> Self {
+        self.absences.entry(player_id).or_default().extend(games);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Ids of all players, team by team
+    pub fn player_ids(&self) -> Vec<i32> {
+        (1..=self.teams * self.team_size).collect()
+    }
+
+    /// Ids of the players of `team`, numbered from 0
+    pub fn team(&self, team: i32) -> Vec<i32> {
+        (1..=self.team_size).map(|i| team * self.team_size + i).collect()
+    }
+
+    /// Ratings of every player in the tournament's ruleset, last adjusted a day before the
+    /// first match
+    pub fn initial_ratings(&self, rating: f64, volatility: f64) -> Vec<PlayerRating> {
+        let timestamp = self.start_time - Duration::days(1);
+        self.player_ids()
+            .into_iter()
+            .map(|id| {
+                generate_player_rating(
+                    id,
+                    self.ruleset,
+                    rating,
+                    volatility,
+                    2,
+                    Some(timestamp),
+                    Some(timestamp)
+                )
+            })
+            .collect()
+    }
+
+    pub fn build(&self) -> Vec<Match> {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut score_id = 0;
+
+        (1..=self.matches)
+            .map(|match_id| {
+                let start_time = self.start_time + self.match_interval * (match_id - 1);
+                let games = (1..=self.games)
+                    .map(|number| {
+                        let game_id = match_id * 1000 + number;
+                        let game_start = start_time + Duration::minutes(5 * (number - 1) as i64);
+                        let scores = self
+                            .player_ids()
+                            .into_iter()
+                            .filter(|id| !self.absences.get(id).is_some_and(|games| games.contains(&number)))
+                            .map(|player_id| {
+                                score_id += 1;
+                                GameScore {
+                                    id: score_id,
+                                    player_id,
+                                    game_id,
+                                    score: rng.gen_range(100_000..1_000_000),
+                                    placement: 0,
+                                    // Teams are numbered from 1, as 0 marks games not played in teams
+                                    team: if self.team_size > 1 {
+                                        (player_id - 1) / self.team_size + 1
+                                    } else {
+                                        0
+                                    }
+                                }
+                            })
+                            .collect();
+
+                        let mut game = Game {
+                            id: game_id,
+                            ruleset: self.ruleset,
+                            start_time: game_start,
+                            end_time: game_start + Duration::minutes(4),
+                            scores
+                        };
+                        calculate_game_placements(&mut game);
+                        game
+                    })
+                    .collect::<Vec<_>>();
+
+                Match {
+                    id: match_id,
+                    tournament_id: self.tournament_id,
+                    name: format!("Test Match {}", match_id),
+                    start_time,
+                    end_time: start_time + Duration::minutes(5 * self.games as i64),
+                    ruleset: self.ruleset,
+                    rating_exempt: false,
+                    games
+                }
+            })
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -263,4 +456,48 @@ mod tests {
     fn test_invalid_adjustment_count() {
         generate_player_rating(1, Ruleset::Osu, 1000.0, 250.0, 0, None, None);
     }
+
+    #[test]
+    fn test_tournament_builder() {
+        let start_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset();
+        let builder = TournamentBuilder::new()
+            .ruleset(Ruleset::Taiko)
+            .team_size(2)
+            .matches(3)
+            .games(5)
+            .start_time(start_time)
+            .with_absent_player(3, &[2, 4]);
+
+        let matches = builder.build();
+
+        assert_eq!(builder.player_ids(), vec![1, 2, 3, 4]);
+        assert_eq!(builder.team(1), vec![3, 4]);
+        assert_eq!(matches.len(), 3);
+        assert_eq!(matches[2].start_time, start_time + Duration::days(2));
+
+        for match_ in &matches {
+            assert_eq!(match_.ruleset, Ruleset::Taiko);
+            assert_eq!(match_.games.len(), 5);
+
+            for (number, game) in (1..).zip(&match_.games) {
+                let players = game.scores.iter().map(|s| s.player_id).collect::<Vec<_>>();
+                let expected = if number == 2 || number == 4 { 3 } else { 4 };
+                assert_eq!(players.len(), expected);
+                assert_eq!(players.contains(&3), expected == 4);
+
+                let mut placements = game.scores.iter().map(|s| s.placement).collect::<Vec<_>>();
+                placements.sort();
+                assert_eq!(placements, (1..=expected as i32).collect::<Vec<_>>());
+            }
+        }
+
+        // Seeded, so the same builder always produces the same scores
+        let scores = |matches: &[Match]| {
+            matches
+                .iter()
+                .flat_map(|m| m.games.iter().flat_map(|g| g.scores.iter().map(|s| s.score)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(scores(&matches), scores(&builder.build()));
+    }
 }
@@ -0,0 +1,254 @@
+use crate::{
+    database::db_structs::{Match, RatingAdjustment},
+    model::structures::rating_adjustment_type::RatingAdjustmentType
+};
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The tournament of every match and the end of every tournament, used to store the match
+/// adjustments of each tournament as a single settlement
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TournamentSettlements {
+    /// Key: match id, value: tournament id
+    tournaments: HashMap<i32, i32>,
+    /// Key: tournament id, value: end time of the tournament's last match
+    ends: HashMap<i32, DateTime<FixedOffset>>
+}
+
+impl TournamentSettlements {
+    pub fn from_matches(matches: &[Match]) -> TournamentSettlements {
+        let mut settlements = TournamentSettlements::default();
+        for m in matches {
+            settlements.tournaments.insert(m.id, m.tournament_id);
+            settlements
+                .ends
+                .entry(m.tournament_id)
+                .and_modify(|end| *end = (*end).max(m.end_time))
+                .or_insert(m.end_time);
+        }
+
+        settlements
+    }
+}
+
+/// A rating adjustment as stored, possibly settling the match adjustments of a tournament
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettledAdjustment {
+    /// Holds the before values of the first and the after values of the last settled match
+    /// adjustment
+    pub adjustment: RatingAdjustment,
+    /// The tournament settled by the adjustment, None for adjustments which are not settlements
+    pub tournament_id: Option<i32>,
+    /// The match adjustments replaced by the settlement
+    pub matches: Vec<RatingAdjustment>
+}
+
+impl From<&RatingAdjustment> for SettledAdjustment {
+    fn from(adjustment: &RatingAdjustment) -> Self {
+        SettledAdjustment {
+            adjustment: adjustment.clone(),
+            tournament_id: None,
+            matches: Vec::new()
+        }
+    }
+}
+
+/// Replaces each run of consecutive match adjustments of a tournament with a single
+/// `TournamentSettlement` adjustment
+///
+/// A settlement takes effect at the end of its tournament. If the player has another
+/// adjustment before the tournament ends (e.g. a match of a concurrent tournament), the
+/// tournament is settled once per run of matches, each at the time of the next adjustment,
+/// so the stored history stays in chronological order. Replaying the settled history yields
+/// the same ratings at every other adjustment as the full history.
+///
+/// Matches of unknown tournaments and all other adjustments are kept as-is.
+///
+/// # Arguments
+/// * `adjustments` - The adjustments of a single player rating, in chronological order
+pub fn settle_tournaments(
+    adjustments: &[RatingAdjustment],
+    settlements: &TournamentSettlements
+) -> Vec<SettledAdjustment> {
+    let mut settled: Vec<SettledAdjustment> = Vec::new();
+
+    for adjustment in adjustments {
+        let tournament_id = adjustment
+            .match_id
+            .filter(|_| adjustment.adjustment_type == RatingAdjustmentType::Match)
+            .and_then(|match_id| settlements.tournaments.get(&match_id).copied());
+        let Some(tournament_id) = tournament_id else {
+            settled.push(SettledAdjustment::from(adjustment));
+            continue;
+        };
+
+        match settled.last_mut() {
+            Some(previous) if previous.tournament_id == Some(tournament_id) => {
+                previous.adjustment.rating_after = adjustment.rating_after;
+                previous.adjustment.volatility_after = adjustment.volatility_after;
+                previous.matches.push(adjustment.clone());
+            }
+            _ => settled.push(SettledAdjustment {
+                adjustment: RatingAdjustment {
+                    match_id: None,
+                    timestamp: settlements.ends[&tournament_id],
+                    adjustment_type: RatingAdjustmentType::TournamentSettlement,
+                    average_opponent_rating: None,
+                    ..adjustment.clone()
+                },
+                tournament_id: Some(tournament_id),
+                matches: vec![adjustment.clone()]
+            })
+        }
+    }
+
+    // A settlement must not take effect after the player's next adjustment (or the first
+    // match of the next settlement)
+    for i in 1..settled.len() {
+        let next = settled[i]
+            .matches
+            .first()
+            .map_or(settled[i].adjustment.timestamp, |m| m.timestamp);
+        let previous = &mut settled[i - 1];
+        if previous.tournament_id.is_some() && previous.adjustment.timestamp > next {
+            previous.adjustment.timestamp = next;
+        }
+    }
+
+    settled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{settle_tournaments, TournamentSettlements};
+    use crate::{
+        database::db_structs::{Match, RatingAdjustment},
+        model::structures::{
+            rating_adjustment_type::RatingAdjustmentType::{self, Decay, Initial, Match as MatchAdjustment},
+            rating_clamps::RatingClamps,
+            ruleset::Ruleset::Osu
+        }
+    };
+    use chrono::{DateTime, Duration, FixedOffset, TimeZone, Utc};
+
+    fn start() -> DateTime<FixedOffset> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap().fixed_offset()
+    }
+
+    /// Matches 1 to 3 belong to tournament 10, match 4 to tournament 20
+    fn settlements() -> TournamentSettlements {
+        let matches = [(1, 10), (2, 10), (3, 10), (4, 20)]
+            .into_iter()
+            .map(|(id, tournament_id)| Match {
+                id,
+                tournament_id,
+                name: String::new(),
+                start_time: start() + Duration::weeks(id as i64),
+                end_time: start() + Duration::weeks(id as i64) + Duration::hours(2),
+                ruleset: Osu,
+                rating_exempt: false,
+                games: Vec::new()
+            })
+            .collect::<Vec<_>>();
+
+        TournamentSettlements::from_matches(&matches)
+    }
+
+    fn adjustments(adjustments: &[(RatingAdjustmentType, Option<i32>)]) -> Vec<RatingAdjustment> {
+        adjustments
+            .iter()
+            .enumerate()
+            .map(|(i, &(adjustment_type, match_id))| RatingAdjustment {
+                player_id: 1,
+                ruleset: Osu,
+                match_id,
+                rating_before: 1000.0 + i as f64,
+                rating_after: 1001.0 + i as f64,
+                volatility_before: 200.0 - i as f64,
+                volatility_after: 199.0 - i as f64,
+                // Other adjustments are weeks after the matches of the tournaments
+                timestamp: start() + Duration::weeks(match_id.unwrap_or(2 * i as i32) as i64),
+                adjustment_type,
+                average_opponent_rating: None,
+                provisional: false,
+                percentile: None,
+                clamps: RatingClamps::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tournament_matches_are_settled() {
+        let original = adjustments(&[
+            (Initial, None),
+            (MatchAdjustment, Some(1)),
+            (MatchAdjustment, Some(3)),
+            (Decay, None)
+        ]);
+        let settled = settle_tournaments(&original, &settlements());
+
+        assert_eq!(settled.len(), 3);
+
+        let settlement = &settled[1];
+        assert_eq!(settlement.tournament_id, Some(10));
+        assert_eq!(settlement.matches, original[1..3].to_vec());
+        assert_eq!(
+            settlement.adjustment.adjustment_type,
+            RatingAdjustmentType::TournamentSettlement
+        );
+        assert_eq!(settlement.adjustment.match_id, None);
+        assert_eq!(settlement.adjustment.rating_before, original[1].rating_before);
+        assert_eq!(settlement.adjustment.rating_after, original[2].rating_after);
+        assert_eq!(settlement.adjustment.volatility_before, original[1].volatility_before);
+        assert_eq!(settlement.adjustment.volatility_after, original[2].volatility_after);
+        // The tournament ends with match 3
+        assert_eq!(
+            settlement.adjustment.timestamp,
+            start() + Duration::weeks(3) + Duration::hours(2)
+        );
+
+        assert_eq!(settled[0].adjustment, original[0]);
+        assert_eq!(settled[2].adjustment, original[3]);
+    }
+
+    #[test]
+    fn test_interleaved_tournaments_stay_chronological() {
+        // The player only played match 1 of tournament 10, then match 4 of tournament 20
+        let original = adjustments(&[(MatchAdjustment, Some(1)), (MatchAdjustment, Some(4))]);
+        let settled = settle_tournaments(&original, &settlements());
+
+        assert_eq!(settled.len(), 2);
+        assert_eq!(settled[0].tournament_id, Some(10));
+        assert_eq!(settled[1].tournament_id, Some(20));
+        assert_eq!(
+            settled[0].adjustment.timestamp,
+            start() + Duration::weeks(3) + Duration::hours(2)
+        );
+
+        // Tournament 10 is settled at match 1 when match 4 starts before its end
+        let original = adjustments(&[(MatchAdjustment, Some(1)), (MatchAdjustment, Some(4))])
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut a)| {
+                a.timestamp = start() + Duration::weeks(i as i64 + 1);
+                a
+            })
+            .collect::<Vec<_>>();
+        let settled = settle_tournaments(&original, &settlements());
+
+        assert_eq!(settled[0].adjustment.timestamp, original[1].timestamp);
+    }
+
+    #[test]
+    fn test_matches_of_unknown_tournaments_are_kept() {
+        let original = adjustments(&[(Initial, None), (MatchAdjustment, Some(99))]);
+        let settled = settle_tournaments(&original, &settlements());
+
+        assert_eq!(
+            settled.iter().map(|s| s.adjustment.clone()).collect::<Vec<_>>(),
+            original
+        );
+        assert!(settled.iter().all(|s| s.tournament_id.is_none()));
+    }
+}